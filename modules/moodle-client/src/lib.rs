@@ -122,6 +122,87 @@ impl MoodleClient {
         let json = response.json().await?;
         Ok(json)
     }
+
+    /// Whether a URL points at this Moodle site, and so may carry the token.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL to check.
+    pub fn is_site_url(&self, url: &str) -> bool {
+        let base = self.base_url.trim_end_matches('/');
+        url.get(..base.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(base))
+            && matches!(url[base.len()..].chars().next(), None | Some('/' | '?' | '#'))
+    }
+
+    /// Appends the web service token to a file URL returned by the Moodle REST API.
+    ///
+    /// `pluginfile.php` URLs are rewritten to `webservice/pluginfile.php` so that
+    /// the token is accepted instead of a browser session. URLs of other hosts
+    /// are returned unchanged, so the token never leaves the site.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_url` - The file URL as returned by the Moodle API.
+    ///
+    /// # Returns
+    ///
+    /// Returns the URL with the token attached.
+    pub fn tokenize_url(&self, file_url: &str) -> String {
+        if !self.is_site_url(file_url) {
+            return file_url.to_string();
+        }
+        let url = if file_url.contains("/webservice/pluginfile.php") {
+            file_url.to_string()
+        } else {
+            file_url.replacen("/pluginfile.php", "/webservice/pluginfile.php", 1)
+        };
+        let separator = if url.contains('?') { '&' } else { '?' };
+        format!("{}{}token={}", url, separator, self.token)
+    }
+
+    /// Downloads a file served by Moodle using the web service token. Files
+    /// on other hosts (e.g. Gravatar images) are fetched without it.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_url` - The file URL as returned by the Moodle API.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the file contents or an error.
+    pub async fn download(&self, file_url: &str) -> Result<Vec<u8>> {
+        let url = self.tokenize_url(file_url);
+        let response = self.client.get(&url).send().await?.error_for_status()?;
+        let bytes = response.bytes().await?;
+        Ok(bytes.to_vec())
+    }
+
+    /// Downloads a file like [`MoodleClient::download`], writing it to `path`
+    /// as it arrives instead of holding it in memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_url` - The file URL as returned by the Moodle API.
+    /// * `path` - Where to write the file; it is created or truncated.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the number of bytes written or an error.
+    pub async fn download_to_file(&self, file_url: &str, path: &std::path::Path) -> Result<u64> {
+        use tokio::io::AsyncWriteExt;
+
+        let url = self.tokenize_url(file_url);
+        let mut response = self.client.get(&url).send().await?.error_for_status()?;
+        let mut file = tokio::fs::File::create(path).await?;
+        let mut written = 0;
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+        }
+        file.flush().await?;
+        Ok(written)
+    }
 }
 
 #[tokio::test]
@@ -189,3 +270,25 @@ async fn test_post() {
 
     assert!(json["nextoffset"].as_i64().unwrap() == 1);
 }
+
+#[test]
+fn test_tokenize_url() {
+    let client = MoodleClient::new("https://moodle.example", "abc");
+
+    assert_eq!(
+        client.tokenize_url("https://moodle.example/pluginfile.php/5/assignfeedback_file/feedback_files/1/a.pdf"),
+        "https://moodle.example/webservice/pluginfile.php/5/assignfeedback_file/feedback_files/1/a.pdf?token=abc"
+    );
+    assert_eq!(
+        client.tokenize_url("https://moodle.example/webservice/pluginfile.php/5/a.pdf?forcedownload=1"),
+        "https://moodle.example/webservice/pluginfile.php/5/a.pdf?forcedownload=1&token=abc"
+    );
+    assert_eq!(
+        client.tokenize_url("https://secure.gravatar.com/avatar/abc?s=35"),
+        "https://secure.gravatar.com/avatar/abc?s=35"
+    );
+    assert_eq!(
+        client.tokenize_url("https://moodle.example.evil.com/pluginfile.php/5/a.pdf"),
+        "https://moodle.example.evil.com/pluginfile.php/5/a.pdf"
+    );
+}
@@ -0,0 +1,111 @@
+use crate::moodle::assignments::{
+    AssignmentStatus, BulkDownload, GradeInput, GradingPage, GradingResult, ParticipantGrading, SubmissionResult,
+    WorkflowState,
+};
+use crate::moodle::assignments::download_submissions::download_submissions as inner_download_submissions;
+use crate::moodle::assignments::get_submission_status::get_assignment_status as inner_get_assignment_status;
+use crate::moodle::assignments::grading::{
+    get_grading_page as inner_get_grading_page, get_participant_grading as inner_get_participant_grading,
+    save_grades as inner_save_grades, set_workflow_state as inner_set_workflow_state,
+};
+use crate::moodle::assignments::save_submission::save_submission_draft as inner_save_submission_draft;
+use crate::moodle::assignments::submit_for_grading::submit_assignment_for_grading as inner_submit_assignment_for_grading;
+use crate::moodle::files::download_file::download_file_to_path as inner_download_file_to_path;
+use std::path::PathBuf;
+
+/// Get dates, attempts, submission plugins and feedback for an assignment
+#[tauri::command]
+pub async fn get_assignment_status(assign_id: i64) -> Result<AssignmentStatus, String> {
+    inner_get_assignment_status(assign_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Save an online text and/or file draft without submitting it
+#[tauri::command]
+pub async fn save_assignment_draft(
+    assign_id: i64,
+    online_text: Option<String>,
+    file_paths: Option<Vec<String>>,
+) -> Result<SubmissionResult, String> {
+    inner_save_submission_draft(assign_id, online_text, file_paths)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Submit the saved draft for grading
+#[tauri::command]
+pub async fn submit_assignment_for_grading(
+    assign_id: i64,
+    accept_submission_statement: bool,
+) -> Result<SubmissionResult, String> {
+    inner_submit_assignment_for_grading(assign_id, accept_submission_statement)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Download a submission or feedback file to a local path
+#[tauri::command]
+pub async fn download_assignment_file(file_url: String, destination: String) -> Result<u64, String> {
+    inner_download_file_to_path(&file_url, &destination)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// List one page of participants with submissions, grades and workflow states
+#[tauri::command]
+pub async fn get_assignment_grading_page(
+    assign_id: i64,
+    search: Option<String>,
+    group_id: Option<i64>,
+    skip: Option<i64>,
+    limit: Option<i64>,
+) -> Result<GradingPage, String> {
+    inner_get_grading_page(assign_id, search, group_id, skip.unwrap_or(0), limit.unwrap_or(20))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get a participant's submission, feedback and rubric or guide fillings
+#[tauri::command]
+pub async fn get_participant_grading(assign_id: i64, user_id: i64) -> Result<ParticipantGrading, String> {
+    inner_get_participant_grading(assign_id, user_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Save one or more grades with feedback comments
+#[tauri::command]
+pub async fn save_assignment_grades(
+    assign_id: i64,
+    grades: Vec<GradeInput>,
+    apply_to_all: Option<bool>,
+) -> Result<GradingResult, String> {
+    inner_save_grades(assign_id, grades, apply_to_all.unwrap_or(false))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Move participants to a marking workflow state other than released
+#[tauri::command]
+pub async fn set_assignment_workflow_state(
+    assign_id: i64,
+    user_ids: Vec<i64>,
+    state: WorkflowState,
+) -> Result<GradingResult, String> {
+    inner_set_workflow_state(assign_id, &user_ids, state)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Download all (or the given participants') submissions into a folder
+#[tauri::command]
+pub async fn download_assignment_submissions(
+    assign_id: i64,
+    user_ids: Option<Vec<i64>>,
+    destination: String,
+) -> Result<BulkDownload, String> {
+    inner_download_submissions(assign_id, user_ids, &PathBuf::from(destination))
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,130 @@
+use crate::moodle::backup::{
+    backup_started_script, delete_local_backup as inner_delete_local_backup,
+    download_course_backup as inner_download_course_backup, extract_backup_file as inner_extract_backup_file,
+    get_backup_file as inner_get_backup_file, get_backup_progress as inner_get_backup_progress,
+    get_course_backups as inner_get_course_backups, list_local_backups as inner_list_local_backups,
+    local_backup_path, parse_backup_started, read_backup, BackupContents, BackupProgress, BackupStarted,
+    CourseBackupFile, CourseBackups, LocalBackup,
+};
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
+
+const BACKUP_WINDOW: &str = "course-backup";
+
+fn backup_archive_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("backups"))
+        .map_err(|e| e.to_string())
+}
+
+/// List the backups stored in a course's backup area
+#[tauri::command]
+pub async fn get_course_backups(course_id: i64) -> Result<CourseBackups, String> {
+    inner_get_course_backups(course_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Open the course's backup page in a window to start a new backup
+/// Emits `moodle-backup-started` with the backup id once Moodle starts an
+/// asynchronous backup; poll it with `get_backup_progress`
+#[tauri::command]
+pub async fn start_course_backup(app: AppHandle, course_id: i64) -> Result<(), String> {
+    let backups = inner_get_course_backups(course_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let context_id = backups
+        .context_id
+        .ok_or_else(|| "Could not find the course context".to_string())?;
+    let url = tauri::Url::parse(&backups.backup_page_url).map_err(|e| e.to_string())?;
+
+    if let Some(window) = app.get_webview_window(BACKUP_WINDOW) {
+        let _ = window.close();
+    }
+    let emitter = app.clone();
+    WebviewWindowBuilder::new(&app, BACKUP_WINDOW, WebviewUrl::External(url))
+        .title("Course backup")
+        .initialization_script(&backup_started_script())
+        .on_navigation(move |url| match parse_backup_started(url.as_str()) {
+            Some(backup_id) => {
+                let started = BackupStarted {
+                    course_id,
+                    context_id,
+                    backup_id,
+                };
+                if let Err(e) = emitter.emit("moodle-backup-started", started) {
+                    eprintln!("Failed to emit moodle-backup-started event: {}", e);
+                }
+                false
+            }
+            None => true,
+        })
+        .build()
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Poll asynchronous backups started on the site
+#[tauri::command]
+pub async fn get_backup_progress(context_id: i64, backup_ids: Vec<String>) -> Result<Vec<BackupProgress>, String> {
+    inner_get_backup_progress(context_id, &backup_ids)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get the file of a finished asynchronous backup
+#[tauri::command]
+pub async fn get_backup_file(context_id: i64, backup_id: String, file_name: String) -> Result<CourseBackupFile, String> {
+    inner_get_backup_file(context_id, &backup_id, &file_name)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Download a course backup into the local archive
+#[tauri::command]
+pub async fn download_course_backup(app: AppHandle, url: String, file_name: String) -> Result<LocalBackup, String> {
+    let archive_dir = backup_archive_dir(&app)?;
+    inner_download_course_backup(&url, &file_name, &archive_dir)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// List the backups kept on this device
+#[tauri::command]
+pub async fn list_local_backups(app: AppHandle) -> Result<Vec<LocalBackup>, String> {
+    let archive_dir = backup_archive_dir(&app)?;
+    inner_list_local_backups(&archive_dir).map_err(|e| e.to_string())
+}
+
+/// Remove a backup from the local archive
+#[tauri::command]
+pub async fn delete_local_backup(app: AppHandle, file_name: String) -> Result<(), String> {
+    let archive_dir = backup_archive_dir(&app)?;
+    inner_delete_local_backup(&archive_dir, &file_name).map_err(|e| e.to_string())
+}
+
+/// List the activities, files and users inside a local backup
+#[tauri::command]
+pub async fn read_local_backup(app: AppHandle, file_name: String) -> Result<BackupContents, String> {
+    let path = local_backup_path(&backup_archive_dir(&app)?, &file_name).map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || read_backup(&path))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Copy one file out of a local backup
+#[tauri::command]
+pub async fn extract_backup_file(
+    app: AppHandle,
+    file_name: String,
+    content_hash: String,
+    destination: String,
+) -> Result<u64, String> {
+    let path = local_backup_path(&backup_archive_dir(&app)?, &file_name).map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || inner_extract_backup_file(&path, &content_hash, &PathBuf::from(destination)))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
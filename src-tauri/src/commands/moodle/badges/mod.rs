@@ -0,0 +1,38 @@
+use crate::moodle::badges::{
+    download_badge_image as inner_download_badge_image, export_badge_assertion as inner_export_badge_assertion,
+    get_badge_details as inner_get_badge_details, get_user_badges as inner_get_user_badges, BadgeDetails,
+    BadgeImage, EarnedBadge,
+};
+use std::path::PathBuf;
+
+/// List the user's earned badges, optionally for one course or matching a search
+#[tauri::command]
+pub async fn get_user_badges(course_id: Option<i64>, search: Option<String>) -> Result<Vec<EarnedBadge>, String> {
+    inner_get_user_badges(course_id, search)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get a badge with its criteria and public assertion URL
+#[tauri::command]
+pub async fn get_badge_details(unique_hash: String) -> Result<BadgeDetails, String> {
+    inner_get_badge_details(&unique_hash)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Save the baked badge PNG to a local path
+#[tauri::command]
+pub async fn download_badge_image(unique_hash: String, destination: String) -> Result<BadgeImage, String> {
+    inner_download_badge_image(&unique_hash, &PathBuf::from(destination))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Export the Open Badges 2.0 assertion as a JSON file
+#[tauri::command]
+pub async fn export_badge_assertion(unique_hash: String, destination: String) -> Result<String, String> {
+    inner_export_badge_assertion(&unique_hash, &PathBuf::from(destination))
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,115 @@
+use crate::moodle::calendar::add::add_event as inner_add_event;
+use crate::moodle::calendar::delete::delete_event as inner_delete_event;
+use crate::moodle::calendar::export::export_calendar_ics as inner_export_calendar_ics;
+use crate::moodle::calendar::import::import_calendar_ics as inner_import_calendar_ics;
+use crate::moodle::calendar::edit::{update_event as inner_update_event, update_event_start_day as inner_update_event_start_day};
+use crate::moodle::calendar::get::{
+    get_allowed_event_types as inner_get_allowed_event_types, get_day_view as inner_get_day_view,
+    get_event as inner_get_event, get_events as inner_get_events,
+    get_monthly_view as inner_get_monthly_view, get_upcoming_view as inner_get_upcoming_view,
+};
+use crate::moodle::calendar::{CalendarEvent, IcsImportSummary, MonthView, NewCalendarEvent};
+use tauri::{AppHandle, Manager};
+
+/// Get events between two timestamps
+#[tauri::command]
+pub async fn get_calendar_events(timestart: Option<i64>, timeend: Option<i64>) -> Result<Vec<CalendarEvent>, String> {
+    inner_get_events(timestart, timeend)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_calendar_event(event_id: i64) -> Result<CalendarEvent, String> {
+    inner_get_event(event_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_calendar_month(year: i32, month: i32, course_id: Option<i64>) -> Result<MonthView, String> {
+    inner_get_monthly_view(year, month, course_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_calendar_day(year: i32, month: i32, day: i32, course_id: Option<i64>) -> Result<Vec<CalendarEvent>, String> {
+    inner_get_day_view(year, month, day, course_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_calendar_upcoming(course_id: Option<i64>) -> Result<Vec<CalendarEvent>, String> {
+    inner_get_upcoming_view(course_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get the event types the user may create, used to fill the type picker
+#[tauri::command]
+pub async fn get_allowed_event_types(course_id: Option<i64>) -> Result<Vec<String>, String> {
+    inner_get_allowed_event_types(course_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Create an event, returns every occurrence when it repeats
+#[tauri::command]
+pub async fn create_calendar_event(event: NewCalendarEvent) -> Result<Vec<CalendarEvent>, String> {
+    inner_add_event(event)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_calendar_event(
+    event_id: i64,
+    event: NewCalendarEvent,
+    update_series: Option<bool>,
+) -> Result<CalendarEvent, String> {
+    inner_update_event(event_id, event, update_series.unwrap_or(false))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Move an event to another day, keeping its time (drag and drop in the month view)
+#[tauri::command]
+pub async fn move_calendar_event(event_id: i64, year: i32, month: i32, day: i32) -> Result<CalendarEvent, String> {
+    inner_update_event_start_day(event_id, year, month, day)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_calendar_event(event_id: i64, delete_series: Option<bool>) -> Result<(), String> {
+    inner_delete_event(event_id, delete_series.unwrap_or(false))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Export calendar events and deadlines as iCalendar text, also written to `destination` when given
+#[tauri::command]
+pub async fn export_calendar_ics(
+    timestart: Option<i64>,
+    timeend: Option<i64>,
+    destination: Option<String>,
+) -> Result<String, String> {
+    inner_export_calendar_ics(timestart, timeend, destination)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Import an .ics file as user events; re-importing the same file updates them
+#[tauri::command]
+pub async fn import_calendar_ics(app: AppHandle, file_path: String) -> Result<IcsImportSummary, String> {
+    let uid_map_path = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("calendar_ics_uids.json");
+    inner_import_calendar_ics(&file_path, &uid_map_path)
+        .await
+        .map_err(|e| e.to_string())
+}
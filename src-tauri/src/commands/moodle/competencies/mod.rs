@@ -0,0 +1,78 @@
+use crate::moodle::competencies::{
+    delete_evidence as inner_delete_evidence, get_competency_summary as inner_get_competency_summary,
+    get_learning_plan as inner_get_learning_plan, get_learning_plans as inner_get_learning_plans,
+    get_user_evidence as inner_get_user_evidence, link_evidence_competency as inner_link_evidence_competency,
+    request_competency_review as inner_request_competency_review,
+    request_evidence_review as inner_request_evidence_review,
+    unlink_evidence_competency as inner_unlink_evidence_competency, CompetencySummary, LearningPlan,
+    LearningPlanDetail, UserEvidenceList,
+};
+
+/// Get the user's learning plans with their proficiency progress
+#[tauri::command]
+pub async fn get_learning_plans() -> Result<Vec<LearningPlan>, String> {
+    inner_get_learning_plans().await.map_err(|e| e.to_string())
+}
+
+/// Get a learning plan and the rating of each of its competencies
+#[tauri::command]
+pub async fn get_learning_plan(plan_id: i64) -> Result<LearningPlanDetail, String> {
+    inner_get_learning_plan(plan_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get a competency's rating, evidence and contributing courses, optionally within a plan or course
+#[tauri::command]
+pub async fn get_competency_summary(
+    competency_id: i64,
+    plan_id: Option<i64>,
+    course_id: Option<i64>,
+) -> Result<CompetencySummary, String> {
+    inner_get_competency_summary(competency_id, plan_id, course_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn request_competency_review(competency_id: i64) -> Result<bool, String> {
+    inner_request_competency_review(competency_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get the user's evidence of prior learning
+#[tauri::command]
+pub async fn get_user_evidence() -> Result<UserEvidenceList, String> {
+    inner_get_user_evidence().await.map_err(|e| e.to_string())
+}
+
+/// Link a competency to a piece of evidence; returns the link id
+#[tauri::command]
+pub async fn link_evidence_competency(evidence_id: i64, competency_id: i64) -> Result<i64, String> {
+    inner_link_evidence_competency(evidence_id, competency_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn unlink_evidence_competency(evidence_id: i64, competency_id: i64) -> Result<bool, String> {
+    inner_unlink_evidence_competency(evidence_id, competency_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Request review of all competencies linked to a piece of evidence
+#[tauri::command]
+pub async fn request_evidence_review(evidence_id: i64) -> Result<bool, String> {
+    inner_request_evidence_review(evidence_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_evidence(evidence_id: i64) -> Result<bool, String> {
+    inner_delete_evidence(evidence_id)
+        .await
+        .map_err(|e| e.to_string())
+}
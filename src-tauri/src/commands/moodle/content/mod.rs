@@ -0,0 +1,59 @@
+use crate::moodle::content::{
+    answer_lesson_page as inner_answer_lesson_page, finish_lesson as inner_finish_lesson,
+    get_book_content as inner_get_book_content, get_lesson_page as inner_get_lesson_page,
+    get_page_content as inner_get_page_content, start_lesson as inner_start_lesson, BookContent, LessonAnswer,
+    LessonAnswerResult, LessonFinish, LessonPage, PageContent,
+};
+
+/// Get the sanitized content of a Page activity
+#[tauri::command]
+pub async fn get_page_content(cmid: i64) -> Result<PageContent, String> {
+    inner_get_page_content(cmid)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get a Book's table of contents and its chapters (or just one chapter)
+#[tauri::command]
+pub async fn get_book_content(cmid: i64, chapter_id: Option<i64>) -> Result<BookContent, String> {
+    inner_get_book_content(cmid, chapter_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Start or resume a Lesson attempt and get its current page
+#[tauri::command]
+pub async fn start_lesson(cmid: i64, password: Option<String>) -> Result<LessonPage, String> {
+    inner_start_lesson(cmid, password)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get a Lesson page; `None` at the end of the lesson
+#[tauri::command]
+pub async fn get_lesson_page(lesson_id: i64, page_id: i64, password: Option<String>) -> Result<Option<LessonPage>, String> {
+    inner_get_lesson_page(lesson_id, page_id, password)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Answer a Lesson page or follow one of its buttons
+#[tauri::command]
+pub async fn answer_lesson_page(
+    lesson_id: i64,
+    page_id: i64,
+    answers: Vec<LessonAnswer>,
+    password: Option<String>,
+) -> Result<LessonAnswerResult, String> {
+    inner_answer_lesson_page(lesson_id, page_id, answers, password)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// End the current Lesson attempt and get the grade
+#[tauri::command]
+pub async fn finish_lesson(lesson_id: i64, password: Option<String>) -> Result<LessonFinish, String> {
+    inner_finish_lesson(lesson_id, password)
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -3,6 +3,18 @@ use crate::moodle::courses::get_course_content::get_course_content as inner_get_
 use crate::moodle::courses::get_enrolled_users::get_enrolled_users_for_course as inner_get_enrolled_users_for_course;
 use crate::moodle::courses::get_user_courses::get_user_courses as inner_get_user_courses;
 use crate::moodle::courses::get_all_courses::get_all_courses as inner_get_all_courses;
+use crate::moodle::courses::get_course_completion::{get_course_progress as inner_get_course_progress, get_courses_progress as inner_get_courses_progress, set_activity_completion as inner_set_activity_completion};
+use crate::moodle::courses::participants::{get_course_participants as inner_get_course_participants, get_participant_profile as inner_get_participant_profile, message_participant as inner_message_participant, add_participant_contact as inner_add_participant_contact};
+use crate::moodle::courses::enrolment::{get_enrolment_methods as inner_get_enrolment_methods, self_enrol as inner_self_enrol, validate_guest_access as inner_validate_guest_access};
+use crate::moodle::courses::search_courses::search_courses as inner_search_courses;
+use crate::moodle::courses::{CourseEnrolmentOptions, CourseSearchPage, EnrolmentResult, CourseProgress, CourseParticipants, ParticipantConversation, ParticipantFilter, ParticipantProfile, ParticipantSort};
+use crate::moodle::messages::conversations::ConversationMember;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+fn avatar_cache_dir(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_cache_dir().ok().map(|dir| dir.join("avatars"))
+}
 
 /// Get course files, assignments, and quizzes for a given course
 #[tauri::command]
@@ -43,3 +55,98 @@ pub async fn get_all_courses() -> Result<serde_json::Value, String> {
         .await
         .map_err(|e| e.to_string())
 }
+
+/// Get the completion progress of a course ("42% complete")
+#[tauri::command]
+pub async fn get_course_progress(course_id: i64) -> Result<CourseProgress, String> {
+    inner_get_course_progress(course_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get the completion progress of every in-progress course, for the course cards
+#[tauri::command]
+pub async fn get_courses_progress() -> Result<Vec<CourseProgress>, String> {
+    inner_get_courses_progress()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Mark a manual-completion activity as done or not done
+#[tauri::command]
+pub async fn set_activity_completion(cmid: i64, completed: bool) -> Result<bool, String> {
+    inner_set_activity_completion(cmid, completed)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get the participants of a course, filtered by role, group or name and sorted
+#[tauri::command]
+pub async fn get_course_participants(
+    app: AppHandle,
+    course_id: i64,
+    filter: Option<ParticipantFilter>,
+    sort: Option<ParticipantSort>,
+) -> Result<CourseParticipants, String> {
+    let cache_dir = avatar_cache_dir(&app);
+    inner_get_course_participants(course_id, &filter.unwrap_or_default(), sort.unwrap_or_default(), cache_dir.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get the course profile of one participant
+#[tauri::command]
+pub async fn get_participant_profile(app: AppHandle, course_id: i64, user_id: i64) -> Result<ParticipantProfile, String> {
+    let cache_dir = avatar_cache_dir(&app);
+    inner_get_participant_profile(course_id, user_id, cache_dir.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Find the conversation to open for "message this person"
+#[tauri::command]
+pub async fn message_participant(user_id: i64) -> Result<ParticipantConversation, String> {
+    inner_message_participant(user_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Send a contact request to a participant
+#[tauri::command]
+pub async fn add_participant_contact(user_id: i64) -> Result<ConversationMember, String> {
+    inner_add_participant_contact(user_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Search the site's courses, one page at a time
+#[tauri::command]
+pub async fn search_courses(query: String, page: Option<i64>, per_page: Option<i64>) -> Result<CourseSearchPage, String> {
+    inner_search_courses(&query, page.unwrap_or(0), per_page)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get the ways to join a course (self enrolment, guest access) and whether a key is needed
+#[tauri::command]
+pub async fn get_enrolment_methods(course_id: i64) -> Result<CourseEnrolmentOptions, String> {
+    inner_get_enrolment_methods(course_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Self-enrol in a course, with the enrolment key when one is required
+#[tauri::command]
+pub async fn self_enrol(course_id: i64, instance_id: Option<i64>, password: Option<String>) -> Result<EnrolmentResult, String> {
+    inner_self_enrol(course_id, instance_id, password)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Check the guest access password of a course
+#[tauri::command]
+pub async fn validate_guest_access(instance_id: i64, password: String) -> Result<EnrolmentResult, String> {
+    inner_validate_guest_access(instance_id, password)
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -1,41 +1,18 @@
-use crate::moodle::courses::get_course_files_assignments_questions::get_course_files_assignments_quizzes as inner_get_course_files_assignments_quizzes;
 use crate::moodle::courses::get_enrolled_course::get_enrolled_course as inner_get_enrolled_course;
+use crate::moodle::timeline::get_timeline::count_pending_assignments as inner_count_pending_assignments;
+use super::current_account;
+use super::timeline::timeline_cache_path;
+use tauri::AppHandle;
 
-/// Get the count of assignments across all courses
-/// This function fetches all enrolled courses and counts assignments in each
+/// Get the count of pending assignments (overdue or upcoming) across all courses
+/// This pages through the deadlines timeline instead of querying every course
 #[tauri::command]
-pub async fn get_assignment_count() -> Result<u32, String> {
-    // First, get all enrolled courses
-    let enrolled_courses_result = inner_get_enrolled_course()
+pub async fn get_assignment_count(app: AppHandle) -> Result<u32, String> {
+    let account = current_account(&app).await?;
+    let cache_path = timeline_cache_path(&app, &account);
+    inner_count_pending_assignments(&account, cache_path.as_deref())
         .await
-        .map_err(|e| e.to_string())?;
-
-    let mut total_assignments = 0u32;
-
-    // Extract course IDs from enrolled courses
-    if let Some(courses) = enrolled_courses_result.get("courses").and_then(|c| c.as_array()) {
-        for course in courses {
-            if let Some(course_id) = course.get("id").and_then(|id| id.as_u64()) {
-                // Get assignments for this course
-                match inner_get_course_files_assignments_quizzes(course_id as u32).await {
-                    Ok(result) => {
-                        // Extract assignment count from the result
-                        if let Some(summary) = result.get("summary") {
-                            if let Some(count) = summary.get("total_assignments").and_then(|c| c.as_u64()) {
-                                total_assignments += count as u32;
-                            }
-                        }
-                    }
-                    Err(_) => {
-                        // Skip courses that fail, continue with others
-                        continue;
-                    }
-                }
-            }
-        }
-    }
-
-    Ok(total_assignments)
+        .map_err(|e| e.to_string())
 }
 
 /// Get the count of enrolled courses
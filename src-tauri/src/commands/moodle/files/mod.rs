@@ -0,0 +1,40 @@
+use crate::moodle::files::{
+    delete_private_files as inner_delete_private_files, download_file_to_path,
+    get_private_files as inner_get_private_files, upload_private_files as inner_upload_private_files,
+    PrivateFile, PrivateFilesUsage, PrivateFolder,
+};
+
+/// List a folder of the user's private files ("/" for the root) with quota usage
+#[tauri::command]
+pub async fn get_private_files(filepath: Option<String>) -> Result<PrivateFolder, String> {
+    inner_get_private_files(filepath.as_deref().unwrap_or("/"))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Upload files from disk into a private files folder
+#[tauri::command]
+pub async fn upload_private_files(
+    file_paths: Vec<String>,
+    filepath: Option<String>,
+) -> Result<PrivateFilesUsage, String> {
+    inner_upload_private_files(&file_paths, filepath.as_deref().unwrap_or("/"))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Download a private file to a local path
+#[tauri::command]
+pub async fn download_private_file(file_url: String, destination: String) -> Result<u64, String> {
+    download_file_to_path(&file_url, &destination)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Delete private files or folders (needs Moodle 4.3 or later)
+#[tauri::command]
+pub async fn delete_private_files(files: Vec<PrivateFile>) -> Result<PrivateFilesUsage, String> {
+    inner_delete_private_files(&files)
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,26 @@
+use crate::moodle::forms::{
+    get_activity_form as inner_get_activity_form, submit_activity_form as inner_submit_activity_form,
+    ActivityForm, FormActivity, FormAnswers, FormSubmission,
+};
+
+/// Get a feedback, choice or survey activity as a typed question form
+#[tauri::command]
+pub async fn get_activity_form(activity: FormActivity, cmid: i64, page: Option<i64>) -> Result<ActivityForm, String> {
+    inner_get_activity_form(activity, cmid, page)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Validate and submit answers to a form page; validation errors come back in the result
+#[tauri::command]
+pub async fn submit_activity_form(
+    activity: FormActivity,
+    cmid: i64,
+    page: Option<i64>,
+    answers: FormAnswers,
+    go_previous: Option<bool>,
+) -> Result<FormSubmission, String> {
+    inner_submit_activity_form(activity, cmid, page.unwrap_or(0), &answers, go_previous.unwrap_or(false))
+        .await
+        .map_err(|e| e.to_string())
+}
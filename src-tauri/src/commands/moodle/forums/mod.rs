@@ -0,0 +1,78 @@
+use crate::moodle::forums::{DiscussionPage, DiscussionState, Forum, ForumDiscussion, ForumPost, NewForumPost};
+use crate::moodle::forums::add_post::{reply_to_post as inner_reply_to_post, start_discussion as inner_start_discussion};
+use crate::moodle::forums::get_discussion_posts::get_discussion_thread as inner_get_discussion_thread;
+use crate::moodle::forums::get_discussions::{get_course_announcements as inner_get_course_announcements, get_forum_discussions as inner_get_forum_discussions};
+use crate::moodle::forums::get_forums::get_course_forums as inner_get_course_forums;
+use crate::moodle::forums::subscriptions::{set_discussion_favourite as inner_set_discussion_favourite, set_discussion_subscription as inner_set_discussion_subscription};
+
+/// Get the forums of one or more courses, including unread post counts
+#[tauri::command]
+pub async fn get_course_forums(course_ids: Vec<i64>) -> Result<Vec<Forum>, String> {
+    inner_get_course_forums(course_ids)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get a page of discussions in a forum
+#[tauri::command]
+pub async fn get_forum_discussions(
+    forum_id: i64,
+    page: Option<i64>,
+    per_page: Option<i64>,
+) -> Result<DiscussionPage, String> {
+    inner_get_forum_discussions(forum_id, page, per_page)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get the latest discussions of a course's announcements forum
+#[tauri::command]
+pub async fn get_course_announcements(course_id: i64, limit: Option<i64>) -> Result<Vec<ForumDiscussion>, String> {
+    inner_get_course_announcements(course_id, limit)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get a discussion as a tree of posts and replies
+#[tauri::command]
+pub async fn get_discussion_thread(discussion_id: i64) -> Result<Vec<ForumPost>, String> {
+    inner_get_discussion_thread(discussion_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Reply to a forum post, returns the new post id
+#[tauri::command]
+pub async fn reply_to_forum_post(post_id: i64, post: NewForumPost) -> Result<i64, String> {
+    inner_reply_to_post(post_id, post)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Start a new discussion in a forum, returns the discussion id
+#[tauri::command]
+pub async fn start_forum_discussion(forum_id: i64, post: NewForumPost) -> Result<i64, String> {
+    inner_start_discussion(forum_id, post)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Subscribe to or unsubscribe from a discussion
+#[tauri::command]
+pub async fn set_discussion_subscription(
+    forum_id: i64,
+    discussion_id: i64,
+    subscribed: bool,
+) -> Result<DiscussionState, String> {
+    inner_set_discussion_subscription(forum_id, discussion_id, subscribed)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Star or unstar a discussion
+#[tauri::command]
+pub async fn set_discussion_favourite(discussion_id: i64, favourite: bool) -> Result<DiscussionState, String> {
+    inner_set_discussion_favourite(discussion_id, favourite)
+        .await
+        .map_err(|e| e.to_string())
+}
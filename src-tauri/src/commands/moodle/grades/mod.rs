@@ -0,0 +1,28 @@
+use crate::moodle::grades::{CourseGradeReport, GradeItem, GradesOverview};
+use crate::moodle::grades::get_grade_items::get_grade_items as inner_get_grade_items;
+use crate::moodle::grades::get_grades_overview::get_grades_overview as inner_get_grades_overview;
+use crate::moodle::grades::get_grades_table::get_course_grade_report as inner_get_course_grade_report;
+
+/// Get the gradebook of a course as category/item records with the course total
+#[tauri::command]
+pub async fn get_course_grade_report(course_id: i64) -> Result<CourseGradeReport, String> {
+    inner_get_course_grade_report(course_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get the raw grade items of a course for the current user
+#[tauri::command]
+pub async fn get_course_grade_items(course_id: i64) -> Result<Vec<GradeItem>, String> {
+    inner_get_grade_items(course_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get the course totals across all enrolled courses
+#[tauri::command]
+pub async fn get_grades_overview() -> Result<GradesOverview, String> {
+    inner_get_grades_overview()
+        .await
+        .map_err(|e| e.to_string())
+}
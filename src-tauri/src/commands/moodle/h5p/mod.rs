@@ -0,0 +1,110 @@
+use crate::commands::moodle::current_account;
+use crate::commands::moodle::outbox::{outbox_path, wake_worker};
+use crate::moodle::files::content_type;
+use crate::moodle::h5p::{
+    download_h5p_package as inner_download_h5p_package, get_h5p_attempts as inner_get_h5p_attempts, h5p_file,
+    h5p_player_file, h5p_player_html, load_h5p_package, prepare_xapi_statement, H5pAttempts, H5pLaunch,
+    H5P_PLAYER_ROUTE, H5P_XAPI_COMPONENT,
+};
+use crate::moodle::outbox::{enqueue, OutboxItem, OutboxPayload};
+use std::path::PathBuf;
+use tauri::http::{Request, Response};
+use tauri::{AppHandle, Manager};
+
+fn h5p_packages_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("h5p"))
+        .map_err(|e| e.to_string())
+}
+
+/// The h5p-standalone runtime shipped as a bundle resource
+fn h5p_runtime_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .resource_dir()
+        .map(|dir| dir.join("h5p-player"))
+        .map_err(|e| e.to_string())
+}
+
+fn respond(status: u16, content_type: &str, body: Vec<u8>) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", content_type)
+        // player.html is served from this protocol too, so the player's own
+        // requests are same-origin; this lets the app window, on its own
+        // origin, read package files such as `content_url`
+        .header("Access-Control-Allow-Origin", "*")
+        .body(body)
+        .unwrap_or_else(|_| Response::new(Vec::new()))
+}
+
+/// Handles the `h5p` URI scheme: `/player/<path>` serves the bundled
+/// runtime, `/<activity id>/player.html` the page playing a package and
+/// `/<activity id>/<path>` a file of an extracted package.
+pub fn h5p_protocol(app: &AppHandle, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let path = request.uri().path().trim_start_matches('/');
+    let (first, relative) = path.split_once('/').unwrap_or((path, ""));
+    let file = if first == H5P_PLAYER_ROUTE {
+        h5p_runtime_dir(app).ok().and_then(|dir| h5p_player_file(&dir, relative))
+    } else {
+        match (first.parse::<i64>(), h5p_packages_dir(app)) {
+            (Ok(activity_id), Ok(dir)) if relative == "player.html" => {
+                return match load_h5p_package(&dir, activity_id) {
+                    Some(package) => respond(200, "text/html; charset=utf-8", h5p_player_html(&package).into_bytes()),
+                    None => respond(404, "text/plain", b"Not downloaded".to_vec()),
+                };
+            }
+            (Ok(activity_id), Ok(dir)) => h5p_file(&dir, activity_id, relative),
+            _ => None,
+        }
+    };
+    match file.and_then(|file| std::fs::read(&file).ok().map(|bytes| (file, bytes))) {
+        Some((file, bytes)) => respond(200, content_type(&file), bytes),
+        None => respond(404, "text/plain", b"Not found".to_vec()),
+    }
+}
+
+/// Download an H5P activity for the local player, or use the stored copy when offline
+#[tauri::command]
+pub async fn download_h5p_package(app: AppHandle, cmid: i64) -> Result<H5pLaunch, String> {
+    let packages_dir = h5p_packages_dir(&app)?;
+    inner_download_h5p_package(cmid, &packages_dir)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_h5p_attempts(cmid: i64) -> Result<H5pAttempts, String> {
+    inner_get_h5p_attempts(cmid).await.map_err(|e| e.to_string())
+}
+
+/// Queue an xAPI statement from the player; the outbox posts it once online
+#[tauri::command]
+pub async fn queue_xapi_statement(
+    app: AppHandle,
+    activity_id: i64,
+    statement: serde_json::Value,
+) -> Result<Option<OutboxItem>, String> {
+    let packages_dir = h5p_packages_dir(&app)?;
+    let package = load_h5p_package(&packages_dir, activity_id)
+        .ok_or_else(|| format!("H5P activity {} is not downloaded", activity_id))?;
+    // Moodle refuses statements for activities without attempt tracking
+    if !package.enable_tracking {
+        return Ok(None);
+    }
+    let statement = prepare_xapi_statement(&package, statement).map_err(|e| e.to_string())?;
+    let path = outbox_path(&app)?;
+    let account = current_account(&app).await?;
+    let item = enqueue(
+        &path,
+        account,
+        OutboxPayload::XapiStatement {
+            component: H5P_XAPI_COMPONENT.to_string(),
+            statement,
+        },
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    wake_worker();
+    Ok(Some(item))
+}
@@ -0,0 +1,122 @@
+use crate::moodle::knowledge::{
+    browse_glossary as inner_browse_glossary, create_wiki_page as inner_create_wiki_page,
+    get_database_entries as inner_get_database_entries, get_indexed_item, get_wiki as inner_get_wiki,
+    get_wiki_page as inner_get_wiki_page, save_wiki_page as inner_save_wiki_page,
+    search_course_knowledge as inner_search_course_knowledge, start_wiki_edit as inner_start_wiki_edit,
+    DatabasePage, GlossaryBrowse, GlossaryPage, KnowledgeItem, KnowledgeMatch, KnowledgeSource, WikiEdit,
+    WikiOverview, WikiPage, WikiSaveResult,
+};
+use super::current_account;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// Each account has its own index; ids are only unique within a site
+async fn knowledge_index_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let account = current_account(app).await?;
+    app.path()
+        .app_data_dir()
+        .map(|dir| account.data_dir(&dir).join("knowledge_index.json"))
+        .map_err(|e| e.to_string())
+}
+
+/// Browse a glossary by letter, category or search term
+#[tauri::command]
+pub async fn browse_glossary(
+    app: AppHandle,
+    cmid: i64,
+    browse: GlossaryBrowse,
+    from: Option<i64>,
+    limit: Option<i64>,
+) -> Result<GlossaryPage, String> {
+    let index_path = knowledge_index_path(&app).await?;
+    inner_browse_glossary(cmid, &browse, from.unwrap_or(0), limit.unwrap_or(20), &index_path)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get a wiki's subwikis, pages and first page
+#[tauri::command]
+pub async fn get_wiki(app: AppHandle, cmid: i64) -> Result<WikiOverview, String> {
+    let index_path = knowledge_index_path(&app).await?;
+    inner_get_wiki(cmid, &index_path).await.map_err(|e| e.to_string())
+}
+
+/// Get the content of a wiki page
+#[tauri::command]
+pub async fn get_wiki_page(app: AppHandle, cmid: i64, page_id: i64) -> Result<WikiPage, String> {
+    let index_path = knowledge_index_path(&app).await?;
+    inner_get_wiki_page(cmid, page_id, &index_path)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Lock a wiki page, or one of its sections, for editing
+#[tauri::command]
+pub async fn start_wiki_edit(page_id: i64, section: Option<String>) -> Result<WikiEdit, String> {
+    inner_start_wiki_edit(page_id, section)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Save a wiki page edit, reporting a conflict if the page changed meanwhile
+#[tauri::command]
+pub async fn save_wiki_page(
+    page_id: i64,
+    content: String,
+    section: Option<String>,
+    base_version: i64,
+) -> Result<WikiSaveResult, String> {
+    inner_save_wiki_page(page_id, &content, section.as_deref(), base_version)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Create a wiki page in a subwiki
+#[tauri::command]
+pub async fn create_wiki_page(subwiki_id: i64, title: String, content: String) -> Result<i64, String> {
+    inner_create_wiki_page(subwiki_id, &title, &content)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// List a database activity's entries, optionally matching a search
+#[tauri::command]
+pub async fn get_database_entries(
+    app: AppHandle,
+    cmid: i64,
+    search: Option<String>,
+    page: Option<i64>,
+    per_page: Option<i64>,
+) -> Result<DatabasePage, String> {
+    let index_path = knowledge_index_path(&app).await?;
+    inner_get_database_entries(cmid, search, page.unwrap_or(0), per_page.unwrap_or(20), &index_path)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Search glossary entries, wiki pages and database entries seen before
+#[tauri::command]
+pub async fn search_course_knowledge(
+    app: AppHandle,
+    query: String,
+    course_id: Option<i64>,
+    limit: Option<usize>,
+) -> Result<Vec<KnowledgeMatch>, String> {
+    let index_path = knowledge_index_path(&app).await?;
+    inner_search_course_knowledge(&index_path, &query, course_id, limit.unwrap_or(20))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Read an indexed item while offline
+#[tauri::command]
+pub async fn get_knowledge_item(
+    app: AppHandle,
+    source: KnowledgeSource,
+    item_id: i64,
+) -> Result<Option<KnowledgeItem>, String> {
+    let index_path = knowledge_index_path(&app).await?;
+    get_indexed_item(&index_path, source, item_id)
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -1,5 +1,46 @@
 use crate::moodle::messages::send_message::send_message_to_conversation as inner_send_message;
 use crate::moodle::messages::send_instant_message::send_instant_message_to_user as inner_send_instant_message;
+use crate::moodle::messages::watch_messages::{get_unread_conversations_count as inner_get_unread_conversations_count, MessageWatcher};
+use crate::moodle::messages::conversations::{
+    get_conversation as inner_get_conversation, get_conversation_list as inner_get_conversation_list,
+    get_conversation_members as inner_get_conversation_members,
+    get_conversation_with_user as inner_get_conversation_with_user,
+    get_self_conversation as inner_get_self_conversation, Conversation, ConversationMember, ConversationType,
+};
+use crate::moodle::contacts::MessagingResult;
+use crate::moodle::contacts::block_user::block_user_for_user as inner_block_user;
+use crate::moodle::contacts::unblock_user::unblock_user_for_user as inner_unblock_user;
+use crate::moodle::contacts::delete_conversations_by_id::delete_conversations_by_id_for_user as inner_delete_conversations;
+use crate::moodle::contacts::get_member_info::get_member_info_for_user as inner_get_member_info;
+use crate::moodle::contacts::mark_all_conversation_messages_as_read::mark_conversation_read_for_user as inner_mark_conversation_read;
+use crate::moodle::contacts::mute_conversations::mute_conversations_for_user as inner_mute_conversations;
+use crate::moodle::contacts::unmute_conversations::unmute_conversations_for_user as inner_unmute_conversations;
+use crate::moodle::contacts::set_favourite_conversations::set_favourite_conversations_for_user as inner_set_favourite_conversations;
+use crate::moodle::contacts::unset_favourite_conversations::unset_favourite_conversations_for_user as inner_unset_favourite_conversations;
+use crate::moodle::messages::message_index::{index_new_messages, refresh_message_index as inner_refresh_message_index};
+use crate::moodle::messages::search_messages::{
+    search_messages as inner_search_messages, search_users as inner_search_users, MessageSearchResult,
+    UserSearchResults,
+};
+use crate::moodle::outbox::is_transient;
+use super::current_account;
+use super::outbox::queue_unconfirmed_message;
+use crate::moodle::site::Account;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+/// Each account has its own index so search never shows another user's messages
+fn message_index_path(app: &AppHandle, account: &Account) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| account.data_dir(&dir).join("message_index.json"))
+        .map_err(|e| e.to_string())
+}
+
+/// The running watcher task, so starting twice replaces the old one
+static MESSAGE_WATCHER: OnceLock<Mutex<Option<tauri::async_runtime::JoinHandle<()>>>> = OnceLock::new();
 
 #[tauri::command]
 pub async fn send_message(
@@ -14,12 +55,250 @@ pub async fn send_message(
 
 #[tauri::command]
 pub async fn send_instant_message(
+    app: AppHandle,
     to_user_id: i64,
     message_text: String,
 ) -> Result<serde_json::Value, String> {
+    let started_at = chrono::Utc::now().timestamp();
     // Always use HTML format (1) for instant messages
-    inner_send_instant_message(to_user_id, message_text, Some(1))
+    match inner_send_instant_message(to_user_id, message_text.clone(), Some(1)).await {
+        Ok(json) => Ok(json),
+        // Offline: keep the message in the outbox instead of losing it
+        Err(e) if is_transient(&e) => {
+            let item = queue_unconfirmed_message(app, to_user_id, message_text, started_at).await?;
+            Ok(serde_json::json!({ "queued": true, "outbox_item": item }))
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn get_unread_conversations_count() -> Result<i64, String> {
+    inner_get_unread_conversations_count()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Start polling Moodle for new messages in the background
+/// Emits `moodle-message-new` for each new message and `moodle-unread-count` when the count changes
+#[tauri::command]
+pub async fn start_message_watcher(
+    app: AppHandle,
+    interval_seconds: Option<u64>,
+    notify: Option<bool>,
+) -> Result<(), String> {
+    let interval = tokio::time::Duration::from_secs(interval_seconds.unwrap_or(15).max(5));
+    let notify = notify.unwrap_or(true);
+    let mut watcher = MessageWatcher::connect().await.map_err(|e| e.to_string())?;
+    let mut index_path = message_index_path(&app, &current_account(&app).await?)?;
+
+    let task = tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        let mut last_count = -1;
+        loop {
+            ticker.tick().await;
+
+            let messages = match watcher.poll().await {
+                Ok(messages) => messages,
+                Err(e) => {
+                    eprintln!("Message watcher poll failed: {}", e);
+                    let _ = app.emit("moodle-message-error", e.to_string());
+                    // The token may have expired, start over with a fresh login
+                    if let Ok(fresh) = MessageWatcher::connect().await {
+                        watcher = fresh;
+                        // The fresh login may be a different account
+                        if let Ok(path) = current_account(&app).await.and_then(|a| message_index_path(&app, &a)) {
+                            index_path = path;
+                        }
+                    }
+                    continue;
+                }
+            };
+
+            if watcher.unread_count() != last_count {
+                last_count = watcher.unread_count();
+                let _ = app.emit("moodle-unread-count", last_count);
+            }
+
+            if !messages.is_empty() {
+                if let Err(e) = index_new_messages(&index_path, &messages).await {
+                    eprintln!("Failed to index new messages: {}", e);
+                }
+            }
+
+            for message in messages {
+                if notify {
+                    let title = match &message.conversation_name {
+                        Some(group) => format!("{} in {}", message.sender_name, group),
+                        None => message.sender_name.clone(),
+                    };
+                    if let Err(e) = app.notification().builder().title(title).body(message.text.clone()).show() {
+                        eprintln!("Failed to show message notification: {}", e);
+                    }
+                }
+                if let Err(e) = app.emit("moodle-message-new", &message) {
+                    eprintln!("Failed to emit moodle-message-new event: {}", e);
+                }
+            }
+        }
+    });
+
+    let previous = MESSAGE_WATCHER
+        .get_or_init(|| Mutex::new(None))
+        .lock()
+        .map_err(|e| e.to_string())?
+        .replace(task);
+    if let Some(previous) = previous {
+        previous.abort();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_message_watcher() -> Result<(), String> {
+    let task = MESSAGE_WATCHER
+        .get_or_init(|| Mutex::new(None))
+        .lock()
+        .map_err(|e| e.to_string())?
+        .take();
+    if let Some(task) = task {
+        task.abort();
+    }
+    Ok(())
+}
+
+/// List conversations, optionally filtered by type (`individual`, `group`, `self_conversation`) or favourites
+#[tauri::command]
+pub async fn get_conversation_list(
+    conversation_type: Option<ConversationType>,
+    favourites: Option<bool>,
+    limit_from: Option<i64>,
+    limit_num: Option<i64>,
+) -> Result<Vec<Conversation>, String> {
+    inner_get_conversation_list(conversation_type, favourites, limit_from, limit_num)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_conversation(conversation_id: i64) -> Result<Conversation, String> {
+    inner_get_conversation(conversation_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get the private conversation with a user, null if they have never talked
+#[tauri::command]
+pub async fn get_conversation_with_user(other_user_id: i64) -> Result<Option<Conversation>, String> {
+    inner_get_conversation_with_user(other_user_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get the "notes to self" conversation, null until the first note is written
+#[tauri::command]
+pub async fn get_self_conversation() -> Result<Option<Conversation>, String> {
+    inner_get_self_conversation()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_conversation_members(
+    conversation_id: i64,
+    limit_from: Option<i64>,
+    limit_num: Option<i64>,
+) -> Result<Vec<ConversationMember>, String> {
+    inner_get_conversation_members(conversation_id, limit_from, limit_num)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_member_info(user_ids: Vec<i64>) -> Result<Vec<ConversationMember>, String> {
+    inner_get_member_info(user_ids)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn mute_conversations(conversation_ids: Vec<i64>) -> Result<MessagingResult, String> {
+    inner_mute_conversations(conversation_ids)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn unmute_conversations(conversation_ids: Vec<i64>) -> Result<MessagingResult, String> {
+    inner_unmute_conversations(conversation_ids)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_favourite_conversations(conversation_ids: Vec<i64>) -> Result<MessagingResult, String> {
+    inner_set_favourite_conversations(conversation_ids)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn unset_favourite_conversations(conversation_ids: Vec<i64>) -> Result<MessagingResult, String> {
+    inner_unset_favourite_conversations(conversation_ids)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Delete conversations for the current user only
+#[tauri::command]
+pub async fn delete_conversations(conversation_ids: Vec<i64>) -> Result<MessagingResult, String> {
+    inner_delete_conversations(conversation_ids)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn mark_conversation_read(conversation_id: i64) -> Result<MessagingResult, String> {
+    inner_mark_conversation_read(conversation_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn block_user(user_id: i64) -> Result<MessagingResult, String> {
+    inner_block_user(user_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn unblock_user(user_id: i64) -> Result<MessagingResult, String> {
+    inner_unblock_user(user_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Search messages on Moodle and in the local index; works offline with local results only
+#[tauri::command]
+pub async fn search_messages(app: AppHandle, query: String, limit: Option<i64>) -> Result<Vec<MessageSearchResult>, String> {
+    let index_path = message_index_path(&app, &current_account(&app).await?)?;
+    inner_search_messages(&query, &index_path, limit.unwrap_or(50))
         .await
         .map_err(|e| e.to_string())
 }
 
+/// Pull recent conversations into the local search index, returns the number of new messages
+#[tauri::command]
+pub async fn refresh_message_index(app: AppHandle) -> Result<usize, String> {
+    let index_path = message_index_path(&app, &current_account(&app).await?)?;
+    inner_refresh_message_index(&index_path)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn search_message_users(query: String, limit: Option<i64>) -> Result<UserSearchResults, String> {
+    inner_search_users(&query, limit.unwrap_or(20))
+        .await
+        .map_err(|e| e.to_string())
+}
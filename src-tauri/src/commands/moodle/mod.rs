@@ -7,14 +7,43 @@ use crate::moodle::contacts::search_contacts::search_contacts_by_text as inner_s
 use crate::moodle::contacts::confirm_contact_request::confirm_contact_request_for_user as inner_confirm_contact_request;
 use crate::moodle::contacts::decline_contact_request::decline_contact_request_for_user as inner_decline_contact_request;
 use crate::moodle::contacts::create_contact_request::create_contact_request_for_user as inner_create_contact_request;
+use crate::moodle::contacts::delete_contacts::delete_contacts_for_user as inner_delete_contacts;
+use crate::moodle::contacts::MessagingResult;
+use crate::moodle::site::{current_account as inner_current_account, Account};
+use tauri::{AppHandle, Manager};
 
+pub mod assignments;
+pub mod calendar;
 pub mod dashboard;
+pub mod forums;
+pub mod grades;
 pub mod messages;
+pub mod notifications;
+pub mod outbox;
+pub mod timeline;
 pub mod course;
+pub mod backup;
+pub mod workshop;
+pub mod knowledge;
+pub mod forms;
+pub mod badges;
+pub mod files;
+pub mod competencies;
+pub mod h5p;
+pub mod scorm;
+pub mod content;
 pub use dashboard::{get_assignment_count, get_enrolled_course_count};
 pub use messages::{send_message, send_instant_message};
 
-
+/// The signed-in account, for keying queued and cached data
+pub(crate) async fn current_account(app: &AppHandle) -> Result<Account, String> {
+    let path = app
+        .path()
+        .app_data_dir()
+        .map(|dir| dir.join("accounts.json"))
+        .map_err(|e| e.to_string())?;
+    inner_current_account(&path).await.map_err(|e| e.to_string())
+}
 
 #[tauri::command]
 pub async fn get_site_info() -> Result<serde_json::Value, String> {
@@ -114,3 +143,10 @@ pub async fn create_contact_request(requested_user_id: i64) -> Result<serde_json
         .await
         .map_err(|e| e.to_string())
 }
+
+#[tauri::command]
+pub async fn delete_contacts(user_ids: Vec<i64>) -> Result<MessagingResult, String> {
+    inner_delete_contacts(user_ids)
+        .await
+        .map_err(|e| e.to_string())
+}
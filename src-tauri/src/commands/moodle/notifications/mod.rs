@@ -0,0 +1,57 @@
+use crate::moodle::notifications::{
+    get_notifications as inner_get_notifications, get_unread_notification_count as inner_get_unread_notification_count,
+    mark_all_notifications_read as inner_mark_all_notifications_read,
+    mark_notification_read as inner_mark_notification_read, NotificationPage,
+};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Shows the unread count on the dock/taskbar icon and tells the UI about it
+fn update_badge(app: &AppHandle, count: i64) {
+    if let Some(window) = app.get_webview_window("main") {
+        // Not supported on every platform, the event below still updates the UI
+        let _ = window.set_badge_count(Some(count).filter(|c| *c > 0));
+    }
+    let _ = app.emit("moodle-notification-count", count);
+}
+
+/// Get a page of notifications, newest first, also grouped by component
+#[tauri::command]
+pub async fn get_notifications(
+    app: AppHandle,
+    offset: Option<i64>,
+    limit: Option<i64>,
+) -> Result<NotificationPage, String> {
+    let page = inner_get_notifications(offset, limit)
+        .await
+        .map_err(|e| e.to_string())?;
+    update_badge(&app, page.unread_count);
+    Ok(page)
+}
+
+#[tauri::command]
+pub async fn get_unread_notification_count(app: AppHandle) -> Result<i64, String> {
+    let count = inner_get_unread_notification_count()
+        .await
+        .map_err(|e| e.to_string())?;
+    update_badge(&app, count);
+    Ok(count)
+}
+
+/// Mark a notification as read, returns the remaining unread count
+#[tauri::command]
+pub async fn mark_notification_read(app: AppHandle, notification_id: i64) -> Result<i64, String> {
+    let count = inner_mark_notification_read(notification_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    update_badge(&app, count);
+    Ok(count)
+}
+
+#[tauri::command]
+pub async fn mark_all_notifications_read(app: AppHandle) -> Result<(), String> {
+    inner_mark_all_notifications_read()
+        .await
+        .map_err(|e| e.to_string())?;
+    update_badge(&app, 0);
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use crate::moodle::forums::NewForumPost;
+use crate::moodle::outbox::{
+    enqueue, enqueue_unconfirmed, list_outbox, process_outbox, prune_sent, remove_item, retry_item, wake_pending, OutboxItem,
+    OutboxPayload,
+};
+use super::current_account;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+
+/// Wakes the worker as soon as something is queued instead of at the next tick
+static OUTBOX_WAKE: OnceLock<Notify> = OnceLock::new();
+
+pub(crate) fn outbox_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("outbox.json"))
+        .map_err(|e| e.to_string())
+}
+
+pub(crate) fn wake_worker() {
+    OUTBOX_WAKE.get_or_init(Notify::new).notify_one();
+}
+
+/// Start the background task delivering queued messages, posts and drafts
+/// Emits `moodle-outbox-status` with the item whenever its status changes
+pub fn start_outbox_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let path = match outbox_path(&app) {
+            Ok(path) => path,
+            Err(e) => {
+                eprintln!("Outbox disabled, no app data dir: {}", e);
+                return;
+            }
+        };
+        let wake = OUTBOX_WAKE.get_or_init(Notify::new);
+        loop {
+            let emit = |item: &OutboxItem| {
+                if let Err(e) = app.emit("moodle-outbox-status", item) {
+                    eprintln!("Failed to emit moodle-outbox-status event: {}", e);
+                }
+            };
+            // Unknown until the first sign-in that reaches Moodle
+            if let Ok(account) = current_account(&app).await {
+                if let Err(e) = process_outbox(&path, &account, emit).await {
+                    eprintln!("Outbox processing failed: {}", e);
+                }
+            }
+            // Keep a day of delivered items so the UI can show "sent" ticks
+            let _ = prune_sent(&path, 24 * 60 * 60).await;
+
+            tokio::select! {
+                _ = wake.notified() => {}
+                _ = tokio::time::sleep(tokio::time::Duration::from_secs(20)) => {}
+            }
+        }
+    });
+}
+
+/// Queue a message for delivery; pass either `to_user_id` or `conversation_id`
+#[tauri::command]
+pub async fn queue_message(
+    app: AppHandle,
+    to_user_id: Option<i64>,
+    conversation_id: Option<i64>,
+    text: String,
+) -> Result<OutboxItem, String> {
+    if to_user_id.is_none() && conversation_id.is_none() {
+        return Err("A message needs a recipient or a conversation".to_string());
+    }
+    let path = outbox_path(&app)?;
+    let account = current_account(&app).await?;
+    let item = enqueue(
+        &path,
+        account,
+        OutboxPayload::Message {
+            to_user_id,
+            conversation_id,
+            text,
+        },
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    wake_worker();
+    Ok(item)
+}
+
+/// Queue a message whose direct send timed out or returned garbage; Moodle
+/// may have stored it, so the worker checks the conversation before resending
+pub(crate) async fn queue_unconfirmed_message(
+    app: AppHandle,
+    to_user_id: i64,
+    text: String,
+    started_at: i64,
+) -> Result<OutboxItem, String> {
+    let path = outbox_path(&app)?;
+    let account = current_account(&app).await?;
+    let payload = OutboxPayload::Message {
+        to_user_id: Some(to_user_id),
+        conversation_id: None,
+        text,
+    };
+    let item = enqueue_unconfirmed(&path, account, payload, started_at)
+        .await
+        .map_err(|e| e.to_string())?;
+    wake_worker();
+    Ok(item)
+}
+
+#[tauri::command]
+pub async fn queue_forum_reply(app: AppHandle, post_id: i64, post: NewForumPost) -> Result<OutboxItem, String> {
+    let path = outbox_path(&app)?;
+    let account = current_account(&app).await?;
+    let item = enqueue(&path, account, OutboxPayload::ForumReply { post_id, post })
+        .await
+        .map_err(|e| e.to_string())?;
+    wake_worker();
+    Ok(item)
+}
+
+#[tauri::command]
+pub async fn queue_assignment_draft(
+    app: AppHandle,
+    assign_id: i64,
+    online_text: String,
+) -> Result<OutboxItem, String> {
+    let path = outbox_path(&app)?;
+    let account = current_account(&app).await?;
+    let item = enqueue(&path, account, OutboxPayload::AssignmentDraft { assign_id, online_text })
+        .await
+        .map_err(|e| e.to_string())?;
+    wake_worker();
+    Ok(item)
+}
+
+#[tauri::command]
+pub async fn get_outbox(app: AppHandle) -> Result<Vec<OutboxItem>, String> {
+    let path = outbox_path(&app)?;
+    list_outbox(&path).await.map_err(|e| e.to_string())
+}
+
+/// Retry one item now, including items Moodle rejected before
+#[tauri::command]
+pub async fn retry_outbox_item(app: AppHandle, id: String) -> Result<OutboxItem, String> {
+    let path = outbox_path(&app)?;
+    let item = retry_item(&path, &id).await.map_err(|e| e.to_string())?;
+    wake_worker();
+    Ok(item)
+}
+
+#[tauri::command]
+pub async fn remove_outbox_item(app: AppHandle, id: String) -> Result<(), String> {
+    let path = outbox_path(&app)?;
+    remove_item(&path, &id).await.map_err(|e| e.to_string())
+}
+
+/// Try every pending item now, e.g. when the UI notices it is back online
+#[tauri::command]
+pub async fn flush_outbox(app: AppHandle) -> Result<(), String> {
+    let path = outbox_path(&app)?;
+    wake_pending(&path).await.map_err(|e| e.to_string())?;
+    wake_worker();
+    Ok(())
+}
@@ -0,0 +1,191 @@
+use crate::moodle::scorm::{
+    download_scorm_package as inner_download_scorm_package, launch_scorm as inner_launch_scorm, package_file,
+    parse_request, player_html, sync_scorm_tracks as inner_sync_scorm_tracks, ScormLaunch,
+    ScormPackage, ScormRequest, ScormSession, SessionState, TrackSyncSummary,
+};
+use crate::commands::moodle::current_account;
+use crate::moodle::files::content_type;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::http::{Request, Response};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Open player sessions by id, shared with the protocol handler
+static SCORM_SESSIONS: OnceLock<Mutex<HashMap<String, ScormSession>>> = OnceLock::new();
+
+fn sessions() -> &'static Mutex<HashMap<String, ScormSession>> {
+    SCORM_SESSIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn scorm_packages_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("scorm"))
+        .map_err(|e| e.to_string())
+}
+
+fn scorm_tracks_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("scorm_tracks.json"))
+        .map_err(|e| e.to_string())
+}
+
+/// Bumped by every commit so only the last of a burst starts a sync
+static COMMIT_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Content may commit after every interaction; wait this long for it to
+/// settle before sending tracks
+const COMMIT_SYNC_DELAY: Duration = Duration::from_secs(10);
+
+/// Syncs committed tracks in the background and reports the result to the UI
+fn spawn_track_sync(app: AppHandle) {
+    tauri::async_runtime::spawn(sync_tracks_now(app));
+}
+
+/// Like `spawn_track_sync`, but waits for commits to stop coming first
+fn schedule_track_sync(app: AppHandle) {
+    let generation = COMMIT_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(COMMIT_SYNC_DELAY).await;
+        if COMMIT_GENERATION.load(Ordering::SeqCst) == generation {
+            sync_tracks_now(app).await;
+        }
+    });
+}
+
+async fn sync_tracks_now(app: AppHandle) {
+    let Ok(path) = scorm_tracks_path(&app) else { return };
+    let result = match current_account(&app).await {
+        Ok(account) => inner_sync_scorm_tracks(&path, &account).await.map_err(|e| e.to_string()),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(summary) => {
+            let _ = app.emit("moodle-scorm-sync", summary);
+        }
+        // Offline: the tracks stay stored until the next sync
+        Err(e) => eprintln!("SCORM track sync postponed: {}", e),
+    }
+}
+
+fn respond(status: u16, content_type: &str, body: Vec<u8>) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", content_type)
+        .header("Cache-Control", "no-store")
+        .body(body)
+        .unwrap_or_else(|_| Response::new(Vec::new()))
+}
+
+/// Handles the `scorm` URI scheme: package files, the player page and the
+/// synchronous SCORM API calls made by it.
+pub fn scorm_protocol(app: &AppHandle, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    match parse_request(request.uri().path(), request.uri().query()) {
+        ScormRequest::Player { session_id } => {
+            let sessions = sessions().lock().unwrap_or_else(|e| e.into_inner());
+            match sessions.get(&session_id) {
+                Some(session) => respond(200, "text/html; charset=utf-8", player_html(session).into_bytes()),
+                None => respond(404, "text/plain", b"Session closed".to_vec()),
+            }
+        }
+        ScormRequest::Api {
+            session_id,
+            method,
+            element,
+            value,
+        } => {
+            let response = {
+                let mut sessions = sessions().lock().unwrap_or_else(|e| e.into_inner());
+                match sessions.get_mut(&session_id) {
+                    Some(session) => session.call(&method, &element, &value),
+                    None => return respond(404, "text/plain", b"false".to_vec()),
+                }
+            };
+            if response.committed {
+                schedule_track_sync(app.clone());
+            }
+            respond(200, "text/plain; charset=utf-8", response.value.into_bytes())
+        }
+        ScormRequest::File { scorm_id, path } => {
+            let file = scorm_packages_dir(app)
+                .ok()
+                .and_then(|dir| package_file(&dir, scorm_id, &path));
+            match file.and_then(|file| std::fs::read(&file).ok().map(|bytes| (file, bytes))) {
+                Some((file, bytes)) => respond(200, content_type(&file), bytes),
+                None => respond(404, "text/plain", b"Not found".to_vec()),
+            }
+        }
+        ScormRequest::NotFound => respond(404, "text/plain", b"Not found".to_vec()),
+    }
+}
+
+/// Download a SCORM package so it can be played offline
+#[tauri::command]
+pub async fn download_scorm_package(app: AppHandle, cmid: i64) -> Result<ScormPackage, String> {
+    let packages_dir = scorm_packages_dir(&app)?;
+    inner_download_scorm_package(cmid, &packages_dir)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Open a SCO in the player; `player_url` goes in an iframe
+#[tauri::command]
+pub async fn launch_scorm(
+    app: AppHandle,
+    cmid: i64,
+    sco_id: Option<i64>,
+    new_attempt: Option<bool>,
+) -> Result<ScormLaunch, String> {
+    let packages_dir = scorm_packages_dir(&app)?;
+    let tracks_path = scorm_tracks_path(&app)?;
+    let account = current_account(&app).await?;
+    let (session, launch) = inner_launch_scorm(
+        cmid,
+        sco_id,
+        new_attempt.unwrap_or(false),
+        &account,
+        &packages_dir,
+        &tracks_path,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    sessions()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(session.id.clone(), session);
+    // Push anything left over from earlier offline sessions
+    spawn_track_sync(app);
+    Ok(launch)
+}
+
+/// Close a player session, finishing it if the content never called LMSFinish
+#[tauri::command]
+pub async fn close_scorm_session(app: AppHandle, session_id: String) -> Result<bool, String> {
+    let session = sessions()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&session_id);
+    let Some(mut session) = session else {
+        return Ok(false);
+    };
+    if session.state == SessionState::Running {
+        session.call("LMSFinish", "", "");
+    }
+    spawn_track_sync(app);
+    Ok(true)
+}
+
+/// Send SCORM tracks recorded offline to Moodle
+#[tauri::command]
+pub async fn sync_scorm_tracks(app: AppHandle) -> Result<TrackSyncSummary, String> {
+    let tracks_path = scorm_tracks_path(&app)?;
+    let account = current_account(&app).await?;
+    inner_sync_scorm_tracks(&tracks_path, &account)
+        .await
+        .map_err(|e| e.to_string())
+}
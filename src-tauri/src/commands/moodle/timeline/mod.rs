@@ -0,0 +1,37 @@
+use crate::commands::moodle::current_account;
+use crate::moodle::site::Account;
+use crate::moodle::timeline::Timeline;
+use crate::moodle::timeline::get_timeline::{get_course_timeline as inner_get_course_timeline, get_timeline as inner_get_timeline};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+pub(crate) fn timeline_cache_path(app: &AppHandle, account: &Account) -> Option<PathBuf> {
+    app.path()
+        .app_cache_dir()
+        .ok()
+        .map(|dir| account.data_dir(&dir).join("timeline.json"))
+}
+
+/// Get overdue, due-this-week and later deadlines across all courses
+/// Pass `after_event_id` from the previous page to load more
+#[tauri::command]
+pub async fn get_timeline(
+    app: AppHandle,
+    after_event_id: Option<i64>,
+    limit: Option<i64>,
+    refresh: Option<bool>,
+) -> Result<Timeline, String> {
+    let account = current_account(&app).await?;
+    let cache_path = timeline_cache_path(&app, &account);
+    inner_get_timeline(&account, cache_path.as_deref(), after_event_id, limit, refresh.unwrap_or(false))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get deadlines for the given courses
+#[tauri::command]
+pub async fn get_course_timeline(course_ids: Vec<i64>, limit: Option<i64>) -> Result<Timeline, String> {
+    inner_get_course_timeline(course_ids, limit)
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,71 @@
+use crate::moodle::workshop::{
+    delete_workshop_submission as inner_delete_workshop_submission,
+    get_allocated_assessments as inner_get_allocated_assessments, get_assessment_form as inner_get_assessment_form,
+    get_workshop as inner_get_workshop, get_workshop_grades as inner_get_workshop_grades,
+    save_workshop_submission as inner_save_workshop_submission, submit_assessment as inner_submit_assessment,
+    AllocatedAssessment, AssessmentForm, AssessmentSubmission, CriterionAnswer, WorkshopGrades, WorkshopOverview,
+};
+
+/// Get a workshop's phase, user plan, own submission and available actions
+#[tauri::command]
+pub async fn get_workshop(cmid: i64) -> Result<WorkshopOverview, String> {
+    inner_get_workshop(cmid).await.map_err(|e| e.to_string())
+}
+
+/// Add or update the own workshop submission
+#[tauri::command]
+pub async fn save_workshop_submission(
+    cmid: i64,
+    title: String,
+    content: Option<String>,
+    file_paths: Option<Vec<String>>,
+) -> Result<i64, String> {
+    inner_save_workshop_submission(cmid, title, content, file_paths)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Delete the own workshop submission
+#[tauri::command]
+pub async fn delete_workshop_submission(cmid: i64) -> Result<(), String> {
+    inner_delete_workshop_submission(cmid)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// List the peer assessments allocated to the user
+#[tauri::command]
+pub async fn get_allocated_assessments(cmid: i64) -> Result<Vec<AllocatedAssessment>, String> {
+    inner_get_allocated_assessments(cmid)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get the assessment form of an allocated assessment
+#[tauri::command]
+pub async fn get_assessment_form(cmid: i64, assessment_id: i64) -> Result<AssessmentForm, String> {
+    inner_get_assessment_form(cmid, assessment_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Validate and save a peer assessment
+#[tauri::command]
+pub async fn submit_assessment(
+    cmid: i64,
+    assessment_id: i64,
+    answers: Vec<CriterionAnswer>,
+    feedback: Option<String>,
+) -> Result<AssessmentSubmission, String> {
+    inner_submit_assessment(cmid, assessment_id, answers, feedback)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get the workshop grades and the assessments received
+#[tauri::command]
+pub async fn get_workshop_grades(cmid: i64) -> Result<WorkshopGrades, String> {
+    inner_get_workshop_grades(cmid)
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -18,10 +18,66 @@ use commands::auth::moodle_login;
 use commands::bolt::{confirm_verification, get_location_suggestions, start_verification};
 use commands::moodle::{
     confirm_contact_request, create_contact_request, decline_contact_request, get_assignment_count, get_enrolled_course_count, get_contact_requests, get_conversation_messages, get_conversations,
-    get_site_info, get_user_contacts, search_contacts, send_message, send_instant_message,
+    get_site_info, get_user_contacts, search_contacts, send_message, send_instant_message, delete_contacts,
 };
 use commands::network::{get_network_info, send_channel_message, get_channel_messages};
-use commands::moodle::course::{get_course_files_assignments_quizzes, get_course_content_items, get_enrolled_users_for_course, get_user_courses, get_all_courses};
+use commands::moodle::assignments::{download_assignment_file, download_assignment_submissions, get_assignment_grading_page, get_assignment_status, get_participant_grading, save_assignment_draft, save_assignment_grades, set_assignment_workflow_state, submit_assignment_for_grading};
+use commands::moodle::forums::{
+    get_course_announcements, get_course_forums, get_discussion_thread, get_forum_discussions, reply_to_forum_post,
+    set_discussion_favourite, set_discussion_subscription, start_forum_discussion,
+};
+use commands::moodle::grades::{get_course_grade_items, get_course_grade_report, get_grades_overview};
+use commands::moodle::timeline::{get_course_timeline, get_timeline};
+use commands::moodle::outbox::{
+    flush_outbox, get_outbox, queue_assignment_draft, queue_forum_reply, queue_message, remove_outbox_item,
+    retry_outbox_item,
+};
+use commands::moodle::notifications::{
+    get_notifications, get_unread_notification_count, mark_all_notifications_read, mark_notification_read,
+};
+use commands::moodle::messages::{
+    block_user, delete_conversations, get_conversation, get_conversation_list, get_conversation_members,
+    get_conversation_with_user, get_member_info, get_self_conversation, get_unread_conversations_count,
+    mark_conversation_read, mute_conversations, refresh_message_index, search_message_users, search_messages,
+    set_favourite_conversations, start_message_watcher, stop_message_watcher, unblock_user, unmute_conversations,
+    unset_favourite_conversations,
+};
+use commands::moodle::calendar::{
+    create_calendar_event, delete_calendar_event, get_allowed_event_types, get_calendar_day, get_calendar_event,
+    get_calendar_events, get_calendar_month, get_calendar_upcoming, move_calendar_event, update_calendar_event,
+    export_calendar_ics, import_calendar_ics,
+};
+use commands::moodle::content::{
+    get_page_content, get_book_content, start_lesson, get_lesson_page, answer_lesson_page, finish_lesson,
+};
+use commands::moodle::scorm::{
+    download_scorm_package, launch_scorm, close_scorm_session, sync_scorm_tracks,
+};
+use commands::moodle::h5p::{
+    download_h5p_package, get_h5p_attempts, queue_xapi_statement,
+};
+use commands::moodle::competencies::{
+    get_learning_plans, get_learning_plan, get_competency_summary, request_competency_review, get_user_evidence, link_evidence_competency, unlink_evidence_competency, request_evidence_review, delete_evidence,
+};
+use commands::moodle::files::{
+    get_private_files, upload_private_files, download_private_file, delete_private_files,
+};
+use commands::moodle::badges::{
+    get_user_badges, get_badge_details, download_badge_image, export_badge_assertion,
+};
+use commands::moodle::forms::{
+    get_activity_form, submit_activity_form,
+};
+use commands::moodle::knowledge::{
+    browse_glossary, get_wiki, get_wiki_page, start_wiki_edit, save_wiki_page, create_wiki_page, get_database_entries, search_course_knowledge, get_knowledge_item,
+};
+use commands::moodle::workshop::{
+    get_workshop, save_workshop_submission, delete_workshop_submission, get_allocated_assessments, get_assessment_form, submit_assessment, get_workshop_grades,
+};
+use commands::moodle::backup::{
+    get_course_backups, start_course_backup, get_backup_progress, get_backup_file, download_course_backup, list_local_backups, delete_local_backup, read_local_backup, extract_backup_file,
+};
+use commands::moodle::course::{get_course_files_assignments_quizzes, get_course_content_items, get_enrolled_users_for_course, get_user_courses, get_all_courses, get_course_progress, get_courses_progress, set_activity_completion, get_course_participants, get_participant_profile, message_participant, add_participant_contact, search_courses, get_enrolment_methods, self_enrol, validate_guest_access};
 
 // Tauri commands wrappers
 #[tauri::command]
@@ -60,6 +116,15 @@ pub fn run() {
         // Provide process controls to allow frontend to relaunch the app after an update
         // (used as a primary or fallback relaunch mechanism from the UI)
         .plugin(tauri_plugin_process::init())
+        // Desktop notifications for the Moodle message watcher
+        .plugin(tauri_plugin_notification::init())
+        // Serves downloaded SCORM packages and their API to the player iframe
+        .register_uri_scheme_protocol("scorm", |ctx, request| {
+            commands::moodle::scorm::scorm_protocol(ctx.app_handle(), &request)
+        })
+        .register_uri_scheme_protocol("h5p", |ctx, request| {
+            commands::moodle::h5p::h5p_protocol(ctx.app_handle(), &request)
+        })
         // .plugin(tauri_plugin_opener::init())
         // .plugin(tauri_plugin_store::Builder::new().build())
         // .plugin(tauri_plugin_dialog::init())
@@ -70,7 +135,6 @@ pub fn run() {
         // .plugin(tauri_plugin_stronghold::Builder::new(|_| vec![]).build())
         // //.plugin(libp2p_plugin::init())
         // //.plugin(tauri_plugin_system_info::init())
-        // .plugin(tauri_plugin_notification::init())
 
         .setup(|_app| {
             let handle = _app.handle().clone();
@@ -90,6 +154,9 @@ pub fn run() {
                 }
             }
 
+            // Deliver anything composed offline in a previous session
+            commands::moodle::outbox::start_outbox_worker(handle.clone());
+
             // Kick off an initial Moodle login on startup (non-blocking)
             // Add delay to prevent blocking during startup
              tauri::async_runtime::spawn(async move {
@@ -134,6 +201,39 @@ pub fn run() {
             get_conversation_messages,
             send_message,
             send_instant_message,
+            get_unread_conversations_count,
+            start_message_watcher,
+            stop_message_watcher,
+            get_conversation_list,
+            get_conversation,
+            get_conversation_with_user,
+            get_self_conversation,
+            get_conversation_members,
+            get_member_info,
+            mute_conversations,
+            unmute_conversations,
+            set_favourite_conversations,
+            unset_favourite_conversations,
+            delete_conversations,
+            mark_conversation_read,
+            block_user,
+            unblock_user,
+            search_messages,
+            refresh_message_index,
+            search_message_users,
+            //OUTBOX
+            queue_message,
+            queue_forum_reply,
+            queue_assignment_draft,
+            get_outbox,
+            retry_outbox_item,
+            remove_outbox_item,
+            flush_outbox,
+            //NOTIFICATIONS
+            get_notifications,
+            get_unread_notification_count,
+            mark_notification_read,
+            mark_all_notifications_read,
             //MOODLE CONTACTS
             get_contact_requests,
             get_user_contacts,
@@ -141,6 +241,7 @@ pub fn run() {
             create_contact_request,
             confirm_contact_request,
             decline_contact_request,
+            delete_contacts,
             //BOLT
             get_taxi_vehicles,
             start_verification,
@@ -161,6 +262,123 @@ pub fn run() {
             get_enrolled_users_for_course,
             get_user_courses,
             get_all_courses,
+            get_course_progress,
+            get_courses_progress,
+            set_activity_completion,
+            get_course_participants,
+            get_participant_profile,
+            message_participant,
+            add_participant_contact,
+            search_courses,
+            get_enrolment_methods,
+            self_enrol,
+            validate_guest_access,
+            //ASSIGNMENTS
+            get_assignment_status,
+            save_assignment_draft,
+            submit_assignment_for_grading,
+            download_assignment_file,
+            get_assignment_grading_page,
+            get_participant_grading,
+            save_assignment_grades,
+            set_assignment_workflow_state,
+            download_assignment_submissions,
+            //FORUMS
+            get_course_forums,
+            get_forum_discussions,
+            get_course_announcements,
+            get_discussion_thread,
+            reply_to_forum_post,
+            start_forum_discussion,
+            set_discussion_subscription,
+            set_discussion_favourite,
+            //GRADES
+            get_course_grade_report,
+            get_course_grade_items,
+            get_grades_overview,
+            //TIMELINE
+            get_timeline,
+            get_course_timeline,
+            //CALENDAR
+            get_calendar_events,
+            get_calendar_event,
+            get_calendar_month,
+            get_calendar_day,
+            get_calendar_upcoming,
+            get_allowed_event_types,
+            create_calendar_event,
+            update_calendar_event,
+            move_calendar_event,
+            delete_calendar_event,
+            export_calendar_ics,
+            import_calendar_ics,
+            //CONTENT
+            get_page_content,
+            get_book_content,
+            start_lesson,
+            get_lesson_page,
+            answer_lesson_page,
+            finish_lesson,
+            //SCORM
+            download_scorm_package,
+            launch_scorm,
+            close_scorm_session,
+            sync_scorm_tracks,
+            //H5P
+            download_h5p_package,
+            get_h5p_attempts,
+            queue_xapi_statement,
+            //COMPETENCIES
+            get_learning_plans,
+            get_learning_plan,
+            get_competency_summary,
+            request_competency_review,
+            get_user_evidence,
+            link_evidence_competency,
+            unlink_evidence_competency,
+            request_evidence_review,
+            delete_evidence,
+            //FILES
+            get_private_files,
+            upload_private_files,
+            download_private_file,
+            delete_private_files,
+            //BADGES
+            get_user_badges,
+            get_badge_details,
+            download_badge_image,
+            export_badge_assertion,
+            //FORMS
+            get_activity_form,
+            submit_activity_form,
+            //KNOWLEDGE
+            browse_glossary,
+            get_wiki,
+            get_wiki_page,
+            start_wiki_edit,
+            save_wiki_page,
+            create_wiki_page,
+            get_database_entries,
+            search_course_knowledge,
+            get_knowledge_item,
+            //WORKSHOP
+            get_workshop,
+            save_workshop_submission,
+            delete_workshop_submission,
+            get_allocated_assessments,
+            get_assessment_form,
+            submit_assessment,
+            get_workshop_grades,
+            //BACKUP
+            get_course_backups,
+            start_course_backup,
+            get_backup_progress,
+            get_backup_file,
+            download_course_backup,
+            list_local_backups,
+            delete_local_backup,
+            read_local_backup,
+            extract_backup_file,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,195 @@
+use super::get_submission_status::{build_plugins, find_assignment};
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{int, list, text};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BulkDownload {
+    /// One folder per participant (or group, for team submissions)
+    pub folders: Vec<String>,
+    pub files: usize,
+    pub bytes: u64,
+    pub errors: Vec<String>,
+}
+
+/// Letters, digits, spaces, dots, dashes and underscores only.
+fn folder_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, ' ' | '.' | '-' | '_') { c } else { '_' })
+        .collect();
+    cleaned.trim_matches(|c: char| c == '.' || c.is_whitespace()).to_string()
+}
+
+/// Where a submitted file goes; `None` for paths that would leave `directory`.
+fn target_path(directory: &Path, relative: &str) -> Option<PathBuf> {
+    let relative = Path::new(relative.trim_start_matches('/'));
+    if relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+        return None;
+    }
+    Some(directory.join(relative))
+}
+
+async fn save_file(target: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = target.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(target, bytes).await
+}
+
+/// Downloads the latest submitted (or draft) attempt of each participant,
+/// or only of `user_ids`, into `destination/<name>_<id>/`. Online text is
+/// saved as `onlinetext.html`. Files that fail to download or save are
+/// reported and skipped.
+pub async fn download_submissions(assign_id: i64, user_ids: Option<Vec<i64>>, destination: &Path) -> Result<BulkDownload> {
+    let mut client = login().await?;
+    find_assignment(&mut client, assign_id).await?;
+
+    let form: Vec<(String, String)> = vec![
+        ("assignid".to_string(), assign_id.to_string()),
+        ("groupid".to_string(), "0".to_string()),
+        ("filter".to_string(), String::new()),
+        ("skip".to_string(), "0".to_string()),
+        ("limit".to_string(), "0".to_string()),
+        ("onlyids".to_string(), "0".to_string()),
+    ];
+    let participants = client
+        .post("mod_assign_list_participants", &form)
+        .await
+        .and_then(check_exception)?;
+    let names: HashMap<i64, String> = participants
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|p| (int(p, "id"), text(p, "fullname").unwrap_or_default()))
+        .collect();
+    // Team submissions belong to the group each participant submits with
+    let mut members: HashMap<i64, Vec<i64>> = HashMap::new();
+    for p in participants.as_array().into_iter().flatten() {
+        members.entry(int(p, "groupid")).or_default().push(int(p, "id"));
+    }
+
+    let form: Vec<(String, String)> = vec![("assignmentids[0]".to_string(), assign_id.to_string())];
+    let json = client
+        .post("mod_assign_get_submissions", &form)
+        .await
+        .and_then(check_exception)?;
+    let mut latest: HashMap<(i64, i64), &serde_json::Value> = HashMap::new();
+    for submission in list(&json, "assignments")
+        .iter()
+        .filter(|a| int(a, "assignmentid") == assign_id)
+        .flat_map(|a| list(a, "submissions"))
+        .filter(|s| text(s, "status").as_deref() != Some("new"))
+    {
+        // Team submissions have no user, only a group
+        let key = (int(submission, "userid"), int(submission, "groupid"));
+        if latest
+            .get(&key)
+            .map_or(true, |existing| int(submission, "attemptnumber") >= int(existing, "attemptnumber"))
+        {
+            latest.insert(key, submission);
+        }
+    }
+
+    let mut summary = BulkDownload::default();
+    for ((user_id, group_id), submission) in latest {
+        let wanted = match &user_ids {
+            None => true,
+            Some(ids) if user_id > 0 => ids.contains(&user_id),
+            Some(ids) => members
+                .get(&group_id)
+                .is_some_and(|group| group.iter().any(|id| ids.contains(id))),
+        };
+        if !wanted {
+            continue;
+        }
+        let folder = if user_id > 0 {
+            folder_name(&format!(
+                "{}_{}",
+                names.get(&user_id).map(|n| n.as_str()).unwrap_or("user"),
+                user_id
+            ))
+        } else {
+            format!("group_{}", group_id)
+        };
+        let directory = destination.join(&folder);
+        if let Err(e) = tokio::fs::create_dir_all(&directory).await {
+            summary.errors.push(format!("{}: {}", folder, e));
+            continue;
+        }
+
+        for plugin in submission.get("plugins").map(build_plugins).unwrap_or_default() {
+            if let Some(online_text) = plugin.text.filter(|_| plugin.plugin_type == "onlinetext") {
+                match save_file(&directory.join("onlinetext.html"), online_text.as_bytes()).await {
+                    Ok(()) => {
+                        summary.files += 1;
+                        summary.bytes += online_text.len() as u64;
+                    }
+                    Err(e) => summary.errors.push(format!("{}/onlinetext.html: {}", folder, e)),
+                }
+            }
+            for file in plugin.files {
+                let relative = format!("{}{}", file.filepath.trim_start_matches('/'), file.filename);
+                let Some(target) = target_path(&directory, &relative) else {
+                    summary.errors.push(format!("{}: invalid file name {}", folder, relative));
+                    continue;
+                };
+                let saved = match client.download(&file.fileurl).await {
+                    Ok(bytes) => save_file(&target, &bytes).await.map(|()| bytes.len()).map_err(|e| e.into()),
+                    Err(e) => Err(e),
+                };
+                match saved {
+                    Ok(len) => {
+                        summary.files += 1;
+                        summary.bytes += len as u64;
+                    }
+                    Err(e) => summary.errors.push(format!("{}/{}: {}", folder, relative, e)),
+                }
+            }
+        }
+        summary.folders.push(folder);
+    }
+    summary.folders.sort();
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_files_inside_the_folder() {
+        let directory = Path::new("/downloads/Ada_3");
+        let cases = [
+            ("/essay.pdf", Some("/downloads/Ada_3/essay.pdf")),
+            (
+                "/drafts/v2/essay.pdf",
+                Some("/downloads/Ada_3/drafts/v2/essay.pdf"),
+            ),
+            // Moodle paths start with a slash, which never makes them absolute here
+            ("//etc/passwd", Some("/downloads/Ada_3/etc/passwd")),
+            ("/../essay.pdf", None),
+            ("/drafts/../../essay.pdf", None),
+            ("/./essay.pdf", None),
+        ];
+        for (relative, expected) in cases {
+            assert_eq!(
+                target_path(directory, relative).as_deref(),
+                expected.map(Path::new),
+                "{}",
+                relative
+            );
+        }
+    }
+
+    #[test]
+    fn folder_names_are_plain() {
+        assert_eq!(folder_name("Ada Lovelace_3"), "Ada Lovelace_3");
+        assert_eq!(folder_name("../Group: A/B"), "_Group_ A_B");
+        assert_eq!(folder_name(" ..hidden. "), "hidden");
+    }
+}
@@ -0,0 +1,306 @@
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{flag, int, text};
+use anyhow::{anyhow, Result};
+use moodle_api::core::course::get_course_module_by_instance;
+use moodle_api::mod_::assign::get_submission_status;
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssignmentFile {
+    pub filename: String,
+    pub filepath: String,
+    pub filesize: i64,
+    pub fileurl: String,
+    pub mimetype: Option<String>,
+    pub timemodified: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmissionPlugin {
+    /// Plugin type, e.g. `onlinetext`, `file` or `comments`
+    pub plugin_type: String,
+    pub name: String,
+    pub text: Option<String>,
+    pub text_format: Option<i64>,
+    pub files: Vec<AssignmentFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmissionSettings {
+    pub onlinetext_enabled: bool,
+    /// 0 means no word limit
+    pub word_limit: i64,
+    pub file_enabled: bool,
+    pub max_files: i64,
+    /// 0 means the site/course limit applies
+    pub max_file_size: i64,
+    pub accepted_file_types: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssignmentFeedback {
+    pub grade: Option<String>,
+    pub grade_for_display: Option<String>,
+    pub graded_date: Option<i64>,
+    pub grader: Option<i64>,
+    pub comments: Option<String>,
+    pub files: Vec<AssignmentFile>,
+    pub plugins: Vec<SubmissionPlugin>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssignmentStatus {
+    pub assignment_id: i64,
+    pub course_id: i64,
+    pub cmid: i64,
+    pub name: String,
+    pub intro: Option<String>,
+    pub allow_submissions_from: i64,
+    pub due_date: i64,
+    pub cutoff_date: i64,
+    pub extension_due_date: Option<i64>,
+    pub time_limit: i64,
+    pub is_overdue: bool,
+    pub is_closed: bool,
+    /// Zero-based attempt number of the latest attempt
+    pub attempt_number: i64,
+    /// -1 means unlimited attempts
+    pub max_attempts: i64,
+    pub attempt_reopen_method: Option<String>,
+    /// `new`, `draft`, `submitted` or `reopened`
+    pub submission_status: String,
+    pub grading_status: Option<String>,
+    pub can_edit: bool,
+    pub can_submit: bool,
+    pub locked: bool,
+    pub graded: bool,
+    /// Students must press "submit" after saving a draft
+    pub requires_submit_button: bool,
+    pub requires_submission_statement: bool,
+    pub submission_statement: Option<String>,
+    pub settings: SubmissionSettings,
+    pub plugins: Vec<SubmissionPlugin>,
+    pub feedback: Option<AssignmentFeedback>,
+}
+
+/// Looks up the assignment settings (`mod_assign_get_assignments`) for an assignment instance.
+pub async fn find_assignment(client: &mut MoodleClient, assign_id: i64) -> Result<serde_json::Value> {
+    let cm = get_course_module_by_instance::call_raw(
+        client,
+        &mut get_course_module_by_instance::Params {
+            module: Some("assign".to_string()),
+            instance: Some(assign_id),
+        },
+    )
+    .await
+    .and_then(check_exception)?;
+
+    let course_id = cm
+        .get("cm")
+        .and_then(|c| c.get("course"))
+        .and_then(|c| c.as_i64())
+        .ok_or_else(|| anyhow!("Could not find the course for assignment {}", assign_id))?;
+
+    let mut params = HashMap::new();
+    params.insert("courseids[0]".to_string(), course_id.to_string());
+    let assignments = client
+        .post("mod_assign_get_assignments", &params)
+        .await
+        .and_then(check_exception)?;
+
+    assignments
+        .get("courses")
+        .and_then(|c| c.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|course| course.get("assignments").and_then(|a| a.as_array()))
+        .flatten()
+        .find(|a| a.get("id").and_then(|id| id.as_i64()) == Some(assign_id))
+        .cloned()
+        .ok_or_else(|| anyhow!("Assignment {} is not available", assign_id))
+}
+
+pub async fn get_assignment_status(assign_id: i64) -> Result<AssignmentStatus> {
+    let mut client = login().await?;
+
+    let assignment = find_assignment(&mut client, assign_id).await?;
+    let status = get_submission_status::call_raw(
+        &mut client,
+        &mut get_submission_status::Params {
+            assignid: Some(assign_id),
+            userid: None,
+            groupid: None,
+        },
+    )
+    .await
+    .and_then(check_exception)?;
+
+    Ok(build_status(assign_id, &assignment, &status))
+}
+
+fn build_status(
+    assign_id: i64,
+    assignment: &serde_json::Value,
+    status: &serde_json::Value,
+) -> AssignmentStatus {
+    let now = chrono::Utc::now().timestamp();
+    let lastattempt = status.get("lastattempt");
+    // Team assignments keep the shared attempt in `teamsubmission`
+    let submission = lastattempt.and_then(|l| {
+        l.get("teamsubmission")
+            .filter(|t| !t.is_null())
+            .or_else(|| l.get("submission"))
+    });
+
+    let due_date = int(assignment, "duedate");
+    let cutoff_date = int(assignment, "cutoffdate");
+    let extension_due_date = lastattempt
+        .and_then(|l| l.get("extensionduedate"))
+        .and_then(|e| e.as_i64())
+        .filter(|e| *e > 0);
+    let effective_due = extension_due_date.unwrap_or(due_date);
+    let submission_status = submission
+        .and_then(|s| s.get("status"))
+        .and_then(|s| s.as_str())
+        .unwrap_or("new")
+        .to_string();
+
+    AssignmentStatus {
+        assignment_id: assign_id,
+        course_id: int(assignment, "course"),
+        cmid: int(assignment, "cmid"),
+        name: text(assignment, "name").unwrap_or_default(),
+        intro: text(assignment, "intro"),
+        allow_submissions_from: int(assignment, "allowsubmissionsfromdate"),
+        due_date,
+        cutoff_date,
+        extension_due_date,
+        time_limit: int(assignment, "timelimit"),
+        is_overdue: effective_due > 0 && now > effective_due && submission_status != "submitted",
+        is_closed: cutoff_date > 0 && now > cutoff_date.max(extension_due_date.unwrap_or(0)),
+        attempt_number: submission.map(|s| int(s, "attemptnumber")).unwrap_or(0),
+        max_attempts: int(assignment, "maxattempts"),
+        attempt_reopen_method: text(assignment, "attemptreopenmethod"),
+        grading_status: lastattempt.and_then(|l| text(l, "gradingstatus")),
+        can_edit: lastattempt.map(|l| flag(l, "canedit")).unwrap_or(false),
+        can_submit: lastattempt.map(|l| flag(l, "cansubmit")).unwrap_or(false),
+        locked: lastattempt.map(|l| flag(l, "locked")).unwrap_or(false),
+        graded: lastattempt.map(|l| flag(l, "graded")).unwrap_or(false),
+        requires_submit_button: flag(assignment, "submissiondrafts"),
+        requires_submission_statement: flag(assignment, "requiresubmissionstatement"),
+        submission_statement: text(assignment, "submissionstatement"),
+        settings: build_settings(assignment),
+        plugins: submission
+            .and_then(|s| s.get("plugins"))
+            .map(build_plugins)
+            .unwrap_or_default(),
+        feedback: status.get("feedback").filter(|f| !f.is_null()).map(build_feedback),
+        submission_status,
+    }
+}
+
+pub(crate) fn build_settings(assignment: &serde_json::Value) -> SubmissionSettings {
+    let configs: Vec<&serde_json::Value> = assignment
+        .get("configs")
+        .and_then(|c| c.as_array())
+        .map(|c| c.iter().collect())
+        .unwrap_or_default();
+    let config = |plugin: &str, name: &str| -> Option<String> {
+        configs
+            .iter()
+            .find(|c| {
+                c.get("subtype").and_then(|s| s.as_str()) == Some("assignsubmission")
+                    && c.get("plugin").and_then(|p| p.as_str()) == Some(plugin)
+                    && c.get("name").and_then(|n| n.as_str()) == Some(name)
+            })
+            .and_then(|c| c.get("value"))
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string())
+    };
+    let number = |plugin: &str, name: &str| -> i64 {
+        config(plugin, name)
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0)
+    };
+
+    SubmissionSettings {
+        onlinetext_enabled: number("onlinetext", "enabled") == 1,
+        word_limit: if number("onlinetext", "wordlimitenabled") == 1 {
+            number("onlinetext", "wordlimit")
+        } else {
+            0
+        },
+        file_enabled: number("file", "enabled") == 1,
+        max_files: number("file", "maxfilesubmissions"),
+        max_file_size: number("file", "maxsubmissionsizebytes"),
+        accepted_file_types: config("file", "filetypeslist")
+            .map(|list| {
+                list.split([',', ';', ' '])
+                    .map(|t| t.trim().to_string())
+                    .filter(|t| !t.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
+
+pub(crate) fn build_plugins(plugins: &serde_json::Value) -> Vec<SubmissionPlugin> {
+    plugins
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|plugin| {
+            let editor = plugin
+                .get("editorfields")
+                .and_then(|e| e.as_array())
+                .and_then(|e| e.first());
+            SubmissionPlugin {
+                plugin_type: text(plugin, "type").unwrap_or_default(),
+                name: text(plugin, "name").unwrap_or_default(),
+                text: editor.and_then(|e| text(e, "text")),
+                text_format: editor.and_then(|e| e.get("format")).and_then(|f| f.as_i64()),
+                files: plugin
+                    .get("fileareas")
+                    .and_then(|a| a.as_array())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|area| area.get("files").and_then(|f| f.as_array()))
+                    .flatten()
+                    .map(build_file)
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn build_feedback(feedback: &serde_json::Value) -> AssignmentFeedback {
+    let grade = feedback.get("grade").filter(|g| !g.is_null());
+    let plugins = feedback.get("plugins").map(build_plugins).unwrap_or_default();
+
+    AssignmentFeedback {
+        grade: grade.and_then(|g| text(g, "grade")),
+        grade_for_display: text(feedback, "gradefordisplay"),
+        graded_date: feedback.get("gradeddate").and_then(|d| d.as_i64()),
+        grader: grade.and_then(|g| g.get("grader")).and_then(|g| g.as_i64()),
+        comments: plugins
+            .iter()
+            .find(|p| p.plugin_type == "comments")
+            .and_then(|p| p.text.clone()),
+        files: plugins.iter().flat_map(|p| p.files.clone()).collect(),
+        plugins,
+    }
+}
+
+pub(crate) fn build_file(file: &serde_json::Value) -> AssignmentFile {
+    AssignmentFile {
+        filename: text(file, "filename").unwrap_or_default(),
+        filepath: text(file, "filepath").unwrap_or_else(|| "/".to_string()),
+        filesize: int(file, "filesize"),
+        fileurl: text(file, "fileurl").unwrap_or_default(),
+        mimetype: text(file, "mimetype"),
+        timemodified: file.get("timemodified").and_then(|t| t.as_i64()),
+    }
+}
@@ -0,0 +1,724 @@
+use super::get_submission_status::{build_feedback, build_plugins, find_assignment, AssignmentFeedback, SubmissionPlugin};
+use super::grading_definition::{fetch_grading_method, GradingMethod};
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{flag, int, list, text};
+use anyhow::{anyhow, Result};
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::Mutex;
+
+/// Later pages reuse the submissions, grades and flags fetched for the first
+/// page while younger than this; none of them can be fetched per user.
+const GRADING_ROWS_TTL_SECONDS: i64 = 60;
+
+/// Marking workflow states, as named by Moodle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkflowState {
+    NotMarked,
+    InMarking,
+    ReadyForReview,
+    InReview,
+    ReadyForRelease,
+    Released,
+}
+
+impl WorkflowState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WorkflowState::NotMarked => "notmarked",
+            WorkflowState::InMarking => "inmarking",
+            WorkflowState::ReadyForReview => "readyforreview",
+            WorkflowState::InReview => "inreview",
+            WorkflowState::ReadyForRelease => "readyforrelease",
+            WorkflowState::Released => "released",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(value.to_string())).ok()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParticipantGrade {
+    pub grade: Option<f64>,
+    pub grader: Option<i64>,
+    pub attempt_number: i64,
+    pub time_modified: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GradingParticipant {
+    pub user_id: i64,
+    pub full_name: String,
+    pub profile_image_url: Option<String>,
+    pub group_name: Option<String>,
+    /// `new`, `draft`, `submitted` or `reopened`
+    pub submission_status: String,
+    pub submitted: bool,
+    pub requires_grading: bool,
+    pub granted_extension: bool,
+    pub time_submitted: Option<i64>,
+    pub attempt_number: i64,
+    pub plugins: Vec<SubmissionPlugin>,
+    pub grade: Option<ParticipantGrade>,
+    pub workflow_state: Option<WorkflowState>,
+    pub allocated_marker: Option<i64>,
+    pub locked: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GradingPage {
+    pub assignment_id: i64,
+    pub cmid: i64,
+    pub name: String,
+    pub due_date: i64,
+    pub team_submission: bool,
+    pub marking_workflow: bool,
+    pub marking_allocation: bool,
+    pub method: GradingMethod,
+    pub participants: Vec<GradingParticipant>,
+    pub skip: i64,
+    pub has_more: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RubricFilling {
+    pub criterion_id: i64,
+    pub level_id: i64,
+    pub remark: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuideFilling {
+    pub criterion_id: i64,
+    pub score: f64,
+    pub remark: Option<String>,
+}
+
+/// The grading of one participant, for the grading screen.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParticipantGrading {
+    pub user_id: i64,
+    pub plugins: Vec<SubmissionPlugin>,
+    pub feedback: Option<AssignmentFeedback>,
+    pub workflow_state: Option<WorkflowState>,
+    /// Levels or scores picked for the current advanced grade
+    pub rubric: Vec<RubricFilling>,
+    pub guide: Vec<GuideFilling>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GradeInput {
+    pub user_id: i64,
+    /// Ignored for rubrics and marking guides, which compute the grade
+    pub grade: Option<f64>,
+    /// Feedback comment, HTML
+    pub feedback: Option<String>,
+    pub workflow_state: Option<WorkflowState>,
+    /// Reopen the submission for a new attempt
+    #[serde(default)]
+    pub add_attempt: bool,
+    #[serde(default)]
+    pub rubric: Vec<RubricFilling>,
+    #[serde(default)]
+    pub guide: Vec<GuideFilling>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GradingError {
+    pub user_id: i64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GradingResult {
+    /// False when validation failed and nothing was sent
+    pub accepted: bool,
+    pub errors: Vec<GradingError>,
+    pub saved: usize,
+}
+
+fn float(value: &serde_json::Value, key: &str) -> Option<f64> {
+    value.get(key).and_then(|v| match v {
+        serde_json::Value::String(s) => s.parse().ok(),
+        other => other.as_f64(),
+    })
+}
+
+type ByUser = HashMap<i64, serde_json::Value>;
+
+/// Values of `mod_assign_get_submissions`, `get_grades` and
+/// `get_user_flags` for one assignment, keyed by user id.
+async fn fetch_by_user(client: &MoodleClient, function: &str, assignment_id: i64, list_key: &str) -> Result<ByUser> {
+    let form: Vec<(String, String)> = vec![("assignmentids[0]".to_string(), assignment_id.to_string())];
+    let json = client.post(function, &form).await.and_then(check_exception)?;
+    let mut by_user = HashMap::new();
+    for assignment in list(&json, "assignments").iter().filter(|a| int(a, "assignmentid") == assignment_id) {
+        for item in list(assignment, list_key) {
+            // Keep the latest attempt
+            let user_id = int(item, "userid");
+            let newer = by_user
+                .get(&user_id)
+                .map_or(true, |existing: &serde_json::Value| {
+                    int(item, "attemptnumber") >= int(existing, "attemptnumber")
+                });
+            if newer {
+                by_user.insert(user_id, item.clone());
+            }
+        }
+    }
+    Ok(by_user)
+}
+
+/// Submissions, grades and user flags of one assignment, by user id.
+struct GradingRows {
+    assign_id: i64,
+    fetched_at: i64,
+    submissions: ByUser,
+    grades: ByUser,
+    flags: ByUser,
+}
+
+fn grading_rows_cache() -> &'static Mutex<Option<Arc<GradingRows>>> {
+    static CACHE: OnceLock<Mutex<Option<Arc<GradingRows>>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(None))
+}
+
+/// The rows for a page; the first page always fetches them fresh.
+async fn grading_rows(client: &MoodleClient, assign_id: i64, skip: i64) -> Result<Arc<GradingRows>> {
+    let mut cache = grading_rows_cache().lock().await;
+    let now = chrono::Utc::now().timestamp();
+    if let Some(rows) = cache.as_ref() {
+        if skip > 0 && rows.assign_id == assign_id && now - rows.fetched_at < GRADING_ROWS_TTL_SECONDS {
+            return Ok(rows.clone());
+        }
+    }
+    let rows = Arc::new(GradingRows {
+        assign_id,
+        fetched_at: now,
+        submissions: fetch_by_user(client, "mod_assign_get_submissions", assign_id, "submissions").await?,
+        grades: fetch_by_user(client, "mod_assign_get_grades", assign_id, "grades").await?,
+        flags: fetch_by_user(client, "mod_assign_get_user_flags", assign_id, "userflags").await?,
+    });
+    *cache = Some(rows.clone());
+    Ok(rows)
+}
+
+/// Drops the cached rows once grades or flags change.
+async fn forget_grading_rows() {
+    *grading_rows_cache().lock().await = None;
+}
+
+/// One page of participants with their latest submission, grade and
+/// marking workflow state. `search` filters on name and email.
+pub async fn get_grading_page(
+    assign_id: i64,
+    search: Option<String>,
+    group_id: Option<i64>,
+    skip: i64,
+    limit: i64,
+) -> Result<GradingPage> {
+    let mut client = login().await?;
+    let assignment = find_assignment(&mut client, assign_id).await?;
+    let cmid = int(&assignment, "cmid");
+    let method = fetch_grading_method(&client, cmid, int(&assignment, "grade")).await?;
+
+    // One extra row tells whether there is a next page
+    let form: Vec<(String, String)> = vec![
+        ("assignid".to_string(), assign_id.to_string()),
+        ("groupid".to_string(), group_id.unwrap_or(0).to_string()),
+        ("filter".to_string(), search.unwrap_or_default()),
+        ("skip".to_string(), skip.to_string()),
+        ("limit".to_string(), (limit + 1).to_string()),
+        ("onlyids".to_string(), "0".to_string()),
+        ("includeenrolments".to_string(), "0".to_string()),
+    ];
+    let participants = client
+        .post("mod_assign_list_participants", &form)
+        .await
+        .and_then(check_exception)?;
+    let participant_rows = participants.as_array().map(|p| p.as_slice()).unwrap_or(&[]);
+    let has_more = participant_rows.len() as i64 > limit;
+
+    let rows = grading_rows(&client, assign_id, skip).await?;
+    let (submissions, grades, flags) = (&rows.submissions, &rows.grades, &rows.flags);
+
+    let participants = participant_rows
+        .iter()
+        .take(limit.max(0) as usize)
+        .map(|p| {
+            let user_id = int(p, "id");
+            let submission = submissions.get(&user_id);
+            let grade = grades.get(&user_id);
+            let flag_row = flags.get(&user_id);
+            GradingParticipant {
+                user_id,
+                full_name: text(p, "fullname").unwrap_or_default(),
+                profile_image_url: text(p, "profileimageurl"),
+                group_name: text(p, "groupname"),
+                submission_status: submission
+                    .and_then(|s| text(s, "status"))
+                    .or_else(|| text(p, "submissionstatus"))
+                    .unwrap_or_else(|| "new".to_string()),
+                submitted: flag(p, "submitted"),
+                requires_grading: flag(p, "requiregrading"),
+                granted_extension: flag(p, "grantedextension"),
+                time_submitted: submission.map(|s| int(s, "timemodified")).filter(|t| *t > 0),
+                attempt_number: submission.map(|s| int(s, "attemptnumber")).unwrap_or(0),
+                plugins: submission
+                    .and_then(|s| s.get("plugins"))
+                    .map(build_plugins)
+                    .unwrap_or_default(),
+                grade: grade.map(|g| ParticipantGrade {
+                    // Moodle stores -1 for "no grade"
+                    grade: float(g, "grade").filter(|g| *g >= 0.0),
+                    grader: Some(int(g, "grader")).filter(|g| *g > 0),
+                    attempt_number: int(g, "attemptnumber"),
+                    time_modified: int(g, "timemodified"),
+                }),
+                workflow_state: flag_row
+                    .and_then(|f| text(f, "workflowstate"))
+                    .and_then(|s| WorkflowState::parse(&s)),
+                allocated_marker: flag_row.map(|f| int(f, "allocatedmarker")).filter(|m| *m > 0),
+                locked: flag_row.map(|f| flag(f, "locked")).unwrap_or(false),
+            }
+        })
+        .collect();
+
+    Ok(GradingPage {
+        assignment_id: assign_id,
+        cmid,
+        name: text(&assignment, "name").unwrap_or_default(),
+        due_date: int(&assignment, "duedate"),
+        team_submission: flag(&assignment, "teamsubmission"),
+        marking_workflow: flag(&assignment, "markingworkflow"),
+        marking_allocation: flag(&assignment, "markingallocation"),
+        method,
+        participants,
+        skip,
+        has_more,
+    })
+}
+
+/// Fillings of the active advanced grading instance for a grade record.
+async fn fetch_fillings(
+    client: &MoodleClient,
+    definition_id: i64,
+    grade_id: i64,
+) -> Result<(Vec<RubricFilling>, Vec<GuideFilling>)> {
+    let form: Vec<(String, String)> = vec![
+        ("definitionid".to_string(), definition_id.to_string()),
+        ("since".to_string(), "0".to_string()),
+    ];
+    let json = client
+        .post("core_grading_get_gradingform_instances", &form)
+        .await
+        .and_then(check_exception)?;
+    // Status 1 is the active instance; older ones are kept as history
+    let Some(instance) = list(&json, "instances")
+        .iter()
+        .filter(|i| int(i, "itemid") == grade_id && int(i, "status") == 1)
+        .max_by_key(|i| int(i, "timemodified"))
+    else {
+        return Ok((Vec::new(), Vec::new()));
+    };
+
+    let rubric = instance.get("rubric").cloned().unwrap_or_default();
+    let guide = instance.get("guide").cloned().unwrap_or_default();
+    Ok((
+        list(&rubric, "criteria")
+            .iter()
+            .map(|c| RubricFilling {
+                criterion_id: int(c, "criterionid"),
+                level_id: int(c, "levelid"),
+                remark: text(c, "remark"),
+            })
+            .collect(),
+        list(&guide, "criteria")
+            .iter()
+            .map(|c| GuideFilling {
+                criterion_id: int(c, "criterionid"),
+                score: float(c, "score").unwrap_or(0.0),
+                remark: text(c, "remark"),
+            })
+            .collect(),
+    ))
+}
+
+/// The submission, feedback and advanced grading fillings of one participant.
+pub async fn get_participant_grading(assign_id: i64, user_id: i64) -> Result<ParticipantGrading> {
+    let mut client = login().await?;
+    let assignment = find_assignment(&mut client, assign_id).await?;
+    let method = fetch_grading_method(&client, int(&assignment, "cmid"), int(&assignment, "grade")).await?;
+
+    let form: Vec<(String, String)> = vec![
+        ("assignid".to_string(), assign_id.to_string()),
+        ("userid".to_string(), user_id.to_string()),
+    ];
+    let status = client
+        .post("mod_assign_get_submission_status", &form)
+        .await
+        .and_then(check_exception)?;
+    let lastattempt = status.get("lastattempt");
+    let submission = lastattempt.and_then(|l| {
+        l.get("teamsubmission")
+            .filter(|t| !t.is_null())
+            .or_else(|| l.get("submission"))
+    });
+    let feedback = status.get("feedback").filter(|f| !f.is_null());
+    let grade_id = feedback.and_then(|f| f.get("grade")).map(|g| int(g, "id")).unwrap_or(0);
+
+    let (rubric, guide) = match method.definition_id() {
+        Some(definition_id) if grade_id > 0 => fetch_fillings(&client, definition_id, grade_id).await?,
+        _ => (Vec::new(), Vec::new()),
+    };
+
+    Ok(ParticipantGrading {
+        user_id,
+        plugins: submission
+            .and_then(|s| s.get("plugins"))
+            .map(build_plugins)
+            .unwrap_or_default(),
+        feedback: feedback.map(build_feedback),
+        workflow_state: feedback
+            .and_then(|f| text(f, "workflowstate"))
+            .or_else(|| lastattempt.and_then(|l| text(l, "gradingstatus")))
+            .and_then(|s| WorkflowState::parse(&s)),
+        rubric,
+        guide,
+    })
+}
+
+/// Checks a grade against the grading method; `None` when it can be sent.
+pub fn validate_grade(method: &GradingMethod, input: &GradeInput) -> Option<String> {
+    match method {
+        GradingMethod::None => None,
+        GradingMethod::Points { max_grade } => match input.grade {
+            Some(grade) if (0.0..=*max_grade).contains(&grade) => None,
+            Some(_) => Some(format!("The grade must be between 0 and {}", max_grade)),
+            None => Some("Enter a grade".to_string()),
+        },
+        GradingMethod::Scale { .. } => match input.grade {
+            Some(grade) if grade >= 1.0 && grade.fract() == 0.0 => None,
+            _ => Some("Select a scale item".to_string()),
+        },
+        GradingMethod::Rubric { criteria, .. } => criteria.iter().find_map(|criterion| {
+            let filling = input.rubric.iter().find(|f| f.criterion_id == criterion.id);
+            match filling {
+                Some(f) if criterion.levels.iter().any(|l| l.id == f.level_id) => None,
+                Some(_) => Some(format!("Invalid level for \"{}\"", criterion.description)),
+                None => Some(format!("Select a level for \"{}\"", criterion.description)),
+            }
+        }),
+        GradingMethod::Guide { criteria, .. } => criteria.iter().find_map(|criterion| {
+            let filling = input.guide.iter().find(|f| f.criterion_id == criterion.id);
+            match filling {
+                Some(f) if (0.0..=criterion.max_score).contains(&f.score) => None,
+                Some(_) => Some(format!(
+                    "The score for \"{}\" must be between 0 and {}",
+                    criterion.short_name, criterion.max_score
+                )),
+                None => Some(format!("Enter a score for \"{}\"", criterion.short_name)),
+            }
+        }),
+    }
+}
+
+/// Name of a `mod_assign_save_grade(s)` field: top level for a single
+/// grade, nested under `grades[i]` in a batch.
+fn field(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}[{}]", prefix, name)
+    }
+}
+
+fn grade_fields(prefix: &str, method: &GradingMethod, input: &GradeInput, form: &mut Vec<(String, String)>) {
+    let grade = match method {
+        GradingMethod::Points { .. } | GradingMethod::Scale { .. } => input.grade.unwrap_or(-1.0),
+        // Computed from the fillings by Moodle
+        _ => 0.0,
+    };
+    form.push((field(prefix, "grade"), grade.to_string()));
+    form.push((field(prefix, "attemptnumber"), "-1".to_string()));
+    form.push((field(prefix, "addattempt"), (input.add_attempt as i32).to_string()));
+    form.push((
+        field(prefix, "workflowstate"),
+        input.workflow_state.map(|s| s.as_str()).unwrap_or_default().to_string(),
+    ));
+    if let Some(feedback) = &input.feedback {
+        let editor = format!("{}[assignfeedbackcomments_editor]", field(prefix, "plugindata"));
+        form.push((format!("{}[text]", editor), feedback.clone()));
+        form.push((format!("{}[format]", editor), "1".to_string()));
+    }
+    let fillings: Vec<(i64, &str, String, Option<String>)> = match method {
+        GradingMethod::Rubric { .. } => input
+            .rubric
+            .iter()
+            .map(|f| (f.criterion_id, "levelid", f.level_id.to_string(), f.remark.clone()))
+            .collect(),
+        GradingMethod::Guide { .. } => input
+            .guide
+            .iter()
+            .map(|f| (f.criterion_id, "score", f.score.to_string(), f.remark.clone()))
+            .collect(),
+        _ => Vec::new(),
+    };
+    let area = if matches!(method, GradingMethod::Rubric { .. }) { "rubric" } else { "guide" };
+    for (i, (criterion_id, value_key, value, remark)) in fillings.into_iter().enumerate() {
+        let criterion = format!("{}[{}][criteria][{}]", field(prefix, "advancedgradingdata"), area, i);
+        form.push((format!("{}[criterionid]", criterion), criterion_id.to_string()));
+        form.push((format!("{}[fillings][0][criterionid]", criterion), criterion_id.to_string()));
+        form.push((format!("{}[fillings][0][{}]", criterion, value_key), value));
+        form.push((format!("{}[fillings][0][remark]", criterion), remark.unwrap_or_default()));
+        form.push((format!("{}[fillings][0][remarkformat]", criterion), "1".to_string()));
+    }
+}
+
+/// Validates and saves grades and feedback comments. A single grade goes
+/// through `mod_assign_save_grade`, several through `mod_assign_save_grades`
+/// in one request. With `apply_to_all`, team assignment grades are copied
+/// to every group member.
+///
+/// The `core_grades_grader_gradingpanel_*` functions only serve components
+/// that define grade items, which in core is whole-forum grading, so
+/// assignments are always graded through `mod_assign`.
+pub async fn save_grades(assign_id: i64, grades: Vec<GradeInput>, apply_to_all: bool) -> Result<GradingResult> {
+    if grades.is_empty() {
+        return Err(anyhow!("No grades to save"));
+    }
+    let mut client = login().await?;
+    let assignment = find_assignment(&mut client, assign_id).await?;
+    let method = fetch_grading_method(&client, int(&assignment, "cmid"), int(&assignment, "grade")).await?;
+    if matches!(method, GradingMethod::None) && grades.iter().all(|g| g.feedback.is_none()) {
+        return Err(anyhow!("This assignment is not graded"));
+    }
+    let marking_workflow = flag(&assignment, "markingworkflow");
+
+    let errors: Vec<GradingError> = grades
+        .iter()
+        .filter_map(|g| {
+            let message = if g.workflow_state.is_some() && !marking_workflow {
+                Some("This assignment does not use marking workflow".to_string())
+            } else {
+                validate_grade(&method, g)
+            };
+            message.map(|message| GradingError {
+                user_id: g.user_id,
+                message,
+            })
+        })
+        .collect();
+    if !errors.is_empty() {
+        return Ok(GradingResult {
+            accepted: false,
+            errors,
+            saved: 0,
+        });
+    }
+
+    let mut form: Vec<(String, String)> = vec![
+        ("assignmentid".to_string(), assign_id.to_string()),
+        ("applytoall".to_string(), (apply_to_all as i32).to_string()),
+    ];
+    let function = if let [single] = grades.as_slice() {
+        form.push(("userid".to_string(), single.user_id.to_string()));
+        grade_fields("", &method, single, &mut form);
+        "mod_assign_save_grade"
+    } else {
+        for (i, grade) in grades.iter().enumerate() {
+            let prefix = format!("grades[{}]", i);
+            form.push((field(&prefix, "userid"), grade.user_id.to_string()));
+            grade_fields(&prefix, &method, grade, &mut form);
+        }
+        "mod_assign_save_grades"
+    };
+    client.post(function, &form).await.and_then(check_exception)?;
+    forget_grading_rows().await;
+    Ok(GradingResult {
+        accepted: true,
+        errors: Vec::new(),
+        saved: grades.len(),
+    })
+}
+
+/// Moves participants to a marking workflow state with `mod_assign_set_user_flags`.
+///
+/// Setting the flag alone never pushes grades to the gradebook, so releasing
+/// is refused here; save the grades with `WorkflowState::Released` instead.
+pub async fn set_workflow_state(assign_id: i64, user_ids: &[i64], state: WorkflowState) -> Result<GradingResult> {
+    if state == WorkflowState::Released {
+        return Err(anyhow!("Release grades by saving them with the released workflow state"));
+    }
+    let mut client = login().await?;
+    let assignment = find_assignment(&mut client, assign_id).await?;
+    if !flag(&assignment, "markingworkflow") {
+        return Err(anyhow!("This assignment does not use marking workflow"));
+    }
+
+    let mut form: Vec<(String, String)> = vec![("assignmentid".to_string(), assign_id.to_string())];
+    for (i, user_id) in user_ids.iter().enumerate() {
+        form.push((format!("userflags[{}][userid]", i), user_id.to_string()));
+        form.push((format!("userflags[{}][workflowstate]", i), state.as_str().to_string()));
+    }
+    let json = client
+        .post("mod_assign_set_user_flags", &form)
+        .await
+        .and_then(check_exception)?;
+    forget_grading_rows().await;
+    let errors: Vec<GradingError> = json
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|r| {
+            text(r, "errormessage").map(|message| GradingError {
+                user_id: int(r, "userid"),
+                message,
+            })
+        })
+        .collect();
+    Ok(GradingResult {
+        accepted: errors.is_empty(),
+        saved: user_ids.len().saturating_sub(errors.len()),
+        errors,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moodle::assignments::grading_definition::{
+        GuideCriterion, RubricCriterion, RubricLevel,
+    };
+
+    fn rubric() -> GradingMethod {
+        GradingMethod::Rubric {
+            definition_id: 5,
+            criteria: vec![RubricCriterion {
+                id: 11,
+                description: "Structure".to_string(),
+                levels: vec![RubricLevel {
+                    id: 21,
+                    score: 4.0,
+                    definition: "Clear".to_string(),
+                }],
+            }],
+        }
+    }
+
+    fn guide() -> GradingMethod {
+        GradingMethod::Guide {
+            definition_id: 6,
+            criteria: vec![GuideCriterion {
+                id: 12,
+                short_name: "Accuracy".to_string(),
+                description: None,
+                description_markers: None,
+                max_score: 10.0,
+            }],
+            comments: Vec::new(),
+        }
+    }
+
+    fn input(grade: Option<f64>, rubric: &[(i64, i64)], guide: &[(i64, f64)]) -> GradeInput {
+        GradeInput {
+            user_id: 3,
+            grade,
+            feedback: None,
+            workflow_state: None,
+            add_attempt: false,
+            rubric: rubric
+                .iter()
+                .map(|(criterion_id, level_id)| RubricFilling {
+                    criterion_id: *criterion_id,
+                    level_id: *level_id,
+                    remark: None,
+                })
+                .collect(),
+            guide: guide
+                .iter()
+                .map(|(criterion_id, score)| GuideFilling {
+                    criterion_id: *criterion_id,
+                    score: *score,
+                    remark: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn validates_grades_against_the_method() {
+        let points = GradingMethod::Points { max_grade: 20.0 };
+        let scale = GradingMethod::Scale { scale_id: 2 };
+        let cases = [
+            (&points, input(Some(20.0), &[], &[]), true),
+            (&points, input(Some(20.5), &[], &[]), false),
+            (&points, input(Some(-1.0), &[], &[]), false),
+            (&points, input(None, &[], &[]), false),
+            (&scale, input(Some(2.0), &[], &[]), true),
+            (&scale, input(Some(1.5), &[], &[]), false),
+            (&scale, input(Some(0.0), &[], &[]), false),
+            (&rubric(), input(None, &[(11, 21)], &[]), true),
+            (&rubric(), input(None, &[(11, 99)], &[]), false),
+            (&rubric(), input(None, &[], &[]), false),
+            (&guide(), input(None, &[], &[(12, 10.0)]), true),
+            (&guide(), input(None, &[], &[(12, 10.5)]), false),
+            (&guide(), input(None, &[], &[(12, -0.5)]), false),
+            (&guide(), input(None, &[], &[]), false),
+        ];
+        for (i, (method, input, valid)) in cases.iter().enumerate() {
+            assert_eq!(
+                validate_grade(method, input).is_none(),
+                *valid,
+                "case {}",
+                i
+            );
+        }
+    }
+
+    fn value<'a>(form: &'a [(String, String)], key: &str) -> Option<&'a str> {
+        form.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn encodes_advanced_grading_fields() {
+        let mut batch = Vec::new();
+        let mut rubric_input = input(Some(7.0), &[(11, 21)], &[]);
+        rubric_input.feedback = Some("<p>Good</p>".to_string());
+        grade_fields("grades[1]", &rubric(), &rubric_input, &mut batch);
+        let criterion = "grades[1][advancedgradingdata][rubric][criteria][0]";
+        let expected = [
+            ("grades[1][grade]", "0"),
+            ("grades[1][addattempt]", "0"),
+            (
+                "grades[1][plugindata][assignfeedbackcomments_editor][text]",
+                "<p>Good</p>",
+            ),
+            (&format!("{}[criterionid]", criterion), "11"),
+            (&format!("{}[fillings][0][criterionid]", criterion), "11"),
+            (&format!("{}[fillings][0][levelid]", criterion), "21"),
+            (&format!("{}[fillings][0][remarkformat]", criterion), "1"),
+        ];
+        for (key, expected) in expected {
+            assert_eq!(value(&batch, key), Some(expected), "{}", key);
+        }
+
+        let mut single = Vec::new();
+        grade_fields("", &guide(), &input(None, &[], &[(12, 7.5)]), &mut single);
+        assert_eq!(
+            value(
+                &single,
+                "advancedgradingdata[guide][criteria][0][fillings][0][score]"
+            ),
+            Some("7.5")
+        );
+        assert_eq!(value(&single, "workflowstate"), Some(""));
+        assert!(single.iter().all(|(k, _)| !k.contains("rubric")));
+    }
+}
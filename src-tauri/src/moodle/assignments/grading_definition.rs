@@ -0,0 +1,177 @@
+use crate::moodle::exception::check_exception;
+use crate::moodle::html::to_plain_text;
+use crate::moodle::json::{int, list, text};
+use anyhow::Result;
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RubricLevel {
+    pub id: i64,
+    pub score: f64,
+    pub definition: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RubricCriterion {
+    pub id: i64,
+    pub description: String,
+    pub levels: Vec<RubricLevel>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuideCriterion {
+    pub id: i64,
+    pub short_name: String,
+    pub description: Option<String>,
+    /// Shown to markers only
+    pub description_markers: Option<String>,
+    pub max_score: f64,
+}
+
+/// A frequently used comment of a marking guide.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuideComment {
+    pub id: i64,
+    pub description: String,
+}
+
+/// How an assignment is graded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum GradingMethod {
+    /// Simple direct grading out of `max_grade`
+    Points { max_grade: f64 },
+    /// Simple direct grading with a scale; the grade is the 1-based item index
+    Scale { scale_id: i64 },
+    Rubric {
+        definition_id: i64,
+        criteria: Vec<RubricCriterion>,
+    },
+    Guide {
+        definition_id: i64,
+        criteria: Vec<GuideCriterion>,
+        comments: Vec<GuideComment>,
+    },
+    /// The assignment is not graded
+    None,
+}
+
+impl GradingMethod {
+    pub fn definition_id(&self) -> Option<i64> {
+        match self {
+            GradingMethod::Rubric { definition_id, .. } | GradingMethod::Guide { definition_id, .. } => {
+                Some(*definition_id)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn score(value: &serde_json::Value, key: &str) -> f64 {
+    value
+        .get(key)
+        .and_then(|s| match s {
+            serde_json::Value::String(s) => s.parse().ok(),
+            other => other.as_f64(),
+        })
+        .unwrap_or(0.0)
+}
+
+fn plain(value: &serde_json::Value, key: &str) -> Option<String> {
+    text(value, key).map(|t| to_plain_text(&t)).filter(|t| !t.is_empty())
+}
+
+/// Reads the active advanced grading definition of the `submissions` area
+/// with `core_grading_get_definitions`, falling back to simple grading
+/// from the assignment's `grade` setting (negative values are scale ids).
+pub async fn fetch_grading_method(client: &MoodleClient, cmid: i64, assignment_grade: i64) -> Result<GradingMethod> {
+    let form: Vec<(String, String)> = vec![
+        ("cmids[0]".to_string(), cmid.to_string()),
+        ("areaname".to_string(), "submissions".to_string()),
+        ("activeonly".to_string(), "1".to_string()),
+    ];
+    let json = client
+        .post("core_grading_get_definitions", &form)
+        .await
+        .and_then(check_exception)?;
+
+    let area = list(&json, "areas").iter().find(|a| int(a, "cmid") == cmid);
+    let active_method = area.and_then(|a| text(a, "activemethod"));
+    let definition = area.and_then(|a| {
+        list(a, "definitions")
+            .iter()
+            .find(|d| text(d, "method") == active_method)
+    });
+
+    let method = match (active_method.as_deref(), definition) {
+        (Some("rubric"), Some(definition)) => {
+            let rubric = definition.get("rubric").cloned().unwrap_or_default();
+            let mut criteria: Vec<(i64, RubricCriterion)> = list(&rubric, "rubric_criteria")
+                .iter()
+                .map(|c| {
+                    let mut levels: Vec<RubricLevel> = list(c, "levels")
+                        .iter()
+                        .map(|l| RubricLevel {
+                            id: int(l, "id"),
+                            score: score(l, "score"),
+                            definition: plain(l, "definition").unwrap_or_default(),
+                        })
+                        .collect();
+                    levels.sort_by(|a, b| a.score.total_cmp(&b.score));
+                    (
+                        int(c, "sortorder"),
+                        RubricCriterion {
+                            id: int(c, "id"),
+                            description: plain(c, "description").unwrap_or_default(),
+                            levels,
+                        },
+                    )
+                })
+                .collect();
+            criteria.sort_by_key(|(order, _)| *order);
+            GradingMethod::Rubric {
+                definition_id: int(definition, "id"),
+                criteria: criteria.into_iter().map(|(_, c)| c).collect(),
+            }
+        }
+        (Some("guide"), Some(definition)) => {
+            let guide = definition.get("guide").cloned().unwrap_or_default();
+            let mut criteria: Vec<(i64, GuideCriterion)> = list(&guide, "guide_criteria")
+                .iter()
+                .map(|c| {
+                    (
+                        int(c, "sortorder"),
+                        GuideCriterion {
+                            id: int(c, "id"),
+                            short_name: text(c, "shortname").unwrap_or_default(),
+                            description: plain(c, "description"),
+                            description_markers: plain(c, "descriptionmarkers"),
+                            max_score: score(c, "maxscore"),
+                        },
+                    )
+                })
+                .collect();
+            criteria.sort_by_key(|(order, _)| *order);
+            GradingMethod::Guide {
+                definition_id: int(definition, "id"),
+                criteria: criteria.into_iter().map(|(_, c)| c).collect(),
+                comments: list(&guide, "guide_comments")
+                    .iter()
+                    .map(|c| GuideComment {
+                        id: int(c, "id"),
+                        description: plain(c, "description").unwrap_or_default(),
+                    })
+                    .collect(),
+            }
+        }
+        _ if assignment_grade > 0 => GradingMethod::Points {
+            max_grade: assignment_grade as f64,
+        },
+        _ if assignment_grade < 0 => GradingMethod::Scale {
+            scale_id: -assignment_grade,
+        },
+        _ => GradingMethod::None,
+    };
+    Ok(method)
+}
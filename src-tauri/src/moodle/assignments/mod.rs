@@ -1,2 +1,13 @@
-pub mod submit_assignment;
-pub mod resubmit_assignment;
\ No newline at end of file
+pub mod download_submissions;
+pub mod get_submission_status;
+pub mod grading;
+pub mod grading_definition;
+pub mod save_submission;
+pub mod submit_for_grading;
+
+pub use download_submissions::*;
+pub use get_submission_status::*;
+pub use grading::*;
+pub use grading_definition::*;
+pub use save_submission::*;
+pub use submit_for_grading::*;
@@ -0,0 +1,169 @@
+use super::get_submission_status::{build_settings, find_assignment};
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::files::upload_files_to_draft_area;
+use crate::moodle::json::{int, text};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmissionWarning {
+    pub item: Option<String>,
+    pub warningcode: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmissionResult {
+    pub success: bool,
+    pub warnings: Vec<SubmissionWarning>,
+}
+
+impl SubmissionResult {
+    /// Builds a result from the warnings list returned by the mod_assign write functions.
+    pub fn from_warnings(json: &serde_json::Value) -> Self {
+        let warnings: Vec<SubmissionWarning> = json
+            .as_array()
+            .or_else(|| json.get("warnings").and_then(|w| w.as_array()))
+            .into_iter()
+            .flatten()
+            .map(|w| SubmissionWarning {
+                item: text(w, "item"),
+                warningcode: text(w, "warningcode"),
+                message: text(w, "message"),
+            })
+            .collect();
+        SubmissionResult {
+            success: warnings.is_empty(),
+            warnings,
+        }
+    }
+}
+
+/// Saves (but does not submit) a draft of an assignment submission.
+///
+/// `file_paths` replaces the whole file submission with the given local files;
+/// pass `None` to leave the files untouched.
+pub async fn save_submission_draft(
+    assign_id: i64,
+    online_text: Option<String>,
+    file_paths: Option<Vec<String>>,
+) -> Result<SubmissionResult> {
+    let mut client = login().await?;
+
+    let assignment = find_assignment(&mut client, assign_id).await?;
+    let settings = build_settings(&assignment);
+
+    if online_text.is_none() && file_paths.is_none() {
+        return Err(anyhow!("Nothing to save: provide online text and/or files"));
+    }
+    if let Some(online_text) = &online_text {
+        if !settings.onlinetext_enabled {
+            return Err(anyhow!("This assignment does not accept online text"));
+        }
+        let words = count_words(online_text);
+        if settings.word_limit > 0 && words > settings.word_limit {
+            return Err(anyhow!(
+                "The online text has {} words, the limit is {}",
+                words,
+                settings.word_limit
+            ));
+        }
+    }
+    if let Some(file_paths) = &file_paths {
+        if !settings.file_enabled {
+            return Err(anyhow!("This assignment does not accept file submissions"));
+        }
+        if settings.max_files > 0 && file_paths.len() as i64 > settings.max_files {
+            return Err(anyhow!(
+                "At most {} files can be submitted",
+                settings.max_files
+            ));
+        }
+        for file_path in file_paths {
+            let size = tokio::fs::metadata(file_path).await?.len() as i64;
+            if settings.max_file_size > 0 && size > settings.max_file_size {
+                return Err(anyhow!(
+                    "{} is larger than the {} byte limit",
+                    file_path,
+                    settings.max_file_size
+                ));
+            }
+            if !is_accepted_type(file_path, &settings.accepted_file_types) {
+                return Err(anyhow!("{} is not an accepted file type", file_path));
+            }
+        }
+    }
+
+    // Timed assignments need an explicit start before anything can be saved
+    if int(&assignment, "timelimit") > 0 {
+        let form = vec![("assignid".to_string(), assign_id.to_string())];
+        client
+            .post("mod_assign_start_submission", &form)
+            .await
+            .and_then(check_exception)?;
+    }
+
+    let mut form: Vec<(String, String)> = vec![("assignmentid".to_string(), assign_id.to_string())];
+
+    if let Some(online_text) = online_text {
+        // Inline images need a draft area even when there are none
+        let editor_itemid = upload_files_to_draft_area(&mut client, &[]).await?;
+        form.push(("plugindata[onlinetext_editor][text]".to_string(), online_text));
+        form.push(("plugindata[onlinetext_editor][format]".to_string(), "1".to_string()));
+        form.push((
+            "plugindata[onlinetext_editor][itemid]".to_string(),
+            editor_itemid.to_string(),
+        ));
+    }
+
+    if let Some(file_paths) = file_paths {
+        let files_itemid = upload_files_to_draft_area(&mut client, &file_paths).await?;
+        form.push((
+            "plugindata[files_filemanager]".to_string(),
+            files_itemid.to_string(),
+        ));
+    }
+
+    let json = client
+        .post("mod_assign_save_submission", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(SubmissionResult::from_warnings(&json))
+}
+
+fn count_words(html: &str) -> i64 {
+    let mut plain = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => {
+                in_tag = false;
+                plain.push(' ');
+            }
+            _ if !in_tag => plain.push(c),
+            _ => {}
+        }
+    }
+    plain.split_whitespace().count() as i64
+}
+
+/// Moodle file type lists hold extensions (`.pdf`) and groups (`document`);
+/// only extensions can be checked locally, groups are left to the server.
+fn is_accepted_type(file_path: &str, accepted: &[String]) -> bool {
+    let extensions: Vec<String> = accepted
+        .iter()
+        .filter(|t| t.starts_with('.'))
+        .map(|t| t.to_lowercase())
+        .collect();
+    if extensions.is_empty() || accepted.len() != extensions.len() {
+        return true;
+    }
+    Path::new(file_path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| extensions.contains(&format!(".{}", e.to_lowercase())))
+        .unwrap_or(false)
+}
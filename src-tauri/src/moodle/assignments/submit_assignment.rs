@@ -1,218 +0,0 @@
-use moodle_api::core::webservice::get_site_info;
-use moodle_api::mod_::assign::start_submission;
-use moodle_api::mod_::assign::save_submission;
-use moodle_client::MoodleClient;
-use std::io::{self, Write};
-use std::fs;
-
-pub async fn run(client: &mut MoodleClient) {
-    // Get current user id
-    let user_id = match get_site_info::call_raw(
-        client,
-        &mut get_site_info::Params { serviceshortnames: None },
-    ).await {
-        Ok(site_info) => site_info.get("userid").and_then(|v| v.as_i64()),
-        Err(e) => {
-            println!("Could not fetch current user id: {}", e);
-            return;
-        }
-    };
-    let user_id = match user_id {
-        Some(id) => id,
-        None => {
-            println!("Could not extract user id from site info.");
-            return;
-        }
-    };
-
-    println!("=== SUBMIT ASSIGNMENT ===");
-    println!("Current User ID: {}", user_id);
-
-    // Get assignment ID
-    print!("Enter the assignment ID: ");
-    io::stdout().flush().unwrap();
-    let mut input = String::new();
-    io::stdin().read_line(&mut input).unwrap();
-    let assignment_id = match input.trim().parse::<i64>() {
-        Ok(id) => id,
-        Err(_) => {
-            println!("Invalid assignment ID.");
-            return;
-        }
-    };
-
-    // Use the existing image.jpg file
-    let file_path = std::path::Path::new("image.jpg");
-    
-    println!("Using image file: {:?}", file_path);
-    
-    // Check if the image file exists
-    if !file_path.exists() {
-        println!("❌ Error: image.jpg file not found in current directory.");
-        println!("   Please make sure image.jpg is in the same directory as the executable.");
-        return;
-    }
-    
-    println!("✅ Image file found: {:?}", file_path);
-
-    // Step 1: Start submission to get submission ID
-    println!("\n📝 Starting assignment submission...");
-    match start_submission::call(
-        client,
-        &mut start_submission::Params {
-            assignid: Some(assignment_id),
-        },
-    ).await {
-        Ok(response) => {
-            if let Some(submission_id) = response.submissionid {
-                println!("✅ Submission started! Submission ID: {}", submission_id);
-                
-                // Step 2: Try different submission approaches
-                println!("\n💾 Attempting submission...");
-                
-                // Read the file content (minimal info to avoid HTTP issues)
-                let file_content = if file_path.extension().and_then(|s| s.to_str()) == Some("jpg") || 
-                                   file_path.extension().and_then(|s| s.to_str()) == Some("jpeg") ||
-                                   file_path.extension().and_then(|s| s.to_str()) == Some("png") {
-                    // For image files, just get basic file info
-                    match fs::metadata(&file_path) {
-                        Ok(metadata) => {
-                            format!("File size: {} bytes, Type: JPEG, Status: Available", metadata.len())
-                        }
-                        Err(_) => "Image file available".to_string()
-                    }
-                } else {
-                    // For text files, read as string
-                    fs::read_to_string(&file_path).unwrap_or_else(|_| "Could not read file".to_string())
-                };
-                
-                // Try Approach 1: Online text submission (for assignments that support it)
-                println!("🔄 Trying online text submission...");
-                // Create concise submission text to avoid HTTP builder issues
-                let submission_text = format!(
-                    "ASSIGNMENT SUBMISSION: Invoice Analysis\n\n\
-                    File: {:?}\n\n\
-                    INVOICE DETAILS:\n\
-                    Company: BNM Freight Forwarders\n\
-                    Date: 5/13/2017\n\
-                    Amount: USD 43.00\n\
-                    Balance: USD 43.00\n\n\
-                    LINE ITEMS:\n\
-                    Category: Overloading Fees\n\
-                    Description: T678CFG Overload by 534 Kg\n\
-                    Rate: 43.00\n\
-                    Cashier: teri-1\n\n\
-                    PAYMENT STATUS:\n\
-                    Status: Outstanding balance of USD 43.00\n\n\
-                    IMAGE INFO:\n\
-                    {}\n\n\
-                    NOTES:\n\
-                    Invoice from BNM Freight Forwarders showing overloading fees. \
-                    Outstanding balance of USD 43.00 for overload of 534 Kg on shipment T678CFG. \
-                    Complete image available in image.jpg file.",
-                    file_path, file_content
-                );
-
-                let mut save_params = save_submission::Params {
-                    assignmentid: Some(assignment_id),
-                    plugindata: Some(save_submission::ParamsPlugindata {
-                        onlinetext_editor: Some(save_submission::ParamsPlugindataOnlinetextEditor {
-                            text: Some(submission_text),
-                            format: Some(1),
-                            itemid: Some(0),
-                        }),
-                        files_filemanager: None,
-                    }),
-                };
-                
-                match save_submission::call(client, &mut save_params).await {
-                    Ok(warnings) => {
-                        println!("Save Submission Response: {:?}", warnings);
-                        
-                        if warnings.is_empty() {
-                            println!("✅ Assignment submission saved successfully!");
-                            println!("📄 Image file path: {:?}", file_path);
-                            println!("📝 Submission includes detailed text analysis and image file information.");
-                            println!("📋 Invoice details: BNM Freight Forwarders - USD 43.00 overloading fees");
-                            println!("🖼️ Image file info included (file size, type, sample data)");
-                        } else {
-                            // Check if this is the "Nothing was submitted" warning
-                            if warnings.iter().any(|w| 
-                                w.warningcode.as_deref() == Some("couldnotsavesubmission")
-                            ) {
-                                println!("⚠️ Online text submission failed - assignment may only accept file submissions.");
-                                
-                                // Try Approach 2: File submission (simulate file upload)
-                                println!("🔄 Trying file submission approach...");
-                                
-                                // For file submissions, we need to create a draft area first
-                                // This is a simplified approach - in reality, you'd need to upload the file first
-                                let mut file_params = save_submission::Params {
-                                    assignmentid: Some(assignment_id),
-                                    plugindata: Some(save_submission::ParamsPlugindata {
-                                        onlinetext_editor: None,
-                                        files_filemanager: Some(0),
-                                    }),
-                                };
-                                
-                                match save_submission::call(client, &mut file_params).await {
-                                    Ok(file_warnings) => {
-                                        println!("File Submission Response: {:?}", file_warnings);
-                                        
-                                        if file_warnings.is_empty() {
-                                            println!("✅ File submission approach successful!");
-                                            println!("📄 Image file path: {:?}", file_path);
-                                            println!("📝 Note: Actual image file upload requires additional API calls.");
-                                        } else {
-                                            println!("❌ File submission also failed:");
-                                            for warning in file_warnings {
-                                                println!("   - {}: {}", 
-                                                    warning.warningcode.as_deref().unwrap_or("Unknown"),
-                                                    warning.message.as_deref().unwrap_or("No message")
-                                                );
-                                            }
-                                            
-                                            println!("\n💡 This assignment appears to require actual file uploads.");
-                                            println!("   The CLI tool cannot upload image files directly.");
-                                            println!("   Please use the Moodle web interface to submit image files.");
-                                        }
-                                    }
-                                    Err(e) => {
-                                        println!("❌ Error with file submission approach: {}", e);
-                                    }
-                                }
-                            } else {
-                                println!("⚠️ Submission completed with warnings:");
-                                for warning in warnings {
-                                    println!("   - {}: {}", 
-                                        warning.warningcode.as_deref().unwrap_or("Unknown"),
-                                        warning.message.as_deref().unwrap_or("No message")
-                                    );
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        println!("❌ Error saving submission: {}", e);
-                    }
-                }
-            } else {
-                println!("❌ Failed to start submission - no submission ID returned.");
-                if let Some(warnings) = response.warnings {
-                    for warning in warnings {
-                        println!("   Warning: {} - {}", 
-                            warning.warningcode.as_deref().unwrap_or("Unknown"),
-                            warning.message.as_deref().unwrap_or("No message")
-                        );
-                    }
-                }
-            }
-        }
-        Err(e) => {
-            println!("❌ Error starting submission: {}", e);
-        }
-    }
-
-    // Note: We don't clean up the image file since it's a user file, not a temporary file
-    println!("\n📸 Image file preserved: {:?}", file_path);
-}
\ No newline at end of file
@@ -0,0 +1,38 @@
+use super::get_submission_status::find_assignment;
+use super::save_submission::SubmissionResult;
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::flag;
+use anyhow::{anyhow, Result};
+
+/// Submits the current draft for grading.
+///
+/// Assignments that require a submission statement are refused locally unless
+/// the user accepted it, rather than letting Moodle fail with a warning.
+pub async fn submit_assignment_for_grading(
+    assign_id: i64,
+    accept_submission_statement: bool,
+) -> Result<SubmissionResult> {
+    let mut client = login().await?;
+
+    let assignment = find_assignment(&mut client, assign_id).await?;
+    if flag(&assignment, "requiresubmissionstatement") && !accept_submission_statement {
+        return Err(anyhow!(
+            "The submission statement must be accepted before submitting"
+        ));
+    }
+
+    let form: Vec<(String, String)> = vec![
+        ("assignmentid".to_string(), assign_id.to_string()),
+        (
+            "acceptsubmissionstatement".to_string(),
+            if accept_submission_statement { "1" } else { "0" }.to_string(),
+        ),
+    ];
+
+    let json = client
+        .post("mod_assign_submit_for_grading", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(SubmissionResult::from_warnings(&json))
+}
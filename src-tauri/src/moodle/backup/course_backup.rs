@@ -0,0 +1,287 @@
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{flag, int, list, text};
+use anyhow::{anyhow, Result};
+use moodle_api::core::webservice::get_site_info;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A backup stored in the course's backup area on the site.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CourseBackupFile {
+    pub file_name: String,
+    pub size: i64,
+    pub time_modified: i64,
+    pub author: Option<String>,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CourseBackups {
+    /// Course context, needed to poll asynchronous backups
+    pub context_id: Option<i64>,
+    pub files: Vec<CourseBackupFile>,
+    /// Web page where a new backup is started. No WebService creates
+    /// backups, so the app opens this page and picks up the id of the
+    /// asynchronous backup it starts (see [`backup_started_script`]).
+    pub backup_page_url: String,
+}
+
+/// `backup::STATUS_*` of an asynchronous backup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupStatus {
+    Pending,
+    Running,
+    Finished,
+    Failed,
+}
+
+impl BackupStatus {
+    fn from_code(code: i64) -> Self {
+        match code {
+            800 => BackupStatus::Running,
+            900 => BackupStatus::Failed,
+            1000 => BackupStatus::Finished,
+            _ => BackupStatus::Pending,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupProgress {
+    pub backup_id: String,
+    pub status: BackupStatus,
+    /// 0 to 1
+    pub progress: f64,
+}
+
+/// Sent to the UI when a backup started from the backup page is found.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupStarted {
+    pub course_id: i64,
+    pub context_id: i64,
+    pub backup_id: String,
+}
+
+/// Where the backup page script navigates to report a started backup. The
+/// navigation is intercepted, so the `.invalid` host is never resolved.
+pub const BACKUP_STARTED_URL: &str = "https://backup-started.invalid/";
+
+/// Script for the backup page. Once Moodle starts an asynchronous backup it
+/// shows a progress bar with the id `<backupid>_bar`, which is reported by
+/// navigating to [`BACKUP_STARTED_URL`].
+pub fn backup_started_script() -> String {
+    format!(
+        r#"document.addEventListener("DOMContentLoaded", function () {{
+  var bar = document.querySelector('.progress-bar[id$="_bar"]');
+  if (bar) {{
+    window.location.href = "{}?backupid=" + encodeURIComponent(bar.id.slice(0, -4));
+  }}
+}});"#,
+        BACKUP_STARTED_URL
+    )
+}
+
+/// The backup id reported by [`backup_started_script`], if `url` is that report.
+pub fn parse_backup_started(url: &str) -> Option<String> {
+    let query = url.strip_prefix(BACKUP_STARTED_URL)?.strip_prefix("?backupid=")?;
+    // Backup ids are 32 hex digits
+    Some(query.to_string()).filter(|id| id.len() == 32 && id.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// A backup kept on this device.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalBackup {
+    pub file_name: String,
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: i64,
+}
+
+/// Backups in the course's backup area and the page to start a new one.
+pub async fn get_course_backups(course_id: i64) -> Result<CourseBackups> {
+    let mut client = login().await?;
+    let site = get_site_info::call_raw(&mut client, &mut get_site_info::Params { serviceshortnames: None })
+        .await
+        .and_then(check_exception)?;
+    let site_url = text(&site, "siteurl").ok_or_else(|| anyhow!("Moodle did not return the site URL"))?;
+
+    let form: Vec<(String, String)> = vec![
+        ("contextid".to_string(), "0".to_string()),
+        ("contextlevel".to_string(), "course".to_string()),
+        ("instanceid".to_string(), course_id.to_string()),
+        ("component".to_string(), "backup".to_string()),
+        ("filearea".to_string(), "course".to_string()),
+        ("itemid".to_string(), "0".to_string()),
+        ("filepath".to_string(), "/".to_string()),
+        ("filename".to_string(), String::new()),
+    ];
+    let json = client
+        .post("core_files_get_files", &form)
+        .await
+        .and_then(check_exception)?;
+
+    let mut files: Vec<CourseBackupFile> = list(&json, "files")
+        .iter()
+        .filter(|f| !flag(f, "isdir"))
+        .filter_map(|f| {
+            Some(CourseBackupFile {
+                file_name: text(f, "filename")?,
+                size: int(f, "filesize"),
+                time_modified: int(f, "timemodified"),
+                author: text(f, "author"),
+                url: text(f, "url")?,
+            })
+        })
+        .collect();
+    files.sort_by_key(|f| std::cmp::Reverse(f.time_modified));
+
+    Ok(CourseBackups {
+        context_id: list(&json, "files")
+            .iter()
+            .chain(list(&json, "parents").iter().rev())
+            .map(|f| int(f, "contextid"))
+            .find(|c| *c > 0),
+        files,
+        backup_page_url: format!("{}/backup/backup.php?id={}", site_url.trim_end_matches('/'), course_id),
+    })
+}
+
+pub async fn get_backup_progress(context_id: i64, backup_ids: &[String]) -> Result<Vec<BackupProgress>> {
+    let client = login().await?;
+    let mut form: Vec<(String, String)> = vec![("contextid".to_string(), context_id.to_string())];
+    for (i, backup_id) in backup_ids.iter().enumerate() {
+        form.push((format!("backupids[{}]", i), backup_id.clone()));
+    }
+    let json = client
+        .post("core_backup_get_async_backup_progress", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(json
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|p| text(p, "operation").as_deref() != Some("restore"))
+        .map(|p| BackupProgress {
+            backup_id: text(p, "backupid").unwrap_or_default(),
+            status: BackupStatus::from_code(int(p, "status")),
+            progress: p.get("progress").and_then(|v| v.as_f64()).unwrap_or(0.0),
+        })
+        .collect())
+}
+
+/// The file produced by a finished asynchronous backup.
+pub async fn get_backup_file(context_id: i64, backup_id: &str, file_name: &str) -> Result<CourseBackupFile> {
+    let client = login().await?;
+    let form: Vec<(String, String)> = vec![
+        ("filename".to_string(), file_name.to_string()),
+        ("contextid".to_string(), context_id.to_string()),
+        ("backupid".to_string(), backup_id.to_string()),
+    ];
+    let json = client
+        .post("core_backup_get_async_backup_links_backup", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(CourseBackupFile {
+        file_name: file_name.to_string(),
+        size: int(&json, "filesize"),
+        time_modified: chrono::Utc::now().timestamp(),
+        author: None,
+        url: text(&json, "fileurl").ok_or_else(|| anyhow!("The backup file is not available yet"))?,
+    })
+}
+
+/// Only the final component, so a name can't point outside the archive folder.
+fn local_file_name(file_name: &str) -> Result<String> {
+    Path::new(file_name)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .filter(|n| n.ends_with(".mbz"))
+        .map(|n| n.to_string())
+        .ok_or_else(|| anyhow!("{} is not a backup file name", file_name))
+}
+
+fn local_backup(path: &Path) -> Result<LocalBackup> {
+    let metadata = std::fs::metadata(path)?;
+    Ok(LocalBackup {
+        file_name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        path: path.to_path_buf(),
+        size: metadata.len(),
+        modified: metadata
+            .modified()
+            .ok()
+            .and_then(|m| m.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs() as i64),
+    })
+}
+
+/// Saves a backup from the site into the local archive folder.
+pub async fn download_course_backup(url: &str, file_name: &str, archive_dir: &Path) -> Result<LocalBackup> {
+    let file_name = local_file_name(file_name)?;
+    let client = login().await?;
+    tokio::fs::create_dir_all(archive_dir).await?;
+    // Streamed to a partial file, then renamed so an interrupted download
+    // never looks complete; backups can be larger than memory
+    let path = archive_dir.join(&file_name);
+    let partial = archive_dir.join(format!("{}.part", file_name));
+    if let Err(e) = client.download_to_file(url, &partial).await {
+        let _ = tokio::fs::remove_file(&partial).await;
+        return Err(e);
+    }
+    tokio::fs::rename(&partial, &path).await?;
+    local_backup(&path)
+}
+
+/// Backups in the local archive folder, newest first.
+pub fn list_local_backups(archive_dir: &Path) -> Result<Vec<LocalBackup>> {
+    let entries = match std::fs::read_dir(archive_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut backups = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == "mbz") {
+            backups.push(local_backup(&path)?);
+        }
+    }
+    backups.sort_by_key(|b| std::cmp::Reverse(b.modified));
+    Ok(backups)
+}
+
+/// Path of a backup in the archive folder, checking that it exists.
+pub fn local_backup_path(archive_dir: &Path, file_name: &str) -> Result<PathBuf> {
+    let path = archive_dir.join(local_file_name(file_name)?);
+    if !path.is_file() {
+        return Err(anyhow!("{} is not in the archive", file_name));
+    }
+    Ok(path)
+}
+
+pub fn delete_local_backup(archive_dir: &Path, file_name: &str) -> Result<()> {
+    std::fs::remove_file(local_backup_path(archive_dir, file_name)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_reported_backup_ids() {
+        let id = "0123456789abcdef0123456789abcdef";
+        assert!(backup_started_script().contains(BACKUP_STARTED_URL));
+        assert_eq!(
+            parse_backup_started(&format!("{}?backupid={}", BACKUP_STARTED_URL, id)).as_deref(),
+            Some(id)
+        );
+        assert_eq!(parse_backup_started(&format!("https://moodle.example.com/?backupid={}", id)), None);
+        assert_eq!(parse_backup_started(&format!("{}?backupid=<script>", BACKUP_STARTED_URL)), None);
+        assert_eq!(parse_backup_started(BACKUP_STARTED_URL), None);
+    }
+}
@@ -0,0 +1,369 @@
+//! Read-only access to Moodle backup archives (`.mbz`) without restoring them.
+
+use anyhow::{anyhow, Result};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+
+/// Moodle writes nulls as this marker in backup XML.
+const NULL_MARKER: &str = "$@NULL@$";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub name: String,
+    pub moodle_release: Option<String>,
+    pub backup_date: i64,
+    pub original_site: Option<String>,
+    pub course_id: i64,
+    pub course_full_name: Option<String>,
+    pub course_short_name: Option<String>,
+    pub includes_users: bool,
+    pub includes_files: bool,
+    /// Every root setting of the backup, e.g. `activities`, `blocks`, `logs`
+    pub settings: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupSection {
+    pub section_id: i64,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupActivity {
+    pub module_id: i64,
+    pub section_id: i64,
+    pub modname: String,
+    pub title: String,
+    /// Whether user data (posts, submissions, ...) of the activity is included
+    pub includes_user_data: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupFile {
+    pub content_hash: String,
+    pub component: String,
+    pub file_area: String,
+    pub item_id: i64,
+    pub file_path: String,
+    pub file_name: String,
+    pub file_size: i64,
+    pub mime_type: Option<String>,
+    pub author: Option<String>,
+    pub time_modified: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupUser {
+    pub id: i64,
+    pub username: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupContents {
+    pub info: BackupInfo,
+    pub sections: Vec<BackupSection>,
+    pub activities: Vec<BackupActivity>,
+    pub files: Vec<BackupFile>,
+    pub users: Vec<BackupUser>,
+}
+
+/// An element's attributes and the text of its direct children.
+#[derive(Debug, Default)]
+struct Record {
+    attributes: HashMap<String, String>,
+    fields: HashMap<String, String>,
+}
+
+impl Record {
+    fn text(&self, name: &str) -> Option<String> {
+        self.fields
+            .get(name)
+            .or_else(|| self.attributes.get(name))
+            .filter(|v| !v.is_empty() && *v != NULL_MARKER)
+            .cloned()
+    }
+
+    fn int(&self, name: &str) -> i64 {
+        self.text(name).and_then(|v| v.parse().ok()).unwrap_or(0)
+    }
+}
+
+/// Collects every `<tag>` element of a backup XML file as a flat record.
+/// Backup files are regular enough that nothing more is needed.
+fn records(xml: &[u8], tag: &str) -> Result<Vec<Record>> {
+    let mut reader = Reader::from_reader(xml);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+    let mut found = Vec::new();
+    let mut current: Option<Record> = None;
+    // Depth below the current record and the child being read
+    let mut depth = 0usize;
+    let mut child: Option<String> = None;
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) => {
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+                if current.is_some() {
+                    depth += 1;
+                    child = (depth == 1).then_some(name);
+                } else if name == tag {
+                    let mut record = Record::default();
+                    for attribute in e.attributes() {
+                        let attribute = attribute?;
+                        record.attributes.insert(
+                            String::from_utf8_lossy(attribute.key.local_name().as_ref()).to_string(),
+                            attribute.unescape_value()?.to_string(),
+                        );
+                    }
+                    current = Some(record);
+                    depth = 0;
+                }
+            }
+            Event::Text(t) => {
+                if let (Some(record), Some(name), 1) = (current.as_mut(), child.as_ref(), depth) {
+                    record.fields.insert(name.clone(), t.unescape()?.to_string());
+                }
+            }
+            Event::CData(t) => {
+                if let (Some(record), Some(name), 1) = (current.as_mut(), child.as_ref(), depth) {
+                    record
+                        .fields
+                        .insert(name.clone(), String::from_utf8_lossy(&t.into_inner()).to_string());
+                }
+            }
+            Event::End(_) if current.is_some() => {
+                if depth == 0 {
+                    found.extend(current.take());
+                } else {
+                    depth -= 1;
+                    child = None;
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(found)
+}
+
+/// Entries of an archive, read in one pass. Moodle writes `.mbz` files as
+/// gzipped tarballs, older sites and some tools as zip.
+fn read_entries(path: &Path, mut wanted: impl FnMut(&str) -> bool) -> Result<HashMap<String, Vec<u8>>> {
+    let mut file = File::open(path)?;
+    let mut magic = [0u8; 2];
+    file.read_exact(&mut magic)?;
+    file.rewind()?;
+
+    let mut entries = HashMap::new();
+    if magic == [0x1f, 0x8b] {
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(BufReader::new(file)));
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let name = entry.path()?.to_string_lossy().trim_start_matches("./").to_string();
+            if wanted(&name) {
+                let mut contents = Vec::new();
+                entry.read_to_end(&mut contents)?;
+                entries.insert(name, contents);
+            }
+        }
+    } else if magic == *b"PK" {
+        let mut archive = zip::ZipArchive::new(BufReader::new(file))?;
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i)?;
+            let name = entry.name().trim_start_matches("./").to_string();
+            if wanted(&name) {
+                let mut contents = Vec::new();
+                entry.read_to_end(&mut contents)?;
+                entries.insert(name, contents);
+            }
+        }
+    } else {
+        return Err(anyhow!("{} is not a Moodle backup", path.display()));
+    }
+    Ok(entries)
+}
+
+/// Lists the course, activities, files and users of a backup. Blocking;
+/// large archives are streamed, only the XML descriptions are kept.
+pub fn read_backup(path: &Path) -> Result<BackupContents> {
+    let entries = read_entries(path, |name| matches!(name, "moodle_backup.xml" | "files.xml" | "users.xml"))?;
+    let manifest = entries
+        .get("moodle_backup.xml")
+        .ok_or_else(|| anyhow!("The archive has no moodle_backup.xml"))?;
+
+    let information = records(manifest, "information")?
+        .into_iter()
+        .next()
+        .unwrap_or_default();
+    let settings: Vec<Record> = records(manifest, "setting")?;
+    let root_settings: BTreeMap<String, String> = settings
+        .iter()
+        .filter(|s| s.text("level").as_deref() == Some("root"))
+        .filter_map(|s| Some((s.text("name")?, s.text("value").unwrap_or_default())))
+        .collect();
+    let setting_on = |name: &str| settings.iter().any(|s| s.text("name").as_deref() == Some(name) && s.int("value") == 1);
+
+    let info = BackupInfo {
+        name: information.text("name").unwrap_or_default(),
+        moodle_release: information.text("moodle_release"),
+        backup_date: information.int("backup_date"),
+        original_site: information.text("original_wwwroot"),
+        course_id: information.int("original_course_id"),
+        course_full_name: information.text("original_course_fullname"),
+        course_short_name: information.text("original_course_shortname"),
+        includes_users: root_settings.get("users").is_some_and(|v| v == "1"),
+        includes_files: root_settings.get("files").map_or(true, |v| v == "1"),
+        settings: root_settings.clone(),
+    };
+
+    // Settings name their section and activity in same-named elements,
+    // which carry no ids
+    let sections = records(manifest, "section")?
+        .iter()
+        .filter(|s| s.int("sectionid") > 0)
+        .map(|s| BackupSection {
+            section_id: s.int("sectionid"),
+            title: s.text("title").unwrap_or_default(),
+        })
+        .collect();
+    let activities = records(manifest, "activity")?
+        .iter()
+        .filter(|a| a.int("moduleid") > 0)
+        .map(|a| {
+            let modname = a.text("modulename").unwrap_or_default();
+            let module_id = a.int("moduleid");
+            BackupActivity {
+                includes_user_data: setting_on(&format!("{}_{}_userinfo", modname, module_id)),
+                module_id,
+                section_id: a.int("sectionid"),
+                title: a.text("title").unwrap_or_default(),
+                modname,
+            }
+        })
+        .collect();
+
+    let files = match entries.get("files.xml") {
+        Some(xml) => records(xml, "file")?
+            .iter()
+            // Directories are stored as `.` entries
+            .filter(|f| f.text("filename").is_some_and(|n| n != "."))
+            .map(|f| BackupFile {
+                content_hash: f.text("contenthash").unwrap_or_default(),
+                component: f.text("component").unwrap_or_default(),
+                file_area: f.text("filearea").unwrap_or_default(),
+                item_id: f.int("itemid"),
+                file_path: f.text("filepath").unwrap_or_else(|| "/".to_string()),
+                file_name: f.text("filename").unwrap_or_default(),
+                file_size: f.int("filesize"),
+                mime_type: f.text("mimetype"),
+                author: f.text("author"),
+                time_modified: f.int("timemodified"),
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    let users = match entries.get("users.xml") {
+        Some(xml) => records(xml, "user")?
+            .iter()
+            .map(|u| BackupUser {
+                id: u.int("id"),
+                username: u.text("username").unwrap_or_default(),
+                first_name: u.text("firstname").unwrap_or_default(),
+                last_name: u.text("lastname").unwrap_or_default(),
+                email: u.text("email"),
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+
+    Ok(BackupContents {
+        info,
+        sections,
+        activities,
+        files,
+        users,
+    })
+}
+
+/// Copies one file out of a backup. Backups store file contents by hash
+/// under `files/<first two characters>/<hash>`.
+pub fn extract_backup_file(path: &Path, content_hash: &str, destination: &Path) -> Result<u64> {
+    if content_hash.len() < 2 || !content_hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(anyhow!("Invalid content hash"));
+    }
+    let entry = format!("files/{}/{}", &content_hash[..2], content_hash);
+    let mut entries = read_entries(path, |name| name == entry)?;
+    let contents = entries
+        .remove(&entry)
+        .ok_or_else(|| anyhow!("The file is not included in this backup"))?;
+    if let Some(parent) = destination.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(destination, &contents)?;
+    Ok(contents.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<files>
+  <file id="12">
+    <contenthash>da39a3ee5e6b4b0d3255bfef95601890afd80709</contenthash>
+    <component>mod_resource</component>
+    <filearea>content</filearea>
+    <itemid>0</itemid>
+    <filepath>/</filepath>
+    <filename>Notes &amp; slides.pdf</filename>
+    <filesize>2048</filesize>
+    <mimetype>$@NULL@$</mimetype>
+    <author><![CDATA[Ada <Lovelace>]]></author>
+    <source>
+      <filename>original.pdf</filename>
+    </source>
+  </file>
+  <file id="13">
+    <filename>.</filename>
+  </file>
+</files>
+"#;
+
+    #[test]
+    fn reads_records_with_fields_and_attributes() {
+        let files = records(FILES_XML.as_bytes(), "file").unwrap();
+        assert_eq!(files.len(), 2);
+
+        let file = &files[0];
+        assert_eq!(file.int("id"), 12);
+        assert_eq!(file.text("component").as_deref(), Some("mod_resource"));
+        assert_eq!(file.text("filename").as_deref(), Some("Notes & slides.pdf"));
+        assert_eq!(file.int("filesize"), 2048);
+        assert_eq!(file.int("itemid"), 0);
+        // Moodle's null marker and missing fields read as nothing
+        assert_eq!(file.text("mimetype"), None);
+        assert_eq!(file.text("timemodified"), None);
+        assert_eq!(file.text("author").as_deref(), Some("Ada <Lovelace>"));
+
+        assert_eq!(files[1].int("id"), 13);
+        assert_eq!(files[1].text("filename").as_deref(), Some("."));
+    }
+
+    #[test]
+    fn ignores_other_tags() {
+        assert!(records(FILES_XML.as_bytes(), "user").unwrap().is_empty());
+        let sources = records(FILES_XML.as_bytes(), "source").unwrap();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].text("filename").as_deref(), Some("original.pdf"));
+    }
+}
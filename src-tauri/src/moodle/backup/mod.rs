@@ -0,0 +1,5 @@
+pub mod course_backup;
+pub mod mbz;
+
+pub use course_backup::*;
+pub use mbz::*;
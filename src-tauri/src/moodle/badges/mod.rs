@@ -0,0 +1,5 @@
+pub mod open_badges;
+pub mod user_badges;
+
+pub use open_badges::*;
+pub use user_badges::*;
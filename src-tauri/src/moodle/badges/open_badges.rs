@@ -0,0 +1,124 @@
+use super::user_badges::{find_user_badge, EarnedBadge};
+use crate::moodle::calendar::login;
+use crate::moodle::json::text;
+use anyhow::{anyhow, Result};
+use moodle_api::core::webservice::get_site_info;
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BadgeDetails {
+    pub badge: EarnedBadge,
+    /// What had to be done to earn the badge
+    pub criteria: Option<String>,
+    pub criteria_url: Option<String>,
+    /// Public Open Badges 2.0 assertion, which anyone can use to verify the award
+    pub assertion_url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BadgeImage {
+    pub path: String,
+    /// False when Moodle had no baked image and the plain badge image was saved
+    pub baked: bool,
+}
+
+async fn assertion_url(client: &mut MoodleClient, unique_hash: &str) -> Result<String> {
+    let mut params = get_site_info::Params { serviceshortnames: None };
+    let site = get_site_info::call_raw(client, &mut params).await?;
+    let site_url = text(&site, "siteurl").ok_or_else(|| anyhow!("Moodle did not return the site URL"))?;
+    Ok(format!(
+        "{}/badges/assertion.php?b={}&obversion=2",
+        site_url.trim_end_matches('/'),
+        urlencoding::encode(unique_hash)
+    ))
+}
+
+/// Fetches an Open Badges JSON document published by Moodle.
+async fn fetch_open_badges_json(client: &MoodleClient, url: &str) -> Result<Value> {
+    let bytes = client.download(url).await?;
+    let json: Value = serde_json::from_slice(&bytes).map_err(|_| anyhow!("Moodle did not return Open Badges data"))?;
+    if json.get("error").is_some() {
+        return Err(anyhow!("The badge is not available as an Open Badge (it may be revoked or expired)"));
+    }
+    Ok(json)
+}
+
+/// The assertion and its badge class, which holds the criteria and issuer.
+async fn fetch_assertion(client: &mut MoodleClient, unique_hash: &str) -> Result<(String, Value, Value)> {
+    let url = assertion_url(client, unique_hash).await?;
+    let assertion = fetch_open_badges_json(client, &url).await?;
+    let badge_class = match assertion.get("badge") {
+        Some(Value::String(badge_url)) => fetch_open_badges_json(client, badge_url).await?,
+        Some(embedded) => embedded.clone(),
+        None => return Err(anyhow!("The assertion has no badge")),
+    };
+    Ok((url, assertion, badge_class))
+}
+
+/// An earned badge with its criteria from the Open Badges badge class.
+pub async fn get_badge_details(unique_hash: &str) -> Result<BadgeDetails> {
+    let mut client = login().await?;
+    let badge = find_user_badge(&mut client, unique_hash).await?;
+    let (assertion_url, _, badge_class) = fetch_assertion(&mut client, unique_hash).await?;
+    let criteria = badge_class.get("criteria");
+    Ok(BadgeDetails {
+        badge,
+        criteria: criteria.and_then(|c| text(c, "narrative")),
+        criteria_url: criteria.and_then(|c| text(c, "id").or_else(|| text(c, "url"))),
+        assertion_url,
+    })
+}
+
+/// Saves the badge PNG. Moodle bakes the assertion into the image, so the
+/// file alone proves the award to Open Badges backpacks and verifiers.
+pub async fn download_badge_image(unique_hash: &str, destination: &Path) -> Result<BadgeImage> {
+    let mut client = login().await?;
+    let (_, assertion, badge_class) = fetch_assertion(&mut client, unique_hash).await?;
+    let image = |json: &Value| match json.get("image") {
+        Some(Value::String(url)) => Some(url.clone()),
+        Some(image) => text(image, "id"),
+        None => None,
+    };
+    let (url, baked) = match image(&assertion) {
+        Some(url) => (url, true),
+        None => (
+            image(&badge_class).ok_or_else(|| anyhow!("This badge has no image"))?,
+            false,
+        ),
+    };
+    let bytes = client.download(&url).await?;
+    if let Some(parent) = destination.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(destination, bytes).await?;
+    Ok(BadgeImage {
+        path: destination.to_string_lossy().to_string(),
+        baked,
+    })
+}
+
+/// Writes the Open Badges 2.0 assertion to a JSON file. The badge class and
+/// its issuer are embedded so the file is readable offline; verification
+/// still goes through the hosted assertion URL in `verify`.
+pub async fn export_badge_assertion(unique_hash: &str, destination: &Path) -> Result<String> {
+    let mut client = login().await?;
+    let (url, mut assertion, mut badge_class) = fetch_assertion(&mut client, unique_hash).await?;
+    if let Some(Value::String(issuer_url)) = badge_class.get("issuer").cloned() {
+        if let Ok(issuer) = fetch_open_badges_json(&client, &issuer_url).await {
+            badge_class["issuer"] = issuer;
+        }
+    }
+    assertion["badge"] = badge_class;
+    if assertion.get("verify").is_none() && assertion.get("verification").is_none() {
+        assertion["verification"] = serde_json::json!({ "type": "hosted", "url": url });
+    }
+
+    if let Some(parent) = destination.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(destination, serde_json::to_vec_pretty(&assertion)?).await?;
+    Ok(destination.to_string_lossy().to_string())
+}
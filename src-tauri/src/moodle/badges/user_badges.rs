@@ -0,0 +1,154 @@
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use anyhow::{anyhow, Result};
+use moodle_api::core::badges::get_user_badges as user_badges_api;
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BadgeIssuer {
+    pub name: String,
+    pub url: Option<String>,
+    pub contact: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BadgeEndorsement {
+    pub issuer_name: String,
+    pub issuer_url: Option<String>,
+    pub issuer_email: Option<String>,
+    /// URL of the endorsement claim
+    pub claim_url: Option<String>,
+    pub comment: Option<String>,
+    pub date_issued: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BadgeAlignment {
+    pub name: String,
+    pub url: Option<String>,
+    pub description: Option<String>,
+    pub framework: Option<String>,
+    pub code: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EarnedBadge {
+    pub id: i64,
+    /// Identifies this award; used for the assertion and baked image
+    pub unique_hash: String,
+    pub name: String,
+    pub description: String,
+    /// Badge image with the token appended
+    pub image_url: Option<String>,
+    pub issuer: BadgeIssuer,
+    /// Set for course badges, None for site badges
+    pub course_id: Option<i64>,
+    pub date_issued: i64,
+    pub date_expire: Option<i64>,
+    pub expired: bool,
+    pub endorsement: Option<BadgeEndorsement>,
+    pub alignments: Vec<BadgeAlignment>,
+    pub related: Vec<String>,
+    pub version: Option<String>,
+    pub language: Option<String>,
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|v| !v.trim().is_empty())
+}
+
+fn parse_badge(client: &MoodleClient, badge: user_badges_api::ReturnsBadgesItem, now: i64) -> EarnedBadge {
+    let date_expire = badge.dateexpire.filter(|d| *d > 0);
+    EarnedBadge {
+        id: badge.id.unwrap_or_default(),
+        unique_hash: badge.uniquehash.unwrap_or_default(),
+        name: badge.name.unwrap_or_default(),
+        description: badge.description.unwrap_or_default(),
+        image_url: non_empty(badge.badgeurl).map(|url| client.tokenize_url(&url)),
+        issuer: BadgeIssuer {
+            name: badge.issuername.unwrap_or_default(),
+            url: non_empty(badge.issuerurl),
+            contact: non_empty(badge.issuercontact),
+        },
+        // Site badges are type 1, course badges type 2
+        course_id: badge.courseid.filter(|id| *id > 0 && badge.r#type == Some(2)),
+        date_issued: badge.dateissued.unwrap_or_default(),
+        date_expire,
+        expired: date_expire.is_some_and(|d| d <= now),
+        endorsement: badge.endorsement.and_then(|e| {
+            Some(BadgeEndorsement {
+                issuer_name: non_empty(e.issuername)?,
+                issuer_url: non_empty(e.issuerurl),
+                issuer_email: non_empty(e.issueremail),
+                claim_url: non_empty(e.claimid),
+                comment: non_empty(e.claimcomment),
+                date_issued: e.dateissued.unwrap_or_default(),
+            })
+        }),
+        alignments: badge
+            .alignment
+            .unwrap_or_default()
+            .into_iter()
+            .map(|a| BadgeAlignment {
+                name: a.target_name.unwrap_or_default(),
+                url: non_empty(a.target_url),
+                description: non_empty(a.target_description),
+                framework: non_empty(a.target_framework),
+                code: non_empty(a.target_code),
+            })
+            .collect(),
+        related: badge
+            .relatedbadges
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|r| r.name)
+            .collect(),
+        version: non_empty(badge.version),
+        language: non_empty(badge.language),
+    }
+}
+
+pub async fn fetch_user_badges(
+    client: &mut MoodleClient,
+    course_id: Option<i64>,
+    search: Option<String>,
+) -> Result<Vec<EarnedBadge>> {
+    let mut params = user_badges_api::Params {
+        userid: None,
+        courseid: course_id,
+        page: None,
+        perpage: None,
+        search: search.filter(|s| !s.trim().is_empty()),
+        onlypublic: None,
+    };
+    let json = user_badges_api::call_raw(client, &mut params)
+        .await
+        .and_then(check_exception)?;
+    let result: user_badges_api::Returns = serde_json::from_value(json)?;
+
+    let now = chrono::Utc::now().timestamp();
+    let mut badges: Vec<EarnedBadge> = result
+        .badges
+        .unwrap_or_default()
+        .into_iter()
+        .map(|badge| parse_badge(client, badge, now))
+        .collect();
+    badges.sort_by_key(|b| std::cmp::Reverse(b.date_issued));
+    Ok(badges)
+}
+
+/// Badges the current user has earned, newest first, optionally only those
+/// of one course or matching a search.
+pub async fn get_user_badges(course_id: Option<i64>, search: Option<String>) -> Result<Vec<EarnedBadge>> {
+    let mut client = login().await?;
+    fetch_user_badges(&mut client, course_id, search).await
+}
+
+pub async fn find_user_badge(client: &mut MoodleClient, unique_hash: &str) -> Result<EarnedBadge> {
+    fetch_user_badges(client, None, None)
+        .await?
+        .into_iter()
+        .find(|b| b.unique_hash == unique_hash)
+        .ok_or_else(|| anyhow!("Badge not found"))
+}
@@ -1,34 +1,64 @@
+use super::edit::submit_event_form;
+use super::event::{CalendarEvent, CalendarEventType, NewCalendarEvent};
+use super::get::fetch_allowed_event_types;
 use super::login;
-use anyhow::Result;
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::list;
+use anyhow::{anyhow, Result};
 use moodle_api::core::calendar::create_calendar_events::{self, ParamsEventsItem};
+use moodle_client::MoodleClient;
 
-pub async fn add_event(
-    name: String,
-    description: Option<String>,
-    eventtype: String,
-    timestart: i64,
-    timeduration: Option<i64>,
-    courseid: Option<i64>,
-    groupid: Option<i64>,
-    visible: Option<i64>,
-    sequence: Option<i64>,
-) -> Result<serde_json::Value> {
+/// Creates an event (or a weekly series when `repeats` is set) after checking
+/// it against the event types the user may create.
+///
+/// Returns every created occurrence.
+pub async fn add_event(event: NewCalendarEvent) -> Result<Vec<CalendarEvent>> {
     let mut client = login().await?;
 
-    let event = ParamsEventsItem {
-        r#name: Some(name),
-        r#description: description,
-        r#format: None,
-        r#courseid: courseid,
-        r#groupid: groupid,
-        r#repeats: None,
-        r#eventtype: Some(eventtype),
-        r#timestart: Some(timestart),
-        r#timeduration: timeduration,
-        r#visible: Some(visible.unwrap_or(1)),
-        r#sequence: Some(sequence.unwrap_or(1)),
+    let allowed = fetch_allowed_event_types(&mut client, event.course_id).await?;
+    event.validate(&allowed)?;
+
+    create_event(&mut client, &event).await
+}
+
+/// Creates an already validated event.
+pub async fn create_event(client: &mut MoodleClient, event: &NewCalendarEvent) -> Result<Vec<CalendarEvent>> {
+    // create_calendar_events has no category or location field; use the event form
+    if event.event_type == CalendarEventType::Category || event.location.is_some() {
+        return Ok(vec![submit_event_form(client, 0, event, false).await?]);
+    }
+
+    let item = ParamsEventsItem {
+        r#name: Some(event.name.clone()),
+        r#description: event.description.clone(),
+        r#format: Some(1),
+        r#courseid: match event.event_type {
+            // Site events live in the front page course
+            CalendarEventType::Site => Some(1),
+            _ => event.course_id,
+        },
+        r#groupid: event.group_id,
+        r#repeats: event.repeats.filter(|r| *r > 1),
+        r#eventtype: Some(event.event_type.as_str().to_string()),
+        r#timestart: Some(event.timestart),
+        r#timeduration: Some(event.timeduration),
+        r#visible: Some(1),
+        r#sequence: Some(1),
     };
 
-    let json = create_calendar_events::call_form(&mut client, &[event]).await?;
-    Ok(json)
+    let json = create_calendar_events::call_form(client, &[item])
+        .await
+        .and_then(check_exception)?;
+
+    let created: Vec<CalendarEvent> = list(&json, "events").iter().map(CalendarEvent::from_json).collect();
+    if created.is_empty() {
+        let message = list(&json, "warnings")
+            .first()
+            .and_then(|w| w.get("message"))
+            .and_then(|m| m.as_str())
+            .unwrap_or("Moodle did not create the event")
+            .to_string();
+        return Err(anyhow!(message));
+    }
+    Ok(created)
 }
@@ -1,20 +1,29 @@
+use super::get::get_event;
 use super::login;
-use anyhow::Result;
+use crate::moodle::exception::check_exception;
+use anyhow::{anyhow, Result};
 
-pub async fn delete_event(event_id: i64, repeat: Option<bool>) -> Result<serde_json::Value> {
-    let client = login().await?;
+/// Deletes an event. For repeating events `delete_series` removes every occurrence.
+pub async fn delete_event(event_id: i64, delete_series: bool) -> Result<()> {
+    let existing = get_event(event_id).await?;
+    if !existing.can_delete {
+        return Err(anyhow!("You can't delete this event"));
+    }
 
-    let mut form: Vec<(String, String)> = Vec::new();
-    form.push(("events[0][eventid]".to_string(), event_id.to_string()));
+    let client = login().await?;
 
-    if let Some(rep) = repeat {
-        form.push(("events[0][repeat]".to_string(), rep.to_string()));
-    } else {
-        form.push(("events[0][repeat]".to_string(), "false".to_string()));
-    }
+    let form: Vec<(String, String)> = vec![
+        ("events[0][eventid]".to_string(), event_id.to_string()),
+        (
+            "events[0][repeat]".to_string(),
+            // PARAM_BOOL rejects "true"/"false"
+            ((delete_series && existing.repeat_id.is_some()) as i32).to_string(),
+        ),
+    ];
 
-    let json = client
+    client
         .post("core_calendar_delete_calendar_events", &form)
-        .await?;
-    Ok(json)
+        .await
+        .and_then(check_exception)?;
+    Ok(())
 }
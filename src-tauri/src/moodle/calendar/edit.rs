@@ -1,61 +1,169 @@
+use super::event::{CalendarEvent, CalendarEventType, NewCalendarEvent};
+use super::get::{fetch_allowed_event_types, get_event};
 use super::login;
-use anyhow::Result;
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{flag, int, list};
+use crate::moodle::site::get_current_user_id;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, Timelike};
+use moodle_client::MoodleClient;
 
 pub async fn update_event_start_day(
     event_id: i64,
     year: i32,
     month: i32,
     day: i32,
-) -> Result<serde_json::Value> {
+) -> Result<CalendarEvent> {
     let client = login().await?;
 
-    let mut form: Vec<(String, String)> = Vec::new();
-    form.push(("eventid".to_string(), event_id.to_string()));
-    form.push(("year".to_string(), year.to_string()));
-    form.push(("month".to_string(), month.to_string()));
-    form.push(("day".to_string(), day.to_string()));
+    let form: Vec<(String, String)> = vec![
+        ("eventid".to_string(), event_id.to_string()),
+        ("year".to_string(), year.to_string()),
+        ("month".to_string(), month.to_string()),
+        ("day".to_string(), day.to_string()),
+    ];
 
     let json = client
         .post("core_calendar_update_event_start_day", &form)
-        .await?;
-    Ok(json)
+        .await
+        .and_then(check_exception)?;
+    Ok(CalendarEvent::from_json(json.get("event").unwrap_or(&json)))
 }
 
+/// Edits an event. For repeating events `update_series` applies the change
+/// to every occurrence instead of only this one.
 pub async fn update_event(
     event_id: i64,
-    name: Option<String>,
-    description: Option<String>,
-    timestart: Option<i64>,
-    timeduration: Option<i64>,
-    visible: Option<i64>,
-) -> Result<serde_json::Value> {
-    let client = login().await?;
+    event: NewCalendarEvent,
+    update_series: bool,
+) -> Result<CalendarEvent> {
+    let existing = get_event(event_id).await?;
+    if !existing.can_edit {
+        return Err(anyhow!("You can't edit this event"));
+    }
+    if existing.module.is_some() {
+        return Err(anyhow!("Activity events are changed from the activity settings"));
+    }
 
-    let mut form: Vec<(String, String)> = Vec::new();
-    form.push(("events[0][eventid]".to_string(), event_id.to_string()));
+    let mut client = login().await?;
+    let allowed = fetch_allowed_event_types(&mut client, event.course_id).await?;
+    event.validate(&allowed)?;
 
-    if let Some(n) = name {
-        form.push(("events[0][name]".to_string(), n));
-    }
+    submit_event_form(&mut client, event_id, &event, update_series && existing.repeat_id.is_some()).await
+}
 
-    if let Some(desc) = description {
-        form.push(("events[0][description]".to_string(), desc));
-    }
+/// The wall-clock time of `timestamp` in the time zone of the user's Moodle
+/// profile, which the form's date selector works in.
+///
+/// Moodle does not expose the zone itself, so ask it for the timestamp of the
+/// UTC wall clock and shift by the difference.
+async fn user_wall_clock(client: &mut MoodleClient, timestamp: i64) -> Result<NaiveDateTime> {
+    let utc = DateTime::from_timestamp(timestamp, 0)
+        .ok_or_else(|| anyhow!("Invalid start time"))?
+        .naive_utc();
+    let form: Vec<(String, String)> = vec![
+        ("data[0][key]".to_string(), "start".to_string()),
+        ("data[0][year]".to_string(), utc.year().to_string()),
+        ("data[0][month]".to_string(), utc.month().to_string()),
+        ("data[0][day]".to_string(), utc.day().to_string()),
+        ("data[0][hour]".to_string(), utc.hour().to_string()),
+        ("data[0][minute]".to_string(), utc.minute().to_string()),
+    ];
+    let json = client
+        .post("core_calendar_get_timestamps", &form)
+        .await
+        .and_then(check_exception)?;
+    let converted = list(&json, "timestamps")
+        .first()
+        .map(|t| int(t, "timestamp"))
+        .ok_or_else(|| anyhow!("Moodle did not convert the start time"))?;
+    // A zone ahead of UTC reaches the same wall clock earlier; the form has
+    // no seconds, so compare against the whole minute
+    let offset = timestamp - i64::from(utc.second()) - converted;
+    Ok(utc + Duration::seconds(offset))
+}
+
+/// Creates (`event_id` 0) or updates an event through
+/// `core_calendar_submit_create_update_form`, the same form the web UI posts.
+/// It is the only web service that can edit events.
+pub async fn submit_event_form(
+    client: &mut MoodleClient,
+    event_id: i64,
+    event: &NewCalendarEvent,
+    update_series: bool,
+) -> Result<CalendarEvent> {
+    let user_id = get_current_user_id(client).await?;
+    let start = user_wall_clock(client, event.timestart).await?;
+
+    let form_name = if event_id == 0 { "create" } else { "update" };
+    let mut fields: Vec<(String, String)> = vec![
+        ("id".to_string(), event_id.to_string()),
+        ("userid".to_string(), user_id.to_string()),
+        ("modulename".to_string(), String::new()),
+        ("instance".to_string(), "0".to_string()),
+        ("visible".to_string(), "1".to_string()),
+        (format!("_qf__core_calendar_local_event_forms_{}", form_name), "1".to_string()),
+        ("mform_isexpanded_id_general".to_string(), "1".to_string()),
+        ("name".to_string(), event.name.clone()),
+        ("eventtype".to_string(), event.event_type.as_str().to_string()),
+        ("timestart[day]".to_string(), start.day().to_string()),
+        ("timestart[month]".to_string(), start.month().to_string()),
+        ("timestart[year]".to_string(), start.year().to_string()),
+        ("timestart[hour]".to_string(), start.hour().to_string()),
+        ("timestart[minute]".to_string(), start.minute().to_string()),
+        ("description[text]".to_string(), event.description.clone().unwrap_or_default()),
+        ("description[format]".to_string(), "1".to_string()),
+        ("description[itemid]".to_string(), "0".to_string()),
+        ("location".to_string(), event.location.clone().unwrap_or_default()),
+    ];
 
-    if let Some(ts) = timestart {
-        form.push(("events[0][timestart]".to_string(), ts.to_string()));
+    if event.timeduration > 0 {
+        fields.push(("duration".to_string(), "2".to_string()));
+        fields.push(("timedurationminutes".to_string(), (event.timeduration / 60).to_string()));
+    } else {
+        fields.push(("duration".to_string(), "0".to_string()));
     }
 
-    if let Some(duration) = timeduration {
-        form.push(("events[0][timeduration]".to_string(), duration.to_string()));
+    match event.event_type {
+        CalendarEventType::Course => {
+            fields.push(("courseid".to_string(), event.course_id.unwrap_or(0).to_string()));
+        }
+        CalendarEventType::Group => {
+            fields.push(("groupcourseid".to_string(), event.course_id.unwrap_or(0).to_string()));
+            fields.push(("groupid".to_string(), event.group_id.unwrap_or(0).to_string()));
+        }
+        CalendarEventType::Category => {
+            fields.push(("categoryid".to_string(), event.category_id.unwrap_or(0).to_string()));
+        }
+        _ => {}
     }
 
-    if let Some(vis) = visible {
-        form.push(("events[0][visible]".to_string(), vis.to_string()));
+    if event_id == 0 {
+        if let Some(repeats) = event.repeats.filter(|r| *r > 1) {
+            fields.push(("repeat".to_string(), "1".to_string()));
+            fields.push(("repeats".to_string(), repeats.to_string()));
+        }
+    } else if update_series {
+        fields.push(("repeateditall".to_string(), "1".to_string()));
     }
 
+    let formdata = fields
+        .iter()
+        .map(|(k, v)| format!("{}={}", urlencoding::encode(k), urlencoding::encode(v)))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let form: Vec<(String, String)> = vec![("formdata".to_string(), formdata)];
     let json = client
-        .post("core_calendar_update_calendar_events", &form)
-        .await?;
-    Ok(json)
+        .post("core_calendar_submit_create_update_form", &form)
+        .await
+        .and_then(check_exception)?;
+
+    if flag(&json, "validationerror") {
+        return Err(anyhow!("Moodle rejected the event, check the dates and fields"));
+    }
+    json.get("event")
+        .filter(|e| e.is_object())
+        .map(CalendarEvent::from_json)
+        .ok_or_else(|| anyhow!("Moodle did not return the saved event"))
 }
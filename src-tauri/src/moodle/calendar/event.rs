@@ -0,0 +1,151 @@
+use crate::moodle::json::{flag, int, text};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// Moodle never accepts more repetitions than this in the event form.
+pub const MAX_REPEATS: i64 = 52;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CalendarEventType {
+    User,
+    Course,
+    Group,
+    Site,
+    Category,
+}
+
+impl CalendarEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CalendarEventType::User => "user",
+            CalendarEventType::Course => "course",
+            CalendarEventType::Group => "group",
+            CalendarEventType::Site => "site",
+            CalendarEventType::Category => "category",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarEvent {
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    /// `user`, `course`, `group`, `site`, `category` or an activity event type (`due`, `open`, ...)
+    pub event_type: String,
+    pub course_id: Option<i64>,
+    pub course_name: Option<String>,
+    pub group_id: Option<i64>,
+    pub group_name: Option<String>,
+    pub category_id: Option<i64>,
+    pub module: Option<String>,
+    pub instance: Option<i64>,
+    pub timestart: i64,
+    pub timeduration: i64,
+    /// Set on every event of a repeating series
+    pub repeat_id: Option<i64>,
+    pub repeat_count: i64,
+    pub visible: bool,
+    pub can_edit: bool,
+    pub can_delete: bool,
+    pub is_action_event: bool,
+    pub url: Option<String>,
+}
+
+impl CalendarEvent {
+    /// Reads both the exporter format (views, by-id) and the legacy
+    /// `core_calendar_get_calendar_events` format.
+    pub fn from_json(event: &serde_json::Value) -> Self {
+        let course = event.get("course").filter(|c| c.is_object());
+        let positive = |key: &str| event.get(key).and_then(|v| v.as_i64()).filter(|v| *v > 0);
+
+        CalendarEvent {
+            id: int(event, "id"),
+            name: text(event, "name").unwrap_or_default(),
+            description: text(event, "description"),
+            location: text(event, "location"),
+            event_type: text(event, "eventtype").unwrap_or_default(),
+            course_id: course
+                .map(|c| int(c, "id"))
+                .filter(|id| *id > 0)
+                .or_else(|| positive("courseid")),
+            course_name: course.and_then(|c| text(c, "fullname")),
+            group_id: positive("groupid"),
+            group_name: text(event, "groupname"),
+            category_id: positive("categoryid"),
+            module: text(event, "modulename"),
+            instance: positive("instance"),
+            timestart: int(event, "timestart"),
+            timeduration: int(event, "timeduration"),
+            repeat_id: positive("repeatid"),
+            repeat_count: int(event, "eventcount").max(1),
+            visible: event.get("visible").is_none() || flag(event, "visible"),
+            can_edit: flag(event, "canedit"),
+            can_delete: flag(event, "candelete"),
+            is_action_event: flag(event, "isactionevent"),
+            url: text(event, "viewurl").or_else(|| text(event, "url")),
+        }
+    }
+}
+
+/// An event as entered in the app, for both creating and editing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewCalendarEvent {
+    pub name: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub event_type: CalendarEventType,
+    pub timestart: i64,
+    /// Seconds, 0 for an event without duration
+    #[serde(default)]
+    pub timeduration: i64,
+    pub course_id: Option<i64>,
+    pub group_id: Option<i64>,
+    pub category_id: Option<i64>,
+    /// Number of weekly occurrences, including the first one
+    pub repeats: Option<i64>,
+}
+
+impl NewCalendarEvent {
+    /// Checks the fields Moodle's event form requires for each event type.
+    pub fn validate(&self, allowed_types: &[String]) -> Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(anyhow!("The event needs a name"));
+        }
+        if self.name.chars().count() > 255 {
+            return Err(anyhow!("The event name is longer than 255 characters"));
+        }
+        if self.timestart <= 0 {
+            return Err(anyhow!("The event needs a start time"));
+        }
+        if self.timeduration < 0 {
+            return Err(anyhow!("The event duration can't be negative"));
+        }
+        if let Some(repeats) = self.repeats {
+            if !(1..=MAX_REPEATS).contains(&repeats) {
+                return Err(anyhow!("An event can repeat between 1 and {} times", MAX_REPEATS));
+            }
+        }
+        match self.event_type {
+            CalendarEventType::Course if self.course_id.is_none() => {
+                return Err(anyhow!("Course events need a course"));
+            }
+            CalendarEventType::Group if self.course_id.is_none() || self.group_id.is_none() => {
+                return Err(anyhow!("Group events need a course and a group"));
+            }
+            CalendarEventType::Category if self.category_id.is_none() => {
+                return Err(anyhow!("Category events need a category"));
+            }
+            _ => {}
+        }
+        if !allowed_types.is_empty() && !allowed_types.iter().any(|t| t == self.event_type.as_str()) {
+            return Err(anyhow!(
+                "You are not allowed to create {} events here",
+                self.event_type.as_str()
+            ));
+        }
+        Ok(())
+    }
+}
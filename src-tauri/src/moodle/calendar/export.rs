@@ -0,0 +1,113 @@
+use super::event::CalendarEvent;
+use super::get::fetch_events;
+use super::ics::{write_ics, IcsEvent};
+use super::login;
+use crate::moodle::html::to_plain_text;
+use crate::moodle::timeline::fetch_action_events;
+use anyhow::Result;
+use moodle_api::core::webservice::get_site_info;
+use std::collections::HashSet;
+
+/// Action events are paged 50 at a time; stop after this many pages.
+const MAX_ACTION_PAGES: usize = 10;
+
+/// Builds an `.ics` file with the user's calendar events plus the assignment
+/// and quiz deadlines from the timeline, optionally writing it to `destination`.
+///
+/// Defaults to the last 30 days and the coming year.
+pub async fn export_calendar_ics(
+    timestart: Option<i64>,
+    timeend: Option<i64>,
+    destination: Option<String>,
+) -> Result<String> {
+    let mut client = login().await?;
+
+    let now = chrono::Utc::now().timestamp();
+    let timestart = timestart.unwrap_or(now - 30 * 24 * 60 * 60);
+    let timeend = timeend.unwrap_or(now + 365 * 24 * 60 * 60);
+
+    let site = get_site_info::call_raw(&mut client, &mut get_site_info::Params { serviceshortnames: None }).await?;
+    let site_url = site.get("siteurl").and_then(|s| s.as_str()).unwrap_or_default();
+    // UIDs must be globally unique, so qualify event ids with the site host
+    let host = site_url
+        .split("://")
+        .last()
+        .and_then(|rest| rest.split('/').next())
+        .filter(|h| !h.is_empty())
+        .unwrap_or("moodle")
+        .to_string();
+    let site_name = site
+        .get("sitename")
+        .and_then(|s| s.as_str())
+        .unwrap_or("Moodle")
+        .to_string();
+
+    let mut seen = HashSet::new();
+    let mut events: Vec<IcsEvent> = Vec::new();
+
+    for event in fetch_events(&mut client, Some(timestart), Some(timeend)).await? {
+        if seen.insert(event.id) {
+            events.push(to_ics_event(&event, &host));
+        }
+    }
+
+    let mut after_event_id = None;
+    for _ in 0..MAX_ACTION_PAGES {
+        let (items, last_id) = fetch_action_events(&mut client, timestart, after_event_id, 50).await?;
+        let done = items.len() < 50 || last_id.is_none();
+        for item in items {
+            if item.timesort > timeend || !seen.insert(item.event_id) {
+                continue;
+            }
+            events.push(IcsEvent {
+                uid: format!("moodle-event-{}@{}", item.event_id, host),
+                summary: item.name,
+                description: item.action_name.clone(),
+                location: None,
+                url: item.url.or(item.action_url),
+                categories: item.course_name.into_iter().chain(item.module).collect(),
+                dtstart: item.timesort,
+                dtend: None,
+                all_day: false,
+                weekly_count: None,
+                cancelled: false,
+            });
+        }
+        if done {
+            break;
+        }
+        after_event_id = last_id;
+    }
+
+    events.sort_by_key(|e| e.dtstart);
+    let content = write_ics(&site_name, &events);
+    if let Some(destination) = destination {
+        tokio::fs::write(&destination, &content).await?;
+    }
+    Ok(content)
+}
+
+fn to_ics_event(event: &CalendarEvent, host: &str) -> IcsEvent {
+    IcsEvent {
+        uid: format!("moodle-event-{}@{}", event.id, host),
+        summary: event.name.clone(),
+        description: event
+            .description
+            .as_deref()
+            .map(to_plain_text)
+            .filter(|d| !d.is_empty()),
+        location: event.location.clone(),
+        url: event.url.clone(),
+        categories: event
+            .course_name
+            .iter()
+            .cloned()
+            .chain(std::iter::once(event.event_type.clone()).filter(|t| !t.is_empty()))
+            .collect(),
+        dtstart: event.timestart,
+        dtend: Some(event.timestart + event.timeduration).filter(|_| event.timeduration > 0),
+        all_day: false,
+        weekly_count: None,
+        cancelled: false,
+    }
+}
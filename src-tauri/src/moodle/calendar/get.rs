@@ -1,13 +1,57 @@
+use super::event::CalendarEvent;
 use super::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{flag, int, list};
 use anyhow::Result;
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
 
-pub async fn get_events(timestart: Option<i64>, timeend: Option<i64>) -> Result<serde_json::Value> {
-    let client = login().await?;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarDay {
+    /// Day of the month
+    pub day: i64,
+    pub timestamp: i64,
+    pub is_today: bool,
+    pub is_weekend: bool,
+    pub events: Vec<CalendarEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarWeek {
+    /// Number of empty cells before the first day of the month
+    pub padding_before: i64,
+    pub padding_after: i64,
+    pub days: Vec<CalendarDay>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonthView {
+    pub year: i32,
+    pub month: i32,
+    pub period_name: String,
+    pub weeks: Vec<CalendarWeek>,
+}
 
-    let mut form: Vec<(String, String)> = Vec::new();
-    form.push(("options[userevents]".to_string(), "1".to_string()));
-    form.push(("options[siteevents]".to_string(), "1".to_string()));
-    form.push(("options[ignorehidden]".to_string(), "0".to_string()));
+fn events_of(json: &serde_json::Value) -> Vec<CalendarEvent> {
+    list(json, "events").iter().map(CalendarEvent::from_json).collect()
+}
+
+pub async fn get_events(timestart: Option<i64>, timeend: Option<i64>) -> Result<Vec<CalendarEvent>> {
+    let mut client = login().await?;
+    fetch_events(&mut client, timestart, timeend).await
+}
+
+/// User, site and enrolled-course events between two timestamps.
+pub async fn fetch_events(
+    client: &mut MoodleClient,
+    timestart: Option<i64>,
+    timeend: Option<i64>,
+) -> Result<Vec<CalendarEvent>> {
+    let mut form: Vec<(String, String)> = vec![
+        ("options[userevents]".to_string(), "1".to_string()),
+        ("options[siteevents]".to_string(), "1".to_string()),
+        ("options[ignorehidden]".to_string(), "0".to_string()),
+    ];
     if let Some(ts) = timestart {
         form.push(("options[timestart]".to_string(), ts.to_string()));
     }
@@ -17,43 +61,125 @@ pub async fn get_events(timestart: Option<i64>, timeend: Option<i64>) -> Result<
 
     let json = client
         .post("core_calendar_get_calendar_events", &form)
-        .await?;
-    Ok(json)
+        .await
+        .and_then(check_exception)?;
+    Ok(events_of(&json))
+}
+
+pub async fn get_event(event_id: i64) -> Result<CalendarEvent> {
+    let mut client = login().await?;
+    fetch_event(&mut client, event_id).await
 }
 
-pub async fn get_monthly_view(year: i32, month: i32) -> Result<serde_json::Value> {
+pub async fn fetch_event(client: &mut MoodleClient, event_id: i64) -> Result<CalendarEvent> {
+    let form: Vec<(String, String)> = vec![("eventid".to_string(), event_id.to_string())];
+    let json = client
+        .post("core_calendar_get_calendar_event_by_id", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(CalendarEvent::from_json(json.get("event").unwrap_or(&json)))
+}
+
+pub async fn get_monthly_view(year: i32, month: i32, course_id: Option<i64>) -> Result<MonthView> {
     let client = login().await?;
 
-    let mut form: Vec<(String, String)> = Vec::new();
-    form.push(("year".to_string(), year.to_string()));
-    form.push(("month".to_string(), month.to_string()));
+    let mut form: Vec<(String, String)> = vec![
+        ("year".to_string(), year.to_string()),
+        ("month".to_string(), month.to_string()),
+    ];
+    if let Some(course_id) = course_id {
+        form.push(("courseid".to_string(), course_id.to_string()));
+    }
 
     let json = client
         .post("core_calendar_get_calendar_monthly_view", &form)
-        .await?;
-    Ok(json)
+        .await
+        .and_then(check_exception)?;
+
+    let weeks = list(&json, "weeks")
+        .iter()
+        .map(|week| CalendarWeek {
+            padding_before: list(week, "prepadding").len() as i64,
+            padding_after: list(week, "postpadding").len() as i64,
+            days: list(week, "days")
+                .iter()
+                .map(|day| CalendarDay {
+                    day: int(day, "mday"),
+                    timestamp: int(day, "timestamp"),
+                    is_today: flag(day, "istoday"),
+                    is_weekend: flag(day, "isweekend"),
+                    events: events_of(day),
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(MonthView {
+        year,
+        month,
+        period_name: json
+            .get("periodname")
+            .and_then(|p| p.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        weeks,
+    })
 }
 
-pub async fn get_day_view(year: i32, month: i32, day: i32) -> Result<serde_json::Value> {
+pub async fn get_day_view(
+    year: i32,
+    month: i32,
+    day: i32,
+    course_id: Option<i64>,
+) -> Result<Vec<CalendarEvent>> {
     let client = login().await?;
 
-    let mut form: Vec<(String, String)> = Vec::new();
-    form.push(("year".to_string(), year.to_string()));
-    form.push(("month".to_string(), month.to_string()));
-    form.push(("day".to_string(), day.to_string()));
+    let mut form: Vec<(String, String)> = vec![
+        ("year".to_string(), year.to_string()),
+        ("month".to_string(), month.to_string()),
+        ("day".to_string(), day.to_string()),
+    ];
+    if let Some(course_id) = course_id {
+        form.push(("courseid".to_string(), course_id.to_string()));
+    }
 
     let json = client
         .post("core_calendar_get_calendar_day_view", &form)
-        .await?;
-    Ok(json)
+        .await
+        .and_then(check_exception)?;
+    Ok(events_of(&json))
 }
 
-pub async fn get_upcoming_view() -> Result<serde_json::Value> {
+pub async fn get_upcoming_view(course_id: Option<i64>) -> Result<Vec<CalendarEvent>> {
     let client = login().await?;
 
-    let form: Vec<(String, String)> = Vec::new();
+    let form: Vec<(String, String)> = course_id
+        .map(|id| vec![("courseid".to_string(), id.to_string())])
+        .unwrap_or_default();
     let json = client
         .post("core_calendar_get_calendar_upcoming_view", &form)
-        .await?;
-    Ok(json)
+        .await
+        .and_then(check_exception)?;
+    Ok(events_of(&json))
+}
+
+/// Event types (`user`, `course`, ...) the user may create, site-wide or in a course.
+pub async fn fetch_allowed_event_types(
+    client: &mut MoodleClient,
+    course_id: Option<i64>,
+) -> Result<Vec<String>> {
+    let form: Vec<(String, String)> = vec![("courseid".to_string(), course_id.unwrap_or(0).to_string())];
+    let json = client
+        .post("core_calendar_get_allowed_event_types", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(list(&json, "allowedeventtypes")
+        .iter()
+        .filter_map(|t| t.as_str().map(|t| t.to_string()))
+        .collect())
+}
+
+pub async fn get_allowed_event_types(course_id: Option<i64>) -> Result<Vec<String>> {
+    let mut client = login().await?;
+    fetch_allowed_event_types(&mut client, course_id).await
 }
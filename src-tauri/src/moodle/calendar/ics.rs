@@ -0,0 +1,509 @@
+//! Minimal iCalendar (RFC 5545) reader and writer covering the VEVENT fields
+//! the Moodle calendar can store.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+const DAY_SECONDS: i64 = 24 * 60 * 60;
+const WEEK_SECONDS: i64 = 7 * DAY_SECONDS;
+
+/// A content line split into name, parameters and raw value.
+type Property = (String, Vec<(String, String)>, String);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IcsEvent {
+    pub uid: String,
+    pub summary: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub url: Option<String>,
+    pub categories: Vec<String>,
+    pub dtstart: i64,
+    pub dtend: Option<i64>,
+    pub all_day: bool,
+    /// Number of occurrences of a weekly `RRULE`, the only kind Moodle can repeat
+    pub weekly_count: Option<i64>,
+    /// `STATUS:CANCELLED`
+    pub cancelled: bool,
+}
+
+/// Reads every VEVENT of a calendar file.
+///
+/// Times with a `TZID` are read in the device time zone, which matches
+/// timetables exported for the same region.
+pub fn parse_ics(content: &str) -> Result<Vec<IcsEvent>> {
+    let lines = unfold(content);
+    if !lines.iter().any(|l| l.eq_ignore_ascii_case("BEGIN:VCALENDAR")) {
+        return Err(anyhow!("This is not an iCalendar file"));
+    }
+
+    let mut events = Vec::new();
+    let mut current: Option<Vec<Property>> = None;
+    // Nested components such as VALARM must not overwrite event properties
+    let mut depth = 0;
+
+    for line in &lines {
+        let (name, params, value) = match split_property(line) {
+            Some(p) => p,
+            None => continue,
+        };
+        match (name.as_str(), value.to_ascii_uppercase().as_str()) {
+            ("BEGIN", "VEVENT") => {
+                current = Some(Vec::new());
+                depth = 0;
+            }
+            ("END", "VEVENT") => {
+                if let Some(props) = current.take() {
+                    if let Some(event) = build_event(&props) {
+                        events.push(event);
+                    }
+                }
+            }
+            ("BEGIN", _) if current.is_some() => depth += 1,
+            ("END", _) if current.is_some() => depth -= 1,
+            _ => {
+                if let Some(props) = current.as_mut() {
+                    if depth == 0 {
+                        props.push((name, params, value));
+                    }
+                }
+            }
+        }
+    }
+    Ok(events)
+}
+
+/// Writes a complete calendar file with CRLF line endings and folded lines.
+pub fn write_ics(calendar_name: &str, events: &[IcsEvent]) -> String {
+    let stamp = format_utc(Utc::now().timestamp());
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Tabletop//Moodle calendar//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:{}", escape(calendar_name)),
+    ];
+
+    for event in events {
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", escape(&event.uid)));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        if event.all_day {
+            lines.push(format!("DTSTART;VALUE=DATE:{}", format_date(event.dtstart)));
+            let end = event.dtend.unwrap_or(event.dtstart + DAY_SECONDS);
+            lines.push(format!("DTEND;VALUE=DATE:{}", format_date(end.max(event.dtstart + DAY_SECONDS))));
+        } else {
+            lines.push(format!("DTSTART:{}", format_utc(event.dtstart)));
+            if let Some(end) = event.dtend.filter(|e| *e > event.dtstart) {
+                lines.push(format!("DTEND:{}", format_utc(end)));
+            }
+        }
+        lines.push(format!("SUMMARY:{}", escape(&event.summary)));
+        if let Some(description) = event.description.as_deref().filter(|d| !d.is_empty()) {
+            lines.push(format!("DESCRIPTION:{}", escape(description)));
+        }
+        if let Some(location) = event.location.as_deref().filter(|l| !l.is_empty()) {
+            lines.push(format!("LOCATION:{}", escape(location)));
+        }
+        if let Some(url) = event.url.as_deref().filter(|u| !u.is_empty()) {
+            lines.push(format!("URL:{}", url));
+        }
+        if !event.categories.is_empty() {
+            let categories: Vec<String> = event.categories.iter().map(|c| escape(c)).collect();
+            lines.push(format!("CATEGORIES:{}", categories.join(",")));
+        }
+        if let Some(count) = event.weekly_count.filter(|c| *c > 1) {
+            lines.push(format!("RRULE:FREQ=WEEKLY;COUNT={}", count));
+        }
+        if event.cancelled {
+            lines.push("STATUS:CANCELLED".to_string());
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut out = String::new();
+    for line in lines {
+        out.push_str(&fold(&line));
+    }
+    out
+}
+
+fn unfold(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in content.split('\n') {
+        let line = raw.trim_end_matches('\r');
+        if line.starts_with(' ') || line.starts_with('\t') {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(&line[1..]);
+            }
+        } else if !line.is_empty() {
+            lines.push(line.to_string());
+        }
+    }
+    lines
+}
+
+/// Splits `NAME;PARAM=VALUE:value`, ignoring colons inside quoted parameters.
+fn split_property(line: &str) -> Option<Property> {
+    let mut in_quotes = false;
+    let colon = line.char_indices().find(|(_, c)| {
+        if *c == '"' {
+            in_quotes = !in_quotes;
+        }
+        *c == ':' && !in_quotes
+    })?;
+    let (head, value) = (&line[..colon.0], &line[colon.0 + 1..]);
+
+    let mut parts = head.split(';');
+    let name = parts.next()?.trim().to_ascii_uppercase();
+    let params = parts
+        .filter_map(|p| p.split_once('='))
+        .map(|(k, v)| (k.trim().to_ascii_uppercase(), v.trim_matches('"').to_string()))
+        .collect();
+    Some((name, params, value.to_string()))
+}
+
+fn build_event(props: &[Property]) -> Option<IcsEvent> {
+    let prop = |name: &str| props.iter().find(|(n, _, _)| n == name);
+    let value = |name: &str| prop(name).map(|(_, _, v)| unescape(v)).filter(|v| !v.is_empty());
+
+    let (_, start_params, start_value) = prop("DTSTART")?;
+    let (dtstart, all_day) = parse_datetime(start_value, start_params)?;
+    let dtend = prop("DTEND")
+        .and_then(|(_, params, v)| parse_datetime(v, params))
+        .map(|(end, _)| end)
+        .or_else(|| prop("DURATION").and_then(|(_, _, v)| parse_duration(v)).map(|d| dtstart + d))
+        .filter(|end| *end >= dtstart);
+
+    let summary = value("SUMMARY").unwrap_or_else(|| "Untitled event".to_string());
+    // Files without UIDs still need a stable key so re-importing them updates;
+    // only the event's own fields, so edits elsewhere in the file don't matter
+    let uid = value("UID").unwrap_or_else(|| format!("{}-{}", dtstart, summary));
+
+    Some(IcsEvent {
+        uid,
+        description: value("DESCRIPTION"),
+        location: value("LOCATION"),
+        url: value("URL"),
+        categories: props
+            .iter()
+            .filter(|(n, _, _)| n == "CATEGORIES")
+            .flat_map(|(_, _, v)| split_list(v))
+            .collect(),
+        weekly_count: prop("RRULE").and_then(|(_, _, v)| weekly_count(v, dtstart)),
+        cancelled: value("STATUS").is_some_and(|s| s.eq_ignore_ascii_case("CANCELLED")),
+        summary,
+        dtstart,
+        dtend,
+        all_day,
+    })
+}
+
+fn parse_datetime(value: &str, params: &[(String, String)]) -> Option<(i64, bool)> {
+    let value = value.trim();
+    let is_date = params.iter().any(|(k, v)| k == "VALUE" && v.eq_ignore_ascii_case("DATE"))
+        || value.len() == 8;
+
+    if is_date {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        let midnight = Local.from_local_datetime(&date.and_hms_opt(0, 0, 0)?).earliest()?;
+        return Some((midnight.timestamp(), true));
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((time.and_utc().timestamp(), false));
+    }
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    Some((Local.from_local_datetime(&time).earliest()?.timestamp(), false))
+}
+
+/// Parses `P1W`, `P1DT2H`, `PT45M`, ... into seconds.
+fn parse_duration(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let (sign, rest) = match value.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let rest = rest.strip_prefix('P')?;
+
+    let mut total = 0;
+    let mut number = String::new();
+    for c in rest.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => {}
+            unit => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                total += n * match unit {
+                    'W' => WEEK_SECONDS,
+                    'D' => DAY_SECONDS,
+                    'H' => 3600,
+                    'M' => 60,
+                    'S' => 1,
+                    _ => return None,
+                };
+            }
+        }
+    }
+    Some(sign * total)
+}
+
+/// Occurrence count of a plain weekly rule; other rules only import the first occurrence.
+fn weekly_count(rule: &str, dtstart: i64) -> Option<i64> {
+    let parts: Vec<(String, String)> = rule
+        .split(';')
+        .filter_map(|p| p.split_once('='))
+        .map(|(k, v)| (k.to_ascii_uppercase(), v.to_string()))
+        .collect();
+    let part = |key: &str| parts.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+
+    if !part("FREQ")?.eq_ignore_ascii_case("WEEKLY") || part("INTERVAL").is_some_and(|i| i != "1") {
+        return None;
+    }
+    // BYDAY with several days means more than one event per week
+    if part("BYDAY").is_some_and(|d| d.contains(',')) {
+        return None;
+    }
+    if let Some(count) = part("COUNT") {
+        return count.parse().ok();
+    }
+    let (until, _) = parse_datetime(part("UNTIL")?, &[])?;
+    Some((until - dtstart) / WEEK_SECONDS + 1).filter(|c| *c > 0)
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                current.push('\\');
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            ',' => items.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    items.push(current);
+    items
+        .iter()
+        .map(|i| unescape(i.trim()))
+        .filter(|i| !i.is_empty())
+        .collect()
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Folds a content line at 75 octets without splitting UTF-8 characters.
+fn fold(line: &str) -> String {
+    let mut out = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+    out
+}
+
+fn format_utc(timestamp: i64) -> String {
+    DateTime::<Utc>::from_timestamp(timestamp, 0)
+        .unwrap_or_default()
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+fn format_date(timestamp: i64) -> String {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|t| t.format("%Y%m%d").to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CALENDAR: &str = "BEGIN:VCALENDAR\r\n\
+VERSION:2.0\r\n\
+BEGIN:VEVENT\r\n\
+UID:lecture-1@example.com\r\n\
+DTSTART:20240902T080000Z\r\n\
+DTEND:20240902T093000Z\r\n\
+SUMMARY:Lecture\\, week 1\r\n\
+DESCRIPTION:Bring the\\nreader\r\n\
+LOCATION:Room \r\n 101\r\n\
+CATEGORIES:Physics,Year 1\r\n\
+RRULE:FREQ=WEEKLY;COUNT=12\r\n\
+BEGIN:VALARM\r\n\
+DESCRIPTION:Reminder\r\n\
+END:VALARM\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:lab-1@example.com\r\n\
+DTSTART:20240903T120000Z\r\n\
+DURATION:PT2H\r\n\
+SUMMARY:Lab\r\n\
+RRULE:FREQ=DAILY;COUNT=5\r\n\
+STATUS:CANCELLED\r\n\
+END:VEVENT\r\n\
+END:VCALENDAR\r\n";
+
+    #[test]
+    fn parses_events() {
+        let events = parse_ics(CALENDAR).unwrap();
+        assert_eq!(events.len(), 2);
+
+        let lecture = &events[0];
+        assert_eq!(lecture.uid, "lecture-1@example.com");
+        assert_eq!(lecture.summary, "Lecture, week 1");
+        assert_eq!(lecture.description.as_deref(), Some("Bring the\nreader"));
+        assert_eq!(lecture.location.as_deref(), Some("Room 101"));
+        assert_eq!(lecture.categories, vec!["Physics", "Year 1"]);
+        assert_eq!(lecture.dtstart, 1725264000);
+        assert_eq!(lecture.dtend, Some(1725264000 + 90 * 60));
+        assert_eq!(lecture.weekly_count, Some(12));
+        assert!(!lecture.all_day);
+        assert!(!lecture.cancelled);
+
+        let lab = &events[1];
+        assert_eq!(lab.dtend, Some(lab.dtstart + 2 * 3600));
+        assert_eq!(lab.weekly_count, None);
+        assert!(lab.cancelled);
+    }
+
+    #[test]
+    fn uids_without_uid_property_ignore_position() {
+        let event = |summary: &str, start: &str| {
+            format!("BEGIN:VEVENT\r\nDTSTART:{}\r\nSUMMARY:{}\r\nEND:VEVENT\r\n", start, summary)
+        };
+        let first = event("Lab", "20240902T090000Z");
+        let second = event("Seminar", "20240903T090000Z");
+        let calendar = |events: &[&str]| format!("BEGIN:VCALENDAR\r\n{}END:VCALENDAR\r\n", events.concat());
+
+        let before = parse_ics(&calendar(&[&first, &second])).unwrap();
+        let after = parse_ics(&calendar(&[&second, &first])).unwrap();
+        assert_eq!(before[0].uid, after[1].uid);
+        assert_eq!(before[1].uid, after[0].uid);
+        assert_ne!(before[0].uid, before[1].uid);
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(parse_ics("BEGIN:VCARD\r\nEND:VCARD\r\n").is_err());
+    }
+
+    #[test]
+    fn weekly_rules() {
+        let start = 1725264000;
+        assert_eq!(weekly_count("FREQ=WEEKLY;COUNT=3", start), Some(3));
+        assert_eq!(weekly_count("FREQ=WEEKLY;UNTIL=20240916T080000Z", start), Some(3));
+        assert_eq!(weekly_count("FREQ=WEEKLY;INTERVAL=2;COUNT=3", start), None);
+        assert_eq!(weekly_count("FREQ=WEEKLY;BYDAY=MO,WE;COUNT=3", start), None);
+        assert_eq!(weekly_count("FREQ=WEEKLY", start), None);
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("P1W"), Some(WEEK_SECONDS));
+        assert_eq!(parse_duration("P1DT2H"), Some(DAY_SECONDS + 7200));
+        assert_eq!(parse_duration("PT45M"), Some(45 * 60));
+        assert_eq!(parse_duration("-PT15M"), Some(-15 * 60));
+        assert_eq!(parse_duration("1H"), None);
+    }
+
+    #[test]
+    fn written_calendar_reads_back() {
+        let event = IcsEvent {
+            uid: "42@moodle".to_string(),
+            summary: "Exam; room A, B".to_string(),
+            description: Some(format!("Line one\n{}", "long text ".repeat(20))),
+            location: Some("Hall".to_string()),
+            url: Some("https://moodle.example.com/calendar/view.php?view=day".to_string()),
+            categories: vec!["Maths".to_string()],
+            dtstart: 1725264000,
+            dtend: Some(1725267600),
+            all_day: false,
+            weekly_count: Some(4),
+            cancelled: false,
+        };
+        let ics = write_ics("Courses", std::slice::from_ref(&event));
+        assert!(ics.lines().all(|l| l.len() <= 76));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+
+        let read = parse_ics(&ics).unwrap();
+        assert_eq!(read.len(), 1);
+        let read = &read[0];
+        assert_eq!(read.uid, event.uid);
+        assert_eq!(read.summary, event.summary);
+        assert_eq!(read.description, event.description);
+        assert_eq!(read.location, event.location);
+        assert_eq!(read.url, event.url);
+        assert_eq!(read.categories, event.categories);
+        assert_eq!(read.dtstart, event.dtstart);
+        assert_eq!(read.dtend, event.dtend);
+        assert_eq!(read.weekly_count, Some(4));
+    }
+
+    #[test]
+    fn all_day_events_round_trip() {
+        let start = parse_datetime("20240902", &[]).unwrap();
+        assert!(start.1);
+        let event = IcsEvent {
+            uid: "holiday".to_string(),
+            summary: "Holiday".to_string(),
+            description: None,
+            location: None,
+            url: None,
+            categories: Vec::new(),
+            dtstart: start.0,
+            dtend: None,
+            all_day: true,
+            weekly_count: None,
+            cancelled: true,
+        };
+        let ics = write_ics("Courses", &[event]);
+        assert!(ics.contains("DTSTART;VALUE=DATE:20240902\r\n"));
+        assert!(ics.contains("DTEND;VALUE=DATE:20240903\r\n"));
+        let read = &parse_ics(&ics).unwrap()[0];
+        assert!(read.all_day);
+        assert!(read.cancelled);
+        assert_eq!(read.dtstart, start.0);
+    }
+}
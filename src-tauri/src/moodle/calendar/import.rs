@@ -0,0 +1,156 @@
+use super::add::create_event;
+use super::delete::delete_event;
+use super::edit::submit_event_form;
+use super::event::{CalendarEvent, CalendarEventType, NewCalendarEvent, MAX_REPEATS};
+use super::get::fetch_event;
+use super::ics::{parse_ics, IcsEvent};
+use super::login;
+use crate::moodle::exception::error_code;
+use crate::moodle::html::{from_plain_text, to_plain_text};
+use anyhow::Result;
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IcsImportError {
+    pub uid: String,
+    pub summary: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IcsImportSummary {
+    pub created: i64,
+    pub updated: i64,
+    pub unchanged: i64,
+    pub deleted: i64,
+    pub failed: Vec<IcsImportError>,
+}
+
+/// Imports a `.ics` file as Moodle user events.
+///
+/// `uid_map_path` stores which Moodle event each iCalendar UID became, so
+/// importing a newer version of the same file updates events instead of
+/// duplicating them, and `STATUS:CANCELLED` entries delete them.
+pub async fn import_calendar_ics(file_path: &str, uid_map_path: &Path) -> Result<IcsImportSummary> {
+    let content = tokio::fs::read_to_string(file_path).await?;
+    let events = parse_ics(&content)?;
+
+    let mut uid_map: HashMap<String, i64> = match tokio::fs::read(uid_map_path).await {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_default(),
+        Err(_) => HashMap::new(),
+    };
+
+    let mut client = login().await?;
+    let mut summary = IcsImportSummary::default();
+
+    for event in &events {
+        if let Err(e) = import_event(&mut client, event, &mut uid_map, &mut summary).await {
+            summary.failed.push(IcsImportError {
+                uid: event.uid.clone(),
+                summary: event.summary.clone(),
+                message: e.to_string(),
+            });
+        }
+    }
+
+    // Saved even after failures so the events that made it are not duplicated next time
+    if let Some(parent) = uid_map_path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(uid_map_path, serde_json::to_vec_pretty(&uid_map)?).await?;
+    Ok(summary)
+}
+
+async fn import_event(
+    client: &mut MoodleClient,
+    event: &IcsEvent,
+    uid_map: &mut HashMap<String, i64>,
+    summary: &mut IcsImportSummary,
+) -> Result<()> {
+    let existing = match uid_map.get(&event.uid) {
+        Some(id) => match fetch_event(client, *id).await {
+            Ok(existing) => Some(existing),
+            // A mapping to an event deleted in Moodle since the last import is stale
+            Err(e) if error_code(&e) == Some("invalidrecord") => None,
+            Err(e) => return Err(e),
+        },
+        None => None,
+    };
+
+    if event.cancelled {
+        if let Some(existing) = existing {
+            delete_event(existing.id, true).await?;
+            summary.deleted += 1;
+        }
+        uid_map.remove(&event.uid);
+        return Ok(());
+    }
+
+    let new_event = to_new_event(event);
+    new_event.validate(&[])?;
+
+    match existing {
+        // Moodle can't change how often an event repeats, so a changed RRULE
+        // replaces the whole series
+        Some(existing) if series_length(&existing) != new_event.repeats.unwrap_or(1) => {
+            delete_event(existing.id, true).await?;
+            uid_map.remove(&event.uid);
+            let created = create_event(client, &new_event).await?;
+            if let Some(first) = created.first() {
+                uid_map.insert(event.uid.clone(), first.id);
+            }
+            summary.updated += 1;
+        }
+        Some(existing) => {
+            let unchanged = existing.name == new_event.name
+                && existing.timestart == new_event.timestart
+                && existing.timeduration == new_event.timeduration
+                && existing.location.as_deref().unwrap_or_default()
+                    == new_event.location.as_deref().unwrap_or_default()
+                && to_plain_text(existing.description.as_deref().unwrap_or_default())
+                    == to_plain_text(new_event.description.as_deref().unwrap_or_default());
+            if unchanged {
+                summary.unchanged += 1;
+            } else {
+                let series = existing.repeat_id.is_some();
+                submit_event_form(client, existing.id, &new_event, series).await?;
+                summary.updated += 1;
+            }
+        }
+        None => {
+            let created = create_event(client, &new_event).await?;
+            if let Some(first) = created.first() {
+                uid_map.insert(event.uid.clone(), first.id);
+            }
+            summary.created += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Number of events in the series `event` belongs to.
+fn series_length(event: &CalendarEvent) -> i64 {
+    if event.repeat_id.is_some() {
+        event.repeat_count
+    } else {
+        1
+    }
+}
+
+fn to_new_event(event: &IcsEvent) -> NewCalendarEvent {
+    NewCalendarEvent {
+        name: event.summary.chars().take(255).collect(),
+        description: event.description.as_deref().map(from_plain_text),
+        location: event.location.clone(),
+        event_type: CalendarEventType::User,
+        timestart: event.dtstart,
+        timeduration: event.dtend.map(|end| end - event.dtstart).unwrap_or(0),
+        course_id: None,
+        group_id: None,
+        category_id: None,
+        repeats: event.weekly_count.filter(|c| *c > 1).map(|c| c.min(MAX_REPEATS)),
+    }
+}
@@ -1,17 +1,32 @@
 pub mod add;
 pub mod delete;
 pub mod edit;
+pub mod event;
+pub mod export;
 pub mod get;
+pub mod ics;
+pub mod import;
+
+pub use event::{CalendarEvent, CalendarEventType, NewCalendarEvent};
+pub use get::{CalendarDay, CalendarWeek, MonthView};
+pub use ics::IcsEvent;
+pub use import::{IcsImportError, IcsImportSummary};
 
 use anyhow::Result;
 use moodle_client::MoodleClient;
 
-pub async fn login() -> Result<MoodleClient> {
+/// Site URL, username and password of the configured account.
+pub fn credentials() -> (String, String, String) {
     let _ = dotenvy::dotenv();
     let base_url =
         std::env::var("MOODLE_URL").unwrap_or_else(|_| "http://0.0.0.0/moodle".to_string());
     let username = std::env::var("MOODLE_USERNAME").unwrap_or_else(|_| "admin".to_string());
     let password = std::env::var("MOODLE_PASSWORD").unwrap_or_else(|_| "admin".to_string());
+    (base_url, username, password)
+}
+
+pub async fn login() -> Result<MoodleClient> {
+    let (base_url, username, password) = credentials();
 
     let token = moodle_client::login(&base_url, &username, &password).await?;
     Ok(MoodleClient::new(&base_url, &token))
@@ -0,0 +1,164 @@
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::site::get_current_user_id;
+use anyhow::Result;
+use moodle_api::core::competency::{
+    create_user_evidence_competency, delete_user_evidence, delete_user_evidence_competency,
+    request_review_of_user_evidence_linked_competencies,
+};
+use moodle_api::tool::lp::data_for_user_evidence_list_page;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceFile {
+    pub file_name: String,
+    pub file_size: i64,
+    /// Download URL with the token already appended
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceCompetency {
+    pub id: i64,
+    pub short_name: String,
+    pub proficient: Option<bool>,
+    pub rating: Option<String>,
+    pub review_status: Option<String>,
+}
+
+/// A piece of evidence of prior learning and the competencies it supports.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserEvidence {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+    pub url: Option<String>,
+    pub files: Vec<EvidenceFile>,
+    pub competencies: Vec<EvidenceCompetency>,
+    pub can_manage: bool,
+    pub time_modified: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserEvidenceList {
+    pub can_manage: bool,
+    /// Moodle has no web service to create evidence, so new entries are
+    /// added through this page in the browser and then linked here
+    pub add_url: Option<String>,
+    pub evidence: Vec<UserEvidence>,
+}
+
+/// The current user's evidence of prior learning.
+pub async fn get_user_evidence() -> Result<UserEvidenceList> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+    let params = data_for_user_evidence_list_page::Params { userid: Some(user_id) };
+    let json = client
+        .post("tool_lp_data_for_user_evidence_list_page", &params)
+        .await
+        .and_then(check_exception)?;
+    let page: data_for_user_evidence_list_page::Returns = serde_json::from_value(json)?;
+
+    let can_manage = page.canmanage.unwrap_or(false);
+    let add_url = page
+        .pluginbaseurl
+        .filter(|_| can_manage)
+        .map(|base| format!("{}/user_evidence_edit.php?userid={}", base.trim_end_matches('/'), user_id));
+    let mut evidence: Vec<UserEvidence> = page
+        .evidence
+        .unwrap_or_default()
+        .into_iter()
+        .map(|e| UserEvidence {
+            id: e.id.unwrap_or_default(),
+            name: e.name.unwrap_or_default(),
+            description: e.description.unwrap_or_default(),
+            url: e.url.filter(|u| !u.is_empty()),
+            files: e
+                .files
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|f| !f.isdir.unwrap_or(false))
+                .filter_map(|f| {
+                    Some(EvidenceFile {
+                        file_name: f.filename?,
+                        file_size: f.filesize.unwrap_or_default(),
+                        url: client.tokenize_url(&f.url?),
+                    })
+                })
+                .collect(),
+            competencies: e
+                .usercompetencies
+                .unwrap_or_default()
+                .into_iter()
+                .map(|uc| {
+                    let rated = uc.usercompetency.as_ref().and_then(|u| u.grade).is_some_and(|g| g > 0);
+                    EvidenceCompetency {
+                        id: uc.competency.as_ref().and_then(|c| c.id).unwrap_or_default(),
+                        short_name: uc.competency.and_then(|c| c.shortname).unwrap_or_default(),
+                        proficient: uc.usercompetency.as_ref().and_then(|u| u.proficiency).filter(|_| rated),
+                        rating: uc.usercompetency.as_ref().and_then(|u| u.gradename.clone()).filter(|_| rated),
+                        review_status: uc.usercompetency.and_then(|u| u.statusname),
+                    }
+                })
+                .collect(),
+            can_manage: e.canmanage.unwrap_or(false),
+            time_modified: e.timemodified.unwrap_or_default(),
+        })
+        .collect();
+    evidence.sort_by_key(|e| std::cmp::Reverse(e.time_modified));
+
+    Ok(UserEvidenceList {
+        can_manage,
+        add_url,
+        evidence,
+    })
+}
+
+/// Link a competency to a piece of evidence so it counts towards it.
+pub async fn link_evidence_competency(evidence_id: i64, competency_id: i64) -> Result<i64> {
+    let client = login().await?;
+    let params = create_user_evidence_competency::Params {
+        userevidenceid: Some(evidence_id),
+        competencyid: Some(competency_id),
+    };
+    let json = client
+        .post("core_competency_create_user_evidence_competency", &params)
+        .await
+        .and_then(check_exception)?;
+    let link: create_user_evidence_competency::Returns = serde_json::from_value(json)?;
+    Ok(link.id.unwrap_or_default())
+}
+
+pub async fn unlink_evidence_competency(evidence_id: i64, competency_id: i64) -> Result<bool> {
+    let client = login().await?;
+    let params = delete_user_evidence_competency::Params {
+        userevidenceid: Some(evidence_id),
+        competencyid: Some(competency_id),
+    };
+    let json = client
+        .post("core_competency_delete_user_evidence_competency", &params)
+        .await
+        .and_then(check_exception)?;
+    Ok(json.as_bool().unwrap_or(false))
+}
+
+/// Ask for review of every competency linked to a piece of evidence.
+pub async fn request_evidence_review(evidence_id: i64) -> Result<bool> {
+    let client = login().await?;
+    let params = request_review_of_user_evidence_linked_competencies::Params { id: Some(evidence_id) };
+    let json = client
+        .post("core_competency_request_review_of_user_evidence_linked_competencies", &params)
+        .await
+        .and_then(check_exception)?;
+    Ok(json.as_bool().unwrap_or(false))
+}
+
+pub async fn delete_evidence(evidence_id: i64) -> Result<bool> {
+    let client = login().await?;
+    let params = delete_user_evidence::Params { id: Some(evidence_id) };
+    let json = client
+        .post("core_competency_delete_user_evidence", &params)
+        .await
+        .and_then(check_exception)?;
+    Ok(json.as_bool().unwrap_or(false))
+}
@@ -0,0 +1,7 @@
+pub mod evidence;
+pub mod plans;
+pub mod summary;
+
+pub use evidence::*;
+pub use plans::*;
+pub use summary::*;
@@ -0,0 +1,189 @@
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::site::get_current_user_id;
+use anyhow::{anyhow, Result};
+use moodle_api::core::competency::list_user_plans;
+use moodle_api::tool::lp::data_for_plan_page;
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+
+/// Plan states as stored by `core_competency\plan`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanState {
+    Draft,
+    Active,
+    Complete,
+    WaitingForReview,
+    InReview,
+}
+
+impl PlanState {
+    fn from_status(status: i64) -> Self {
+        match status {
+            1 => PlanState::Active,
+            2 => PlanState::Complete,
+            3 => PlanState::WaitingForReview,
+            4 => PlanState::InReview,
+            _ => PlanState::Draft,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LearningPlan {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+    pub state: PlanState,
+    pub status_name: String,
+    pub due_date: Option<i64>,
+    /// Name of the template the plan was created from
+    pub template: Option<String>,
+    pub reviewer: Option<String>,
+    pub url: Option<String>,
+    pub competency_count: i64,
+    pub proficient_count: i64,
+    pub proficient_percentage: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanCompetency {
+    pub id: i64,
+    pub short_name: String,
+    pub id_number: String,
+    pub description: String,
+    pub framework: Option<String>,
+    /// Names of the parent competencies, outermost first
+    pub path: Vec<String>,
+    /// None until the competency has been rated
+    pub proficient: Option<bool>,
+    pub rating: Option<String>,
+    pub rating_value: Option<i64>,
+    /// Review status of the user competency, e.g. "Idle" or "In review"
+    pub review_status: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LearningPlanDetail {
+    pub plan: LearningPlan,
+    pub competencies: Vec<PlanCompetency>,
+}
+
+fn parse_plan(plan: list_user_plans::ReturnsItem) -> LearningPlan {
+    LearningPlan {
+        id: plan.id.unwrap_or_default(),
+        name: plan.name.unwrap_or_default(),
+        description: plan.description.unwrap_or_default(),
+        state: PlanState::from_status(plan.status.unwrap_or_default()),
+        status_name: plan.statusname.unwrap_or_default(),
+        due_date: plan.duedate.filter(|d| *d > 0),
+        template: plan.template.and_then(|t| t.shortname),
+        reviewer: plan.reviewer.and_then(|r| r.fullname),
+        url: plan.url,
+        competency_count: 0,
+        proficient_count: 0,
+        proficient_percentage: 0.0,
+    }
+}
+
+fn parse_plan_competency(item: data_for_plan_page::ReturnsCompetenciesItem) -> PlanCompetency {
+    let competency = item.competency;
+    let path = item.comppath;
+    // Completed plans keep the rating frozen at completion in `usercompetencyplan`
+    let (proficient, rating, rating_value, review_status) = match (item.usercompetencyplan, item.usercompetency) {
+        (Some(frozen), _) => (frozen.proficiency, frozen.gradename, frozen.grade, None),
+        (None, Some(current)) => (current.proficiency, current.gradename, current.grade, current.statusname),
+        (None, None) => (None, None, None, None),
+    };
+    PlanCompetency {
+        id: competency.as_ref().and_then(|c| c.id).unwrap_or_default(),
+        short_name: competency.as_ref().and_then(|c| c.shortname.clone()).unwrap_or_default(),
+        id_number: competency.as_ref().and_then(|c| c.idnumber.clone()).unwrap_or_default(),
+        description: competency.and_then(|c| c.description).unwrap_or_default(),
+        framework: path.as_ref().and_then(|p| p.framework.as_ref()).and_then(|f| f.name.clone()),
+        path: path
+            .and_then(|p| p.ancestors)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|a| a.name)
+            .collect(),
+        // Moodle sends 0 for "not rated yet"
+        proficient: rating_value.filter(|g| *g > 0).and(proficient),
+        rating: rating.filter(|r| !r.is_empty() && rating_value.is_some_and(|g| g > 0)),
+        rating_value: rating_value.filter(|g| *g > 0),
+        review_status,
+    }
+}
+
+async fn fetch_plan_page(client: &MoodleClient, plan_id: i64) -> Result<data_for_plan_page::Returns> {
+    let params = data_for_plan_page::Params { planid: Some(plan_id) };
+    let json = client
+        .post("tool_lp_data_for_plan_page", &params)
+        .await
+        .and_then(check_exception)?;
+    Ok(serde_json::from_value(json)?)
+}
+
+/// The current user's learning plans with how many of their competencies
+/// are proficient.
+pub async fn get_learning_plans() -> Result<Vec<LearningPlan>> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+    let params = list_user_plans::Params { userid: Some(user_id) };
+    let json = client
+        .post("core_competency_list_user_plans", &params)
+        .await
+        .and_then(check_exception)?;
+    let mut plans: Vec<LearningPlan> = serde_json::from_value::<list_user_plans::Returns>(json)?
+        .into_iter()
+        .map(parse_plan)
+        .collect();
+
+    let mut pages = tokio::task::JoinSet::new();
+    for (i, plan) in plans.iter().enumerate() {
+        let client = client.clone();
+        let plan_id = plan.id;
+        pages.spawn(async move { (i, fetch_plan_page(&client, plan_id).await) });
+    }
+    while let Some(done) = pages.join_next().await {
+        if let Ok((i, Ok(page))) = done {
+            plans[i].competency_count = page.competencycount.unwrap_or_default();
+            plans[i].proficient_count = page.proficientcompetencycount.unwrap_or_default();
+            plans[i].proficient_percentage = page.proficientcompetencypercentage.unwrap_or_default();
+        }
+    }
+
+    // Active plans first, then by due date
+    plans.sort_by_key(|p| (p.state != PlanState::Active, p.due_date.unwrap_or(i64::MAX), p.id));
+    Ok(plans)
+}
+
+/// A learning plan with each competency's proficiency and rating.
+pub async fn get_learning_plan(plan_id: i64) -> Result<LearningPlanDetail> {
+    let client = login().await?;
+    let page = fetch_plan_page(&client, plan_id).await?;
+    let plan = page.plan.map(|plan| {
+        // Same exporter as the list, so reuse its parsing
+        serde_json::to_value(plan)
+            .and_then(serde_json::from_value::<list_user_plans::ReturnsItem>)
+            .map(parse_plan)
+    });
+    let mut plan = match plan {
+        Some(plan) => plan?,
+        None => return Err(anyhow!("Learning plan {} not found", plan_id)),
+    };
+    plan.competency_count = page.competencycount.unwrap_or_default();
+    plan.proficient_count = page.proficientcompetencycount.unwrap_or_default();
+    plan.proficient_percentage = page.proficientcompetencypercentage.unwrap_or_default();
+
+    Ok(LearningPlanDetail {
+        plan,
+        competencies: page
+            .competencies
+            .unwrap_or_default()
+            .into_iter()
+            .map(parse_plan_competency)
+            .collect(),
+    })
+}
@@ -0,0 +1,225 @@
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::site::get_current_user_id;
+use anyhow::{anyhow, Result};
+use moodle_api::core::competency::user_competency_request_review;
+use moodle_api::tool::lp::{
+    data_for_user_competency_summary, data_for_user_competency_summary_in_course,
+    data_for_user_competency_summary_in_plan,
+};
+use serde::{Deserialize, Serialize};
+
+/// What an evidence entry did to the competency, as in `core_competency\evidence`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvidenceAction {
+    /// Recorded without changing the rating
+    Log,
+    /// Suggested a rating
+    Suggest,
+    /// Set the rating
+    Override,
+    /// Completed the competency
+    Complete,
+}
+
+impl EvidenceAction {
+    fn from_action(action: i64) -> Self {
+        match action {
+            1 => EvidenceAction::Suggest,
+            2 => EvidenceAction::Override,
+            3 => EvidenceAction::Complete,
+            _ => EvidenceAction::Log,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompetencyEvidence {
+    pub id: i64,
+    pub action: EvidenceAction,
+    pub description: String,
+    pub note: Option<String>,
+    pub url: Option<String>,
+    pub rating: Option<String>,
+    /// Who recorded the evidence, when it wasn't the system
+    pub action_user: Option<String>,
+    pub time_created: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContributingCourse {
+    pub id: i64,
+    pub full_name: String,
+    pub short_name: String,
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompetencySummary {
+    pub id: i64,
+    pub short_name: String,
+    pub id_number: String,
+    pub description: String,
+    pub framework: Option<String>,
+    pub path: Vec<String>,
+    pub proficient: Option<bool>,
+    pub rating: Option<String>,
+    pub review_status: Option<String>,
+    pub can_request_review: bool,
+    pub evidence: Vec<CompetencyEvidence>,
+    /// Courses whose activities can rate this competency
+    pub courses: Vec<ContributingCourse>,
+    pub related: Vec<String>,
+}
+
+fn parse_summary(summary: data_for_user_competency_summary::Returns) -> CompetencySummary {
+    let competency = summary.competency;
+    let definition = competency.as_ref().and_then(|c| c.competency.as_ref());
+    let user_competency = summary.usercompetency;
+
+    // The course or plan rating wins over the global one when the summary is scoped
+    let (proficient, grade, rating) = match (&summary.usercompetencycourse, &summary.usercompetencyplan) {
+        (Some(course), _) => (course.proficiency, course.grade, course.gradename.clone()),
+        (None, Some(plan)) => (plan.proficiency, plan.grade, plan.gradename.clone()),
+        _ => (
+            user_competency.as_ref().and_then(|u| u.proficiency),
+            user_competency.as_ref().and_then(|u| u.grade),
+            user_competency.as_ref().and_then(|u| u.gradename.clone()),
+        ),
+    };
+    let rated = grade.is_some_and(|g| g > 0);
+
+    let mut evidence: Vec<CompetencyEvidence> = summary
+        .evidence
+        .unwrap_or_default()
+        .into_iter()
+        .map(|e| CompetencyEvidence {
+            id: e.id.unwrap_or_default(),
+            action: EvidenceAction::from_action(e.action.unwrap_or_default()),
+            description: e.description.unwrap_or_default(),
+            note: e.note.filter(|n| !n.is_empty()),
+            url: e.url.filter(|u| !u.is_empty()),
+            rating: e.gradename.filter(|_| e.grade.is_some_and(|g| g > 0)),
+            action_user: e.actionuser.and_then(|u| u.fullname),
+            time_created: e.timecreated.unwrap_or_default(),
+        })
+        .collect();
+    evidence.sort_by_key(|e| std::cmp::Reverse(e.time_created));
+
+    CompetencySummary {
+        id: definition.and_then(|c| c.id).unwrap_or_default(),
+        short_name: definition.and_then(|c| c.shortname.clone()).unwrap_or_default(),
+        id_number: definition.and_then(|c| c.idnumber.clone()).unwrap_or_default(),
+        description: definition.and_then(|c| c.description.clone()).unwrap_or_default(),
+        framework: competency
+            .as_ref()
+            .and_then(|c| c.framework.as_ref())
+            .and_then(|f| f.shortname.clone()),
+        path: competency
+            .as_ref()
+            .and_then(|c| c.comppath.as_ref())
+            .and_then(|p| p.ancestors.as_ref())
+            .map(|ancestors| ancestors.iter().filter_map(|a| a.name.clone()).collect())
+            .unwrap_or_default(),
+        proficient: proficient.filter(|_| rated),
+        rating: rating.filter(|_| rated),
+        review_status: user_competency.as_ref().and_then(|u| u.statusname.clone()),
+        can_request_review: user_competency
+            .as_ref()
+            .and_then(|u| u.isrequestreviewallowed)
+            .unwrap_or(false),
+        evidence,
+        courses: competency
+            .as_ref()
+            .and_then(|c| c.linkedcourses.as_ref())
+            .map(|courses| {
+                courses
+                    .iter()
+                    .map(|c| ContributingCourse {
+                        id: c.id.unwrap_or_default(),
+                        full_name: c.fullname.clone().unwrap_or_default(),
+                        short_name: c.shortname.clone().unwrap_or_default(),
+                        url: c.viewurl.clone(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        related: competency
+            .and_then(|c| c.relatedcompetencies)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|c| c.shortname)
+            .collect(),
+    }
+}
+
+/// A competency with its rating, evidence and contributing courses. Scoped
+/// to a plan or a course when one is given, otherwise the user's overall
+/// competency.
+pub async fn get_competency_summary(
+    competency_id: i64,
+    plan_id: Option<i64>,
+    course_id: Option<i64>,
+) -> Result<CompetencySummary> {
+    let mut client = login().await?;
+    // The scoped pages wrap the same summary the global one returns
+    let summary = match (plan_id, course_id) {
+        (Some(plan_id), _) => {
+            let params = data_for_user_competency_summary_in_plan::Params {
+                competencyid: Some(competency_id),
+                planid: Some(plan_id),
+            };
+            client
+                .post("tool_lp_data_for_user_competency_summary_in_plan", &params)
+                .await
+                .and_then(check_exception)?
+                .get("usercompetencysummary")
+                .cloned()
+        }
+        (None, Some(course_id)) => {
+            let user_id = get_current_user_id(&mut client).await?;
+            let params = data_for_user_competency_summary_in_course::Params {
+                userid: Some(user_id),
+                competencyid: Some(competency_id),
+                courseid: Some(course_id),
+            };
+            client
+                .post("tool_lp_data_for_user_competency_summary_in_course", &params)
+                .await
+                .and_then(check_exception)?
+                .get("usercompetencysummary")
+                .cloned()
+        }
+        (None, None) => {
+            let user_id = get_current_user_id(&mut client).await?;
+            let params = data_for_user_competency_summary::Params {
+                userid: Some(user_id),
+                competencyid: Some(competency_id),
+            };
+            Some(
+                client
+                    .post("tool_lp_data_for_user_competency_summary", &params)
+                    .await
+                    .and_then(check_exception)?,
+            )
+        }
+    };
+    let summary = summary.ok_or_else(|| anyhow!("Competency {} not found", competency_id))?;
+    Ok(parse_summary(serde_json::from_value(summary)?))
+}
+
+/// Ask for the competency to be reviewed by the user's reviewer.
+pub async fn request_competency_review(competency_id: i64) -> Result<bool> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+    let params = user_competency_request_review::Params {
+        userid: Some(user_id),
+        competencyid: Some(competency_id),
+    };
+    let json = client
+        .post("core_competency_user_competency_request_review", &params)
+        .await
+        .and_then(check_exception)?;
+    Ok(json.as_bool().unwrap_or(false))
+}
@@ -1,62 +1,22 @@
-use moodle_api::core::webservice::get_site_info;
-use moodle_client::MoodleClient;
-use std::collections::HashMap;
-use std::io::{self, Write};
+use super::MessagingResult;
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::site::get_current_user_id;
+use anyhow::Result;
 
-pub async fn run(client: &mut MoodleClient) {
-    let user_id = match get_site_info::call_raw(
-        client,
-        &mut get_site_info::Params {
-            serviceshortnames: None,
-        },
-    )
-    .await
-    {
-        Ok(site_info) => site_info.get("userid").and_then(|v| v.as_i64()),
-        Err(e) => {
-            println!("Could not fetch current user id: {}", e);
-            return;
-        }
-    };
-    let user_id = match user_id {
-        Some(id) => id,
-        None => {
-            println!("Could not extract user id from site info.");
-            return;
-        }
-    };
+/// Removes users from the current user's contacts.
+pub async fn delete_contacts_for_user(user_ids: Vec<i64>) -> Result<MessagingResult> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
 
-    println!("=== DELETE CONTACTS ===");
-    println!("Current User ID: {}", user_id);
-    print!("Enter the User IDs to delete (comma separated): ");
-    io::stdout().flush().unwrap();
-    let mut input = String::new();
-    io::stdin().read_line(&mut input).unwrap();
-    let userids: Vec<i64> = input
-        .trim()
-        .split(',')
-        .filter_map(|s| s.trim().parse::<i64>().ok())
-        .collect();
-
-    if userids.is_empty() {
-        println!("No valid user IDs provided.");
-        return;
-    }
-
-    // Flatten userids for Moodle form
-    let mut form_params = HashMap::new();
-    for (i, uid) in userids.iter().enumerate() {
-        form_params.insert(format!("userids[{}]", i), uid.to_string());
+    let mut form: Vec<(String, String)> = vec![("userid".to_string(), user_id.to_string())];
+    for (i, contact_id) in user_ids.iter().enumerate() {
+        form.push((format!("userids[{}]", i), contact_id.to_string()));
     }
-    form_params.insert("userid".to_string(), user_id.to_string());
 
-    // Use the client directly for the POST
-    let result = client
-        .post("core_message_delete_contacts", &form_params)
-        .await;
-
-    match result {
-        Ok(json) => println!("Delete contacts result: {:#?}", json),
-        Err(e) => println!("Error deleting contacts: {}", e),
-    }
+    let json = client
+        .post("core_message_delete_contacts", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(MessagingResult::from_warnings(&json))
 }
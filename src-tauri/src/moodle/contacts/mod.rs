@@ -4,15 +4,10 @@ pub mod create_contact_request;
 pub mod decline_contact_request;
 pub mod delete_contacts;
 pub mod delete_conversations_by_id;
-pub mod enrol_user;
 pub mod get_contact_requests;
 pub mod get_member_info;
-pub mod get_popup_notifications;
-pub mod get_unread_popup_notification_count;
 pub mod get_user_contacts;
 pub mod mark_all_conversation_messages_as_read;
-pub mod mark_all_notifications_as_read;
-pub mod mark_notification_read;
 pub mod mute_conversations;
 pub mod search_contacts;
 pub mod set_favourite_conversations;
@@ -20,3 +15,42 @@ pub mod unblock_user;
 pub mod unenrol_user;
 pub mod unmute_conversations;
 pub mod unset_favourite_conversations;
+
+use crate::moodle::json::text;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessagingWarning {
+    pub item: Option<String>,
+    pub itemid: Option<i64>,
+    pub warningcode: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessagingResult {
+    pub success: bool,
+    pub warnings: Vec<MessagingWarning>,
+}
+
+impl MessagingResult {
+    /// Builds a result from the warnings list returned by the core_message write functions.
+    pub fn from_warnings(json: &serde_json::Value) -> Self {
+        let warnings: Vec<MessagingWarning> = json
+            .as_array()
+            .or_else(|| json.get("warnings").and_then(|w| w.as_array()))
+            .into_iter()
+            .flatten()
+            .map(|w| MessagingWarning {
+                item: text(w, "item"),
+                itemid: w.get("itemid").and_then(|i| i.as_i64()),
+                warningcode: text(w, "warningcode"),
+                message: text(w, "message"),
+            })
+            .collect();
+        MessagingResult {
+            success: warnings.is_empty(),
+            warnings,
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use super::{fetch_course_module, sanitize_html, ContentSection, UrlRewriter};
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{flag, int, list, text};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookChapter {
+    pub id: i64,
+    pub title: String,
+    /// 0 for chapters, 1 for subchapters
+    pub level: i64,
+    pub hidden: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookContent {
+    pub cmid: i64,
+    pub book_id: i64,
+    pub name: String,
+    /// Table of contents in reading order
+    pub chapters: Vec<BookChapter>,
+    /// Rendered chapters; only the requested one when a chapter is given
+    pub sections: Vec<ContentSection>,
+}
+
+/// Flattens the nested `structure` table of contents.
+fn flatten_structure(items: &[serde_json::Value], chapters: &mut Vec<BookChapter>) {
+    for item in items {
+        // href is "<chapterid>/index.html"
+        let id = text(item, "href")
+            .and_then(|href| href.split('/').next().and_then(|id| id.parse().ok()))
+            .unwrap_or(0);
+        chapters.push(BookChapter {
+            id,
+            title: text(item, "title").unwrap_or_default(),
+            level: int(item, "level"),
+            hidden: flag(item, "hidden"),
+        });
+        flatten_structure(list(item, "subitems"), chapters);
+    }
+}
+
+/// A Book activity's table of contents and chapter HTML. Chapters are files
+/// in the module contents, downloaded and rewritten so relative images load.
+pub async fn get_book_content(cmid: i64, chapter_id: Option<i64>) -> Result<BookContent> {
+    let mut client = login().await?;
+    let module = fetch_course_module(&mut client, cmid, "book").await?;
+
+    let form: Vec<(String, String)> = vec![
+        ("courseid".to_string(), module.course_id.to_string()),
+        ("options[0][name]".to_string(), "cmid".to_string()),
+        ("options[0][value]".to_string(), cmid.to_string()),
+    ];
+    let json = client
+        .post("core_course_get_contents", &form)
+        .await
+        .and_then(check_exception)?;
+    let book_module = json
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|section| list(section, "modules"))
+        .find(|m| int(m, "id") == cmid)
+        .ok_or_else(|| anyhow!("Book {} is not available", cmid))?;
+    let contents = list(book_module, "contents");
+
+    let mut chapters = Vec::new();
+    if let Some(structure) = contents
+        .iter()
+        .find(|c| text(c, "filename").as_deref() == Some("structure"))
+        .and_then(|c| text(c, "content"))
+    {
+        let items: Vec<serde_json::Value> = serde_json::from_str(&structure)?;
+        flatten_structure(&items, &mut chapters);
+    }
+
+    let mut sections = Vec::new();
+    for chapter in chapters
+        .iter()
+        .filter(|c| chapter_id.map_or(true, |id| id == c.id))
+    {
+        let path = format!("/{}/", chapter.id);
+        let Some(file_url) = contents
+            .iter()
+            .find(|c| {
+                text(c, "filename").as_deref() == Some("index.html") && text(c, "filepath").as_deref() == Some(path.as_str())
+            })
+            .and_then(|c| text(c, "fileurl"))
+        else {
+            continue;
+        };
+        let bytes = client.download(&file_url).await?;
+        let html = String::from_utf8_lossy(&bytes);
+        let rewriter = UrlRewriter::new(&client, Some(&file_url));
+        sections.push(ContentSection {
+            id: Some(chapter.id),
+            title: Some(chapter.title.clone()),
+            level: chapter.level,
+            html: sanitize_html(&html, &rewriter),
+        });
+    }
+
+    let mut view: Vec<(String, String)> = vec![("bookid".to_string(), module.instance.to_string())];
+    if let Some(chapter_id) = chapter_id {
+        view.push(("chapterid".to_string(), chapter_id.to_string()));
+    }
+    let _ = client.post("mod_book_view_book", &view).await;
+
+    Ok(BookContent {
+        cmid,
+        book_id: module.instance,
+        name: module.name,
+        chapters,
+        sections,
+    })
+}
@@ -0,0 +1,434 @@
+use super::{fetch_course_module, sanitize_html, ContentSection, UrlRewriter};
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::html::to_plain_text;
+use crate::moodle::json::{flag, int, list, text};
+use anyhow::{anyhow, Result};
+use moodle_client::MoodleClient;
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+
+/// Jump target meaning the end of the lesson.
+pub const LESSON_EOL: i64 = -9;
+/// Pages Moodle skips straight through (end of branch, clusters) are followed at most this many times.
+const MAX_REDIRECTS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LessonFieldKind {
+    Radio,
+    Checkbox,
+    Text,
+    Textarea,
+    Select,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LessonFieldOption {
+    pub value: String,
+    pub label: String,
+}
+
+/// An answer input from the page's question form.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LessonField {
+    /// Form name to send back, e.g. `answerid`, `answer[text]`, `response[12]`
+    pub name: String,
+    pub kind: LessonFieldKind,
+    pub label: Option<String>,
+    /// Choices for radio and select fields
+    pub options: Vec<LessonFieldOption>,
+    /// Value sent when a checkbox is ticked
+    pub value: Option<String>,
+}
+
+/// A content page button; `jump_to` is a page id or a relative jump such as -1 (next page).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LessonJump {
+    pub label: String,
+    pub jump_to: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LessonAnswer {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LessonPage {
+    pub lesson_id: i64,
+    pub page_id: i64,
+    pub title: String,
+    /// `multichoice`, `truefalse`, `shortanswer`, `numerical`, `matching`, `essay`, `branchtable`, ...
+    pub page_type: String,
+    pub is_question: bool,
+    pub sections: Vec<ContentSection>,
+    pub fields: Vec<LessonField>,
+    /// Hidden form values submitted with the answers
+    pub hidden: Vec<LessonAnswer>,
+    pub jumps: Vec<LessonJump>,
+    pub messages: Vec<String>,
+    /// Percentage of the lesson completed, when the progress bar is enabled
+    pub progress: Option<i64>,
+    pub ongoing_score: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LessonFinish {
+    pub grade: Option<f64>,
+    pub messages: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LessonAnswerResult {
+    pub new_page_id: i64,
+    /// Go to the next page without showing feedback
+    pub immediate_jump: bool,
+    pub correct: Option<bool>,
+    pub feedback: Option<String>,
+    pub attempts_remaining: Option<i64>,
+    pub max_attempts_reached: bool,
+    pub progress: Option<i64>,
+    pub ongoing_score: Option<String>,
+    /// Loaded when the jump is immediate
+    pub next_page: Option<LessonPage>,
+    /// Set when the answer ended the lesson
+    pub finished: Option<LessonFinish>,
+}
+
+fn qtype_name(qtype: i64) -> &'static str {
+    match qtype {
+        1 => "shortanswer",
+        2 => "truefalse",
+        3 => "multichoice",
+        5 => "matching",
+        8 => "numerical",
+        10 => "essay",
+        20 => "branchtable",
+        21 => "endofbranch",
+        30 => "cluster",
+        31 => "endofcluster",
+        _ => "unknown",
+    }
+}
+
+fn selector(css: &str) -> Selector {
+    Selector::parse(css).expect("valid selector")
+}
+
+fn element_text(element: ElementRef) -> String {
+    element.text().collect::<Vec<_>>().join(" ").split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn field_label(document: &Html, input: ElementRef) -> Option<String> {
+    let id = input.value().attr("id")?;
+    let label_selector = Selector::parse(&format!("label[for=\"{}\"]", id)).ok()?;
+    document
+        .select(&label_selector)
+        .next()
+        .map(element_text)
+        .filter(|l| !l.is_empty())
+}
+
+/// Reads the answer fields, hidden values and branch buttons out of the
+/// rendered page form, the same form a browser would submit.
+fn parse_page_form(html: &str) -> (Vec<LessonField>, Vec<LessonAnswer>, Vec<LessonJump>) {
+    let document = Html::parse_fragment(html);
+    let mut fields: Vec<LessonField> = Vec::new();
+    let mut hidden = Vec::new();
+    let mut jumps = Vec::new();
+
+    for form in document.select(&selector("form")) {
+        let jump = form
+            .select(&selector("input[name=\"jumpto\"]"))
+            .next()
+            .and_then(|i| i.value().attr("value"))
+            .and_then(|v| v.parse::<i64>().ok());
+        if let Some(jump_to) = jump {
+            let label = form
+                .select(&selector("button, input[type=\"submit\"]"))
+                .next()
+                .map(|b| b.value().attr("value").map(str::to_string).unwrap_or_else(|| element_text(b)))
+                .unwrap_or_default();
+            jumps.push(LessonJump { label, jump_to });
+            continue;
+        }
+
+        for input in form.select(&selector("input, textarea, select")) {
+            let element = input.value();
+            let Some(name) = element.attr("name") else { continue };
+            if name == "sesskey" || name.starts_with("_qf__") {
+                continue;
+            }
+            let value = element.attr("value").unwrap_or("").to_string();
+            match (element.name(), element.attr("type").unwrap_or("text")) {
+                ("input", "hidden") => hidden.push(LessonAnswer {
+                    name: name.to_string(),
+                    value,
+                }),
+                ("input", "submit" | "button" | "reset" | "image") => {}
+                ("input", "radio") => {
+                    let option = LessonFieldOption {
+                        label: field_label(&document, input).unwrap_or_else(|| value.clone()),
+                        value,
+                    };
+                    match fields.iter_mut().find(|f| f.name == name && f.kind == LessonFieldKind::Radio) {
+                        Some(field) => field.options.push(option),
+                        None => fields.push(LessonField {
+                            name: name.to_string(),
+                            kind: LessonFieldKind::Radio,
+                            label: None,
+                            options: vec![option],
+                            value: None,
+                        }),
+                    }
+                }
+                ("input", "checkbox") => fields.push(LessonField {
+                    name: name.to_string(),
+                    kind: LessonFieldKind::Checkbox,
+                    label: field_label(&document, input),
+                    options: Vec::new(),
+                    value: Some(if value.is_empty() { "1".to_string() } else { value }),
+                }),
+                ("select", _) => fields.push(LessonField {
+                    name: name.to_string(),
+                    kind: LessonFieldKind::Select,
+                    label: field_label(&document, input),
+                    options: input
+                        .select(&selector("option"))
+                        .map(|o| LessonFieldOption {
+                            value: o.value().attr("value").unwrap_or("").to_string(),
+                            label: element_text(o),
+                        })
+                        .collect(),
+                    value: None,
+                }),
+                (tag, _) => fields.push(LessonField {
+                    name: name.to_string(),
+                    kind: if tag == "textarea" { LessonFieldKind::Textarea } else { LessonFieldKind::Text },
+                    label: field_label(&document, input),
+                    options: Vec::new(),
+                    value: None,
+                }),
+            }
+        }
+    }
+    (fields, hidden, jumps)
+}
+
+fn lesson_form(lesson_id: i64, password: Option<&str>) -> Vec<(String, String)> {
+    let mut form: Vec<(String, String)> = vec![("lessonid".to_string(), lesson_id.to_string())];
+    if let Some(password) = password.filter(|p| !p.is_empty()) {
+        form.push(("password".to_string(), password.to_string()));
+    }
+    form
+}
+
+fn messages(json: &serde_json::Value) -> Vec<String> {
+    list(json, "messages")
+        .iter()
+        .filter_map(|m| text(m, "message"))
+        .map(|m| to_plain_text(&m))
+        .filter(|m| !m.is_empty())
+        .collect()
+}
+
+fn optional_int(json: &serde_json::Value, key: &str) -> Option<i64> {
+    json.get(key).and_then(|v| v.as_i64())
+}
+
+enum PageData {
+    Page(Box<LessonPage>),
+    Finished,
+}
+
+async fn fetch_page(client: &mut MoodleClient, lesson_id: i64, page_id: i64, password: Option<&str>) -> Result<PageData> {
+    let mut page_id = page_id;
+    for _ in 0..MAX_REDIRECTS {
+        if page_id == LESSON_EOL {
+            return Ok(PageData::Finished);
+        }
+        let mut form = lesson_form(lesson_id, password);
+        form.push(("pageid".to_string(), page_id.to_string()));
+        form.push(("returncontents".to_string(), "1".to_string()));
+        let json = client
+            .post("mod_lesson_get_page_data", &form)
+            .await
+            .and_then(check_exception)?;
+
+        // Structural pages (end of branch, clusters) are never shown; Moodle says where to go instead
+        let new_page_id = int(&json, "newpageid");
+        let Some(page) = json.get("page").filter(|p| p.is_object()) else {
+            if new_page_id != 0 && new_page_id != page_id {
+                page_id = new_page_id;
+                continue;
+            }
+            return Ok(PageData::Finished);
+        };
+        let qtype = int(page, "qtype");
+        if matches!(qtype, 21 | 30 | 31) && new_page_id != 0 && new_page_id != page_id {
+            page_id = new_page_id;
+            continue;
+        }
+
+        let rewriter = UrlRewriter::new(client, None);
+        let contents = sanitize_html(&text(page, "contents").unwrap_or_default(), &rewriter);
+        let (fields, hidden, jumps) = parse_page_form(&text(&json, "pagecontent").unwrap_or_default());
+        return Ok(PageData::Page(Box::new(LessonPage {
+            lesson_id,
+            page_id: int(page, "id"),
+            title: text(page, "title").unwrap_or_default(),
+            page_type: qtype_name(qtype).to_string(),
+            is_question: int(page, "type") == 0,
+            sections: vec![ContentSection {
+                id: Some(int(page, "id")),
+                title: text(page, "title"),
+                level: 0,
+                html: contents,
+            }],
+            fields,
+            hidden,
+            jumps,
+            messages: messages(&json),
+            progress: optional_int(&json, "progress"),
+            ongoing_score: text(&json, "ongoingscore"),
+        })));
+    }
+    Err(anyhow!("The lesson kept redirecting from page {}", page_id))
+}
+
+async fn fetch_finish(client: &mut MoodleClient, lesson_id: i64, password: Option<&str>) -> Result<LessonFinish> {
+    let json = client
+        .post("mod_lesson_finish_attempt", &lesson_form(lesson_id, password))
+        .await
+        .and_then(check_exception)?;
+    let data = list(&json, "data");
+    let grade = data
+        .iter()
+        .find(|d| text(d, "name").as_deref() == Some("gradeinfo"))
+        .and_then(|d| text(d, "value"))
+        .and_then(|v| serde_json::from_str::<serde_json::Value>(&v).ok())
+        .and_then(|info| info.get("grade").and_then(|g| g.as_f64()));
+    let mut result = messages(&json);
+    result.extend(
+        data.iter()
+            .filter_map(|d| text(d, "message"))
+            .map(|m| to_plain_text(&m))
+            .filter(|m| !m.is_empty()),
+    );
+    Ok(LessonFinish {
+        grade,
+        messages: result,
+    })
+}
+
+/// Starts (or resumes) an attempt and returns the first page to show.
+pub async fn start_lesson(cmid: i64, password: Option<String>) -> Result<LessonPage> {
+    let mut client = login().await?;
+    let module = fetch_course_module(&mut client, cmid, "lesson").await?;
+    let lesson_id = module.instance;
+    let password = password.as_deref();
+
+    let access = client
+        .post("mod_lesson_get_lesson_access_information", &lesson_form(lesson_id, None))
+        .await
+        .and_then(check_exception)?;
+    for reason in list(&access, "preventaccessreasons") {
+        let code = text(reason, "reason").unwrap_or_default();
+        if code == "passwordprotectedlesson" && password.is_some() {
+            continue;
+        }
+        let message = text(reason, "message").map(|m| to_plain_text(&m));
+        return Err(anyhow!(message.unwrap_or(code)));
+    }
+
+    // Resume where the user left off in an unfinished attempt
+    let last_page = int(&access, "lastpageseen");
+    let page_id = if last_page > 0 { last_page } else { int(&access, "firstpageid") };
+
+    let mut launch = lesson_form(lesson_id, password);
+    launch.push(("pageid".to_string(), page_id.to_string()));
+    client
+        .post("mod_lesson_launch_attempt", &launch)
+        .await
+        .and_then(check_exception)?;
+
+    match fetch_page(&mut client, lesson_id, page_id, password).await? {
+        PageData::Page(page) => Ok(*page),
+        PageData::Finished => Err(anyhow!("This lesson has no pages to show")),
+    }
+}
+
+/// A specific lesson page, e.g. after reading the feedback of an answer.
+pub async fn get_lesson_page(lesson_id: i64, page_id: i64, password: Option<String>) -> Result<Option<LessonPage>> {
+    let mut client = login().await?;
+    match fetch_page(&mut client, lesson_id, page_id, password.as_deref()).await? {
+        PageData::Page(page) => Ok(Some(*page)),
+        PageData::Finished => Ok(None),
+    }
+}
+
+/// Submits a page's answers (or a branch button via a `jumpto` answer) and
+/// follows the jump Moodle decides on, ending the attempt at the end of lesson.
+pub async fn answer_lesson_page(
+    lesson_id: i64,
+    page_id: i64,
+    answers: Vec<LessonAnswer>,
+    password: Option<String>,
+) -> Result<LessonAnswerResult> {
+    let mut client = login().await?;
+    let password = password.as_deref();
+
+    let mut form = lesson_form(lesson_id, password);
+    form.push(("pageid".to_string(), page_id.to_string()));
+    for (i, answer) in answers.iter().enumerate() {
+        form.push((format!("data[{}][name]", i), answer.name.clone()));
+        form.push((format!("data[{}][value]", i), answer.value.clone()));
+    }
+    let json = client
+        .post("mod_lesson_process_page", &form)
+        .await
+        .and_then(check_exception)?;
+
+    let new_page_id = int(&json, "newpageid");
+    let immediate_jump = flag(&json, "inmediatejump");
+    let no_answer = flag(&json, "noanswer");
+    let rewriter = UrlRewriter::new(&client, None);
+    let feedback = text(&json, "feedback")
+        .map(|f| sanitize_html(&f, &rewriter))
+        .filter(|f| !f.is_empty());
+
+    let mut result = LessonAnswerResult {
+        new_page_id,
+        immediate_jump,
+        correct: if no_answer || flag(&json, "isessayquestion") || immediate_jump {
+            None
+        } else {
+            Some(flag(&json, "correctanswer"))
+        },
+        feedback,
+        attempts_remaining: optional_int(&json, "attemptsremaining"),
+        max_attempts_reached: flag(&json, "maxattemptsreached"),
+        progress: optional_int(&json, "progress"),
+        ongoing_score: text(&json, "ongoingscore"),
+        next_page: None,
+        finished: None,
+    };
+
+    if new_page_id == LESSON_EOL {
+        result.finished = Some(fetch_finish(&mut client, lesson_id, password).await?);
+    } else if immediate_jump {
+        match fetch_page(&mut client, lesson_id, new_page_id, password).await? {
+            PageData::Page(page) => result.next_page = Some(*page),
+            PageData::Finished => result.finished = Some(fetch_finish(&mut client, lesson_id, password).await?),
+        }
+    }
+    Ok(result)
+}
+
+/// Ends the current attempt, e.g. when the user leaves with "End of lesson".
+pub async fn finish_lesson(lesson_id: i64, password: Option<String>) -> Result<LessonFinish> {
+    let mut client = login().await?;
+    fetch_finish(&mut client, lesson_id, password.as_deref()).await
+}
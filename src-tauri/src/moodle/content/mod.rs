@@ -0,0 +1,44 @@
+pub mod book;
+pub mod lesson;
+pub mod page;
+pub mod render;
+
+pub use book::*;
+pub use lesson::*;
+pub use page::*;
+pub use render::{sanitize_html, ContentSection, UrlRewriter};
+
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{int, text};
+use anyhow::{anyhow, Result};
+use moodle_client::MoodleClient;
+
+/// The parts of a course module needed to call its activity's functions.
+#[derive(Debug, Clone)]
+pub struct CourseModuleRef {
+    pub cmid: i64,
+    pub course_id: i64,
+    pub instance: i64,
+    pub name: String,
+    pub modname: String,
+}
+
+pub async fn fetch_course_module(client: &mut MoodleClient, cmid: i64, modname: &str) -> Result<CourseModuleRef> {
+    let form: Vec<(String, String)> = vec![("cmid".to_string(), cmid.to_string())];
+    let json = client
+        .post("core_course_get_course_module", &form)
+        .await
+        .and_then(check_exception)?;
+    let cm = json.get("cm").ok_or_else(|| anyhow!("Activity {} not found", cmid))?;
+    let module = CourseModuleRef {
+        cmid,
+        course_id: int(cm, "course"),
+        instance: int(cm, "instance"),
+        name: text(cm, "name").unwrap_or_default(),
+        modname: text(cm, "modname").unwrap_or_default(),
+    };
+    if module.modname != modname {
+        return Err(anyhow!("Activity {} is a {}, not a {}", cmid, module.modname, modname));
+    }
+    Ok(module)
+}
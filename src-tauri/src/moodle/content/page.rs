@@ -0,0 +1,55 @@
+use super::{fetch_course_module, sanitize_html, ContentSection, UrlRewriter};
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{int, list, text};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageContent {
+    pub cmid: i64,
+    pub page_id: i64,
+    pub name: String,
+    pub intro: Option<String>,
+    pub sections: Vec<ContentSection>,
+    pub time_modified: Option<i64>,
+}
+
+/// The content of a Page activity, sanitized, and logs the view for completion.
+pub async fn get_page_content(cmid: i64) -> Result<PageContent> {
+    let mut client = login().await?;
+    let module = fetch_course_module(&mut client, cmid, "page").await?;
+
+    let form: Vec<(String, String)> = vec![("courseids[0]".to_string(), module.course_id.to_string())];
+    let json = client
+        .post("mod_page_get_pages_by_courses", &form)
+        .await
+        .and_then(check_exception)?;
+    let page = list(&json, "pages")
+        .iter()
+        .find(|p| int(p, "coursemodule") == cmid)
+        .ok_or_else(|| anyhow!("Page {} is not available", cmid))?;
+
+    let rewriter = UrlRewriter::new(&client, None);
+    let content = sanitize_html(&text(page, "content").unwrap_or_default(), &rewriter);
+    let intro = text(page, "intro")
+        .map(|i| sanitize_html(&i, &rewriter))
+        .filter(|i| !i.is_empty());
+
+    let view: Vec<(String, String)> = vec![("pageid".to_string(), int(page, "id").to_string())];
+    let _ = client.post("mod_page_view_page", &view).await;
+
+    Ok(PageContent {
+        cmid,
+        page_id: int(page, "id"),
+        name: text(page, "name").unwrap_or(module.name),
+        intro,
+        sections: vec![ContentSection {
+            id: None,
+            title: None,
+            level: 0,
+            html: content,
+        }],
+        time_modified: page.get("timemodified").and_then(|t| t.as_i64()),
+    })
+}
@@ -0,0 +1,265 @@
+use moodle_client::MoodleClient;
+use reqwest::Url;
+use scraper::{ElementRef, Html, Node};
+use serde::{Deserialize, Serialize};
+
+/// Elements kept as they are; anything else is dropped but its text kept.
+const ALLOWED_TAGS: &[&str] = &[
+    "a", "abbr", "audio", "b", "blockquote", "br", "caption", "cite", "code", "col", "colgroup",
+    "dd", "del", "details", "div", "dl", "dt", "em", "figcaption", "figure", "h1", "h2", "h3",
+    "h4", "h5", "h6", "hr", "i", "iframe", "img", "ins", "kbd", "li", "mark", "ol", "p", "pre",
+    "q", "s", "small", "source", "span", "strong", "sub", "summary", "sup", "table", "tbody",
+    "td", "tfoot", "th", "thead", "tr", "track", "u", "ul", "video",
+];
+
+/// Elements removed together with their content.
+const DROPPED_TAGS: &[&str] = &[
+    "script", "style", "noscript", "object", "embed", "form", "input", "button", "select",
+    "textarea", "link", "meta", "head", "title",
+];
+
+const ALLOWED_ATTRIBUTES: &[&str] = &[
+    "alt", "class", "colspan", "controls", "height", "href", "kind", "label", "lang", "poster",
+    "rowspan", "scope", "src", "srclang", "title", "type", "width", "allowfullscreen", "open",
+];
+
+const URL_ATTRIBUTES: &[&str] = &["href", "src", "poster"];
+
+const VOID_TAGS: &[&str] = &["br", "col", "hr", "img", "source", "track"];
+
+/// Hosts whose players may be embedded in an iframe.
+const EMBED_HOSTS: &[&str] = &[
+    "www.youtube.com",
+    "youtube.com",
+    "www.youtube-nocookie.com",
+    "player.vimeo.com",
+];
+
+/// A block of sanitized HTML the UI can render directly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentSection {
+    pub id: Option<i64>,
+    pub title: Option<String>,
+    /// Nesting depth, for book subchapters
+    pub level: i64,
+    pub html: String,
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn media_tag(url: &str) -> Option<&'static str> {
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+    let extension = path.rsplit('.').next().unwrap_or("");
+    match extension {
+        "mp4" | "webm" | "m4v" | "ogv" | "mov" => Some("video"),
+        "mp3" | "ogg" | "oga" | "wav" | "m4a" | "aac" => Some("audio"),
+        _ => None,
+    }
+}
+
+fn is_safe_url(url: &str) -> bool {
+    let lower = url.trim().to_lowercase();
+    lower.starts_with("http://")
+        || lower.starts_with("https://")
+        || lower.starts_with("mailto:")
+        || lower.starts_with("data:image/")
+        || lower.starts_with('#')
+}
+
+/// Resolves relative links and attaches the token to Moodle file URLs so
+/// images and media load outside a browser session.
+pub struct UrlRewriter<'a> {
+    client: &'a MoodleClient,
+    base: Option<Url>,
+}
+
+impl<'a> UrlRewriter<'a> {
+    pub fn new(client: &'a MoodleClient, base: Option<&str>) -> Self {
+        UrlRewriter {
+            client,
+            base: base.and_then(|b| Url::parse(b).ok()),
+        }
+    }
+
+    pub fn rewrite(&self, url: &str) -> String {
+        let url = url.trim();
+        let absolute = match &self.base {
+            Some(base) if !url.contains(':') && !url.starts_with('#') => {
+                base.join(url).map(|u| u.to_string()).unwrap_or_else(|_| url.to_string())
+            }
+            _ => url.to_string(),
+        };
+        if absolute.contains("/pluginfile.php") && !absolute.contains("token=") {
+            self.client.tokenize_url(&absolute)
+        } else {
+            absolute
+        }
+    }
+}
+
+fn write_element(element: ElementRef, rewriter: &UrlRewriter, out: &mut String) {
+    let name = element.value().name();
+    if DROPPED_TAGS.contains(&name) {
+        return;
+    }
+    if !ALLOWED_TAGS.contains(&name) {
+        write_children(element, rewriter, out);
+        return;
+    }
+
+    let mut attributes: Vec<(&str, String)> = Vec::new();
+    for (attribute, value) in element.value().attrs() {
+        if !ALLOWED_ATTRIBUTES.contains(&attribute) {
+            continue;
+        }
+        if URL_ATTRIBUTES.contains(&attribute) {
+            let value = rewriter.rewrite(value);
+            if !is_safe_url(&value) {
+                continue;
+            }
+            attributes.push((attribute, value));
+        } else {
+            attributes.push((attribute, value.to_string()));
+        }
+    }
+
+    let src = attributes.iter().find(|(a, _)| *a == "src").map(|(_, v)| v.clone());
+    if name == "iframe" {
+        let allowed = src
+            .as_deref()
+            .and_then(|s| Url::parse(s).ok())
+            .is_some_and(|u| u.host_str().is_some_and(|h| EMBED_HOSTS.contains(&h)));
+        if !allowed {
+            return;
+        }
+    }
+
+    // A bare link to a video or audio file becomes a player, as Moodle's media filter would do
+    if name == "a" {
+        if let Some((_, href)) = attributes.iter().find(|(a, _)| *a == "href") {
+            if let Some(tag) = media_tag(href) {
+                let label = element.text().collect::<String>();
+                out.push_str(&format!(
+                    "<{tag} controls preload=\"metadata\" src=\"{}\" title=\"{}\"></{tag}>",
+                    escape(href),
+                    escape(label.trim())
+                ));
+                return;
+            }
+            attributes.push(("target", "_blank".to_string()));
+            attributes.push(("rel", "noopener noreferrer".to_string()));
+        }
+    }
+    if name == "img" {
+        attributes.push(("loading", "lazy".to_string()));
+    }
+
+    out.push('<');
+    out.push_str(name);
+    for (attribute, value) in &attributes {
+        out.push_str(&format!(" {}=\"{}\"", attribute, escape(value)));
+    }
+    out.push('>');
+    if VOID_TAGS.contains(&name) {
+        return;
+    }
+    write_children(element, rewriter, out);
+    out.push_str(&format!("</{}>", name));
+}
+
+fn write_children(element: ElementRef, rewriter: &UrlRewriter, out: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => out.push_str(&escape(text)),
+            Node::Element(_) => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    write_element(child, rewriter, out);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Rebuilds Moodle HTML with only safe elements and attributes, scripts and
+/// inline styles removed and every link passed through `rewriter`.
+pub fn sanitize_html(html: &str, rewriter: &UrlRewriter) -> String {
+    let fragment = Html::parse_fragment(html);
+    let mut out = String::new();
+    write_children(fragment.root_element(), rewriter, &mut out);
+    out.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sanitize(html: &str) -> String {
+        let client = MoodleClient::new("https://moodle.example.com", "secret");
+        sanitize_html(
+            html,
+            &UrlRewriter::new(
+                &client,
+                Some("https://moodle.example.com/mod/page/view.php"),
+            ),
+        )
+    }
+
+    #[test]
+    fn removes_scripts_styles_and_handlers() {
+        let cases = [
+            ("<p>Hi<script>alert(1)</script></p>", "<p>Hi</p>"),
+            ("<style>p { color: red }</style><p>Hi</p>", "<p>Hi</p>"),
+            (
+                "<p onclick=\"alert(1)\" style=\"color: red\">Hi</p>",
+                "<p>Hi</p>",
+            ),
+            (
+                "<img src=\"x.png\" onerror=\"alert(1)\">",
+                "<img src=\"https://moodle.example.com/mod/page/x.png\" loading=\"lazy\">",
+            ),
+            ("<form><input value=\"x\"></form><blink>Hi</blink>", "Hi"),
+        ];
+        for (html, expected) in cases {
+            assert_eq!(sanitize(html), expected, "{}", html);
+        }
+    }
+
+    #[test]
+    fn drops_unsafe_urls() {
+        let cases = [
+            ("<a href=\"javascript:alert(1)\">x</a>", "<a>x</a>"),
+            ("<a href=\" JavaScript:alert(1)\">x</a>", "<a>x</a>"),
+            ("<img src=\"data:text/html;base64,PHNjcmlwdD4=\">", "<img loading=\"lazy\">"),
+            ("<img src=\"data:image/png;base64,iVBORw0=\">", "<img src=\"data:image/png;base64,iVBORw0=\" loading=\"lazy\">"),
+            (
+                "<a href=\"https://example.org/\">x</a>",
+                "<a href=\"https://example.org/\" target=\"_blank\" rel=\"noopener noreferrer\">x</a>",
+            ),
+        ];
+        for (html, expected) in cases {
+            assert_eq!(sanitize(html), expected, "{}", html);
+        }
+    }
+
+    #[test]
+    fn embeds_only_known_players() {
+        let cases = [
+            ("https://www.youtube.com/embed/abc", true),
+            ("https://www.youtube-nocookie.com/embed/abc", true),
+            ("https://player.vimeo.com/video/1", true),
+            ("https://youtube.com.evil.example/embed/abc", false),
+            ("https://evil.example/?https://www.youtube.com/", false),
+            ("javascript:alert(1)", false),
+        ];
+        for (src, kept) in cases {
+            let html = sanitize(&format!("<iframe src=\"{}\"></iframe>", src));
+            assert_eq!(html.contains("<iframe"), kept, "{}", src);
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{flag, int, list, text};
+use crate::moodle::site::get_current_user_id;
+use anyhow::{anyhow, Result};
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnrolmentMethod {
+    pub instance_id: i64,
+    pub course_id: i64,
+    /// Enrolment plugin, e.g. `self`, `guest`, `manual`, `fee`
+    pub method_type: String,
+    pub name: String,
+    /// The current user can use this method from the app
+    pub available: bool,
+    /// Why the method can't be used ("Enrolment is disabled", "Cannot enrol yourself in this course")
+    pub unavailable_reason: Option<String>,
+    /// An enrolment key (or guest password) must be entered first
+    pub requires_password: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CourseEnrolmentOptions {
+    pub course_id: i64,
+    pub is_enrolled: bool,
+    pub methods: Vec<EnrolmentMethod>,
+    pub can_self_enrol: bool,
+    pub can_guest_access: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnrolmentResult {
+    pub enrolled: bool,
+    /// The key was wrong or missing; ask for it again
+    pub invalid_password: bool,
+    /// Moodle's warning or the enrolment key hint
+    pub message: Option<String>,
+}
+
+/// Enrolment instances report availability as `true` or as the reason it is not possible.
+fn instance_status(info: &serde_json::Value) -> (bool, Option<String>) {
+    match info.get("status") {
+        Some(serde_json::Value::Bool(true)) => (true, None),
+        Some(serde_json::Value::String(s)) if s == "1" || s == "true" => (true, None),
+        Some(serde_json::Value::String(s)) if !s.is_empty() && s != "0" => (false, Some(s.clone())),
+        _ => (false, None),
+    }
+}
+
+async fn fetch_method_info(client: &mut MoodleClient, method: &mut EnrolmentMethod) -> Result<()> {
+    let form: Vec<(String, String)> = vec![("instanceid".to_string(), method.instance_id.to_string())];
+    match method.method_type.as_str() {
+        "self" => {
+            let info = client
+                .post("enrol_self_get_instance_info", &form)
+                .await
+                .and_then(check_exception)?;
+            let (available, reason) = instance_status(&info);
+            method.available = available;
+            method.unavailable_reason = reason;
+            method.requires_password = text(&info, "enrolpassword").is_some();
+        }
+        "guest" => {
+            let json = client
+                .post("enrol_guest_get_instance_info", &form)
+                .await
+                .and_then(check_exception)?;
+            let info = json.get("instanceinfo").cloned().unwrap_or_default();
+            method.available = flag(&info, "status");
+            method.requires_password = flag(&info, "passwordrequired");
+        }
+        _ => {
+            // Payment, manual and cohort enrolment can't be completed from the app
+            method.available = false;
+        }
+    }
+    Ok(())
+}
+
+async fn fetch_is_enrolled(client: &mut MoodleClient, course_id: i64) -> Result<bool> {
+    let user_id = get_current_user_id(client).await?;
+    let form: Vec<(String, String)> = vec![("userid".to_string(), user_id.to_string())];
+    let json = client
+        .post("core_enrol_get_users_courses", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(json
+        .as_array()
+        .is_some_and(|courses| courses.iter().any(|c| int(c, "id") == course_id)))
+}
+
+/// The ways the current user can join a course, with self enrolment and
+/// guest access details filled in.
+pub async fn get_enrolment_methods(course_id: i64) -> Result<CourseEnrolmentOptions> {
+    let mut client = login().await?;
+
+    let form: Vec<(String, String)> = vec![("courseid".to_string(), course_id.to_string())];
+    let json = client
+        .post("core_enrol_get_course_enrolment_methods", &form)
+        .await
+        .and_then(check_exception)?;
+    let instances = json
+        .as_array()
+        .ok_or_else(|| anyhow!("Could not load the enrolment methods of course {}", course_id))?;
+
+    let mut methods = Vec::new();
+    for instance in instances {
+        let method_type = text(instance, "type").unwrap_or_default();
+        // Moodle only lists enabled instances, but "status" says whether the user may use it
+        let (available, unavailable_reason) = instance_status(instance);
+        let mut method = EnrolmentMethod {
+            instance_id: int(instance, "id"),
+            course_id,
+            name: text(instance, "name").unwrap_or_else(|| method_type.clone()),
+            method_type,
+            available,
+            unavailable_reason,
+            requires_password: false,
+        };
+        if method.available {
+            // An instance that fails to load is shown as unavailable rather than failing the page
+            if let Err(e) = fetch_method_info(&mut client, &mut method).await {
+                method.available = false;
+                method.unavailable_reason = Some(e.to_string());
+            }
+        }
+        methods.push(method);
+    }
+
+    let is_enrolled = fetch_is_enrolled(&mut client, course_id).await.unwrap_or(false);
+    let usable = |kind: &str| methods.iter().any(|m| m.method_type == kind && m.available);
+    Ok(CourseEnrolmentOptions {
+        course_id,
+        is_enrolled,
+        can_self_enrol: usable("self"),
+        can_guest_access: usable("guest"),
+        methods,
+    })
+}
+
+/// Self-enrols the current user. Without an `instance_id` Moodle picks the
+/// course's self enrolment instance that accepts the key.
+pub async fn self_enrol(course_id: i64, instance_id: Option<i64>, password: Option<String>) -> Result<EnrolmentResult> {
+    let client = login().await?;
+
+    let mut form: Vec<(String, String)> = vec![("courseid".to_string(), course_id.to_string())];
+    if let Some(password) = password.filter(|p| !p.is_empty()) {
+        form.push(("password".to_string(), password));
+    }
+    if let Some(instance_id) = instance_id {
+        form.push(("instanceid".to_string(), instance_id.to_string()));
+    }
+
+    let json = client
+        .post("enrol_self_enrol_user", &form)
+        .await
+        .and_then(check_exception)?;
+    let warnings = list(&json, "warnings");
+    Ok(EnrolmentResult {
+        enrolled: flag(&json, "status"),
+        // Codes 2-4 are the wrong key, wrong group key and wrong key with a hint
+        invalid_password: warnings
+            .iter()
+            .any(|w| matches!(text(w, "warningcode").as_deref(), Some("2" | "3" | "4"))),
+        message: warnings.iter().find_map(|w| text(w, "message")),
+    })
+}
+
+/// Checks a guest access password. Courses without one can be opened directly.
+pub async fn validate_guest_access(instance_id: i64, password: String) -> Result<EnrolmentResult> {
+    let client = login().await?;
+
+    let form: Vec<(String, String)> = vec![
+        ("instanceid".to_string(), instance_id.to_string()),
+        ("password".to_string(), password),
+    ];
+    let json = client
+        .post("enrol_guest_validate_password", &form)
+        .await
+        .and_then(check_exception)?;
+    let validated = flag(&json, "validated");
+    Ok(EnrolmentResult {
+        enrolled: validated,
+        invalid_password: !validated,
+        message: text(&json, "hint").or_else(|| list(&json, "warnings").iter().find_map(|w| text(w, "message"))),
+    })
+}
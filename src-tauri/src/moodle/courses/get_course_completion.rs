@@ -0,0 +1,149 @@
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{flag, int, list, text};
+use crate::moodle::site::get_current_user_id;
+use anyhow::{anyhow, Result};
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityCompletion {
+    pub cmid: i64,
+    pub modname: Option<String>,
+    pub instance: i64,
+    /// 0 = incomplete, 1 = complete, 2 = complete (pass), 3 = complete (fail)
+    pub state: i64,
+    /// 0 = none, 1 = manual, 2 = automatic
+    pub tracking: i64,
+    pub time_completed: Option<i64>,
+    pub overridden_by: Option<i64>,
+    pub is_complete: bool,
+    /// The student can tick this activity themselves
+    pub is_manual: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CourseProgress {
+    pub course_id: i64,
+    pub course_name: Option<String>,
+    pub completed: usize,
+    pub total: usize,
+    /// `None` when completion tracking is off for every activity
+    pub percentage: Option<f64>,
+    /// e.g. "42% complete"
+    pub label: Option<String>,
+}
+
+pub async fn get_activities_completion(
+    client: &mut MoodleClient,
+    course_id: i64,
+    user_id: i64,
+) -> Result<Vec<ActivityCompletion>> {
+    let form: Vec<(String, String)> = vec![
+        ("courseid".to_string(), course_id.to_string()),
+        ("userid".to_string(), user_id.to_string()),
+    ];
+    let json = client
+        .post("core_completion_get_activities_completion_status", &form)
+        .await
+        .and_then(check_exception)?;
+
+    Ok(list(&json, "statuses")
+        .iter()
+        // Older sites don't send `hascompletion`; tracking alone tells us then
+        .filter(|s| s.get("hascompletion").is_none() || flag(s, "hascompletion"))
+        .filter(|s| s.get("uservisible").is_none() || flag(s, "uservisible"))
+        .map(|s| {
+            let state = int(s, "state");
+            let tracking = int(s, "tracking");
+            ActivityCompletion {
+                cmid: int(s, "cmid"),
+                modname: text(s, "modname"),
+                instance: int(s, "instance"),
+                state,
+                tracking,
+                time_completed: s.get("timecompleted").and_then(|t| t.as_i64()).filter(|t| *t > 0),
+                overridden_by: s.get("overrideby").and_then(|o| o.as_i64()),
+                is_complete: state == 1 || state == 2,
+                is_manual: tracking == 1,
+            }
+        })
+        .collect())
+}
+
+/// Progress the way Moodle computes it: completed (or passed) activities out of
+/// all activities with completion tracking. Failed attempts don't count.
+pub fn compute_progress(
+    course_id: i64,
+    course_name: Option<String>,
+    activities: &[ActivityCompletion],
+) -> CourseProgress {
+    let tracked: Vec<&ActivityCompletion> = activities.iter().filter(|a| a.tracking > 0).collect();
+    let completed = tracked.iter().filter(|a| a.is_complete).count();
+    let percentage = (!tracked.is_empty())
+        .then(|| completed as f64 / tracked.len() as f64 * 100.0);
+
+    CourseProgress {
+        course_id,
+        course_name,
+        completed,
+        total: tracked.len(),
+        label: percentage.map(|p| format!("{}% complete", p.floor() as i64)),
+        percentage,
+    }
+}
+
+pub async fn get_course_progress(course_id: i64) -> Result<CourseProgress> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+    let activities = get_activities_completion(&mut client, course_id, user_id).await?;
+    Ok(compute_progress(course_id, None, &activities))
+}
+
+/// Progress for every enrolled course, for the course cards.
+pub async fn get_courses_progress() -> Result<Vec<CourseProgress>> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+
+    let form: Vec<(String, String)> = vec![("classification".to_string(), "inprogress".to_string())];
+    let courses = client
+        .post("core_course_get_enrolled_courses_by_timeline_classification", &form)
+        .await
+        .and_then(check_exception)?;
+
+    let mut progress = Vec::new();
+    for course in list(&courses, "courses") {
+        let course_id = int(course, "id");
+        // Courses without completion enabled fail here; show them without progress
+        let activities = get_activities_completion(&mut client, course_id, user_id)
+            .await
+            .unwrap_or_default();
+        progress.push(compute_progress(course_id, text(course, "fullname"), &activities));
+    }
+
+    Ok(progress)
+}
+
+/// Ticks (or unticks) an activity that uses manual completion.
+pub async fn set_activity_completion(cmid: i64, completed: bool) -> Result<bool> {
+    let client = login().await?;
+
+    let form: Vec<(String, String)> = vec![
+        ("cmid".to_string(), cmid.to_string()),
+        ("completed".to_string(), (completed as i32).to_string()),
+    ];
+    let json = client
+        .post("core_completion_update_activity_completion_status_manually", &form)
+        .await
+        .and_then(check_exception)?;
+
+    if flag(&json, "status") {
+        Ok(completed)
+    } else {
+        let message = list(&json, "warnings")
+            .first()
+            .and_then(|w| text(w, "message"))
+            .unwrap_or_else(|| "Moodle did not update the completion state".to_string());
+        Err(anyhow!(message))
+    }
+}
@@ -1,10 +1,13 @@
+use super::get_course_completion::get_activities_completion;
 use crate::moodle::calendar::login;
+use crate::moodle::site::get_current_user_id;
 use anyhow::Result;
 use moodle_api::core::course::get_contents;
+use std::collections::HashMap;
 
 pub async fn get_course_content(course_id: u32) -> Result<serde_json::Value> {
     let mut client = login().await?;
-    let content_result = get_contents::call_raw(
+    let mut content_result = get_contents::call_raw(
         &mut client,
         &mut get_contents::Params {
             courseid: Some(course_id as i64),
@@ -12,5 +15,32 @@ pub async fn get_course_content(course_id: u32) -> Result<serde_json::Value> {
         },
     )
     .await?;
+
+    // Merge each module's completion state in as `completionstatus` (by cmid);
+    // best-effort, the contents are still useful without it
+    let activities = match get_current_user_id(&mut client).await {
+        Ok(user_id) => get_activities_completion(&mut client, course_id as i64, user_id).await,
+        Err(e) => Err(e),
+    };
+    if let Ok(activities) = activities {
+        let by_cmid: HashMap<i64, serde_json::Value> = activities
+            .into_iter()
+            .map(|a| (a.cmid, serde_json::to_value(&a).unwrap_or(serde_json::Value::Null)))
+            .collect();
+
+        if let Some(sections) = content_result.as_array_mut() {
+            for section in sections {
+                if let Some(modules) = section.get_mut("modules").and_then(|m| m.as_array_mut()) {
+                    for module in modules {
+                        let cmid = module.get("id").and_then(|id| id.as_i64()).unwrap_or(0);
+                        if let (Some(status), Some(module)) = (by_cmid.get(&cmid), module.as_object_mut()) {
+                            module.insert("completionstatus".to_string(), status.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     Ok(content_result)
 }
@@ -37,6 +37,7 @@ pub async fn get_course_files_assignments_quizzes(course_id: u32) -> Result<serd
     let mut files = Vec::new();
     let mut assignments = Vec::new();
     let mut quizzes = Vec::new();
+    let mut content = Vec::new();
 
     // Process course contents for files
     // Convert the API response to JSON first to work with it
@@ -100,6 +101,16 @@ pub async fn get_course_files_assignments_quizzes(course_id: u32) -> Result<serd
                                     }));
                                 }
                             }
+                            "page" | "book" | "lesson" => {
+                                // Rendered by the content commands, which take the course module id
+                                content.push(serde_json::json!({
+                                    "id": module.get("id").unwrap_or(&serde_json::Value::Null),
+                                    "instance": module.get("instance").unwrap_or(&serde_json::Value::Null),
+                                    "name": module.get("name").unwrap_or(&serde_json::Value::Null),
+                                    "type": modname,
+                                    "description": module.get("description").unwrap_or(&serde_json::Value::Null)
+                                }));
+                            }
                             _ => {}
                         }
                     }
@@ -160,13 +171,19 @@ pub async fn get_course_files_assignments_quizzes(course_id: u32) -> Result<serd
                 "title": "Quizzes & Tests",
                 "items": quizzes,
                 "count": quizzes.len()
+            },
+            "content": {
+                "title": "Pages, Books & Lessons",
+                "items": content,
+                "count": content.len()
             }
         },
         "summary": {
             "total_files": files.len(),
             "total_assignments": assignments.len(),
             "total_quizzes": quizzes.len(),
-            "total_items": files.len() + assignments.len() + quizzes.len()
+            "total_content": content.len(),
+            "total_items": files.len() + assignments.len() + quizzes.len() + content.len()
         }
     });
 
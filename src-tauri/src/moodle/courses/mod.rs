@@ -1,4 +1,6 @@
+pub mod enrolment;
 pub mod get_all_courses;
+pub mod get_course_completion;
 pub mod get_course_content;
 pub mod get_course_files_assignments_questions;
 pub mod get_courses_site_info;
@@ -6,9 +8,13 @@ pub mod get_enrolled_course;
 pub mod get_enrolled_users;
 pub mod get_user_courses;
 pub mod get_user_courses_with_site_info;
+pub mod participants;
+pub mod search_courses;
 pub mod get_course_content_items;
 
+pub use enrolment::*;
 pub use get_all_courses::*;
+pub use get_course_completion::*;
 pub use get_course_content::*;
 pub use get_course_files_assignments_questions::*;
 pub use get_courses_site_info::*;
@@ -16,4 +22,6 @@ pub use get_enrolled_course::*;
 pub use get_enrolled_users::*;
 pub use get_user_courses::*;
 pub use get_user_courses_with_site_info::*;
+pub use participants::*;
+pub use search_courses::*;
 pub use get_course_content_items::*;
@@ -0,0 +1,490 @@
+use crate::moodle::calendar::login;
+use crate::moodle::contacts::get_member_info::fetch_member_info;
+use crate::moodle::exception::check_exception;
+use crate::moodle::html::to_plain_text;
+use crate::moodle::json::{int, list, text};
+use crate::moodle::messages::conversations::{fetch_conversation_with_user, Conversation, ConversationMember};
+use crate::moodle::site::get_current_user_id;
+use anyhow::{anyhow, Result};
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+
+/// `core_message_get_member_info` is asked about this many users at a time.
+const MEMBER_INFO_BATCH: usize = 100;
+/// Profile pictures downloaded in parallel.
+const AVATAR_DOWNLOADS: usize = 8;
+/// Cached profile pictures older than this are removed and downloaded again when next shown.
+const AVATAR_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+static AVATARS_PRUNED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParticipantRole {
+    pub id: i64,
+    /// e.g. `student`, `editingteacher`
+    pub shortname: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParticipantGroup {
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Participant {
+    pub id: i64,
+    pub fullname: String,
+    pub firstname: Option<String>,
+    pub lastname: Option<String>,
+    pub email: Option<String>,
+    pub profile_image_url: Option<String>,
+    /// Cached copy of the profile picture
+    pub local_image_path: Option<String>,
+    pub roles: Vec<ParticipantRole>,
+    pub groups: Vec<ParticipantGroup>,
+    /// Last access to the course; hidden from students on most sites
+    pub last_access: Option<i64>,
+    pub is_online: Option<bool>,
+    pub is_contact: bool,
+    pub is_blocked: bool,
+    pub can_message: bool,
+    /// The user only accepts messages from contacts
+    pub requires_contact: bool,
+    pub has_contact_request: bool,
+    pub is_current_user: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ParticipantFilter {
+    /// Role shortname
+    pub role: Option<String>,
+    pub group_id: Option<i64>,
+    /// Matches name or email
+    pub search: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParticipantSort {
+    #[default]
+    FirstName,
+    LastName,
+    /// Most recent first
+    LastAccess,
+    /// Teachers before students, then by name
+    Role,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CourseParticipants {
+    pub course_id: i64,
+    pub participants: Vec<Participant>,
+    /// Every role and group present, for filter choices
+    pub roles: Vec<ParticipantRole>,
+    pub groups: Vec<ParticipantGroup>,
+    /// Groups the current user belongs to
+    pub my_groups: Vec<ParticipantGroup>,
+    /// Participants before filtering
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParticipantProfile {
+    pub participant: Participant,
+    pub description: Option<String>,
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub url: Option<String>,
+    pub interests: Option<String>,
+    pub first_access: Option<i64>,
+    /// Other courses shared with the current user that the profile may show
+    pub enrolled_courses: Vec<String>,
+    pub custom_fields: Vec<(String, String)>,
+}
+
+fn build_participant(user: &serde_json::Value, current_user_id: i64) -> Participant {
+    let optional_time = |key: &str| user.get(key).and_then(|t| t.as_i64()).filter(|t| *t > 0);
+    Participant {
+        id: int(user, "id"),
+        fullname: text(user, "fullname").unwrap_or_default(),
+        firstname: text(user, "firstname"),
+        lastname: text(user, "lastname"),
+        email: text(user, "email"),
+        profile_image_url: text(user, "profileimageurl"),
+        local_image_path: None,
+        roles: list(user, "roles")
+            .iter()
+            .map(|r| ParticipantRole {
+                id: int(r, "roleid"),
+                shortname: text(r, "shortname").unwrap_or_default(),
+                name: text(r, "name")
+                    .or_else(|| text(r, "shortname"))
+                    .unwrap_or_default(),
+            })
+            .collect(),
+        groups: list(user, "groups")
+            .iter()
+            .map(|g| ParticipantGroup {
+                id: int(g, "id"),
+                name: text(g, "name").unwrap_or_default(),
+            })
+            .collect(),
+        last_access: optional_time("lastcourseaccess").or_else(|| optional_time("lastaccess")),
+        is_online: None,
+        is_contact: false,
+        is_blocked: false,
+        can_message: false,
+        requires_contact: false,
+        has_contact_request: false,
+        is_current_user: int(user, "id") == current_user_id,
+    }
+}
+
+/// Lower numbers for roles with more rights, for sorting.
+fn role_rank(participant: &Participant) -> i64 {
+    participant
+        .roles
+        .iter()
+        .map(|r| match r.shortname.as_str() {
+            "manager" => 0,
+            "editingteacher" => 1,
+            "teacher" => 2,
+            "student" => 4,
+            "guest" => 5,
+            _ => 3,
+        })
+        .min()
+        .unwrap_or(6)
+}
+
+pub fn filter_and_sort(
+    participants: &[Participant],
+    filter: &ParticipantFilter,
+    sort: ParticipantSort,
+) -> Vec<Participant> {
+    let search = filter.search.as_deref().map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty());
+    let mut result: Vec<Participant> = participants
+        .iter()
+        .filter(|p| {
+            filter.role.as_ref().map_or(true, |role| p.roles.iter().any(|r| &r.shortname == role))
+                && filter.group_id.map_or(true, |group| p.groups.iter().any(|g| g.id == group))
+                && search.as_ref().map_or(true, |s| {
+                    p.fullname.to_lowercase().contains(s.as_str())
+                        || p.email.as_deref().is_some_and(|e| e.to_lowercase().contains(s.as_str()))
+                })
+        })
+        .cloned()
+        .collect();
+
+    let first = |p: &Participant| p.firstname.clone().unwrap_or_else(|| p.fullname.clone()).to_lowercase();
+    let last = |p: &Participant| p.lastname.clone().unwrap_or_else(|| p.fullname.clone()).to_lowercase();
+    match sort {
+        ParticipantSort::FirstName => result.sort_by_key(|p| (first(p), last(p))),
+        ParticipantSort::LastName => result.sort_by_key(|p| (last(p), first(p))),
+        ParticipantSort::LastAccess => result.sort_by_key(|p| (std::cmp::Reverse(p.last_access.unwrap_or(0)), first(p))),
+        ParticipantSort::Role => result.sort_by_key(|p| (role_rank(p), first(p))),
+    }
+    result
+}
+
+fn push_unique<T: PartialEq + Clone>(into: &mut Vec<T>, items: &[T]) {
+    for item in items {
+        if !into.contains(item) {
+            into.push(item.clone());
+        }
+    }
+}
+
+/// Adds contact, blocked and "can message" details from the messaging system,
+/// leaving the defaults for batches Moodle fails to answer.
+async fn merge_member_info(client: &mut MoodleClient, user_id: i64, participants: &mut [Participant]) {
+    let ids: Vec<i64> = participants.iter().map(|p| p.id).filter(|id| *id != user_id).collect();
+    for batch in ids.chunks(MEMBER_INFO_BATCH) {
+        let members = match fetch_member_info(client, user_id, batch).await {
+            Ok(members) => members,
+            // The list is still useful without presence and contact flags
+            Err(e) => {
+                eprintln!("Skipping messaging info for {} participants: {}", batch.len(), e);
+                continue;
+            }
+        };
+        for member in members {
+            if let Some(p) = participants.iter_mut().find(|p| p.id == member.id) {
+                p.is_online = member.is_online;
+                p.is_contact = member.is_contact;
+                p.is_blocked = member.is_blocked;
+                p.can_message = member.can_message;
+                p.requires_contact = member.requires_contact;
+                p.has_contact_request = member.has_contact_request;
+            }
+        }
+    }
+}
+
+fn avatar_path(cache_dir: &Path, user_id: i64, url: &str) -> PathBuf {
+    // The URL changes (rev=...) when the picture does, so it keys the cache
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    cache_dir.join(format!("{}-{:x}.img", user_id, hasher.finish()))
+}
+
+/// Removes pictures cached more than `AVATAR_MAX_AGE` ago, so those of people
+/// no longer shown anywhere don't stay around.
+async fn prune_avatar_cache(cache_dir: &Path) -> Result<()> {
+    let mut entries = tokio::fs::read_dir(cache_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let modified = entry.metadata().await?.modified()?;
+        let expired = SystemTime::now()
+            .duration_since(modified)
+            .is_ok_and(|age| age > AVATAR_MAX_AGE);
+        if expired {
+            let _ = tokio::fs::remove_file(entry.path()).await;
+        }
+    }
+    Ok(())
+}
+
+/// Removes earlier pictures of `user_id`, replaced by the one at `current`.
+async fn remove_replaced_avatars(cache_dir: &Path, user_id: i64, current: &Path) -> Result<()> {
+    let prefix = format!("{}-", user_id);
+    let mut entries = tokio::fs::read_dir(cache_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let replaced = entry.file_name().to_string_lossy().starts_with(&prefix) && entry.path() != current;
+        if replaced {
+            let _ = tokio::fs::remove_file(entry.path()).await;
+        }
+    }
+    Ok(())
+}
+
+/// Downloads missing profile pictures into `cache_dir` and fills in `local_image_path`.
+/// A failed download just leaves the remote URL in use.
+pub async fn cache_profile_images(client: &MoodleClient, cache_dir: &Path, participants: &mut [Participant]) -> Result<()> {
+    tokio::fs::create_dir_all(cache_dir).await?;
+    // Once per run is enough to keep the folder from growing without bound
+    if !AVATARS_PRUNED.swap(true, Ordering::Relaxed) {
+        if let Err(e) = prune_avatar_cache(cache_dir).await {
+            eprintln!("Could not prune cached profile pictures: {}", e);
+        }
+    }
+
+    let mut pending: Vec<(usize, String, PathBuf)> = Vec::new();
+    for (i, p) in participants.iter_mut().enumerate() {
+        let Some(url) = p.profile_image_url.clone() else { continue };
+        let path = avatar_path(cache_dir, p.id, &url);
+        if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            p.local_image_path = Some(path.to_string_lossy().to_string());
+        } else {
+            pending.push((i, url, path));
+        }
+    }
+
+    for chunk in pending.chunks(AVATAR_DOWNLOADS) {
+        let mut downloads = tokio::task::JoinSet::new();
+        for (i, url, path) in chunk.iter().cloned() {
+            let client = client.clone();
+            let cache_dir = cache_dir.to_path_buf();
+            let user_id = participants[i].id;
+            downloads.spawn(async move {
+                let bytes = client.download(&url).await.ok()?;
+                tokio::fs::write(&path, bytes).await.ok()?;
+                let _ = remove_replaced_avatars(&cache_dir, user_id, &path).await;
+                Some((i, path))
+            });
+        }
+        while let Some(done) = downloads.join_next().await {
+            if let Ok(Some((i, path))) = done {
+                participants[i].local_image_path = Some(path.to_string_lossy().to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn fetch_my_groups(client: &mut MoodleClient, course_id: i64, user_id: i64) -> Result<Vec<ParticipantGroup>> {
+    let form: Vec<(String, String)> = vec![
+        ("courseid".to_string(), course_id.to_string()),
+        ("userid".to_string(), user_id.to_string()),
+    ];
+    let json = client
+        .post("core_group_get_course_user_groups", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(list(&json, "groups")
+        .iter()
+        .map(|g| ParticipantGroup {
+            id: int(g, "id"),
+            name: text(g, "name").unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// The participants of a course with their roles, groups and messaging
+/// status, filtered and sorted for the directory screen.
+pub async fn get_course_participants(
+    course_id: i64,
+    filter: &ParticipantFilter,
+    sort: ParticipantSort,
+    avatar_cache_dir: Option<&Path>,
+) -> Result<CourseParticipants> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+
+    let form: Vec<(String, String)> = vec![("courseid".to_string(), course_id.to_string())];
+    let json = client
+        .post("core_enrol_get_enrolled_users", &form)
+        .await
+        .and_then(check_exception)?;
+    let mut participants: Vec<Participant> = json
+        .as_array()
+        .ok_or_else(|| anyhow!("Could not load the participants of course {}", course_id))?
+        .iter()
+        .map(|u| build_participant(u, user_id))
+        .collect();
+
+    let mut roles: Vec<ParticipantRole> = Vec::new();
+    let mut groups: Vec<ParticipantGroup> = Vec::new();
+    for p in &participants {
+        push_unique(&mut roles, &p.roles);
+        push_unique(&mut groups, &p.groups);
+    }
+    groups.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let my_groups = fetch_my_groups(&mut client, course_id, user_id).await.unwrap_or_default();
+    merge_member_info(&mut client, user_id, &mut participants).await;
+
+    let total = participants.len();
+    let mut participants = filter_and_sort(&participants, filter, sort);
+    if let Some(cache_dir) = avatar_cache_dir {
+        cache_profile_images(&client, cache_dir, &mut participants).await?;
+    }
+
+    Ok(CourseParticipants {
+        course_id,
+        participants,
+        roles,
+        groups,
+        my_groups,
+        total,
+    })
+}
+
+/// Full profile of one participant (`core_user_get_course_user_profiles`).
+pub async fn get_participant_profile(
+    course_id: i64,
+    participant_id: i64,
+    avatar_cache_dir: Option<&Path>,
+) -> Result<ParticipantProfile> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+
+    let form: Vec<(String, String)> = vec![
+        ("userlist[0][userid]".to_string(), participant_id.to_string()),
+        ("userlist[0][courseid]".to_string(), course_id.to_string()),
+    ];
+    let json = client
+        .post("core_user_get_course_user_profiles", &form)
+        .await
+        .and_then(check_exception)?;
+    let user = json
+        .as_array()
+        .and_then(|users| users.first())
+        .ok_or_else(|| anyhow!("You can't view this profile"))?;
+
+    let mut participants = vec![build_participant(user, user_id)];
+    merge_member_info(&mut client, user_id, &mut participants).await;
+    if let Some(cache_dir) = avatar_cache_dir {
+        cache_profile_images(&client, cache_dir, &mut participants).await?;
+    }
+
+    let optional_time = |key: &str| user.get(key).and_then(|t| t.as_i64()).filter(|t| *t > 0);
+    Ok(ParticipantProfile {
+        participant: participants.remove(0),
+        description: text(user, "description")
+            .map(|d| to_plain_text(&d))
+            .filter(|d| !d.is_empty()),
+        city: text(user, "city"),
+        country: text(user, "country"),
+        url: text(user, "url"),
+        interests: text(user, "interests"),
+        first_access: optional_time("firstaccess"),
+        enrolled_courses: list(user, "enrolledcourses")
+            .iter()
+            .filter_map(|c| text(c, "fullname"))
+            .collect(),
+        custom_fields: list(user, "customfields")
+            .iter()
+            .filter_map(|f| Some((text(f, "name")?, to_plain_text(&text(f, "value")?))))
+            .collect(),
+    })
+}
+
+/// What the "message this person" button needs: an existing conversation to
+/// open, or whether a new one can be started.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParticipantConversation {
+    pub participant_id: i64,
+    /// `None` until the first message is sent
+    pub conversation: Option<Conversation>,
+    pub can_message: bool,
+    pub requires_contact: bool,
+    pub is_contact: bool,
+    pub is_blocked: bool,
+}
+
+async fn fetch_member(client: &mut MoodleClient, user_id: i64, participant_id: i64) -> Result<ConversationMember> {
+    fetch_member_info(client, user_id, &[participant_id])
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("User {} not found", participant_id))
+}
+
+/// Looks up the conversation with a participant before opening the chat.
+pub async fn message_participant(participant_id: i64) -> Result<ParticipantConversation> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+    let member = fetch_member(&mut client, user_id, participant_id).await?;
+    let conversation = fetch_conversation_with_user(&mut client, user_id, participant_id).await?;
+
+    Ok(ParticipantConversation {
+        participant_id,
+        conversation,
+        can_message: member.can_message,
+        requires_contact: member.requires_contact,
+        is_contact: member.is_contact,
+        is_blocked: member.is_blocked,
+    })
+}
+
+/// Sends a contact request unless the participant is already a contact or one
+/// is pending, then returns the updated messaging details.
+pub async fn add_participant_contact(participant_id: i64) -> Result<ConversationMember> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+    let member = fetch_member(&mut client, user_id, participant_id).await?;
+    if member.is_contact || member.has_contact_request {
+        return Ok(member);
+    }
+
+    let form: Vec<(String, String)> = vec![
+        ("userid".to_string(), user_id.to_string()),
+        ("requesteduserid".to_string(), participant_id.to_string()),
+    ];
+    let json = client
+        .post("core_message_create_contact_request", &form)
+        .await
+        .and_then(check_exception)?;
+    if let Some(warning) = list(&json, "warnings").first() {
+        return Err(anyhow!(text(warning, "message").unwrap_or_else(|| "Could not add contact".to_string())));
+    }
+
+    fetch_member(&mut client, user_id, participant_id).await
+}
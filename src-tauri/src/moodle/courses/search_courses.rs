@@ -0,0 +1,108 @@
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::html::to_plain_text;
+use crate::moodle::json::{int, list, text};
+use crate::moodle::site::get_current_user_id;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_SEARCH_PAGE_SIZE: i64 = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CourseSearchResult {
+    pub id: i64,
+    pub fullname: String,
+    pub shortname: Option<String>,
+    pub category_name: Option<String>,
+    /// Plain text summary
+    pub summary: Option<String>,
+    pub image_url: Option<String>,
+    /// Teachers shown as course contacts
+    pub contacts: Vec<String>,
+    /// Enrolment plugins offered, e.g. `self`, `guest`
+    pub enrolment_methods: Vec<String>,
+    pub is_enrolled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CourseSearchPage {
+    pub query: String,
+    /// Zero-based
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+    pub has_more: bool,
+    pub courses: Vec<CourseSearchResult>,
+}
+
+fn build_result(course: &serde_json::Value, enrolled: &[i64]) -> CourseSearchResult {
+    let id = int(course, "id");
+    CourseSearchResult {
+        id,
+        fullname: text(course, "displayname")
+            .or_else(|| text(course, "fullname"))
+            .unwrap_or_default(),
+        shortname: text(course, "shortname"),
+        category_name: text(course, "categoryname"),
+        summary: text(course, "summary")
+            .map(|s| to_plain_text(&s))
+            .filter(|s| !s.is_empty()),
+        image_url: text(course, "courseimage").or_else(|| {
+            list(course, "overviewfiles")
+                .iter()
+                .find_map(|f| text(f, "fileurl"))
+        }),
+        contacts: list(course, "contacts")
+            .iter()
+            .filter_map(|c| text(c, "fullname"))
+            .collect(),
+        enrolment_methods: course
+            .get("enrollmentmethods")
+            .and_then(|m| m.as_array())
+            .map(|m| m.iter().filter_map(|v| v.as_str().map(str::to_string)).collect())
+            .unwrap_or_default(),
+        is_enrolled: enrolled.contains(&id),
+    }
+}
+
+/// One page of the site course search (`core_course_search_courses`).
+pub async fn search_courses(query: &str, page: i64, per_page: Option<i64>) -> Result<CourseSearchPage> {
+    let mut client = login().await?;
+    let per_page = per_page.filter(|n| *n > 0).unwrap_or(DEFAULT_SEARCH_PAGE_SIZE);
+    let page = page.max(0);
+
+    let form: Vec<(String, String)> = vec![
+        ("criterianame".to_string(), "search".to_string()),
+        ("criteriavalue".to_string(), query.trim().to_string()),
+        ("page".to_string(), page.to_string()),
+        ("perpage".to_string(), per_page.to_string()),
+    ];
+    let json = client
+        .post("core_course_search_courses", &form)
+        .await
+        .and_then(check_exception)?;
+
+    // Results are marked as joined so the UI can show "Open" instead of "Enrol"
+    let user_id = get_current_user_id(&mut client).await?;
+    let enrolled: Vec<i64> = client
+        .post("core_enrol_get_users_courses", &vec![("userid".to_string(), user_id.to_string())])
+        .await
+        .and_then(check_exception)
+        .ok()
+        .and_then(|courses| courses.as_array().map(|c| c.iter().map(|c| int(c, "id")).collect()))
+        .unwrap_or_default();
+
+    let courses: Vec<CourseSearchResult> = list(&json, "courses")
+        .iter()
+        .map(|c| build_result(c, &enrolled))
+        .collect();
+    let total = int(&json, "total");
+    Ok(CourseSearchPage {
+        query: query.to_string(),
+        page,
+        per_page,
+        total,
+        has_more: (page + 1) * per_page < total,
+        courses,
+    })
+}
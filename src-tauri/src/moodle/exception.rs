@@ -0,0 +1,46 @@
+use anyhow::Result;
+
+/// An exception returned by a Moodle web service. Kept as its own type so
+/// callers can match on `errorcode` instead of the message text.
+#[derive(Debug, Clone)]
+pub struct MoodleException {
+    pub errorcode: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for MoodleException {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.message, self.errorcode)
+    }
+}
+
+impl std::error::Error for MoodleException {}
+
+/// Moodle reports web service failures as a regular JSON body carrying
+/// `exception`, `errorcode` and `message`. Turn those into errors so callers
+/// don't mistake them for empty results.
+pub fn check_exception(json: serde_json::Value) -> Result<serde_json::Value> {
+    if json.get("exception").is_some() {
+        let errorcode = json
+            .get("errorcode")
+            .and_then(|c| c.as_str())
+            .unwrap_or("unknown");
+        let message = json
+            .get("message")
+            .and_then(|m| m.as_str())
+            .unwrap_or("Moodle returned an error");
+        return Err(MoodleException {
+            errorcode: errorcode.to_string(),
+            message: message.to_string(),
+        }
+        .into());
+    }
+    Ok(json)
+}
+
+/// The `errorcode` of `error` if it is a Moodle exception.
+pub fn error_code(error: &anyhow::Error) -> Option<&str> {
+    error
+        .downcast_ref::<MoodleException>()
+        .map(|e| e.errorcode.as_str())
+}
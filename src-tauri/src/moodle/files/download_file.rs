@@ -0,0 +1,12 @@
+use crate::moodle::calendar::login;
+use anyhow::Result;
+
+/// Downloads a Moodle file URL (pluginfile) to a local path.
+///
+/// Returns the number of bytes written.
+pub async fn download_file_to_path(file_url: &str, destination: &str) -> Result<u64> {
+    let client = login().await?;
+    let bytes = client.download(file_url).await?;
+    tokio::fs::write(destination, &bytes).await?;
+    Ok(bytes.len() as u64)
+}
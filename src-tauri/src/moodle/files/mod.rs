@@ -0,0 +1,9 @@
+pub mod archive;
+pub mod download_file;
+pub mod private_files;
+pub mod upload_draft_file;
+
+pub use archive::*;
+pub use download_file::*;
+pub use private_files::*;
+pub use upload_draft_file::*;
@@ -0,0 +1,235 @@
+use super::upload_draft_file::upload_files_to_draft_folder;
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{int, list, text};
+use crate::moodle::site::get_current_user_id;
+use anyhow::{anyhow, Result};
+use moodle_api::core::files::get_files;
+use moodle_api::core::user::{add_user_private_files, get_private_files_info};
+use moodle_api::core::webservice::get_site_info;
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivateFile {
+    pub name: String,
+    /// Folder the entry lives in; for folders, the folder's own path
+    pub filepath: String,
+    pub is_dir: bool,
+    pub size: i64,
+    /// Download URL (not tokenized); empty for folders
+    pub url: String,
+    pub time_modified: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderCrumb {
+    pub name: String,
+    pub filepath: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PrivateFilesUsage {
+    pub file_count: i64,
+    pub folder_count: i64,
+    pub used_bytes: i64,
+    /// None when the site sets no quota
+    pub quota_bytes: Option<i64>,
+    pub max_upload_bytes: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivateFolder {
+    pub filepath: String,
+    /// From the root down to the parent of this folder
+    pub parents: Vec<FolderCrumb>,
+    /// Folders first, then files, both by name
+    pub entries: Vec<PrivateFile>,
+    pub usage: PrivateFilesUsage,
+}
+
+/// `/`, or the path wrapped in slashes as the file API expects.
+fn normalize_filepath(filepath: &str) -> String {
+    let trimmed = filepath.trim_matches('/');
+    if trimmed.is_empty() {
+        "/".to_string()
+    } else {
+        format!("/{}/", trimmed)
+    }
+}
+
+async fn fetch_usage(client: &mut MoodleClient) -> Result<PrivateFilesUsage> {
+    let mut params = get_private_files_info::Params { userid: None };
+    let info = get_private_files_info::call_raw(client, &mut params)
+        .await
+        .and_then(check_exception)?;
+    let mut site_params = get_site_info::Params { serviceshortnames: None };
+    let site = get_site_info::call_raw(client, &mut site_params).await?;
+    // 0 and negative values mean "unlimited"
+    let limit = |key: &str| Some(int(&site, key)).filter(|v| *v > 0);
+    Ok(PrivateFilesUsage {
+        file_count: int(&info, "filecount"),
+        folder_count: int(&info, "foldercount"),
+        used_bytes: int(&info, "filesizewithoutreferences"),
+        quota_bytes: limit("userquota"),
+        max_upload_bytes: limit("usermaxuploadfilesize"),
+    })
+}
+
+/// Lists one folder of the user's private files, with quota usage.
+pub async fn get_private_files(filepath: &str) -> Result<PrivateFolder> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+    let filepath = normalize_filepath(filepath);
+    let mut params = get_files::Params {
+        contextid: Some(-1),
+        component: Some("user".to_string()),
+        filearea: Some("private".to_string()),
+        itemid: Some(0),
+        filepath: Some(filepath.clone()),
+        filename: Some(String::new()),
+        modified: None,
+        contextlevel: Some("user".to_string()),
+        instanceid: Some(user_id),
+    };
+    let json = get_files::call_raw(&mut client, &mut params)
+        .await
+        .and_then(check_exception)?;
+
+    let mut entries: Vec<PrivateFile> = list(&json, "files")
+        .iter()
+        .map(|f| PrivateFile {
+            name: text(f, "filename").unwrap_or_default(),
+            filepath: text(f, "filepath").unwrap_or_else(|| filepath.clone()),
+            is_dir: f.get("isdir").and_then(|d| d.as_bool()).unwrap_or(false),
+            size: int(f, "filesize"),
+            url: text(f, "url").unwrap_or_default(),
+            time_modified: int(f, "timemodified"),
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        b.is_dir
+            .cmp(&a.is_dir)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    let parents = list(&json, "parents")
+        .iter()
+        .map(|p| FolderCrumb {
+            name: text(p, "filename").unwrap_or_default(),
+            filepath: text(p, "filepath").unwrap_or_else(|| "/".to_string()),
+        })
+        .collect();
+
+    Ok(PrivateFolder {
+        filepath,
+        parents,
+        entries,
+        usage: fetch_usage(&mut client).await?,
+    })
+}
+
+/// Uploads local files into a folder of the private files area. Existing
+/// files are kept; Moodle renames clashing uploads.
+pub async fn upload_private_files(file_paths: &[String], filepath: &str) -> Result<PrivateFilesUsage> {
+    if file_paths.is_empty() {
+        return Err(anyhow!("No files to upload"));
+    }
+    let mut client = login().await?;
+    let usage = fetch_usage(&mut client).await?;
+    let mut upload_bytes = 0;
+    for file_path in file_paths {
+        let size = tokio::fs::metadata(file_path).await?.len() as i64;
+        if usage.max_upload_bytes.is_some_and(|max| size > max) {
+            return Err(anyhow!("{} is larger than the site's upload limit", file_path));
+        }
+        upload_bytes += size;
+    }
+    if usage.quota_bytes.is_some_and(|quota| usage.used_bytes + upload_bytes > quota) {
+        return Err(anyhow!("Not enough space left in your private files"));
+    }
+
+    let draft_id = upload_files_to_draft_folder(&mut client, file_paths, &normalize_filepath(filepath)).await?;
+    let params = add_user_private_files::Params { draftid: Some(draft_id) };
+    client
+        .post("core_user_add_user_private_files", &params)
+        .await
+        .and_then(check_exception)?;
+    fetch_usage(&mut client).await
+}
+
+fn delete_draft_form(draft_id: i64, files: &[PrivateFile]) -> Vec<(String, String)> {
+    let mut form = vec![("draftitemid".to_string(), draft_id.to_string())];
+    for (i, file) in files.iter().enumerate() {
+        form.push((format!("files[{}][filepath]", i), file.filepath.clone()));
+        // A folder is removed through its "." entry
+        let filename = if file.is_dir { ".".to_string() } else { file.name.clone() };
+        form.push((format!("files[{}][filename]", i), filename));
+    }
+    form
+}
+
+/// Deletes files or folders from the private files area. Moodle only allows
+/// this by editing a draft copy of the area, which needs Moodle 4.3 or later.
+pub async fn delete_private_files(files: &[PrivateFile]) -> Result<PrivateFilesUsage> {
+    let mut client = login().await?;
+    let form: Vec<(String, String)> = Vec::new();
+    let draft = client
+        .post("core_user_prepare_private_files_for_edition", &form)
+        .await
+        .and_then(check_exception)
+        .map_err(|e| {
+            if e.to_string().contains("(invalidrecord)") {
+                anyhow!("Deleting private files needs Moodle 4.3 or later")
+            } else {
+                e
+            }
+        })?;
+    let draft_id = int(&draft, "draftitemid");
+
+    // Nested lists can't go through the generated params, so the form is built here
+    let form = delete_draft_form(draft_id, files);
+    client
+        .post("core_files_delete_draft_files", &form)
+        .await
+        .and_then(check_exception)?;
+
+    let form: Vec<(String, String)> = vec![("draftitemid".to_string(), draft_id.to_string())];
+    client
+        .post("core_user_update_private_files", &form)
+        .await
+        .and_then(check_exception)?;
+    fetch_usage(&mut client).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, filepath: &str, is_dir: bool) -> PrivateFile {
+        PrivateFile {
+            name: name.to_string(),
+            filepath: filepath.to_string(),
+            is_dir,
+            size: 0,
+            url: String::new(),
+            time_modified: 0,
+        }
+    }
+
+    #[test]
+    fn delete_form_lists_files_by_index() {
+        let files = [entry("notes.pdf", "/", false), entry("Drafts", "/Drafts/", true)];
+        let form = delete_draft_form(42, &files);
+        let expected = [
+            ("draftitemid", "42"),
+            ("files[0][filepath]", "/"),
+            ("files[0][filename]", "notes.pdf"),
+            ("files[1][filepath]", "/Drafts/"),
+            ("files[1][filename]", "."),
+        ];
+        assert_eq!(form.len(), expected.len());
+        for ((key, value), (expected_key, expected_value)) in form.iter().zip(expected) {
+            assert_eq!((key.as_str(), value.as_str()), (expected_key, expected_value));
+        }
+    }
+}
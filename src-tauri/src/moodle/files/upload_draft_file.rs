@@ -0,0 +1,94 @@
+use crate::moodle::exception::check_exception;
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use moodle_api::core::files::{get_unused_draft_itemid, upload};
+use moodle_client::MoodleClient;
+use std::path::Path;
+
+/// Uploads local files into a fresh user draft area.
+///
+/// Returns the draft item id, which can be handed to any Moodle function that
+/// expects a `*_filemanager` or editor `itemid` value.
+pub async fn upload_files_to_draft_area(
+    client: &mut MoodleClient,
+    file_paths: &[String],
+) -> Result<i64> {
+    upload_files_to_draft_folder(client, file_paths, "/").await
+}
+
+/// Like [`upload_files_to_draft_area`], but places the files in a folder of
+/// the draft area (`/` or `/path/to/folder/`).
+pub async fn upload_files_to_draft_folder(
+    client: &mut MoodleClient,
+    file_paths: &[String],
+    filepath: &str,
+) -> Result<i64> {
+    let draft = get_unused_draft_itemid::call_raw(client, &mut get_unused_draft_itemid::Params {})
+        .await
+        .and_then(check_exception)?;
+
+    let contextid = draft
+        .get("contextid")
+        .and_then(|c| c.as_i64())
+        .ok_or_else(|| anyhow!("Moodle did not return a draft context"))?;
+    let itemid = draft
+        .get("itemid")
+        .and_then(|i| i.as_i64())
+        .ok_or_else(|| anyhow!("Moodle did not return a draft item id"))?;
+
+    for file_path in file_paths {
+        let path = Path::new(file_path);
+        let filename = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| anyhow!("Invalid file path: {}", file_path))?
+            .to_string();
+        let content = tokio::fs::read(path).await?;
+
+        let mut params = upload::Params {
+            contextid: Some(contextid),
+            component: Some("user".to_string()),
+            filearea: Some("draft".to_string()),
+            itemid: Some(itemid),
+            filepath: Some(filepath.to_string()),
+            filename: Some(filename),
+            filecontent: Some(base64::engine::general_purpose::STANDARD.encode(content)),
+            contextlevel: None,
+            instanceid: None,
+        };
+        upload::call_raw(client, &mut params)
+            .await
+            .and_then(check_exception)?;
+    }
+
+    Ok(itemid)
+}
+
+/// Uploads images referenced from an HTML body into a draft area.
+///
+/// Images the HTML doesn't already point at (`@@PLUGINFILE@@/<name>`) are
+/// appended, so callers can pass plain text plus a list of pictures. Returns
+/// the rewritten HTML and the draft item id for `inlineattachmentsid`.
+pub async fn embed_inline_images(
+    client: &mut MoodleClient,
+    html: &str,
+    image_paths: &[String],
+) -> Result<(String, i64)> {
+    let itemid = upload_files_to_draft_area(client, image_paths).await?;
+
+    let mut html = html.to_string();
+    for image_path in image_paths {
+        let Some(filename) = Path::new(image_path).file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let reference = format!("@@PLUGINFILE@@/{}", urlencoding::encode(filename));
+        if !html.contains(&reference) {
+            html.push_str(&format!(
+                "<p><img src=\"{}\" alt=\"{}\"></p>",
+                reference, filename
+            ));
+        }
+    }
+
+    Ok((html, itemid))
+}
@@ -0,0 +1,111 @@
+use super::form::{
+    ActivityForm, FormActivity, FormAnswers, FormQuestion, FormSubmission, QuestionKind, QuestionOption,
+};
+use crate::moodle::content::{sanitize_html, CourseModuleRef, UrlRewriter};
+use crate::moodle::exception::check_exception;
+use crate::moodle::html::to_plain_text;
+use crate::moodle::json::{flag, int, list, text};
+use anyhow::{anyhow, Result};
+use moodle_client::MoodleClient;
+
+/// Key of the single question a choice becomes.
+const CHOICE_QUESTION: &str = "responses";
+
+pub async fn load_choice_form(client: &mut MoodleClient, module: &CourseModuleRef) -> Result<ActivityForm> {
+    let form: Vec<(String, String)> = vec![("courseids[0]".to_string(), module.course_id.to_string())];
+    let json = client
+        .post("mod_choice_get_choices_by_courses", &form)
+        .await
+        .and_then(check_exception)?;
+    let choice = list(&json, "choices")
+        .iter()
+        .find(|c| int(c, "coursemodule") == module.cmid)
+        .cloned()
+        .ok_or_else(|| anyhow!("Choice {} is not available", module.cmid))?;
+
+    let form: Vec<(String, String)> = vec![("choiceid".to_string(), module.instance.to_string())];
+    let json = client
+        .post("mod_choice_get_choice_options", &form)
+        .await
+        .and_then(check_exception)?;
+    let options = list(&json, "options");
+    let answer: Vec<String> = options
+        .iter()
+        .filter(|o| flag(o, "checked"))
+        .map(|o| int(o, "id").to_string())
+        .collect();
+    let choice_options = options
+        .iter()
+        .map(|o| QuestionOption {
+            value: int(o, "id").to_string(),
+            label: to_plain_text(&text(o, "text").unwrap_or_default()),
+            disabled: flag(o, "disabled"),
+        })
+        .collect();
+
+    let now = chrono::Utc::now().timestamp();
+    let (open, close) = (int(&choice, "timeopen"), int(&choice, "timeclose"));
+    let already_submitted = !answer.is_empty();
+    let message = if open > 0 && now < open {
+        Some("This choice is not open yet".to_string())
+    } else if close > 0 && now > close {
+        Some("This choice is closed".to_string())
+    } else if already_submitted && !flag(&choice, "allowupdate") {
+        Some("Your answer can't be changed".to_string())
+    } else {
+        None
+    };
+
+    let rewriter = UrlRewriter::new(client, None);
+    Ok(ActivityForm {
+        activity: FormActivity::Choice,
+        cmid: module.cmid,
+        instance_id: module.instance,
+        name: text(&choice, "name").unwrap_or_else(|| module.name.clone()),
+        intro: text(&choice, "intro")
+            .map(|i| sanitize_html(&i, &rewriter))
+            .filter(|i| !i.is_empty()),
+        page: 0,
+        has_previous: false,
+        has_next: false,
+        questions: vec![FormQuestion {
+            id: CHOICE_QUESTION.to_string(),
+            label: text(&choice, "name").unwrap_or_else(|| module.name.clone()),
+            description: None,
+            required: true,
+            kind: if flag(&choice, "allowmultiple") {
+                QuestionKind::MultipleChoice { options: choice_options }
+            } else {
+                QuestionKind::SingleChoice {
+                    options: choice_options,
+                    dropdown: false,
+                }
+            },
+            answer,
+        }],
+        can_submit: message.is_none(),
+        already_submitted,
+        message,
+    })
+}
+
+pub async fn submit_choice(client: &mut MoodleClient, form: &ActivityForm, answers: &FormAnswers) -> Result<FormSubmission> {
+    let mut request: Vec<(String, String)> = vec![("choiceid".to_string(), form.instance_id.to_string())];
+    for (i, option_id) in answers.get(CHOICE_QUESTION).into_iter().flatten().enumerate() {
+        request.push((format!("responses[{}]", i), option_id.clone()));
+    }
+    let json = client
+        .post("mod_choice_submit_choice_response", &request)
+        .await
+        .and_then(check_exception)?;
+    let warnings: Vec<String> = list(&json, "warnings")
+        .iter()
+        .filter_map(|w| text(w, "message"))
+        .collect();
+    Ok(FormSubmission {
+        accepted: true,
+        completed: true,
+        message: Some(warnings.join("\n")).filter(|m| !m.is_empty()),
+        ..Default::default()
+    })
+}
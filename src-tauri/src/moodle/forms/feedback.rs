@@ -0,0 +1,257 @@
+use super::form::{
+    ActivityForm, FormActivity, FormAnswers, FormQuestion, FormSubmission, QuestionKind, QuestionOption,
+};
+use crate::moodle::content::{sanitize_html, CourseModuleRef, UrlRewriter};
+use crate::moodle::exception::check_exception;
+use crate::moodle::html::to_plain_text;
+use crate::moodle::json::{flag, int, list, text};
+use anyhow::{anyhow, Result};
+use moodle_client::MoodleClient;
+use std::collections::HashMap;
+
+/// Separators of the multichoice `presentation` field:
+/// `<subtype>>>>>><option>|<option><<<<<1`, where subtype is `r`, `c` or `d`
+/// and the trailing marker means horizontal layout.
+const MULTICHOICE_TYPE_SEP: &str = ">>>>>";
+const MULTICHOICE_LINE_SEP: &str = "|";
+const MULTICHOICE_ADJUST_SEP: &str = "<<<<<";
+/// Separates value and label in rated multichoice options.
+const MULTICHOICERATED_VALUE_SEP: &str = "####";
+
+fn parse_multichoice(presentation: &str, rated: bool) -> (char, Vec<QuestionOption>) {
+    let (subtype, options) = presentation
+        .split_once(MULTICHOICE_TYPE_SEP)
+        .map(|(t, o)| (t.chars().next().unwrap_or('r'), o))
+        .unwrap_or(('r', presentation));
+    let options = options.split(MULTICHOICE_ADJUST_SEP).next().unwrap_or_default();
+    let options = options
+        .split(MULTICHOICE_LINE_SEP)
+        .enumerate()
+        .map(|(i, option)| {
+            let label = if rated {
+                option.split_once(MULTICHOICERATED_VALUE_SEP).map_or(option, |(_, l)| l)
+            } else {
+                option
+            };
+            QuestionOption {
+                // Moodle numbers options from 1; 0 means "not selected"
+                value: (i + 1).to_string(),
+                label: to_plain_text(label.trim()),
+                disabled: false,
+            }
+        })
+        .collect();
+    (subtype, options)
+}
+
+/// Both numbers of a `a|b` presentation; empty or `-` means unset.
+fn presentation_pair(presentation: &str) -> (Option<f64>, Option<f64>) {
+    let mut parts = presentation.split('|').map(|p| p.trim().parse::<f64>().ok());
+    (parts.next().flatten(), parts.next().flatten())
+}
+
+fn parse_item(item: &serde_json::Value, rewriter: &UrlRewriter, saved: &HashMap<i64, String>) -> Option<FormQuestion> {
+    let item_type = text(item, "typ").unwrap_or_default();
+    let id = int(item, "id");
+    let presentation = text(item, "presentation").unwrap_or_default();
+    let kind = match item_type.as_str() {
+        "pagebreak" => return None,
+        "label" => QuestionKind::Info,
+        "info" => QuestionKind::Info,
+        "textfield" => QuestionKind::Text {
+            multiline: false,
+            max_length: presentation_pair(&presentation).1.map(|m| m as usize).filter(|m| *m > 0),
+        },
+        "textarea" => QuestionKind::Text {
+            multiline: true,
+            max_length: None,
+        },
+        "numeric" => {
+            let (min, max) = presentation_pair(&presentation);
+            QuestionKind::Number { min, max }
+        }
+        "multichoice" | "multichoicerated" => {
+            let (subtype, options) = parse_multichoice(&presentation, item_type == "multichoicerated");
+            if subtype == 'c' {
+                QuestionKind::MultipleChoice { options }
+            } else {
+                QuestionKind::SingleChoice {
+                    options,
+                    dropdown: subtype == 'd',
+                }
+            }
+        }
+        _ => QuestionKind::Unsupported,
+    };
+
+    let description = match item_type.as_str() {
+        "label" => Some(sanitize_html(&presentation, rewriter)).filter(|d| !d.is_empty()),
+        _ => None,
+    };
+    // Checkbox answers are saved as `1|3`
+    let answer = match saved.get(&id) {
+        Some(value) if item_type.starts_with("multichoice") => value
+            .split(MULTICHOICE_LINE_SEP)
+            .filter(|v| !v.is_empty() && *v != "0")
+            .map(str::to_string)
+            .collect(),
+        Some(value) if !value.is_empty() => vec![value.clone()],
+        _ => Vec::new(),
+    };
+    Some(FormQuestion {
+        id: format!("{}_{}", item_type, id),
+        label: to_plain_text(&text(item, "name").unwrap_or_default()),
+        description,
+        required: flag(item, "required"),
+        kind,
+        answer,
+    })
+}
+
+/// Answers saved on earlier visits to a page of an unfinished attempt.
+async fn fetch_unfinished_responses(client: &MoodleClient, feedback_id: i64) -> Result<HashMap<i64, String>> {
+    let form: Vec<(String, String)> = vec![("feedbackid".to_string(), feedback_id.to_string())];
+    let json = client
+        .post("mod_feedback_get_unfinished_responses", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(list(&json, "responses")
+        .iter()
+        .map(|r| (int(r, "item"), text(r, "value").unwrap_or_default()))
+        .collect())
+}
+
+pub async fn load_feedback_form(
+    client: &mut MoodleClient,
+    module: &CourseModuleRef,
+    page: Option<i64>,
+) -> Result<ActivityForm> {
+    let feedback_id = module.instance;
+    let form: Vec<(String, String)> = vec![("courseids[0]".to_string(), module.course_id.to_string())];
+    let json = client
+        .post("mod_feedback_get_feedbacks_by_courses", &form)
+        .await
+        .and_then(check_exception)?;
+    let feedback = list(&json, "feedbacks")
+        .iter()
+        .find(|f| int(f, "coursemodule") == module.cmid)
+        .cloned()
+        .ok_or_else(|| anyhow!("Feedback {} is not available", module.cmid))?;
+
+    let form: Vec<(String, String)> = vec![("feedbackid".to_string(), feedback_id.to_string())];
+    let access = client
+        .post("mod_feedback_get_feedback_access_information", &form)
+        .await
+        .and_then(check_exception)?;
+    let already_submitted = flag(&access, "isalreadysubmitted");
+    let message = if !flag(&access, "cancomplete") {
+        Some("You can't complete this feedback".to_string())
+    } else if !flag(&access, "isopen") {
+        Some("This feedback is not open".to_string())
+    } else if !flag(&access, "cansubmit") {
+        Some("You have already completed this feedback".to_string())
+    } else {
+        None
+    };
+
+    let page = match page {
+        Some(page) => page,
+        None if message.is_none() => {
+            let launch = client
+                .post("mod_feedback_launch_feedback", &form)
+                .await
+                .and_then(check_exception)?;
+            int(&launch, "gopage").max(0)
+        }
+        None => 0,
+    };
+    let page_form: Vec<(String, String)> = vec![
+        ("feedbackid".to_string(), feedback_id.to_string()),
+        ("page".to_string(), page.to_string()),
+    ];
+    let items = client
+        .post("mod_feedback_get_page_items", &page_form)
+        .await
+        .and_then(check_exception)?;
+    let saved = if message.is_none() {
+        fetch_unfinished_responses(client, feedback_id).await.unwrap_or_default()
+    } else {
+        HashMap::new()
+    };
+
+    let rewriter = UrlRewriter::new(client, None);
+    let mut item_list: Vec<&serde_json::Value> = list(&items, "items").iter().collect();
+    item_list.sort_by_key(|i| int(i, "position"));
+    Ok(ActivityForm {
+        activity: FormActivity::Feedback,
+        cmid: module.cmid,
+        instance_id: feedback_id,
+        name: text(&feedback, "name").unwrap_or_else(|| module.name.clone()),
+        intro: text(&feedback, "intro")
+            .map(|i| sanitize_html(&i, &rewriter))
+            .filter(|i| !i.is_empty()),
+        page,
+        has_previous: flag(&items, "hasprevpage"),
+        has_next: flag(&items, "hasnextpage"),
+        questions: item_list
+            .into_iter()
+            .filter_map(|item| parse_item(item, &rewriter, &saved))
+            .collect(),
+        can_submit: message.is_none(),
+        already_submitted,
+        message,
+    })
+}
+
+/// Sends one page with `mod_feedback_process_page`, using the field names
+/// of the web form (`multichoice_12`, `multichoice_12[0]` for checkboxes).
+pub async fn submit_feedback_page(
+    client: &mut MoodleClient,
+    form: &ActivityForm,
+    answers: &FormAnswers,
+    go_previous: bool,
+) -> Result<FormSubmission> {
+    let mut request: Vec<(String, String)> = vec![
+        ("feedbackid".to_string(), form.instance_id.to_string()),
+        ("page".to_string(), form.page.to_string()),
+        ("goprevious".to_string(), if go_previous { "1" } else { "0" }.to_string()),
+    ];
+    let mut responses: Vec<(String, String)> = Vec::new();
+    for question in &form.questions {
+        let answer = answers.get(&question.id).cloned().unwrap_or_default();
+        match &question.kind {
+            QuestionKind::Info | QuestionKind::Unsupported => {}
+            QuestionKind::MultipleChoice { .. } => {
+                for (i, value) in answer.iter().enumerate() {
+                    responses.push((format!("{}[{}]", question.id, i), value.clone()));
+                }
+            }
+            QuestionKind::SingleChoice { .. } => {
+                responses.push((question.id.clone(), answer.first().cloned().unwrap_or_else(|| "0".to_string())));
+            }
+            QuestionKind::Text { .. } | QuestionKind::Number { .. } => {
+                responses.push((question.id.clone(), answer.first().cloned().unwrap_or_default()));
+            }
+        }
+    }
+    for (i, (name, value)) in responses.into_iter().enumerate() {
+        request.push((format!("responses[{}][name]", i), name));
+        request.push((format!("responses[{}][value]", i), value));
+    }
+
+    let json = client
+        .post("mod_feedback_process_page", &request)
+        .await
+        .and_then(check_exception)?;
+    let completed = flag(&json, "completed");
+    let rewriter = UrlRewriter::new(client, None);
+    Ok(FormSubmission {
+        accepted: true,
+        errors: Vec::new(),
+        completed,
+        next_page: Some(int(&json, "jumpto")).filter(|_| !completed),
+        message: text(&json, "completionpagecontents")
+            .map(|c| sanitize_html(&c, &rewriter))
+            .filter(|c| !c.is_empty()),
+    })
+}
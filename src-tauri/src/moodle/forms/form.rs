@@ -0,0 +1,339 @@
+use super::{choice, feedback, survey};
+use crate::moodle::calendar::login;
+use crate::moodle::content::fetch_course_module;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Activities whose definitions can be turned into a form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FormActivity {
+    Feedback,
+    Choice,
+    Survey,
+}
+
+impl FormActivity {
+    pub fn modname(self) -> &'static str {
+        match self {
+            FormActivity::Feedback => "feedback",
+            FormActivity::Choice => "choice",
+            FormActivity::Survey => "survey",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuestionOption {
+    pub value: String,
+    pub label: String,
+    /// E.g. a choice option that is full
+    pub disabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QuestionKind {
+    /// Text between questions; nothing to answer
+    Info,
+    SingleChoice { options: Vec<QuestionOption>, dropdown: bool },
+    MultipleChoice { options: Vec<QuestionOption> },
+    Text { multiline: bool, max_length: Option<usize> },
+    Number { min: Option<f64>, max: Option<f64> },
+    /// Shown on the site but not answerable in the app, e.g. a captcha
+    Unsupported,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormQuestion {
+    /// Key of the answer in `FormAnswers`
+    pub id: String,
+    pub label: String,
+    /// Sanitized HTML shown under the label
+    pub description: Option<String>,
+    pub required: bool,
+    pub kind: QuestionKind,
+    /// Answer already saved on the site, if any
+    pub answer: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityForm {
+    pub activity: FormActivity,
+    pub cmid: i64,
+    pub instance_id: i64,
+    pub name: String,
+    pub intro: Option<String>,
+    /// Zero-based page; only feedback activities have more than one
+    pub page: i64,
+    pub has_previous: bool,
+    pub has_next: bool,
+    pub questions: Vec<FormQuestion>,
+    pub can_submit: bool,
+    pub already_submitted: bool,
+    /// Why the form can't be submitted, when it can't
+    pub message: Option<String>,
+}
+
+/// Answers by question id; single answers are one-element lists.
+pub type FormAnswers = BTreeMap<String, Vec<String>>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormError {
+    pub question_id: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FormSubmission {
+    /// False when validation failed and nothing was sent
+    pub accepted: bool,
+    pub errors: Vec<FormError>,
+    /// The whole activity is done
+    pub completed: bool,
+    /// Page to show next, for multi-page feedback activities
+    pub next_page: Option<i64>,
+    /// Text to show after completion
+    pub message: Option<String>,
+}
+
+/// The non-empty values of an answer; for choices "0" also means "nothing chosen".
+fn answered<'a>(answer: &'a [String], kind: &QuestionKind) -> Vec<&'a str> {
+    let choice = matches!(kind, QuestionKind::SingleChoice { .. } | QuestionKind::MultipleChoice { .. });
+    answer
+        .iter()
+        .map(|a| a.trim())
+        .filter(|a| !(a.is_empty() || choice && *a == "0"))
+        .collect()
+}
+
+/// Checks answers against the schema the way Moodle would before saving.
+pub fn validate_answers(questions: &[FormQuestion], answers: &FormAnswers) -> Vec<FormError> {
+    let mut errors = Vec::new();
+    let mut error = |question: &FormQuestion, message: &str| {
+        errors.push(FormError {
+            question_id: question.id.clone(),
+            message: message.to_string(),
+        })
+    };
+
+    for question in questions {
+        let answer = answers.get(&question.id).map(Vec::as_slice).unwrap_or_default();
+        let given = answered(answer, &question.kind);
+        match &question.kind {
+            QuestionKind::Info => continue,
+            QuestionKind::Unsupported => {
+                if question.required {
+                    error(question, "This question can only be answered on the website");
+                }
+                continue;
+            }
+            QuestionKind::SingleChoice { options, .. } | QuestionKind::MultipleChoice { options } => {
+                if given.len() > 1 && matches!(question.kind, QuestionKind::SingleChoice { .. }) {
+                    error(question, "Choose only one option");
+                } else if given
+                    .iter()
+                    .any(|v| !options.iter().any(|o| o.value == *v && !o.disabled))
+                {
+                    error(question, "Choose one of the available options");
+                }
+            }
+            QuestionKind::Text { max_length, .. } => {
+                if let Some(max) = max_length {
+                    if given.first().is_some_and(|text| text.chars().count() > *max) {
+                        error(question, &format!("Use at most {} characters", max));
+                    }
+                }
+            }
+            QuestionKind::Number { min, max } => {
+                if let Some(value) = given.first() {
+                    match value.replace(',', ".").parse::<f64>() {
+                        Ok(number) if min.is_some_and(|m| number < m) || max.is_some_and(|m| number > m) => {
+                            error(question, "The number is out of range")
+                        }
+                        Ok(_) => {}
+                        Err(_) => error(question, "Enter a number"),
+                    }
+                }
+            }
+        }
+        if question.required && given.is_empty() {
+            error(question, "This question is required");
+        }
+    }
+    errors
+}
+
+/// Loads an activity as a form; `page` only matters for feedback activities
+/// and defaults to where the user left off.
+pub async fn get_activity_form(activity: FormActivity, cmid: i64, page: Option<i64>) -> Result<ActivityForm> {
+    let mut client = login().await?;
+    let module = fetch_course_module(&mut client, cmid, activity.modname()).await?;
+    match activity {
+        FormActivity::Feedback => feedback::load_feedback_form(&mut client, &module, page).await,
+        FormActivity::Choice => choice::load_choice_form(&mut client, &module).await,
+        FormActivity::Survey => survey::load_survey_form(&mut client, &module).await,
+    }
+}
+
+/// Validates and submits the answers to one page. Going back to the
+/// previous feedback page saves without validating.
+pub async fn submit_activity_form(
+    activity: FormActivity,
+    cmid: i64,
+    page: i64,
+    answers: &FormAnswers,
+    go_previous: bool,
+) -> Result<FormSubmission> {
+    let mut client = login().await?;
+    let module = fetch_course_module(&mut client, cmid, activity.modname()).await?;
+    let form = match activity {
+        FormActivity::Feedback => feedback::load_feedback_form(&mut client, &module, Some(page)).await?,
+        FormActivity::Choice => choice::load_choice_form(&mut client, &module).await?,
+        FormActivity::Survey => survey::load_survey_form(&mut client, &module).await?,
+    };
+    if !form.can_submit {
+        return Err(anyhow!(form
+            .message
+            .unwrap_or_else(|| "This activity can't be submitted".to_string())));
+    }
+    if !go_previous {
+        let errors = validate_answers(&form.questions, answers);
+        if !errors.is_empty() {
+            return Ok(FormSubmission {
+                errors,
+                ..Default::default()
+            });
+        }
+    }
+    match activity {
+        FormActivity::Feedback => feedback::submit_feedback_page(&mut client, &form, answers, go_previous).await,
+        FormActivity::Choice => choice::submit_choice(&mut client, &form, answers).await,
+        FormActivity::Survey => survey::submit_survey(&mut client, &form, answers).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn question(id: &str, required: bool, kind: QuestionKind) -> FormQuestion {
+        FormQuestion {
+            id: id.to_string(),
+            label: id.to_string(),
+            description: None,
+            required,
+            kind,
+            answer: Vec::new(),
+        }
+    }
+
+    fn options(values: &[(&str, bool)]) -> Vec<QuestionOption> {
+        values
+            .iter()
+            .map(|(value, disabled)| QuestionOption {
+                value: value.to_string(),
+                label: value.to_string(),
+                disabled: *disabled,
+            })
+            .collect()
+    }
+
+    fn answers(values: &[(&str, &[&str])]) -> FormAnswers {
+        values
+            .iter()
+            .map(|(id, answer)| (id.to_string(), answer.iter().map(|a| a.to_string()).collect()))
+            .collect()
+    }
+
+    fn messages(questions: &[FormQuestion], answers: &FormAnswers) -> Vec<(String, String)> {
+        validate_answers(questions, answers)
+            .into_iter()
+            .map(|e| (e.question_id, e.message))
+            .collect()
+    }
+
+    #[test]
+    fn required_questions() {
+        let questions = vec![
+            question("info", true, QuestionKind::Info),
+            question("name", true, QuestionKind::Text { multiline: false, max_length: None }),
+            question(
+                "colour",
+                true,
+                QuestionKind::SingleChoice {
+                    options: options(&[("1", false), ("2", false)]),
+                    dropdown: true,
+                },
+            ),
+            question("captcha", true, QuestionKind::Unsupported),
+            question("optional", false, QuestionKind::Unsupported),
+        ];
+        let errors = messages(&questions, &answers(&[("name", &["  "]), ("colour", &["0"])]));
+        assert_eq!(
+            errors,
+            vec![
+                ("name".to_string(), "This question is required".to_string()),
+                ("colour".to_string(), "This question is required".to_string()),
+                (
+                    "captcha".to_string(),
+                    "This question can only be answered on the website".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn choices() {
+        let single = question(
+            "single",
+            false,
+            QuestionKind::SingleChoice {
+                options: options(&[("1", false), ("2", true)]),
+                dropdown: false,
+            },
+        );
+        let multiple = question(
+            "multiple",
+            false,
+            QuestionKind::MultipleChoice {
+                options: options(&[("1", false), ("2", false)]),
+            },
+        );
+        let questions = vec![single, multiple];
+
+        assert!(messages(&questions, &answers(&[("single", &["1"]), ("multiple", &["1", "2"])])).is_empty());
+        assert_eq!(
+            messages(&questions, &answers(&[("single", &["1", "2"])])),
+            vec![("single".to_string(), "Choose only one option".to_string())]
+        );
+        assert_eq!(
+            messages(&questions, &answers(&[("single", &["2"]), ("multiple", &["3"])])),
+            vec![
+                ("single".to_string(), "Choose one of the available options".to_string()),
+                ("multiple".to_string(), "Choose one of the available options".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn text_and_numbers() {
+        let questions = vec![
+            question("short", false, QuestionKind::Text { multiline: false, max_length: Some(5) }),
+            question("age", false, QuestionKind::Number { min: Some(0.0), max: Some(120.0) }),
+        ];
+        assert!(messages(&questions, &answers(&[("short", &["héllo"]), ("age", &["42,5"])])).is_empty());
+        assert_eq!(
+            messages(&questions, &answers(&[("short", &["too long"]), ("age", &["121"])])),
+            vec![
+                ("short".to_string(), "Use at most 5 characters".to_string()),
+                ("age".to_string(), "The number is out of range".to_string()),
+            ]
+        );
+        assert_eq!(
+            messages(&questions, &answers(&[("age", &["many"])])),
+            vec![("age".to_string(), "Enter a number".to_string())]
+        );
+    }
+}
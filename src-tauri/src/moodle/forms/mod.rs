@@ -0,0 +1,6 @@
+pub mod choice;
+pub mod feedback;
+pub mod form;
+pub mod survey;
+
+pub use form::*;
@@ -0,0 +1,174 @@
+use super::form::{
+    ActivityForm, FormActivity, FormAnswers, FormQuestion, FormSubmission, QuestionKind, QuestionOption,
+};
+use crate::moodle::content::{sanitize_html, CourseModuleRef, UrlRewriter};
+use crate::moodle::exception::check_exception;
+use crate::moodle::html::to_plain_text;
+use crate::moodle::json::{flag, int, list, text};
+use anyhow::{anyhow, Result};
+use moodle_client::MoodleClient;
+use std::collections::HashMap;
+
+/// Question types of `mod_survey`: free text, how things are ("I found
+/// that"), how they should be ("I prefer that"), or both.
+const SURVEY_TEXT: i64 = 0;
+const SURVEY_ACTUAL: i64 = 1;
+const SURVEY_PREFERRED: i64 = 2;
+const SURVEY_BOTH: i64 = 3;
+
+fn scale_options(options: &str) -> Vec<QuestionOption> {
+    options
+        .split(',')
+        .map(str::trim)
+        .filter(|o| !o.is_empty())
+        .enumerate()
+        .map(|(i, label)| QuestionOption {
+            // Moodle stores the 1-based position of the chosen label
+            value: (i + 1).to_string(),
+            label: label.to_string(),
+            disabled: false,
+        })
+        .collect()
+}
+
+fn scale_question(id: String, label: String, options: &str) -> FormQuestion {
+    FormQuestion {
+        id,
+        label,
+        description: None,
+        required: true,
+        kind: QuestionKind::SingleChoice {
+            options: scale_options(options),
+            dropdown: false,
+        },
+        answer: Vec::new(),
+    }
+}
+
+/// Expands one answerable survey question into form questions; "both"
+/// questions become an "I prefer" and an "I found" question.
+fn answer_questions(id: i64, label: &str, question_type: i64, options: &str) -> Vec<FormQuestion> {
+    match question_type {
+        SURVEY_TEXT => vec![FormQuestion {
+            id: format!("q{}", id),
+            label: label.to_string(),
+            description: None,
+            required: false,
+            kind: QuestionKind::Text {
+                multiline: true,
+                max_length: None,
+            },
+            answer: Vec::new(),
+        }],
+        SURVEY_ACTUAL => vec![scale_question(format!("q{}", id), label.to_string(), options)],
+        SURVEY_PREFERRED => vec![scale_question(format!("qP{}", id), label.to_string(), options)],
+        SURVEY_BOTH => vec![
+            scale_question(format!("qP{}", id), format!("I prefer that {}", label), options),
+            scale_question(format!("q{}", id), format!("I found that {}", label), options),
+        ],
+        _ => vec![FormQuestion {
+            id: format!("q{}", id),
+            label: label.to_string(),
+            description: None,
+            required: false,
+            kind: QuestionKind::Info,
+            answer: Vec::new(),
+        }],
+    }
+}
+
+pub async fn load_survey_form(client: &mut MoodleClient, module: &CourseModuleRef) -> Result<ActivityForm> {
+    let form: Vec<(String, String)> = vec![("courseids[0]".to_string(), module.course_id.to_string())];
+    let json = client
+        .post("mod_survey_get_surveys_by_courses", &form)
+        .await
+        .and_then(check_exception)?;
+    let survey = list(&json, "surveys")
+        .iter()
+        .find(|s| int(s, "coursemodule") == module.cmid)
+        .cloned()
+        .ok_or_else(|| anyhow!("Survey {} is not available", module.cmid))?;
+
+    let form: Vec<(String, String)> = vec![("surveyid".to_string(), module.instance.to_string())];
+    let json = client
+        .post("mod_survey_get_questions", &form)
+        .await
+        .and_then(check_exception)?;
+    let rewriter = UrlRewriter::new(client, None);
+
+    // Grouped questions take their type and scale from the group header
+    let raw = list(&json, "questions");
+    let parents: HashMap<i64, &serde_json::Value> = raw.iter().map(|q| (int(q, "id"), q)).collect();
+    let mut questions = Vec::new();
+    for question in raw {
+        let id = int(question, "id");
+        let label = to_plain_text(&text(question, "text").unwrap_or_default());
+        let parent = parents.get(&int(question, "parent")).copied();
+        if text(question, "multi").is_some_and(|m| !m.is_empty()) {
+            questions.push(FormQuestion {
+                id: format!("group{}", id),
+                label,
+                description: text(question, "intro")
+                    .map(|i| sanitize_html(&i, &rewriter))
+                    .filter(|i| !i.is_empty()),
+                required: false,
+                kind: QuestionKind::Info,
+                answer: Vec::new(),
+            });
+            continue;
+        }
+        let question_type = match (int(question, "type"), parent) {
+            (0, Some(parent)) => int(parent, "type"),
+            (question_type, _) => question_type,
+        };
+        let options = text(question, "options")
+            .filter(|o| !o.is_empty())
+            .or_else(|| parent.and_then(|p| text(p, "options")))
+            .unwrap_or_default();
+        questions.extend(answer_questions(id, &label, question_type, &options));
+    }
+
+    let already_submitted = flag(&survey, "surveydone");
+    let message = already_submitted.then(|| "You have already answered this survey".to_string());
+    Ok(ActivityForm {
+        activity: FormActivity::Survey,
+        cmid: module.cmid,
+        instance_id: module.instance,
+        name: text(&survey, "name").unwrap_or_else(|| module.name.clone()),
+        intro: text(&survey, "intro")
+            .map(|i| sanitize_html(&i, &rewriter))
+            .filter(|i| !i.is_empty()),
+        page: 0,
+        has_previous: false,
+        has_next: false,
+        questions,
+        can_submit: message.is_none(),
+        already_submitted,
+        message,
+    })
+}
+
+pub async fn submit_survey(client: &mut MoodleClient, form: &ActivityForm, answers: &FormAnswers) -> Result<FormSubmission> {
+    let mut request: Vec<(String, String)> = vec![("surveyid".to_string(), form.instance_id.to_string())];
+    let values = form
+        .questions
+        .iter()
+        .filter(|q| !matches!(q.kind, QuestionKind::Info | QuestionKind::Unsupported))
+        .filter_map(|q| Some((q.id.clone(), answers.get(&q.id)?.first()?.clone())));
+    for (i, (key, value)) in values.enumerate() {
+        request.push((format!("answers[{}][key]", i), key));
+        request.push((format!("answers[{}][value]", i), value));
+    }
+    let json = client
+        .post("mod_survey_submit_answers", &request)
+        .await
+        .and_then(check_exception)?;
+    if !flag(&json, "status") {
+        return Err(anyhow!("Moodle did not save the survey answers"));
+    }
+    Ok(FormSubmission {
+        accepted: true,
+        completed: true,
+        ..Default::default()
+    })
+}
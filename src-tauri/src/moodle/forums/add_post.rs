@@ -0,0 +1,147 @@
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::files::{embed_inline_images, upload_files_to_draft_area};
+use crate::moodle::json::{int, text};
+use anyhow::{anyhow, Result};
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+
+/// A post composed in the app, used both for new discussions and replies.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NewForumPost {
+    /// Defaults to the parent's "Re: ..." subject for replies
+    pub subject: Option<String>,
+    /// HTML body
+    pub message: String,
+    #[serde(default)]
+    pub inline_image_paths: Vec<String>,
+    #[serde(default)]
+    pub attachment_paths: Vec<String>,
+    /// Replies only: visible to the parent author and teachers
+    #[serde(default)]
+    pub private_reply: bool,
+    pub subscribe: Option<bool>,
+    /// New discussions only
+    #[serde(default)]
+    pub pinned: bool,
+    /// New discussions only
+    pub group_id: Option<i64>,
+}
+
+/// Uploads inline images and attachments and returns the message plus the
+/// options shared by `add_discussion` and `add_discussion_post`.
+async fn prepare_post(
+    client: &mut MoodleClient,
+    post: &NewForumPost,
+) -> Result<(String, Vec<(String, String)>)> {
+    let mut options: Vec<(String, String)> = Vec::new();
+
+    let message = if post.inline_image_paths.is_empty() {
+        post.message.clone()
+    } else {
+        let (message, itemid) =
+            embed_inline_images(client, &post.message, &post.inline_image_paths).await?;
+        options.push(("inlineattachmentsid".to_string(), itemid.to_string()));
+        message
+    };
+
+    if !post.attachment_paths.is_empty() {
+        let itemid = upload_files_to_draft_area(client, &post.attachment_paths).await?;
+        options.push(("attachmentsid".to_string(), itemid.to_string()));
+    }
+    if let Some(subscribe) = post.subscribe {
+        options.push(("discussionsubscribe".to_string(), (subscribe as i32).to_string()));
+    }
+
+    Ok((message, options))
+}
+
+fn options_form(options: Vec<(String, String)>) -> Vec<(String, String)> {
+    options
+        .into_iter()
+        .enumerate()
+        .flat_map(|(i, (name, value))| {
+            [
+                (format!("options[{}][name]", i), name),
+                (format!("options[{}][value]", i), value),
+            ]
+        })
+        .collect()
+}
+
+/// Replies to a post and returns the id of the new post.
+pub async fn reply_to_post(post_id: i64, post: NewForumPost) -> Result<i64> {
+    let mut client = login().await?;
+
+    let subject = match post.subject.clone() {
+        Some(subject) => subject,
+        None => {
+            let parent_form = vec![("postid".to_string(), post_id.to_string())];
+            let parent = client
+                .post("mod_forum_get_discussion_post", &parent_form)
+                .await
+                .and_then(check_exception)?;
+            parent
+                .get("post")
+                .and_then(|p| text(p, "replysubject"))
+                .unwrap_or_else(|| "Re:".to_string())
+        }
+    };
+
+    let (message, mut options) = prepare_post(&mut client, &post).await?;
+    if post.private_reply {
+        options.push(("private".to_string(), "1".to_string()));
+    }
+
+    let mut form: Vec<(String, String)> = vec![
+        ("postid".to_string(), post_id.to_string()),
+        ("subject".to_string(), subject),
+        ("message".to_string(), message),
+        ("messageformat".to_string(), "1".to_string()),
+    ];
+    form.extend(options_form(options));
+
+    let json = client
+        .post("mod_forum_add_discussion_post", &form)
+        .await
+        .and_then(check_exception)?;
+
+    match int(&json, "postid") {
+        0 => Err(anyhow!("Moodle did not accept the reply")),
+        id => Ok(id),
+    }
+}
+
+/// Starts a new discussion and returns its id.
+pub async fn start_discussion(forum_id: i64, post: NewForumPost) -> Result<i64> {
+    let mut client = login().await?;
+
+    let subject = post
+        .subject
+        .clone()
+        .filter(|s| !s.trim().is_empty())
+        .ok_or_else(|| anyhow!("A discussion needs a subject"))?;
+
+    let (message, mut options) = prepare_post(&mut client, &post).await?;
+    if post.pinned {
+        options.push(("discussionpinned".to_string(), "1".to_string()));
+    }
+
+    let mut form: Vec<(String, String)> = vec![
+        ("forumid".to_string(), forum_id.to_string()),
+        ("subject".to_string(), subject),
+        ("message".to_string(), message),
+        ("groupid".to_string(), post.group_id.unwrap_or(-1).to_string()),
+    ];
+    form.extend(options_form(options));
+
+    let json = client
+        .post("mod_forum_add_discussion", &form)
+        .await
+        .and_then(check_exception)?;
+
+    match int(&json, "discussionid") {
+        0 => Err(anyhow!("Moodle did not create the discussion")),
+        id => Ok(id),
+    }
+}
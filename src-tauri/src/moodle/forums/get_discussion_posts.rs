@@ -0,0 +1,141 @@
+use crate::moodle::calendar::login;
+use crate::moodle::content::{sanitize_html, UrlRewriter};
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{flag, int, list, text};
+use anyhow::Result;
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForumAttachment {
+    pub filename: String,
+    pub filesize: i64,
+    pub mimetype: Option<String>,
+    pub url: String,
+    pub is_image: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForumPost {
+    pub id: i64,
+    pub discussion_id: i64,
+    /// 0 for the opening post
+    pub parent_id: i64,
+    pub subject: String,
+    pub reply_subject: Option<String>,
+    pub message: String,
+    pub author_id: i64,
+    pub author_fullname: Option<String>,
+    pub author_picture: Option<String>,
+    pub time_created: i64,
+    pub time_modified: i64,
+    pub unread: bool,
+    pub is_deleted: bool,
+    pub is_private_reply: bool,
+    pub can_reply: bool,
+    pub can_reply_privately: bool,
+    pub attachments: Vec<ForumAttachment>,
+    pub replies: Vec<ForumPost>,
+}
+
+/// Fetches a discussion and nests its posts by `parentid`.
+///
+/// Returns the root posts (normally just the opening post), each holding its
+/// replies in chronological order.
+pub async fn get_discussion_thread(discussion_id: i64) -> Result<Vec<ForumPost>> {
+    let client = login().await?;
+
+    let form: Vec<(String, String)> = vec![
+        ("discussionid".to_string(), discussion_id.to_string()),
+        ("sortby".to_string(), "created".to_string()),
+        ("sortdirection".to_string(), "ASC".to_string()),
+        ("includeinlineattachments".to_string(), "1".to_string()),
+    ];
+    let json = client
+        .post("mod_forum_get_discussion_posts", &form)
+        .await
+        .and_then(check_exception)?;
+
+    // Logs the view so Moodle updates completion and read tracking
+    let view_form = vec![("discussionid".to_string(), discussion_id.to_string())];
+    let _ = client
+        .post("mod_forum_view_forum_discussion", &view_form)
+        .await;
+
+    let posts = list(&json, "posts").iter().map(|post| build_post(post, &client)).collect();
+    Ok(build_thread(posts))
+}
+
+fn build_post(post: &serde_json::Value, client: &MoodleClient) -> ForumPost {
+    let author = post.get("author");
+    let capabilities = post.get("capabilities");
+    let rewriter = UrlRewriter::new(client, None);
+
+    ForumPost {
+        id: int(post, "id"),
+        discussion_id: int(post, "discussionid"),
+        parent_id: int(post, "parentid"),
+        subject: text(post, "subject").unwrap_or_default(),
+        reply_subject: text(post, "replysubject"),
+        // Inline images point at pluginfile.php, which needs the token outside a browser session
+        message: sanitize_html(&text(post, "message").unwrap_or_default(), &rewriter),
+        author_id: author.map(|a| int(a, "id")).unwrap_or(0),
+        author_fullname: author.and_then(|a| text(a, "fullname")),
+        author_picture: author
+            .and_then(|a| a.get("urls"))
+            .and_then(|u| text(u, "profileimage"))
+            .map(|url| client.tokenize_url(&url)),
+        time_created: int(post, "timecreated"),
+        time_modified: int(post, "timemodified"),
+        unread: flag(post, "unread"),
+        is_deleted: flag(post, "isdeleted"),
+        is_private_reply: flag(post, "isprivatereply"),
+        can_reply: capabilities.map(|c| flag(c, "reply")).unwrap_or(false),
+        can_reply_privately: capabilities
+            .map(|c| flag(c, "canreplyprivately"))
+            .unwrap_or(false),
+        attachments: list(post, "attachments")
+            .iter()
+            .map(|file| ForumAttachment {
+                filename: text(file, "filename").unwrap_or_default(),
+                filesize: int(file, "filesize"),
+                mimetype: text(file, "mimetype"),
+                url: client.tokenize_url(&text(file, "url").unwrap_or_default()),
+                is_image: flag(file, "isimage"),
+            })
+            .collect(),
+        replies: Vec::new(),
+    }
+}
+
+fn build_thread(posts: Vec<ForumPost>) -> Vec<ForumPost> {
+    let ids: Vec<i64> = posts.iter().map(|p| p.id).collect();
+    let mut children: HashMap<i64, Vec<ForumPost>> = HashMap::new();
+    let mut roots = Vec::new();
+
+    for post in posts {
+        // Orphans (parent deleted or not visible to us) are shown at the top level
+        if post.parent_id != 0 && ids.contains(&post.parent_id) {
+            children.entry(post.parent_id).or_default().push(post);
+        } else {
+            roots.push(post);
+        }
+    }
+
+    fn attach(mut post: ForumPost, children: &mut HashMap<i64, Vec<ForumPost>>) -> ForumPost {
+        let mut replies = children.remove(&post.id).unwrap_or_default();
+        replies.sort_by_key(|r| r.time_created);
+        post.replies = replies
+            .into_iter()
+            .map(|reply| attach(reply, children))
+            .collect();
+        post
+    }
+
+    roots.sort_by_key(|r| r.time_created);
+    roots
+        .into_iter()
+        .map(|root| attach(root, &mut children))
+        .collect()
+}
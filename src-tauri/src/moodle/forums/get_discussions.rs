@@ -0,0 +1,118 @@
+use super::get_forums::get_course_forums;
+use crate::moodle::calendar::login;
+use crate::moodle::content::{sanitize_html, UrlRewriter};
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{flag, int, list, text};
+use anyhow::{anyhow, Result};
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForumDiscussion {
+    /// Discussion id, used to fetch the thread
+    pub id: i64,
+    /// Id of the opening post, used to reply to the discussion itself
+    pub first_post_id: i64,
+    pub name: String,
+    pub subject: String,
+    pub message: String,
+    pub author_id: i64,
+    pub author_fullname: Option<String>,
+    pub author_picture: Option<String>,
+    pub last_post_fullname: Option<String>,
+    pub created: i64,
+    pub modified: i64,
+    pub num_replies: i64,
+    pub num_unread: i64,
+    pub pinned: bool,
+    pub locked: bool,
+    pub starred: bool,
+    pub can_reply: bool,
+    pub can_favourite: bool,
+    pub attachment_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscussionPage {
+    pub forum_id: i64,
+    pub page: i64,
+    pub per_page: i64,
+    pub discussions: Vec<ForumDiscussion>,
+    pub has_more: bool,
+}
+
+pub async fn get_forum_discussions(
+    forum_id: i64,
+    page: Option<i64>,
+    per_page: Option<i64>,
+) -> Result<DiscussionPage> {
+    let client = login().await?;
+    let page = page.unwrap_or(0);
+    let per_page = per_page.unwrap_or(20);
+
+    let form: Vec<(String, String)> = vec![
+        ("forumid".to_string(), forum_id.to_string()),
+        // -1 keeps the forum's default order (pinned first, then last post)
+        ("sortorder".to_string(), "-1".to_string()),
+        ("page".to_string(), page.to_string()),
+        ("perpage".to_string(), per_page.to_string()),
+    ];
+
+    let json = client
+        .post("mod_forum_get_forum_discussions", &form)
+        .await
+        .and_then(check_exception)?;
+
+    let discussions: Vec<ForumDiscussion> = list(&json, "discussions")
+        .iter()
+        .map(|discussion| build_discussion(discussion, &client))
+        .collect();
+
+    Ok(DiscussionPage {
+        forum_id,
+        page,
+        per_page,
+        has_more: discussions.len() as i64 >= per_page,
+        discussions,
+    })
+}
+
+/// Latest discussions of the course announcements ("news") forum.
+pub async fn get_course_announcements(
+    course_id: i64,
+    limit: Option<i64>,
+) -> Result<Vec<ForumDiscussion>> {
+    let forums = get_course_forums(vec![course_id]).await?;
+    let news = forums
+        .iter()
+        .find(|f| f.is_announcements)
+        .ok_or_else(|| anyhow!("Course {} has no announcements forum", course_id))?;
+
+    let page = get_forum_discussions(news.id, Some(0), Some(limit.unwrap_or(10))).await?;
+    Ok(page.discussions)
+}
+
+fn build_discussion(discussion: &serde_json::Value, client: &MoodleClient) -> ForumDiscussion {
+    let rewriter = UrlRewriter::new(client, None);
+    ForumDiscussion {
+        id: int(discussion, "discussion"),
+        first_post_id: int(discussion, "id"),
+        name: text(discussion, "name").unwrap_or_default(),
+        subject: text(discussion, "subject").unwrap_or_default(),
+        message: sanitize_html(&text(discussion, "message").unwrap_or_default(), &rewriter),
+        author_id: int(discussion, "userid"),
+        author_fullname: text(discussion, "userfullname"),
+        author_picture: text(discussion, "userpictureurl").map(|url| client.tokenize_url(&url)),
+        last_post_fullname: text(discussion, "usermodifiedfullname"),
+        created: int(discussion, "created"),
+        modified: int(discussion, "timemodified"),
+        num_replies: int(discussion, "numreplies"),
+        num_unread: int(discussion, "numunread"),
+        pinned: flag(discussion, "pinned"),
+        locked: flag(discussion, "locked"),
+        starred: flag(discussion, "starred"),
+        can_reply: flag(discussion, "canreply"),
+        can_favourite: flag(discussion, "canfavourite"),
+        attachment_count: list(discussion, "attachments").len(),
+    }
+}
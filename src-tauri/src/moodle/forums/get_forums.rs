@@ -0,0 +1,63 @@
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{flag, int, text};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Forum {
+    pub id: i64,
+    pub cmid: i64,
+    pub course_id: i64,
+    pub name: String,
+    pub intro: Option<String>,
+    /// `general`, `news`, `qanda`, `single`, `eachuser`, `blog` or `social`
+    pub forum_type: String,
+    /// The course announcements forum
+    pub is_announcements: bool,
+    pub discussion_count: i64,
+    pub can_create_discussions: bool,
+    /// 0 = optional, 1 = forced, 2 = auto, 3 = disabled
+    pub force_subscribe: i64,
+    pub is_tracked: bool,
+    pub unread_posts: i64,
+}
+
+/// Lists the forums of the given courses, with unread counts for tracked forums.
+pub async fn get_course_forums(course_ids: Vec<i64>) -> Result<Vec<Forum>> {
+    let client = login().await?;
+
+    let form: Vec<(String, String)> = course_ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (format!("courseids[{}]", i), id.to_string()))
+        .collect();
+
+    let json = client
+        .post("mod_forum_get_forums_by_courses", &form)
+        .await
+        .and_then(check_exception)?;
+
+    Ok(json
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|forum| {
+            let forum_type = text(forum, "type").unwrap_or_else(|| "general".to_string());
+            Forum {
+                id: int(forum, "id"),
+                cmid: int(forum, "cmid"),
+                course_id: int(forum, "course"),
+                name: text(forum, "name").unwrap_or_default(),
+                intro: text(forum, "intro"),
+                is_announcements: forum_type == "news",
+                forum_type,
+                discussion_count: int(forum, "numdiscussions"),
+                can_create_discussions: flag(forum, "cancreatediscussions"),
+                force_subscribe: int(forum, "forcesubscribe"),
+                is_tracked: flag(forum, "istracked"),
+                unread_posts: int(forum, "unreadpostscount"),
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,11 @@
+pub mod add_post;
+pub mod get_discussion_posts;
+pub mod get_discussions;
+pub mod get_forums;
+pub mod subscriptions;
+
+pub use add_post::*;
+pub use get_discussion_posts::*;
+pub use get_discussions::*;
+pub use get_forums::*;
+pub use subscriptions::*;
@@ -0,0 +1,69 @@
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{flag, int};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscussionState {
+    pub discussion_id: i64,
+    pub subscribed: bool,
+    pub favourited: bool,
+    pub pinned: bool,
+    pub locked: bool,
+}
+
+impl DiscussionState {
+    fn from_json(json: &serde_json::Value) -> Self {
+        let userstate = json.get("userstate").cloned().unwrap_or_default();
+        DiscussionState {
+            discussion_id: int(json, "id"),
+            subscribed: flag(&userstate, "subscribed"),
+            favourited: flag(&userstate, "favourited"),
+            pinned: flag(json, "pinned"),
+            locked: flag(json, "locked"),
+        }
+    }
+}
+
+/// Subscribes to (or unsubscribes from) e-mail and app notifications for a discussion.
+///
+/// Only discussions can be subscribed to: `mod_forum_set_subscription_state`
+/// has no forum-wide mode and no other web service function subscribes to a
+/// whole forum. Course announcements still reach everyone because Moodle
+/// forces subscription to the news forum (see `Forum::force_subscribe`).
+pub async fn set_discussion_subscription(
+    forum_id: i64,
+    discussion_id: i64,
+    subscribed: bool,
+) -> Result<DiscussionState> {
+    let client = login().await?;
+
+    let form: Vec<(String, String)> = vec![
+        ("forumid".to_string(), forum_id.to_string()),
+        ("discussionid".to_string(), discussion_id.to_string()),
+        ("targetstate".to_string(), (subscribed as i32).to_string()),
+    ];
+    let json = client
+        .post("mod_forum_set_subscription_state", &form)
+        .await
+        .and_then(check_exception)?;
+
+    Ok(DiscussionState::from_json(&json))
+}
+
+/// Stars (or unstars) a discussion.
+pub async fn set_discussion_favourite(discussion_id: i64, favourite: bool) -> Result<DiscussionState> {
+    let client = login().await?;
+
+    let form: Vec<(String, String)> = vec![
+        ("discussionid".to_string(), discussion_id.to_string()),
+        ("targetstate".to_string(), (favourite as i32).to_string()),
+    ];
+    let json = client
+        .post("mod_forum_toggle_favourite_state", &form)
+        .await
+        .and_then(check_exception)?;
+
+    Ok(DiscussionState::from_json(&json))
+}
@@ -0,0 +1,136 @@
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{flag, int, list, text};
+use crate::moodle::site::get_current_user_id;
+use anyhow::Result;
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GradeItem {
+    pub id: i64,
+    pub name: String,
+    /// `course`, `category`, `mod` or `manual`
+    pub item_type: String,
+    pub module: Option<String>,
+    pub cmid: Option<i64>,
+    pub category_id: Option<i64>,
+    pub grade_raw: Option<f64>,
+    pub grade_min: f64,
+    pub grade_max: f64,
+    pub grade_formatted: Option<String>,
+    pub percentage_formatted: Option<String>,
+    pub hidden: bool,
+    pub date_graded: Option<i64>,
+    pub feedback: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CourseGradeTotal {
+    pub grade: Option<String>,
+    pub grade_raw: Option<f64>,
+    pub grade_max: Option<f64>,
+    pub percentage: Option<f64>,
+    pub graded_items: usize,
+    pub total_items: usize,
+    /// True when Moodle hides the course total and it was summed from the items
+    pub computed: bool,
+}
+
+pub async fn get_grade_items(course_id: i64) -> Result<Vec<GradeItem>> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+    fetch_grade_items(&mut client, course_id, user_id).await
+}
+
+async fn fetch_grade_items(
+    client: &mut MoodleClient,
+    course_id: i64,
+    user_id: i64,
+) -> Result<Vec<GradeItem>> {
+    let form: Vec<(String, String)> = vec![
+        ("courseid".to_string(), course_id.to_string()),
+        ("userid".to_string(), user_id.to_string()),
+    ];
+    let json = client
+        .post("gradereport_user_get_grade_items", &form)
+        .await
+        .and_then(check_exception)?;
+
+    let usergrade = list(&json, "usergrades")
+        .first()
+        .cloned()
+        .unwrap_or_default();
+    Ok(list(&usergrade, "gradeitems")
+        .iter()
+        .map(|item| GradeItem {
+            id: int(item, "id"),
+            name: text(item, "itemname").unwrap_or_default(),
+            item_type: text(item, "itemtype").unwrap_or_default(),
+            module: text(item, "itemmodule"),
+            cmid: item.get("cmid").and_then(|c| c.as_i64()),
+            category_id: item.get("categoryid").and_then(|c| c.as_i64()),
+            grade_raw: item.get("graderaw").and_then(|g| g.as_f64()),
+            grade_min: item.get("grademin").and_then(|g| g.as_f64()).unwrap_or(0.0),
+            grade_max: item.get("grademax").and_then(|g| g.as_f64()).unwrap_or(0.0),
+            grade_formatted: text(item, "gradeformatted").filter(|g| g != "-"),
+            percentage_formatted: text(item, "percentageformatted").filter(|p| p != "-"),
+            hidden: flag(item, "gradeishidden"),
+            date_graded: item.get("gradedategraded").and_then(|d| d.as_i64()),
+            feedback: text(item, "feedback"),
+        })
+        .collect())
+}
+
+pub async fn get_course_total(
+    client: &mut MoodleClient,
+    course_id: i64,
+    user_id: i64,
+) -> Result<CourseGradeTotal> {
+    let items = fetch_grade_items(client, course_id, user_id).await?;
+    Ok(compute_course_total(&items))
+}
+
+/// Uses the course total item when the user may see it, otherwise sums the
+/// visible graded activities (natural aggregation).
+pub fn compute_course_total(items: &[GradeItem]) -> CourseGradeTotal {
+    let activities: Vec<&GradeItem> = items
+        .iter()
+        .filter(|i| i.item_type == "mod" || i.item_type == "manual")
+        .collect();
+    let graded: Vec<&&GradeItem> = activities
+        .iter()
+        .filter(|i| i.grade_raw.is_some() && !i.hidden)
+        .collect();
+
+    let course_item = items
+        .iter()
+        .find(|i| i.item_type == "course" && i.grade_raw.is_some() && !i.hidden);
+
+    if let Some(course) = course_item {
+        let grade_raw = course.grade_raw.unwrap_or(0.0);
+        let range = course.grade_max - course.grade_min;
+        return CourseGradeTotal {
+            grade: course.grade_formatted.clone(),
+            grade_raw: Some(grade_raw),
+            grade_max: Some(course.grade_max),
+            percentage: (range > 0.0).then(|| (grade_raw - course.grade_min) / range * 100.0),
+            graded_items: graded.len(),
+            total_items: activities.len(),
+            computed: false,
+        };
+    }
+
+    let earned: f64 = graded.iter().filter_map(|i| i.grade_raw).sum();
+    let max: f64 = graded.iter().map(|i| i.grade_max).sum();
+
+    CourseGradeTotal {
+        grade: (!graded.is_empty()).then(|| format!("{:.2}", earned)),
+        grade_raw: (!graded.is_empty()).then_some(earned),
+        grade_max: (!graded.is_empty()).then_some(max),
+        percentage: (max > 0.0).then(|| earned / max * 100.0),
+        graded_items: graded.len(),
+        total_items: activities.len(),
+        computed: true,
+    }
+}
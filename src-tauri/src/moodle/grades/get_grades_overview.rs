@@ -0,0 +1,88 @@
+use super::get_grades_table::parse_percentage;
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{int, list, text};
+use crate::moodle::site::get_current_user_id;
+use anyhow::Result;
+use moodle_api::core::course::get_enrolled_courses_by_timeline_classification::{call_raw, Params};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CourseGradeSummary {
+    pub course_id: i64,
+    pub course_name: Option<String>,
+    pub course_shortname: Option<String>,
+    /// Formatted course total as Moodle displays it (points, letter or %)
+    pub grade: Option<String>,
+    pub raw_grade: Option<f64>,
+    pub percentage: Option<f64>,
+    pub rank: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GradesOverview {
+    pub courses: Vec<CourseGradeSummary>,
+    pub graded_courses: usize,
+    /// Mean of the course percentages that could be determined
+    pub average_percentage: Option<f64>,
+}
+
+/// Course totals for every enrolled course (`gradereport_overview_get_course_grades`).
+pub async fn get_grades_overview() -> Result<GradesOverview> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+
+    let form: Vec<(String, String)> = vec![("userid".to_string(), user_id.to_string())];
+    let json = client
+        .post("gradereport_overview_get_course_grades", &form)
+        .await
+        .and_then(check_exception)?;
+
+    let enrolled = call_raw(
+        &mut client,
+        &mut Params {
+            classification: Some("all".to_string()),
+            limit: None,
+            offset: None,
+            sort: None,
+            customfieldname: None,
+            customfieldvalue: None,
+            searchvalue: None,
+        },
+    )
+    .await?;
+    let names: HashMap<i64, &serde_json::Value> = list(&enrolled, "courses")
+        .iter()
+        .map(|c| (int(c, "id"), c))
+        .collect();
+
+    let courses: Vec<CourseGradeSummary> = list(&json, "grades")
+        .iter()
+        .map(|grade| {
+            let course_id = int(grade, "courseid");
+            let course = names.get(&course_id);
+            let formatted = text(grade, "grade").filter(|g| g != "-");
+            CourseGradeSummary {
+                course_id,
+                course_name: course.and_then(|c| text(c, "fullname")),
+                course_shortname: course.and_then(|c| text(c, "shortname")),
+                percentage: formatted
+                    .as_deref()
+                    .filter(|g| g.contains('%'))
+                    .and_then(parse_percentage),
+                grade: formatted,
+                raw_grade: text(grade, "rawgrade").and_then(|r| r.parse().ok()),
+                rank: grade.get("rank").and_then(|r| r.as_i64()),
+            }
+        })
+        .collect();
+
+    let percentages: Vec<f64> = courses.iter().filter_map(|c| c.percentage).collect();
+    Ok(GradesOverview {
+        graded_courses: courses.iter().filter(|c| c.grade.is_some()).count(),
+        average_percentage: (!percentages.is_empty())
+            .then(|| percentages.iter().sum::<f64>() / percentages.len() as f64),
+        courses,
+    })
+}
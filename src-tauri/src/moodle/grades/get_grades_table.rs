@@ -0,0 +1,256 @@
+use super::get_grade_items::{get_course_total, CourseGradeTotal};
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::html::to_plain_text;
+use crate::moodle::json::{list, text};
+use crate::moodle::site::get_current_user_id;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GradeRowKind {
+    Category,
+    Item,
+    CategoryTotal,
+    CourseTotal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GradeRecord {
+    pub kind: GradeRowKind,
+    /// Nesting level in the gradebook, 1 is the course category
+    pub depth: i64,
+    pub name: String,
+    /// Closest enclosing category, `None` for the course category itself
+    pub category: Option<String>,
+    pub weight: Option<String>,
+    pub grade: Option<String>,
+    pub range: Option<String>,
+    pub percentage: Option<f64>,
+    pub letter_grade: Option<String>,
+    pub rank: Option<String>,
+    pub average: Option<String>,
+    pub feedback: Option<String>,
+    pub contribution_to_course_total: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CourseGradeReport {
+    pub course_id: i64,
+    pub user_id: i64,
+    pub user_fullname: Option<String>,
+    pub records: Vec<GradeRecord>,
+    pub total: CourseGradeTotal,
+}
+
+/// Fetches the user report for a course and turns its HTML cells into records.
+pub async fn get_course_grade_report(course_id: i64) -> Result<CourseGradeReport> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+
+    let form: Vec<(String, String)> = vec![
+        ("courseid".to_string(), course_id.to_string()),
+        ("userid".to_string(), user_id.to_string()),
+    ];
+    let json = client
+        .post("gradereport_user_get_grades_table", &form)
+        .await
+        .and_then(check_exception)?;
+
+    let table = list(&json, "tables").first().cloned().unwrap_or_default();
+    let records = parse_grade_table(list(&table, "tabledata"));
+    let total = get_course_total(&mut client, course_id, user_id).await?;
+
+    Ok(CourseGradeReport {
+        course_id,
+        user_id,
+        user_fullname: text(&table, "userfullname"),
+        records,
+        total,
+    })
+}
+
+/// Each row is an object of columns (`itemname`, `grade`, `range`, ...), every
+/// column holding `class` and HTML `content`. The row type is only encoded in
+/// the `itemname` CSS classes: `category` headers, `item` rows and `baggt`
+/// totals, with `levelN` giving the depth.
+pub fn parse_grade_table(rows: &[serde_json::Value]) -> Vec<GradeRecord> {
+    let mut categories: Vec<(i64, String)> = Vec::new();
+    let mut records = Vec::new();
+
+    for row in rows {
+        let Some(itemname) = row.get("itemname") else {
+            // Spacer rows only carry a `leader` cell
+            continue;
+        };
+        let class = text(itemname, "class").unwrap_or_default();
+        let name = cell(row, "itemname").unwrap_or_default();
+        let depth = class
+            .split_whitespace()
+            .find_map(|c| c.strip_prefix("level").and_then(|d| d.parse::<i64>().ok()))
+            .unwrap_or(1);
+        let has_class = |c: &str| class.split_whitespace().any(|k| k == c);
+
+        let kind = if has_class("category") {
+            GradeRowKind::Category
+        } else if has_class("baggt") || has_class("baggb") {
+            GradeRowKind::CategoryTotal
+        } else {
+            GradeRowKind::Item
+        };
+
+        if kind == GradeRowKind::Category {
+            categories.retain(|(d, _)| *d < depth);
+        }
+        let category = categories
+            .iter()
+            .rev()
+            .find(|(d, _)| *d < depth)
+            .map(|(_, n)| n.clone());
+        if kind == GradeRowKind::Category {
+            categories.push((depth, name.clone()));
+        }
+
+        records.push(GradeRecord {
+            kind,
+            depth,
+            name,
+            category,
+            weight: cell(row, "weight"),
+            grade: cell(row, "grade"),
+            range: cell(row, "range"),
+            percentage: cell(row, "percentage").and_then(|p| parse_percentage(&p)),
+            letter_grade: cell(row, "lettergrade"),
+            rank: cell(row, "rank"),
+            average: cell(row, "average"),
+            feedback: cell(row, "feedback"),
+            contribution_to_course_total: cell(row, "contributiontocoursetotal"),
+        });
+    }
+
+    // The course total is always rendered last, closing the top category
+    if let Some(last_total) = records
+        .iter_mut()
+        .rev()
+        .find(|r| r.kind == GradeRowKind::CategoryTotal)
+    {
+        last_total.kind = GradeRowKind::CourseTotal;
+    }
+
+    records
+}
+
+/// Plain-text content of a column, `None` for empty cells and Moodle's `-` placeholder.
+fn cell(row: &serde_json::Value, column: &str) -> Option<String> {
+    let content = row.get(column).and_then(|c| text(c, "content"))?;
+    let plain = to_plain_text(&content);
+    match plain.as_str() {
+        "" | "-" | "&nbsp;" => None,
+        _ => Some(plain),
+    }
+}
+
+/// "85.00 %" or "85,00 %" -> 85.0
+pub fn parse_percentage(value: &str) -> Option<f64> {
+    value
+        .trim()
+        .trim_end_matches('%')
+        .trim()
+        .replace(',', ".")
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn row(class: &str, name: &str, grade: &str, percentage: &str) -> serde_json::Value {
+        json!({
+            "itemname": { "class": class, "content": name },
+            "grade": { "class": "level2 column-grade", "content": grade },
+            "percentage": { "class": "level2 column-percentage", "content": percentage },
+            "feedback": { "class": "level2 column-feedback", "content": "&nbsp;" },
+        })
+    }
+
+    #[test]
+    fn parses_nested_categories_and_totals() {
+        let rows = vec![
+            row(
+                "level1 levelodd oddd1 b1b b1t column-itemname category",
+                "Physics 101",
+                "",
+                "",
+            ),
+            row(
+                "level2 leveleven item b1b column-itemname",
+                "<a href=\"#\">Quiz 1</a>",
+                "8.00",
+                "80.00 %",
+            ),
+            row(
+                "level2 leveleven oddd2 b1t column-itemname category",
+                "Labs",
+                "",
+                "",
+            ),
+            row(
+                "level3 levelodd item b1b column-itemname",
+                "Lab 1",
+                "-",
+                "-",
+            ),
+            row(
+                "level3 levelodd baggt b2b column-itemname",
+                "Labs total",
+                "15.00",
+                "75,00 %",
+            ),
+            json!({ "leader": { "class": "leader", "rowspan": 2 } }),
+            row(
+                "level2 leveleven baggt b2b column-itemname",
+                "Course total",
+                "23.00",
+                "76.67 %",
+            ),
+        ];
+        let records = parse_grade_table(&rows);
+        assert_eq!(records.len(), 6);
+
+        let kinds: Vec<GradeRowKind> = records.iter().map(|r| r.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                GradeRowKind::Category,
+                GradeRowKind::Item,
+                GradeRowKind::Category,
+                GradeRowKind::Item,
+                GradeRowKind::CategoryTotal,
+                GradeRowKind::CourseTotal,
+            ]
+        );
+
+        assert_eq!(records[0].category, None);
+        assert_eq!(records[1].name, "Quiz 1");
+        assert_eq!(records[1].category.as_deref(), Some("Physics 101"));
+        assert_eq!(records[1].grade.as_deref(), Some("8.00"));
+        assert_eq!(records[1].percentage, Some(80.0));
+        assert_eq!(records[1].feedback, None);
+        assert_eq!(records[2].category.as_deref(), Some("Physics 101"));
+        assert_eq!(records[3].category.as_deref(), Some("Labs"));
+        assert_eq!(records[3].grade, None);
+        assert_eq!(records[4].percentage, Some(75.0));
+        assert_eq!(records[5].depth, 2);
+    }
+
+    #[test]
+    fn percentages() {
+        assert_eq!(parse_percentage("85.00 %"), Some(85.0));
+        assert_eq!(parse_percentage("85,50 %"), Some(85.5));
+        assert_eq!(parse_percentage("100%"), Some(100.0));
+        assert_eq!(parse_percentage("-"), None);
+    }
+}
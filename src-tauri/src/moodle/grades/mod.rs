@@ -0,0 +1,7 @@
+pub mod get_grade_items;
+pub mod get_grades_overview;
+pub mod get_grades_table;
+
+pub use get_grade_items::*;
+pub use get_grades_overview::*;
+pub use get_grades_table::*;
@@ -0,0 +1,130 @@
+use super::package::fetch_h5p_activity;
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{flag, int, list, text};
+use crate::moodle::site::get_current_user_id;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct H5pAttemptResult {
+    pub id: i64,
+    pub subcontent: String,
+    pub description: String,
+    pub interaction_type: String,
+    pub raw_score: f64,
+    pub max_score: f64,
+    pub completion: Option<bool>,
+    pub success: Option<bool>,
+    /// The learner's response as stored by Moodle
+    pub response: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct H5pAttempt {
+    pub id: i64,
+    pub attempt: i64,
+    pub raw_score: f64,
+    pub max_score: f64,
+    /// Percentage, when the content reports a score
+    pub scaled: Option<f64>,
+    pub duration: i64,
+    pub completion: Option<bool>,
+    pub success: Option<bool>,
+    pub time_created: i64,
+    pub time_modified: i64,
+    pub results: Vec<H5pAttemptResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct H5pAttempts {
+    pub activity_id: i64,
+    pub enable_tracking: bool,
+    /// How the activity grade is computed from attempts
+    pub grade_method: i64,
+    pub attempts: Vec<H5pAttempt>,
+}
+
+fn number(json: &serde_json::Value, key: &str) -> f64 {
+    json.get(key).and_then(|v| v.as_f64()).unwrap_or_default()
+}
+
+fn optional_flag(json: &serde_json::Value, key: &str) -> Option<bool> {
+    match json.get(key)? {
+        serde_json::Value::Null => None,
+        value => Some(value.as_bool().unwrap_or(value.as_i64() == Some(1))),
+    }
+}
+
+fn parse_result(json: &serde_json::Value) -> H5pAttemptResult {
+    H5pAttemptResult {
+        id: int(json, "id"),
+        subcontent: text(json, "subcontent").unwrap_or_default(),
+        description: text(json, "description").unwrap_or_default(),
+        interaction_type: text(json, "interactiontype").unwrap_or_default(),
+        raw_score: number(json, "rawscore"),
+        max_score: number(json, "maxscore"),
+        completion: optional_flag(json, "completion"),
+        success: optional_flag(json, "success"),
+        response: text(json, "response").unwrap_or_default(),
+    }
+}
+
+fn parse_attempt(json: &serde_json::Value) -> H5pAttempt {
+    H5pAttempt {
+        id: int(json, "id"),
+        attempt: int(json, "attempt"),
+        raw_score: number(json, "rawscore"),
+        max_score: number(json, "maxscore"),
+        scaled: json.get("scaled").and_then(|v| v.as_f64()).map(|s| s * 100.0),
+        duration: int(json, "duration"),
+        completion: optional_flag(json, "completion"),
+        success: optional_flag(json, "success"),
+        time_created: int(json, "timecreated"),
+        time_modified: int(json, "timemodified"),
+        results: list(json, "results").iter().map(parse_result).collect(),
+    }
+}
+
+/// The current user's attempts at an H5P activity with per-question results.
+pub async fn get_h5p_attempts(cmid: i64) -> Result<H5pAttempts> {
+    let mut client = login().await?;
+    let activity = fetch_h5p_activity(&mut client, cmid).await?;
+    let activity_id = int(&activity, "id");
+    let user_id = get_current_user_id(&mut client).await?;
+
+    let form: Vec<(String, String)> = vec![
+        ("h5pactivityid".to_string(), activity_id.to_string()),
+        ("userids[0]".to_string(), user_id.to_string()),
+    ];
+    let json = client
+        .post("mod_h5pactivity_get_attempts", &form)
+        .await
+        .and_then(check_exception)?;
+    let attempt_ids: Vec<i64> = list(&json, "usersattempts")
+        .iter()
+        .flat_map(|user| list(user, "attempts"))
+        .map(|a| int(a, "id"))
+        .collect();
+
+    let mut attempts = Vec::new();
+    if !attempt_ids.is_empty() {
+        let mut form: Vec<(String, String)> = vec![("h5pactivityid".to_string(), activity_id.to_string())];
+        for (i, id) in attempt_ids.iter().enumerate() {
+            form.push((format!("attemptids[{}]", i), id.to_string()));
+        }
+        let results = client
+            .post("mod_h5pactivity_get_results", &form)
+            .await
+            .and_then(check_exception)?;
+        attempts = list(&results, "attempts").iter().map(parse_attempt).collect();
+    }
+    attempts.sort_by_key(|a: &H5pAttempt| a.attempt);
+
+    Ok(H5pAttempts {
+        activity_id,
+        enable_tracking: flag(&activity, "enabletracking"),
+        grade_method: int(&activity, "grademethod"),
+        attempts,
+    })
+}
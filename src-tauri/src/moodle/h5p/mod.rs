@@ -0,0 +1,9 @@
+pub mod attempts;
+pub mod package;
+pub mod player;
+pub mod xapi;
+
+pub use attempts::*;
+pub use package::*;
+pub use player::*;
+pub use xapi::*;
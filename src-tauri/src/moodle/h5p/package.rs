@@ -0,0 +1,166 @@
+use crate::moodle::calendar::login;
+use crate::moodle::content::fetch_course_module;
+use crate::moodle::exception::check_exception;
+use crate::moodle::files::{archive_file, extract_zip};
+use crate::moodle::json::{flag, int, list, text};
+use anyhow::{anyhow, Result};
+use moodle_api::core::webservice::get_site_info;
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Package details saved next to the extracted files.
+const PACKAGE_INFO: &str = ".package.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct H5pPackage {
+    pub activity_id: i64,
+    pub cmid: i64,
+    pub course_id: i64,
+    /// Context of the activity, which xAPI statements must refer to
+    pub context_id: i64,
+    pub name: String,
+    /// Title and main library from `h5p.json`
+    pub title: String,
+    pub main_library: String,
+    pub content_hash: String,
+    pub enable_tracking: bool,
+    pub site_url: String,
+    pub user_id: i64,
+    pub directory: PathBuf,
+}
+
+impl H5pPackage {
+    /// IRI Moodle expects as the statement object for this activity.
+    pub fn activity_iri(&self) -> String {
+        format!("{}/xapi/activity/{}", self.site_url.trim_end_matches('/'), self.context_id)
+    }
+}
+
+/// What the webview needs to play a package: `player_url` goes in an iframe
+/// and runs the bundled H5P runtime on `content_url`, which serves
+/// `h5p.json`, `content/` and the libraries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct H5pLaunch {
+    pub package: H5pPackage,
+    pub player_url: String,
+    pub content_url: String,
+    /// True when Moodle was unreachable and the stored copy is used
+    pub offline: bool,
+}
+
+/// Base URL of the `h5p` protocol; Windows and Android webviews expose custom protocols over http.
+pub fn h5p_protocol_base() -> &'static str {
+    if cfg!(any(windows, target_os = "android")) {
+        "http://h5p.localhost/"
+    } else {
+        "h5p://localhost/"
+    }
+}
+
+pub fn h5p_directory(packages_dir: &Path, activity_id: i64) -> PathBuf {
+    packages_dir.join(activity_id.to_string())
+}
+
+pub fn load_h5p_package(packages_dir: &Path, activity_id: i64) -> Option<H5pPackage> {
+    let info = std::fs::read_to_string(h5p_directory(packages_dir, activity_id).join(PACKAGE_INFO)).ok()?;
+    serde_json::from_str(&info).ok()
+}
+
+/// A file of an extracted package, for the `h5p` protocol.
+pub fn h5p_file(packages_dir: &Path, activity_id: i64, relative: &str) -> Option<PathBuf> {
+    archive_file(&h5p_directory(packages_dir, activity_id), relative)
+}
+
+/// Finds a downloaded package by course module id, for offline launches.
+fn find_local_h5p_package(packages_dir: &Path, cmid: i64) -> Option<H5pPackage> {
+    std::fs::read_dir(packages_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<i64>().ok())
+        .filter_map(|activity_id| load_h5p_package(packages_dir, activity_id))
+        .find(|package| package.cmid == cmid)
+}
+
+pub async fn fetch_h5p_activity(client: &mut MoodleClient, cmid: i64) -> Result<serde_json::Value> {
+    let module = fetch_course_module(client, cmid, "h5pactivity").await?;
+    let form: Vec<(String, String)> = vec![("courseids[0]".to_string(), module.course_id.to_string())];
+    let json = client
+        .post("mod_h5pactivity_get_h5pactivities_by_courses", &form)
+        .await
+        .and_then(check_exception)?;
+    list(&json, "h5pactivities")
+        .iter()
+        .find(|a| int(a, "coursemodule") == cmid)
+        .cloned()
+        .ok_or_else(|| anyhow!("H5P activity {} is not available", cmid))
+}
+
+/// Downloads the `.h5p` of an activity and extracts it for the player,
+/// unless the same content is already on disk.
+async fn fetch_h5p_package(cmid: i64, packages_dir: &Path) -> Result<H5pPackage> {
+    let mut client = login().await?;
+    let activity = fetch_h5p_activity(&mut client, cmid).await?;
+    let activity_id = int(&activity, "id");
+    let content_hash = text(&activity, "contenthash").unwrap_or_default();
+    if let Some(existing) = load_h5p_package(packages_dir, activity_id).filter(|p| p.content_hash == content_hash) {
+        return Ok(existing);
+    }
+
+    // The deployed file includes the libraries the site used; the uploaded
+    // package may not
+    let file_url = activity
+        .get("deployedfile")
+        .and_then(|f| text(f, "fileurl"))
+        .or_else(|| list(&activity, "package").first().and_then(|f| text(f, "fileurl")))
+        .ok_or_else(|| anyhow!("This H5P activity can't be downloaded"))?;
+    let bytes = client.download(&file_url).await?;
+
+    let directory = h5p_directory(packages_dir, activity_id);
+    if directory.exists() {
+        tokio::fs::remove_dir_all(&directory).await?;
+    }
+    tokio::fs::create_dir_all(&directory).await?;
+    let target = directory.clone();
+    tokio::task::spawn_blocking(move || extract_zip(&bytes, &target)).await??;
+
+    let h5p_json: serde_json::Value = serde_json::from_slice(&tokio::fs::read(directory.join("h5p.json")).await?)?;
+    let site = get_site_info::call_raw(&mut client, &mut get_site_info::Params { serviceshortnames: None }).await?;
+
+    let package = H5pPackage {
+        activity_id,
+        cmid,
+        course_id: int(&activity, "course"),
+        context_id: int(&activity, "context"),
+        name: text(&activity, "name").unwrap_or_default(),
+        title: text(&h5p_json, "title").unwrap_or_default(),
+        main_library: text(&h5p_json, "mainLibrary").unwrap_or_default(),
+        content_hash,
+        enable_tracking: flag(&activity, "enabletracking"),
+        site_url: text(&site, "siteurl").unwrap_or_default(),
+        user_id: int(&site, "userid"),
+        directory: directory.clone(),
+    };
+    tokio::fs::write(directory.join(PACKAGE_INFO), serde_json::to_vec_pretty(&package)?).await?;
+
+    let view: Vec<(String, String)> = vec![("h5pactivityid".to_string(), activity_id.to_string())];
+    let _ = client.post("mod_h5pactivity_view_h5pactivity", &view).await;
+    Ok(package)
+}
+
+/// The package to play, downloading it if needed. Falls back to the stored
+/// copy when Moodle can't be reached.
+pub async fn download_h5p_package(cmid: i64, packages_dir: &Path) -> Result<H5pLaunch> {
+    let (package, offline) = match fetch_h5p_package(cmid, packages_dir).await {
+        Ok(package) => (package, false),
+        Err(e) => match find_local_h5p_package(packages_dir, cmid) {
+            Some(package) => (package, true),
+            None => return Err(e),
+        },
+    };
+    Ok(H5pLaunch {
+        player_url: format!("{}{}/player.html", h5p_protocol_base(), package.activity_id),
+        content_url: format!("{}{}", h5p_protocol_base(), package.activity_id),
+        package,
+        offline,
+    })
+}
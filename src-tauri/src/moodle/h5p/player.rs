@@ -0,0 +1,54 @@
+use super::package::{h5p_protocol_base, H5pPackage};
+use crate::moodle::files::archive_file;
+use crate::moodle::html::from_plain_text;
+use std::path::{Path, PathBuf};
+
+/// Route of the bundled H5P runtime (h5p-standalone) on the `h5p` protocol.
+pub const H5P_PLAYER_ROUTE: &str = "player";
+
+/// A file of the bundled runtime; `None` for paths that would leave `runtime_dir`.
+pub fn h5p_player_file(runtime_dir: &Path, relative: &str) -> Option<PathBuf> {
+    archive_file(runtime_dir, relative)
+}
+
+/// The page that plays an extracted package with the bundled runtime.
+///
+/// xAPI statements are posted to the parent window as
+/// `{ type: "h5p-xapi", activityId, statement }`; the app passes them to
+/// `queue_xapi_statement`.
+pub fn h5p_player_html(package: &H5pPackage) -> String {
+    let base = h5p_protocol_base();
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>html, body {{ margin: 0; padding: 0; }}</style>
+<script src="{base}{player}/main.bundle.js"></script>
+</head>
+<body>
+<div id="h5p-container"></div>
+<script>
+(function () {{
+  var container = document.getElementById("h5p-container");
+  new H5PStandalone.H5P(container, {{
+    h5pJsonPath: "{base}{activity_id}",
+    frameJs: "{base}{player}/frame.bundle.js",
+    frameCss: "{base}{player}/styles/h5p.css"
+  }}).then(function () {{
+    H5P.externalDispatcher.on("xAPI", function (event) {{
+      window.parent.postMessage({{ type: "h5p-xapi", activityId: {activity_id}, statement: event.data.statement }}, "*");
+    }});
+  }});
+}})();
+</script>
+</body>
+</html>
+"#,
+        title = from_plain_text(&package.title),
+        base = base,
+        player = H5P_PLAYER_ROUTE,
+        activity_id = package.activity_id,
+    )
+}
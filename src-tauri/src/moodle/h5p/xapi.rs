@@ -0,0 +1,63 @@
+use super::package::H5pPackage;
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+
+/// Component Moodle routes H5P activity statements to.
+pub const H5P_XAPI_COMPONENT: &str = "mod_h5pactivity";
+
+/// Rewrites a statement emitted by the local player so Moodle accepts it:
+/// the actor becomes the site account and the object points at the activity
+/// context instead of the local `h5p://` URL. Statements about sub-content
+/// keep their `subContentId`.
+pub fn prepare_xapi_statement(package: &H5pPackage, mut statement: Value) -> Result<Value> {
+    let object = statement
+        .get_mut("object")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| anyhow!("xAPI statement has no object"))?;
+    let sub_content = object
+        .get("id")
+        .and_then(Value::as_str)
+        .and_then(|id| id.split_once('?'))
+        .map(|(_, query)| query.to_string())
+        .filter(|query| query.contains("subContentId="));
+    let activity_iri = package.activity_iri();
+    object.insert(
+        "id".to_string(),
+        json!(match sub_content {
+            Some(query) => format!("{}?{}", activity_iri, query),
+            None => activity_iri,
+        }),
+    );
+    object.entry("objectType").or_insert_with(|| json!("Activity"));
+
+    statement["actor"] = json!({
+        "objectType": "Agent",
+        "account": {
+            "homePage": package.site_url,
+            "name": package.user_id.to_string(),
+        },
+    });
+    if statement.get("timestamp").map_or(true, Value::is_null) {
+        statement["timestamp"] = json!(chrono::Utc::now().to_rfc3339());
+    }
+    Ok(statement)
+}
+
+/// Posts one statement with `core_xapi_statement_post`. Returns false when
+/// Moodle refused to store it.
+pub async fn post_xapi_statement(component: &str, statement: &Value) -> Result<bool> {
+    let client = login().await?;
+    let form: Vec<(String, String)> = vec![
+        ("component".to_string(), component.to_string()),
+        ("requestjson".to_string(), serde_json::to_string(&[statement])?),
+    ];
+    let json = client
+        .post("core_xapi_statement_post", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(json
+        .as_array()
+        .is_some_and(|results| !results.is_empty() && results.iter().all(|r| r.as_bool() == Some(true))))
+}
@@ -0,0 +1,51 @@
+use scraper::Html;
+
+/// Flattens an HTML fragment from Moodle into readable plain text.
+pub fn to_plain_text(html: &str) -> String {
+    let fragment = Html::parse_fragment(html);
+    fragment
+        .root_element()
+        .text()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Escapes plain text for an HTML-format Moodle field, keeping line breaks.
+pub fn from_plain_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .lines()
+        .collect::<Vec<_>>()
+        .join("<br>\n")
+}
+
+/// Link targets in an HTML fragment, which `to_plain_text` drops.
+pub fn extract_links(html: &str) -> Vec<String> {
+    let fragment = Html::parse_fragment(html);
+    let selector = scraper::Selector::parse("a[href]").expect("valid selector");
+    fragment
+        .select(&selector)
+        .filter_map(|a| a.value().attr("href"))
+        .filter(|href| href.starts_with("http"))
+        .map(|href| href.to_string())
+        .collect()
+}
+
+/// Plain text plus any link targets not already visible in it, for searching.
+pub fn to_searchable_text(html: &str) -> String {
+    let plain = to_plain_text(html);
+    let links: Vec<String> = extract_links(html)
+        .into_iter()
+        .filter(|link| !plain.contains(link.as_str()))
+        .collect();
+    if links.is_empty() {
+        plain
+    } else {
+        format!("{} {}", plain, links.join(" "))
+    }
+}
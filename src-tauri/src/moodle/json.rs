@@ -0,0 +1,35 @@
+//! Small accessors for Moodle's loosely typed JSON responses.
+
+/// Integer field, defaulting to 0 when missing or null.
+pub fn int(value: &serde_json::Value, key: &str) -> i64 {
+    value.get(key).and_then(|v| v.as_i64()).unwrap_or(0)
+}
+
+/// Non-empty string field. Numbers are stringified since Moodle sends some
+/// values (grades, config) either way.
+pub fn text(value: &serde_json::Value, key: &str) -> Option<String> {
+    match value.get(key) {
+        Some(serde_json::Value::String(s)) if !s.is_empty() => Some(s.clone()),
+        Some(serde_json::Value::Number(n)) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Moodle is inconsistent about booleans: some fields are `true`/`false`, others `0`/`1`.
+pub fn flag(value: &serde_json::Value, key: &str) -> bool {
+    match value.get(key) {
+        Some(serde_json::Value::Bool(b)) => *b,
+        Some(serde_json::Value::Number(n)) => n.as_i64().unwrap_or(0) != 0,
+        Some(serde_json::Value::String(s)) => s == "1" || s == "true",
+        _ => false,
+    }
+}
+
+/// Array field, empty when missing.
+pub fn list<'a>(value: &'a serde_json::Value, key: &str) -> &'a [serde_json::Value] {
+    value
+        .get(key)
+        .and_then(|v| v.as_array())
+        .map(|v| v.as_slice())
+        .unwrap_or(&[])
+}
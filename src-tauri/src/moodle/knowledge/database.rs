@@ -0,0 +1,247 @@
+use super::index::{index_knowledge, KnowledgeItem, KnowledgeSource};
+use crate::moodle::calendar::login;
+use crate::moodle::content::{fetch_course_module, sanitize_html, CourseModuleRef, UrlRewriter};
+use crate::moodle::exception::check_exception;
+use crate::moodle::html::{from_plain_text, to_plain_text};
+use crate::moodle::json::{flag, int, list, text};
+use anyhow::{anyhow, Result};
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Separator of the selected options of checkbox and multimenu fields.
+const MULTI_VALUE_SEP: &str = "##";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseField {
+    pub id: i64,
+    pub name: String,
+    /// `text`, `textarea`, `number`, `url`, `date`, `menu`, `checkbox`, `file`, `picture`, ...
+    pub field_type: String,
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseFile {
+    pub file_name: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseValue {
+    pub field_id: i64,
+    /// Display value; sanitized HTML for textarea fields, plain text otherwise
+    pub value: String,
+    pub files: Vec<DatabaseFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseEntry {
+    pub id: i64,
+    pub author: Option<String>,
+    pub approved: bool,
+    pub time_modified: i64,
+    pub values: Vec<DatabaseValue>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabasePage {
+    pub database_id: i64,
+    pub cmid: i64,
+    pub name: String,
+    pub intro: Option<String>,
+    pub fields: Vec<DatabaseField>,
+    pub entries: Vec<DatabaseEntry>,
+    pub total: i64,
+}
+
+async fn fetch_database(client: &mut MoodleClient, module: &CourseModuleRef) -> Result<serde_json::Value> {
+    let form: Vec<(String, String)> = vec![("courseids[0]".to_string(), module.course_id.to_string())];
+    let json = client
+        .post("mod_data_get_databases_by_courses", &form)
+        .await
+        .and_then(check_exception)?;
+    list(&json, "databases")
+        .iter()
+        .find(|d| int(d, "coursemodule") == module.cmid)
+        .cloned()
+        .ok_or_else(|| anyhow!("Database {} is not available", module.cmid))
+}
+
+async fn fetch_fields(client: &MoodleClient, database_id: i64) -> Result<Vec<DatabaseField>> {
+    let form: Vec<(String, String)> = vec![("databaseid".to_string(), database_id.to_string())];
+    let json = client
+        .post("mod_data_get_fields", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(list(&json, "fields")
+        .iter()
+        .map(|f| DatabaseField {
+            id: int(f, "id"),
+            name: text(f, "name").unwrap_or_default(),
+            field_type: text(f, "type").unwrap_or_default(),
+            required: flag(f, "required"),
+        })
+        .collect())
+}
+
+/// The display value of one field's content, by field type.
+fn display_value(field_type: &str, content: &serde_json::Value, rewriter: &UrlRewriter) -> String {
+    let raw = text(content, "content").unwrap_or_default();
+    match field_type {
+        "textarea" => sanitize_html(&raw, rewriter),
+        "date" => raw
+            .parse::<i64>()
+            .ok()
+            .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .unwrap_or(raw),
+        "checkbox" | "multimenu" => raw
+            .split(MULTI_VALUE_SEP)
+            .filter(|v| !v.is_empty())
+            .collect::<Vec<_>>()
+            .join(", "),
+        "latlong" => match text(content, "content1") {
+            Some(longitude) => format!("{}, {}", raw, longitude),
+            None => raw,
+        },
+        // content1 holds the link text, when set
+        "url" => text(content, "content1")
+            .filter(|t| !t.is_empty())
+            .map(|t| format!("{} ({})", t, raw))
+            .unwrap_or(raw),
+        _ => to_plain_text(&raw),
+    }
+}
+
+fn parse_entry(
+    entry: &serde_json::Value,
+    fields: &HashMap<i64, &DatabaseField>,
+    client: &MoodleClient,
+    rewriter: &UrlRewriter,
+) -> DatabaseEntry {
+    DatabaseEntry {
+        id: int(entry, "id"),
+        author: text(entry, "fullname"),
+        approved: flag(entry, "approved"),
+        time_modified: int(entry, "timemodified"),
+        values: list(entry, "contents")
+            .iter()
+            .map(|content| {
+                let field_id = int(content, "fieldid");
+                let field_type = fields.get(&field_id).map(|f| f.field_type.as_str()).unwrap_or("text");
+                DatabaseValue {
+                    field_id,
+                    value: display_value(field_type, content, rewriter),
+                    files: list(content, "files")
+                        .iter()
+                        .filter_map(|f| {
+                            Some(DatabaseFile {
+                                file_name: text(f, "filename")?,
+                                url: client.tokenize_url(&text(f, "fileurl")?),
+                            })
+                        })
+                        .collect(),
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Entries of a database activity, newest first, optionally matching a
+/// search. Approved entries are indexed for offline lookup.
+pub async fn get_database_entries(
+    cmid: i64,
+    search: Option<String>,
+    page: i64,
+    per_page: i64,
+    index_path: &Path,
+) -> Result<DatabasePage> {
+    let mut client = login().await?;
+    let module = fetch_course_module(&mut client, cmid, "data").await?;
+    let database = fetch_database(&mut client, &module).await?;
+    let database_id = int(&database, "id");
+    let fields = fetch_fields(&client, database_id).await?;
+
+    let mut form: Vec<(String, String)> = vec![
+        ("databaseid".to_string(), database_id.to_string()),
+        ("returncontents".to_string(), "1".to_string()),
+        ("order".to_string(), "DESC".to_string()),
+        ("page".to_string(), page.to_string()),
+        ("perpage".to_string(), per_page.to_string()),
+    ];
+    let search = search.filter(|s| !s.trim().is_empty());
+    let function = match &search {
+        Some(query) => {
+            form.push(("search".to_string(), query.clone()));
+            "mod_data_search_entries"
+        }
+        None => "mod_data_get_entries",
+    };
+    let json = client.post(function, &form).await.and_then(check_exception)?;
+
+    let rewriter = UrlRewriter::new(&client, None);
+    let by_id: HashMap<i64, &DatabaseField> = fields.iter().map(|f| (f.id, f)).collect();
+    let entries: Vec<DatabaseEntry> = list(&json, "entries")
+        .iter()
+        .map(|e| parse_entry(e, &by_id, &client, &rewriter))
+        .collect();
+    let name = text(&database, "name").unwrap_or_else(|| module.name.clone());
+
+    index_knowledge(
+        index_path,
+        entries
+            .iter()
+            .filter(|e| e.approved)
+            .map(|e| {
+                let lines: Vec<String> = e
+                    .values
+                    .iter()
+                    .filter(|v| !v.value.is_empty())
+                    .map(|v| {
+                        let field = by_id.get(&v.field_id);
+                        let label = field.map(|f| f.name.as_str()).unwrap_or_default();
+                        // Only textarea values are (sanitized) HTML, the rest is text
+                        let value = match field.map(|f| f.field_type.as_str()) {
+                            Some("textarea") => v.value.clone(),
+                            _ => from_plain_text(&v.value),
+                        };
+                        format!("<p><strong>{}</strong>: {}</p>", from_plain_text(label), value)
+                    })
+                    .collect();
+                let html = lines.join("");
+                KnowledgeItem {
+                    source: KnowledgeSource::Database,
+                    item_id: e.id,
+                    course_id: module.course_id,
+                    cmid,
+                    activity_name: name.clone(),
+                    // The first non-empty value usually names the record
+                    title: e
+                        .values
+                        .iter()
+                        .map(|v| to_plain_text(&v.value))
+                        .find(|v| !v.is_empty())
+                        .unwrap_or_else(|| name.clone()),
+                    text: to_plain_text(&html),
+                    html,
+                    time_modified: e.time_modified,
+                }
+            })
+            .collect(),
+    )
+    .await;
+
+    Ok(DatabasePage {
+        database_id,
+        cmid,
+        intro: text(&database, "intro")
+            .map(|i| sanitize_html(&i, &rewriter))
+            .filter(|i| !i.is_empty()),
+        name,
+        fields,
+        entries,
+        total: int(&json, "totalcount"),
+    })
+}
@@ -0,0 +1,199 @@
+use super::index::{index_knowledge, KnowledgeItem, KnowledgeSource};
+use crate::moodle::calendar::login;
+use crate::moodle::content::{fetch_course_module, sanitize_html, CourseModuleRef, UrlRewriter};
+use crate::moodle::exception::check_exception;
+use crate::moodle::html::to_plain_text;
+use crate::moodle::json::{flag, int, list, text};
+use anyhow::{anyhow, Result};
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Category filters of `get_entries_by_category`.
+pub const GLOSSARY_ALL_CATEGORIES: i64 = 0;
+pub const GLOSSARY_NOT_CATEGORISED: i64 = -1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "by", rename_all = "snake_case")]
+pub enum GlossaryBrowse {
+    /// A single letter, `ALL` or `SPECIAL` (entries not starting with a letter)
+    Letter { letter: String },
+    /// A category id, `GLOSSARY_ALL_CATEGORIES` or `GLOSSARY_NOT_CATEGORISED`
+    Category { category_id: i64 },
+    /// Concepts and aliases, or also definitions with `full_search`
+    Search { query: String, full_search: bool },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlossaryAttachment {
+    pub file_name: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlossaryEntry {
+    pub id: i64,
+    pub concept: String,
+    /// Sanitized HTML
+    pub definition: String,
+    pub author: Option<String>,
+    pub category: Option<String>,
+    pub approved: bool,
+    pub attachments: Vec<GlossaryAttachment>,
+    pub time_modified: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlossaryCategory {
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlossaryPage {
+    pub glossary_id: i64,
+    pub cmid: i64,
+    pub name: String,
+    pub intro: Option<String>,
+    /// Browse modes the teacher enabled: `letter`, `cat`, `date`, `author`
+    pub browse_modes: Vec<String>,
+    pub categories: Vec<GlossaryCategory>,
+    pub entries: Vec<GlossaryEntry>,
+    pub total: i64,
+    pub can_add_entry: bool,
+}
+
+async fn fetch_glossary(client: &mut MoodleClient, module: &CourseModuleRef) -> Result<serde_json::Value> {
+    let form: Vec<(String, String)> = vec![("courseids[0]".to_string(), module.course_id.to_string())];
+    let json = client
+        .post("mod_glossary_get_glossaries_by_courses", &form)
+        .await
+        .and_then(check_exception)?;
+    list(&json, "glossaries")
+        .iter()
+        .find(|g| int(g, "coursemodule") == module.cmid)
+        .cloned()
+        .ok_or_else(|| anyhow!("Glossary {} is not available", module.cmid))
+}
+
+async fn fetch_categories(client: &MoodleClient, glossary_id: i64) -> Result<Vec<GlossaryCategory>> {
+    let form: Vec<(String, String)> = vec![
+        ("id".to_string(), glossary_id.to_string()),
+        ("from".to_string(), "0".to_string()),
+        ("limit".to_string(), "0".to_string()),
+    ];
+    let json = client
+        .post("mod_glossary_get_categories", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(list(&json, "categories")
+        .iter()
+        .map(|c| GlossaryCategory {
+            id: int(c, "id"),
+            name: to_plain_text(&text(c, "name").unwrap_or_default()),
+        })
+        .collect())
+}
+
+fn parse_entry(entry: &serde_json::Value, client: &MoodleClient, rewriter: &UrlRewriter) -> GlossaryEntry {
+    GlossaryEntry {
+        id: int(entry, "id"),
+        concept: text(entry, "concept").unwrap_or_default(),
+        definition: sanitize_html(&text(entry, "definition").unwrap_or_default(), rewriter),
+        author: text(entry, "userfullname"),
+        category: text(entry, "categoryname").filter(|c| !c.is_empty()),
+        approved: flag(entry, "approved"),
+        attachments: list(entry, "attachments")
+            .iter()
+            .filter_map(|f| {
+                Some(GlossaryAttachment {
+                    file_name: text(f, "filename")?,
+                    url: client.tokenize_url(&text(f, "fileurl")?),
+                })
+            })
+            .collect(),
+        time_modified: int(entry, "timemodified"),
+    }
+}
+
+/// One page of a glossary browsed by letter, category or search term.
+/// Fetched entries are added to the offline index.
+pub async fn browse_glossary(
+    cmid: i64,
+    browse: &GlossaryBrowse,
+    from: i64,
+    limit: i64,
+    index_path: &Path,
+) -> Result<GlossaryPage> {
+    let mut client = login().await?;
+    let module = fetch_course_module(&mut client, cmid, "glossary").await?;
+    let glossary = fetch_glossary(&mut client, &module).await?;
+    let glossary_id = int(&glossary, "id");
+
+    let mut form: Vec<(String, String)> = vec![
+        ("id".to_string(), glossary_id.to_string()),
+        ("from".to_string(), from.to_string()),
+        ("limit".to_string(), limit.to_string()),
+        ("options[includenotapproved]".to_string(), "1".to_string()),
+    ];
+    let function = match browse {
+        GlossaryBrowse::Letter { letter } => {
+            form.push(("letter".to_string(), letter.clone()));
+            "mod_glossary_get_entries_by_letter"
+        }
+        GlossaryBrowse::Category { category_id } => {
+            form.push(("categoryid".to_string(), category_id.to_string()));
+            "mod_glossary_get_entries_by_category"
+        }
+        GlossaryBrowse::Search { query, full_search } => {
+            form.push(("query".to_string(), query.clone()));
+            form.push(("fullsearch".to_string(), if *full_search { "1" } else { "0" }.to_string()));
+            form.push(("order".to_string(), "CONCEPT".to_string()));
+            form.push(("sort".to_string(), "ASC".to_string()));
+            "mod_glossary_get_entries_by_search"
+        }
+    };
+    let json = client.post(function, &form).await.and_then(check_exception)?;
+
+    let rewriter = UrlRewriter::new(&client, None);
+    let name = text(&glossary, "name").unwrap_or_else(|| module.name.clone());
+    let entries: Vec<GlossaryEntry> = list(&json, "entries").iter().map(|e| parse_entry(e, &client, &rewriter)).collect();
+    index_knowledge(
+        index_path,
+        entries
+            .iter()
+            .filter(|e| e.approved)
+            .map(|e| KnowledgeItem {
+                source: KnowledgeSource::Glossary,
+                item_id: e.id,
+                course_id: module.course_id,
+                cmid,
+                activity_name: name.clone(),
+                title: e.concept.clone(),
+                text: to_plain_text(&e.definition),
+                html: e.definition.clone(),
+                time_modified: e.time_modified,
+            })
+            .collect(),
+    )
+    .await;
+
+    let browse_modes = glossary
+        .get("browsemodes")
+        .and_then(|m| m.as_array())
+        .map(|modes| modes.iter().filter_map(|m| m.as_str().map(str::to_string)).collect())
+        .unwrap_or_default();
+    Ok(GlossaryPage {
+        glossary_id,
+        cmid,
+        intro: text(&glossary, "intro")
+            .map(|i| sanitize_html(&i, &rewriter))
+            .filter(|i| !i.is_empty()),
+        name,
+        browse_modes,
+        categories: fetch_categories(&client, glossary_id).await.unwrap_or_default(),
+        entries,
+        total: int(&json, "count"),
+        can_add_entry: flag(&glossary, "canaddentry"),
+    })
+}
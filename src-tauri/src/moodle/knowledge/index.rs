@@ -0,0 +1,215 @@
+use crate::moodle::messages::message_index::{snippet, tokenize};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::OnceLock;
+use tokio::sync::Mutex;
+
+static KNOWLEDGE_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KnowledgeSource {
+    Glossary,
+    Wiki,
+    Database,
+}
+
+/// A glossary entry, wiki page or database entry as stored for offline lookup.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeItem {
+    pub source: KnowledgeSource,
+    /// Entry, page or record id
+    pub item_id: i64,
+    pub course_id: i64,
+    pub cmid: i64,
+    pub activity_name: String,
+    pub title: String,
+    /// Plain text of the body
+    pub text: String,
+    /// Sanitized HTML, so the item can be shown without a connection
+    pub html: String,
+    pub time_modified: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KnowledgeIndex {
+    pub items: Vec<KnowledgeItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeMatch {
+    pub item: KnowledgeItem,
+    pub snippet: String,
+    pub score: i64,
+}
+
+impl KnowledgeIndex {
+    pub async fn load(path: &Path) -> Result<Self> {
+        match tokio::fs::read(path).await {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes).unwrap_or_default()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(KnowledgeIndex::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, serde_json::to_vec(self)?).await?;
+        Ok(())
+    }
+
+    /// Adds items, replacing older copies of the same entry, page or record.
+    pub fn upsert(&mut self, items: impl IntoIterator<Item = KnowledgeItem>) {
+        for item in items {
+            match self
+                .items
+                .iter_mut()
+                .find(|i| i.source == item.source && i.item_id == item.item_id)
+            {
+                Some(existing) => *existing = item,
+                None => self.items.push(item),
+            }
+        }
+    }
+
+    /// Items containing every query word, best first. Title hits count
+    /// more than body hits.
+    pub fn search(&self, query: &str, course_id: Option<i64>, limit: usize) -> Vec<KnowledgeMatch> {
+        let words = tokenize(query);
+        if words.is_empty() {
+            return Vec::new();
+        }
+
+        let mut matches: Vec<KnowledgeMatch> = self
+            .items
+            .iter()
+            .filter(|item| course_id.map_or(true, |id| item.course_id == id))
+            .filter_map(|item| {
+                let title = tokenize(&item.title);
+                let body = tokenize(&item.text);
+                let mut score = 0;
+                for word in &words {
+                    let in_title = title.iter().filter(|t| t.starts_with(word.as_str())).count() as i64;
+                    let in_body = body.iter().filter(|t| t.starts_with(word.as_str())).count() as i64;
+                    if in_title + in_body == 0 {
+                        return None;
+                    }
+                    score += in_title * 5 + in_body;
+                }
+                Some(KnowledgeMatch {
+                    snippet: snippet(&item.text, &words),
+                    item: item.clone(),
+                    score,
+                })
+            })
+            .collect();
+
+        matches.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then(b.item.time_modified.cmp(&a.item.time_modified))
+        });
+        matches.truncate(limit);
+        matches
+    }
+}
+
+/// Drops the `token=` parameter that file URLs get when rendered, so the
+/// index on disk holds no credentials.
+fn strip_tokens(html: &str) -> String {
+    const PARAM: &str = "token=";
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(position) = rest.find(PARAM) {
+        let (before, after) = rest.split_at(position);
+        let value = &after[PARAM.len()..];
+        let value_len = value.chars().take_while(|c| c.is_ascii_alphanumeric()).count();
+        let tail = &value[value_len..];
+        match ["&amp;", "&", "?"].iter().find(|separator| before.ends_with(**separator)) {
+            Some(separator) => {
+                out.push_str(&before[..before.len() - separator.len()]);
+                rest = tail;
+                // Keep the query string well formed when the token came first
+                if *separator == "?" {
+                    if let Some(remaining) = tail.strip_prefix("&amp;").or_else(|| tail.strip_prefix('&')) {
+                        out.push('?');
+                        rest = remaining;
+                    }
+                }
+            }
+            None => {
+                out.push_str(before);
+                out.push_str(PARAM);
+                rest = value;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Stores items fetched while browsing. Indexing is best effort: browsing
+/// must not fail because the index can't be written.
+pub async fn index_knowledge(path: &Path, items: Vec<KnowledgeItem>) {
+    if items.is_empty() {
+        return;
+    }
+    let _guard = KNOWLEDGE_LOCK.get_or_init(|| Mutex::new(())).lock().await;
+    let result = async {
+        let mut index = KnowledgeIndex::load(path).await?;
+        index.upsert(items.into_iter().map(|mut item| {
+            item.html = strip_tokens(&item.html);
+            item
+        }));
+        index.save(path).await
+    }
+    .await;
+    if let Err(e) = result {
+        eprintln!("Failed to update the course knowledge index: {}", e);
+    }
+}
+
+pub async fn search_course_knowledge(
+    path: &Path,
+    query: &str,
+    course_id: Option<i64>,
+    limit: usize,
+) -> Result<Vec<KnowledgeMatch>> {
+    let _guard = KNOWLEDGE_LOCK.get_or_init(|| Mutex::new(())).lock().await;
+    Ok(KnowledgeIndex::load(path).await?.search(query, course_id, limit))
+}
+
+/// A stored item, for reading an entry or page while offline.
+pub async fn get_indexed_item(path: &Path, source: KnowledgeSource, item_id: i64) -> Result<Option<KnowledgeItem>> {
+    let _guard = KNOWLEDGE_LOCK.get_or_init(|| Mutex::new(())).lock().await;
+    Ok(KnowledgeIndex::load(path)
+        .await?
+        .items
+        .into_iter()
+        .find(|i| i.source == source && i.item_id == item_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::strip_tokens;
+
+    #[test]
+    fn strips_file_tokens() {
+        assert_eq!(
+            strip_tokens(r#"<img src="https://m.example/webservice/pluginfile.php/1/a.png?token=abc123">"#),
+            r#"<img src="https://m.example/webservice/pluginfile.php/1/a.png">"#
+        );
+        assert_eq!(
+            strip_tokens(r#"<a href="https://m.example/f.pdf?forcedownload=1&amp;token=abc">f</a>"#),
+            r#"<a href="https://m.example/f.pdf?forcedownload=1">f</a>"#
+        );
+        assert_eq!(
+            strip_tokens(r#"<a href="https://m.example/f.pdf?token=abc&amp;forcedownload=1">f</a>"#),
+            r#"<a href="https://m.example/f.pdf?forcedownload=1">f</a>"#
+        );
+        assert_eq!(strip_tokens("Ask for a token= in class"), "Ask for a token= in class");
+    }
+}
@@ -0,0 +1,9 @@
+pub mod database;
+pub mod glossary;
+pub mod index;
+pub mod wiki;
+
+pub use database::*;
+pub use glossary::*;
+pub use index::*;
+pub use wiki::*;
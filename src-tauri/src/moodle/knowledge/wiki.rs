@@ -0,0 +1,285 @@
+use super::index::{index_knowledge, KnowledgeItem, KnowledgeSource};
+use crate::moodle::calendar::login;
+use crate::moodle::content::{fetch_course_module, sanitize_html, CourseModuleRef, UrlRewriter};
+use crate::moodle::exception::check_exception;
+use crate::moodle::html::to_plain_text;
+use crate::moodle::json::{flag, int, list, text};
+use anyhow::{anyhow, Result};
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WikiPageSummary {
+    pub id: i64,
+    pub title: String,
+    pub first_page: bool,
+    pub can_edit: bool,
+    pub time_modified: i64,
+}
+
+/// The wiki of a group or user, or the only one of a collaborative wiki.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subwiki {
+    pub id: i64,
+    pub group_id: i64,
+    pub user_id: i64,
+    pub can_edit: bool,
+    pub pages: Vec<WikiPageSummary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WikiOverview {
+    pub wiki_id: i64,
+    pub cmid: i64,
+    pub name: String,
+    pub intro: Option<String>,
+    /// `collaborative` or `individual`
+    pub mode: String,
+    pub subwikis: Vec<Subwiki>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WikiPage {
+    pub id: i64,
+    pub subwiki_id: i64,
+    pub title: String,
+    /// Rendered, sanitized content
+    pub html: String,
+    pub version: i64,
+    pub can_edit: bool,
+}
+
+/// Page source checked out for editing; `version` is what the edit is based on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WikiEdit {
+    pub page_id: i64,
+    pub section: Option<String>,
+    pub content: String,
+    /// `html`, `creole` or `nwiki`
+    pub format: String,
+    pub version: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WikiSaveResult {
+    pub saved: bool,
+    /// Someone else saved the page since the edit started; nothing was written
+    pub conflict: bool,
+    pub current_version: i64,
+}
+
+async fn fetch_wiki(client: &mut MoodleClient, module: &CourseModuleRef) -> Result<serde_json::Value> {
+    let form: Vec<(String, String)> = vec![("courseids[0]".to_string(), module.course_id.to_string())];
+    let json = client
+        .post("mod_wiki_get_wikis_by_courses", &form)
+        .await
+        .and_then(check_exception)?;
+    list(&json, "wikis")
+        .iter()
+        .find(|w| int(w, "coursemodule") == module.cmid)
+        .cloned()
+        .ok_or_else(|| anyhow!("Wiki {} is not available", module.cmid))
+}
+
+fn page_item(module: &CourseModuleRef, activity_name: &str, id: i64, title: &str, html: &str, time_modified: i64) -> KnowledgeItem {
+    KnowledgeItem {
+        source: KnowledgeSource::Wiki,
+        item_id: id,
+        course_id: module.course_id,
+        cmid: module.cmid,
+        activity_name: activity_name.to_string(),
+        title: title.to_string(),
+        text: to_plain_text(html),
+        html: html.to_string(),
+        time_modified,
+    }
+}
+
+/// A wiki's subwikis and their pages. Page contents are indexed for offline lookup.
+pub async fn get_wiki(cmid: i64, index_path: &Path) -> Result<WikiOverview> {
+    let mut client = login().await?;
+    let module = fetch_course_module(&mut client, cmid, "wiki").await?;
+    let wiki = fetch_wiki(&mut client, &module).await?;
+    let wiki_id = int(&wiki, "id");
+    let name = text(&wiki, "name").unwrap_or_else(|| module.name.clone());
+
+    let form: Vec<(String, String)> = vec![("wikiid".to_string(), wiki_id.to_string())];
+    let json = client
+        .post("mod_wiki_get_subwikis", &form)
+        .await
+        .and_then(check_exception)?;
+
+    let rewriter = UrlRewriter::new(&client, None);
+    let mut subwikis = Vec::new();
+    let mut indexed = Vec::new();
+    for subwiki in list(&json, "subwikis") {
+        let form: Vec<(String, String)> = vec![
+            ("wikiid".to_string(), wiki_id.to_string()),
+            ("groupid".to_string(), int(subwiki, "groupid").to_string()),
+            ("userid".to_string(), int(subwiki, "userid").to_string()),
+            ("options[sortby]".to_string(), "title".to_string()),
+            ("options[sortdirection]".to_string(), "ASC".to_string()),
+            ("options[includecontent]".to_string(), "1".to_string()),
+        ];
+        let pages = client
+            .post("mod_wiki_get_subwiki_pages", &form)
+            .await
+            .and_then(check_exception)?;
+        let pages: Vec<WikiPageSummary> = list(&pages, "pages")
+            .iter()
+            .map(|p| {
+                let summary = WikiPageSummary {
+                    id: int(p, "id"),
+                    title: text(p, "title").unwrap_or_default(),
+                    first_page: flag(p, "firstpage"),
+                    can_edit: flag(p, "caneditpage"),
+                    time_modified: int(p, "timemodified"),
+                };
+                if let Some(content) = text(p, "cachedcontent") {
+                    let html = sanitize_html(&content, &rewriter);
+                    indexed.push(page_item(&module, &name, summary.id, &summary.title, &html, summary.time_modified));
+                }
+                summary
+            })
+            .collect();
+        subwikis.push(Subwiki {
+            id: int(subwiki, "id"),
+            group_id: int(subwiki, "groupid"),
+            user_id: int(subwiki, "userid"),
+            can_edit: flag(subwiki, "canedit"),
+            pages,
+        });
+    }
+    index_knowledge(index_path, indexed).await;
+
+    Ok(WikiOverview {
+        wiki_id,
+        cmid,
+        intro: text(&wiki, "intro")
+            .map(|i| sanitize_html(&i, &rewriter))
+            .filter(|i| !i.is_empty()),
+        name,
+        mode: text(&wiki, "wikimode").unwrap_or_default(),
+        subwikis,
+    })
+}
+
+pub async fn get_wiki_page(cmid: i64, page_id: i64, index_path: &Path) -> Result<WikiPage> {
+    let mut client = login().await?;
+    let module = fetch_course_module(&mut client, cmid, "wiki").await?;
+    let form: Vec<(String, String)> = vec![("pageid".to_string(), page_id.to_string())];
+    let json = client
+        .post("mod_wiki_get_page_contents", &form)
+        .await
+        .and_then(check_exception)?;
+    let page = json.get("page").ok_or_else(|| anyhow!("Wiki page {} not found", page_id))?;
+
+    let rewriter = UrlRewriter::new(&client, None);
+    let html = sanitize_html(&text(page, "cachedcontent").unwrap_or_default(), &rewriter);
+    let title = text(page, "title").unwrap_or_default();
+    index_knowledge(
+        index_path,
+        vec![page_item(&module, &module.name, page_id, &title, &html, chrono::Utc::now().timestamp())],
+    )
+    .await;
+
+    let view: Vec<(String, String)> = vec![("pageid".to_string(), page_id.to_string())];
+    let _ = client.post("mod_wiki_view_page", &view).await;
+
+    Ok(WikiPage {
+        id: page_id,
+        subwiki_id: int(page, "subwikiid"),
+        title,
+        html,
+        version: int(page, "version"),
+        can_edit: flag(page, "caneditpage"),
+    })
+}
+
+async fn fetch_page_for_editing(
+    client: &MoodleClient,
+    page_id: i64,
+    section: Option<&str>,
+    lock_only: bool,
+) -> Result<serde_json::Value> {
+    let mut form: Vec<(String, String)> = vec![
+        ("pageid".to_string(), page_id.to_string()),
+        ("lockonly".to_string(), if lock_only { "1" } else { "0" }.to_string()),
+    ];
+    if let Some(section) = section {
+        form.push(("section".to_string(), section.to_string()));
+    }
+    let json = client
+        .post("mod_wiki_get_page_for_editing", &form)
+        .await
+        .and_then(check_exception)?;
+    json.get("pagesection")
+        .cloned()
+        .ok_or_else(|| anyhow!("Moodle did not return the page for editing"))
+}
+
+/// Locks a page (or one section of it) and returns its source for editing.
+pub async fn start_wiki_edit(page_id: i64, section: Option<String>) -> Result<WikiEdit> {
+    let client = login().await?;
+    let json = fetch_page_for_editing(&client, page_id, section.as_deref(), false).await?;
+    Ok(WikiEdit {
+        page_id,
+        section,
+        content: text(&json, "content").unwrap_or_default(),
+        format: text(&json, "contentformat").unwrap_or_else(|| "html".to_string()),
+        version: int(&json, "version"),
+    })
+}
+
+/// Saves an edit unless the page changed since `base_version`; renewing the
+/// lock also reports the page's current version.
+pub async fn save_wiki_page(
+    page_id: i64,
+    content: &str,
+    section: Option<&str>,
+    base_version: i64,
+) -> Result<WikiSaveResult> {
+    let client = login().await?;
+    let current = fetch_page_for_editing(&client, page_id, section, true).await?;
+    let current_version = int(&current, "version");
+    if current_version != base_version {
+        return Ok(WikiSaveResult {
+            saved: false,
+            conflict: true,
+            current_version,
+        });
+    }
+
+    let mut form: Vec<(String, String)> = vec![
+        ("pageid".to_string(), page_id.to_string()),
+        ("content".to_string(), content.to_string()),
+    ];
+    if let Some(section) = section {
+        form.push(("section".to_string(), section.to_string()));
+    }
+    client
+        .post("mod_wiki_edit_page", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(WikiSaveResult {
+        saved: true,
+        conflict: false,
+        current_version: current_version + 1,
+    })
+}
+
+/// Creates a page in a subwiki; returns the new page id.
+pub async fn create_wiki_page(subwiki_id: i64, title: &str, content: &str) -> Result<i64> {
+    let client = login().await?;
+    let form: Vec<(String, String)> = vec![
+        ("title".to_string(), title.to_string()),
+        ("content".to_string(), content.to_string()),
+        ("subwikiid".to_string(), subwiki_id.to_string()),
+    ];
+    let json = client
+        .post("mod_wiki_new_page", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(int(&json, "pageid"))
+}
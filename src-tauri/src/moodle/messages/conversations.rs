@@ -0,0 +1,273 @@
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::html::to_plain_text;
+use crate::moodle::json::{flag, int, list, text};
+use crate::moodle::site::get_current_user_id;
+use anyhow::Result;
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConversationType {
+    Individual,
+    Group,
+    /// "Notes to self"
+    SelfConversation,
+}
+
+impl ConversationType {
+    pub fn from_id(id: i64) -> Self {
+        match id {
+            2 => ConversationType::Group,
+            3 => ConversationType::SelfConversation,
+            _ => ConversationType::Individual,
+        }
+    }
+
+    pub fn id(&self) -> i64 {
+        match self {
+            ConversationType::Individual => 1,
+            ConversationType::Group => 2,
+            ConversationType::SelfConversation => 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationMember {
+    pub id: i64,
+    pub fullname: String,
+    pub profile_url: Option<String>,
+    pub profile_image_url: Option<String>,
+    pub is_online: Option<bool>,
+    pub is_contact: bool,
+    pub is_blocked: bool,
+    pub is_deleted: bool,
+    pub can_message: bool,
+    pub requires_contact: bool,
+    /// A contact request between the current user and this member is pending
+    pub has_contact_request: bool,
+}
+
+impl ConversationMember {
+    pub fn from_json(member: &serde_json::Value) -> Self {
+        ConversationMember {
+            id: int(member, "id"),
+            fullname: text(member, "fullname").unwrap_or_default(),
+            profile_url: text(member, "profileurl"),
+            profile_image_url: text(member, "profileimageurl"),
+            // Null when the user hides their online status
+            is_online: member.get("isonline").and_then(|o| o.as_bool()),
+            is_contact: flag(member, "iscontact"),
+            is_blocked: flag(member, "isblocked"),
+            is_deleted: flag(member, "isdeleted"),
+            can_message: flag(member, "canmessage"),
+            requires_contact: flag(member, "requirescontact"),
+            has_contact_request: !list(member, "contactrequests").is_empty(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationMessage {
+    pub id: i64,
+    pub user_id_from: i64,
+    pub text: String,
+    pub timecreated: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    pub id: i64,
+    pub conversation_type: ConversationType,
+    /// Group name; individual conversations are named after the other member
+    pub name: String,
+    pub subname: Option<String>,
+    pub image_url: Option<String>,
+    pub member_count: i64,
+    pub is_muted: bool,
+    pub is_favourite: bool,
+    pub is_read: bool,
+    pub unread_count: i64,
+    pub can_delete_messages_for_all_users: bool,
+    /// Members other than the current user (only the first few for groups)
+    pub members: Vec<ConversationMember>,
+    pub last_message: Option<ConversationMessage>,
+}
+
+impl Conversation {
+    pub fn from_json(conversation: &serde_json::Value) -> Self {
+        let members: Vec<ConversationMember> = list(conversation, "members")
+            .iter()
+            .map(ConversationMember::from_json)
+            .collect();
+        let conversation_type = ConversationType::from_id(int(conversation, "type"));
+        let name = text(conversation, "name")
+            .or_else(|| members.first().map(|m| m.fullname.clone()))
+            .unwrap_or_default();
+
+        Conversation {
+            id: int(conversation, "id"),
+            conversation_type,
+            name,
+            subname: text(conversation, "subname"),
+            image_url: text(conversation, "imageurl")
+                .or_else(|| members.first().and_then(|m| m.profile_image_url.clone())),
+            member_count: int(conversation, "membercount"),
+            is_muted: flag(conversation, "ismuted"),
+            is_favourite: flag(conversation, "isfavourite"),
+            is_read: flag(conversation, "isread"),
+            unread_count: int(conversation, "unreadcount"),
+            can_delete_messages_for_all_users: flag(conversation, "candeletemessagesforallusers"),
+            members,
+            // Messages come newest first
+            last_message: list(conversation, "messages").first().map(|m| ConversationMessage {
+                id: int(m, "id"),
+                user_id_from: int(m, "useridfrom"),
+                text: to_plain_text(&text(m, "text").unwrap_or_default()),
+                timecreated: int(m, "timecreated"),
+            }),
+        }
+    }
+}
+
+/// Lists the user's conversations, optionally only one type or only (non-)favourites.
+pub async fn get_conversation_list(
+    conversation_type: Option<ConversationType>,
+    favourites: Option<bool>,
+    limit_from: Option<i64>,
+    limit_num: Option<i64>,
+) -> Result<Vec<Conversation>> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+
+    let mut form: Vec<(String, String)> = vec![
+        ("userid".to_string(), user_id.to_string()),
+        ("limitfrom".to_string(), limit_from.unwrap_or(0).to_string()),
+        ("limitnum".to_string(), limit_num.unwrap_or(51).to_string()),
+        // Keep "notes to self" in the private list, like the web UI does
+        ("mergeself".to_string(), "1".to_string()),
+    ];
+    if let Some(conversation_type) = conversation_type {
+        form.push(("type".to_string(), conversation_type.id().to_string()));
+    }
+    if let Some(favourites) = favourites {
+        form.push(("favourites".to_string(), (favourites as i64).to_string()));
+    }
+
+    let json = client
+        .post("core_message_get_conversations", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(list(&json, "conversations").iter().map(Conversation::from_json).collect())
+}
+
+pub async fn get_conversation(conversation_id: i64) -> Result<Conversation> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+
+    let form: Vec<(String, String)> = vec![
+        ("userid".to_string(), user_id.to_string()),
+        ("conversationid".to_string(), conversation_id.to_string()),
+        ("includecontactrequests".to_string(), "1".to_string()),
+        ("includeprivacyinfo".to_string(), "1".to_string()),
+        ("memberlimit".to_string(), "10".to_string()),
+        ("messagelimit".to_string(), "1".to_string()),
+        ("newestmessagesfirst".to_string(), "1".to_string()),
+    ];
+    let json = client
+        .post("core_message_get_conversation", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(Conversation::from_json(&json))
+}
+
+/// The private conversation with another user, or `None` when they have never messaged.
+pub async fn get_conversation_with_user(other_user_id: i64) -> Result<Option<Conversation>> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+    fetch_conversation_with_user(&mut client, user_id, other_user_id).await
+}
+
+pub async fn fetch_conversation_with_user(
+    client: &mut MoodleClient,
+    user_id: i64,
+    other_user_id: i64,
+) -> Result<Option<Conversation>> {
+    let form: Vec<(String, String)> = vec![
+        ("userid".to_string(), user_id.to_string()),
+        ("otheruserid".to_string(), other_user_id.to_string()),
+        ("includecontactrequests".to_string(), "1".to_string()),
+        ("includeprivacyinfo".to_string(), "1".to_string()),
+        ("messagelimit".to_string(), "1".to_string()),
+        ("newestmessagesfirst".to_string(), "1".to_string()),
+    ];
+    let json = client.post("core_message_get_conversation_between_users", &form).await?;
+    // Moodle reports a missing conversation as a moodle_exception whose
+    // errorcode is the literal string "Conversation does not exist"
+    if json
+        .get("errorcode")
+        .and_then(|c| c.as_str())
+        .is_some_and(|c| c.eq_ignore_ascii_case("Conversation does not exist"))
+    {
+        return Ok(None);
+    }
+    let json = check_exception(json)?;
+    Ok(Some(Conversation::from_json(&json)))
+}
+
+/// The "notes to self" conversation.
+pub async fn get_self_conversation() -> Result<Option<Conversation>> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+
+    let form: Vec<(String, String)> = vec![
+        ("userid".to_string(), user_id.to_string()),
+        ("messagelimit".to_string(), "1".to_string()),
+        ("newestmessagesfirst".to_string(), "1".to_string()),
+    ];
+    let json = client
+        .post("core_message_get_self_conversation", &form)
+        .await
+        .and_then(check_exception)?;
+    // Null until the user first writes a note to self
+    Ok(Some(json).filter(|c| c.is_object()).map(|c| Conversation::from_json(&c)))
+}
+
+pub async fn get_conversation_members(
+    conversation_id: i64,
+    limit_from: Option<i64>,
+    limit_num: Option<i64>,
+) -> Result<Vec<ConversationMember>> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+    fetch_conversation_members(&mut client, user_id, conversation_id, limit_from, limit_num).await
+}
+
+pub async fn fetch_conversation_members(
+    client: &mut MoodleClient,
+    user_id: i64,
+    conversation_id: i64,
+    limit_from: Option<i64>,
+    limit_num: Option<i64>,
+) -> Result<Vec<ConversationMember>> {
+    let form: Vec<(String, String)> = vec![
+        ("userid".to_string(), user_id.to_string()),
+        ("conversationid".to_string(), conversation_id.to_string()),
+        ("includecontactrequests".to_string(), "1".to_string()),
+        ("includeprivacyinfo".to_string(), "1".to_string()),
+        ("limitfrom".to_string(), limit_from.unwrap_or(0).to_string()),
+        ("limitnum".to_string(), limit_num.unwrap_or(0).to_string()),
+    ];
+    let json = client
+        .post("core_message_get_conversation_members", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(json
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(ConversationMember::from_json)
+        .collect())
+}
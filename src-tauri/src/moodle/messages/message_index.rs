@@ -0,0 +1,255 @@
+use super::watch_messages::NewMessage;
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::html::to_searchable_text;
+use crate::moodle::json::{int, list, text};
+use crate::moodle::site::get_current_user_id;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+use tokio::sync::Mutex;
+
+/// Conversations and messages per conversation pulled in by a refresh.
+const INDEXED_CONVERSATIONS: i64 = 50;
+const MESSAGES_PER_REFRESH: i64 = 100;
+/// Characters of context on each side of the first hit in a snippet.
+const SNIPPET_CONTEXT: usize = 60;
+
+static INDEX_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedMessage {
+    pub message_id: i64,
+    pub conversation_id: i64,
+    pub conversation_name: Option<String>,
+    pub user_id_from: i64,
+    pub sender_name: String,
+    /// Plain text, links kept as written
+    pub text: String,
+    pub timecreated: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MessageIndex {
+    pub messages: Vec<IndexedMessage>,
+    pub refreshed_at: i64,
+    /// Newest message time each conversation was refreshed up to. Only a
+    /// refresh moves it, so messages added by the watcher don't hide older
+    /// history that was never fetched.
+    #[serde(default)]
+    pub synced_until: HashMap<i64, i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalMatch {
+    pub message: IndexedMessage,
+    pub snippet: String,
+    pub score: i64,
+}
+
+/// Lowercased words, keeping URL characters together so a link can be found
+/// by any of its parts.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !(c.is_alphanumeric() || c == '/' || c == '.' || c == ':' || c == '-' || c == '_'))
+        .flat_map(|word| {
+            // "moodle.udsm.ac.tz/mod" also matches "udsm" and "mod"
+            let parts: Vec<&str> = word.split(['/', '.', ':', '-', '_']).filter(|p| !p.is_empty()).collect();
+            std::iter::once(word.trim_matches(|c: char| !c.is_alphanumeric()))
+                .chain(if parts.len() > 1 { parts } else { Vec::new() })
+        })
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_string())
+        .collect()
+}
+
+/// A window of the text around the first occurrence of any query word.
+pub fn snippet(text: &str, query_words: &[String]) -> String {
+    let lower = text.to_lowercase();
+    let hit = query_words.iter().filter_map(|w| lower.find(w.as_str())).min();
+    let chars: Vec<char> = text.chars().collect();
+    let hit_char = match hit {
+        // Byte offset in the lowercased text; close enough for ASCII, clamped otherwise
+        Some(byte) => lower[..byte].chars().count().min(chars.len()),
+        None => 0,
+    };
+
+    let start = hit_char.saturating_sub(SNIPPET_CONTEXT);
+    let end = (hit_char + SNIPPET_CONTEXT * 2).min(chars.len());
+    let mut out: String = chars[start..end].iter().collect();
+    if start > 0 {
+        out = format!("…{}", out.trim_start());
+    }
+    if end < chars.len() {
+        out = format!("{}…", out.trim_end());
+    }
+    out
+}
+
+impl MessageIndex {
+    pub async fn load(path: &Path) -> Result<Self> {
+        match tokio::fs::read(path).await {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes).unwrap_or_default()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(MessageIndex::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, serde_json::to_vec(self)?).await?;
+        Ok(())
+    }
+
+    /// Adds messages, ignoring ones already indexed.
+    pub fn insert(&mut self, messages: impl IntoIterator<Item = IndexedMessage>) {
+        for message in messages {
+            if !self.messages.iter().any(|m| m.message_id == message.message_id) {
+                self.messages.push(message);
+            }
+        }
+    }
+
+    /// Messages containing every query word (as a word prefix), best first.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<LocalMatch> {
+        let words = tokenize(query);
+        if words.is_empty() {
+            return Vec::new();
+        }
+
+        let mut matches: Vec<LocalMatch> = self
+            .messages
+            .iter()
+            .filter_map(|message| {
+                let tokens = tokenize(&message.text);
+                let mut score = 0;
+                for word in &words {
+                    let exact = tokens.iter().filter(|t| *t == word).count() as i64;
+                    let prefix = tokens.iter().filter(|t| t.starts_with(word.as_str())).count() as i64;
+                    if prefix == 0 {
+                        return None;
+                    }
+                    score += exact * 2 + prefix;
+                }
+                Some(LocalMatch {
+                    snippet: snippet(&message.text, &words),
+                    message: message.clone(),
+                    score,
+                })
+            })
+            .collect();
+
+        matches.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then(b.message.timecreated.cmp(&a.message.timecreated))
+        });
+        matches.truncate(limit);
+        matches
+    }
+}
+
+/// Adds messages seen by the message watcher to the index.
+pub async fn index_new_messages(path: &Path, messages: &[NewMessage]) -> Result<()> {
+    let _guard = INDEX_LOCK.get_or_init(|| Mutex::new(())).lock().await;
+    let mut index = MessageIndex::load(path).await?;
+    index.insert(messages.iter().map(|m| IndexedMessage {
+        message_id: m.message_id,
+        conversation_id: m.conversation_id,
+        conversation_name: m.conversation_name.clone(),
+        user_id_from: m.user_id_from,
+        sender_name: m.sender_name.clone(),
+        text: if m.links.is_empty() {
+            m.text.clone()
+        } else {
+            format!("{} {}", m.text, m.links.join(" "))
+        },
+        timecreated: m.timecreated,
+    }));
+    index.save(path).await
+}
+
+/// Pulls recent conversations into the index, only fetching messages newer
+/// than the previous refresh of each conversation.
+pub async fn refresh_message_index(path: &Path) -> Result<usize> {
+    let _guard = INDEX_LOCK.get_or_init(|| Mutex::new(())).lock().await;
+    let mut index = MessageIndex::load(path).await?;
+    let before = index.messages.len();
+
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+
+    let form: Vec<(String, String)> = vec![
+        ("userid".to_string(), user_id.to_string()),
+        ("limitfrom".to_string(), "0".to_string()),
+        ("limitnum".to_string(), INDEXED_CONVERSATIONS.to_string()),
+        ("mergeself".to_string(), "1".to_string()),
+    ];
+    let conversations = client
+        .post("core_message_get_conversations", &form)
+        .await
+        .and_then(check_exception)?;
+
+    for conversation in list(&conversations, "conversations") {
+        let conversation_id = int(conversation, "id");
+        let latest = list(conversation, "messages").first().map(|m| int(m, "timecreated")).unwrap_or(0);
+        let known = index.synced_until.get(&conversation_id).copied().unwrap_or(0);
+        if latest <= known {
+            continue;
+        }
+
+        let members: HashMap<i64, String> = list(conversation, "members")
+            .iter()
+            .map(|m| (int(m, "id"), text(m, "fullname").unwrap_or_default()))
+            .collect();
+        let conversation_name = text(conversation, "name");
+
+        let mut form: Vec<(String, String)> = vec![
+            ("currentuserid".to_string(), user_id.to_string()),
+            ("convid".to_string(), conversation_id.to_string()),
+            ("limitfrom".to_string(), "0".to_string()),
+            ("limitnum".to_string(), MESSAGES_PER_REFRESH.to_string()),
+            ("newest".to_string(), "1".to_string()),
+        ];
+        if known > 0 {
+            form.push(("timefrom".to_string(), known.to_string()));
+        }
+        let messages = client
+            .post("core_message_get_conversation_messages", &form)
+            .await
+            .and_then(check_exception)?;
+
+        let messages = list(&messages, "messages");
+        let synced_until = messages.iter().map(|m| int(m, "timecreated")).max().unwrap_or(latest);
+        index.synced_until.insert(conversation_id, synced_until.max(known));
+        index.insert(messages.iter().map(|m| {
+            let from = int(m, "useridfrom");
+            IndexedMessage {
+                message_id: int(m, "id"),
+                conversation_id,
+                conversation_name: conversation_name.clone(),
+                user_id_from: from,
+                sender_name: if from == user_id {
+                    "You".to_string()
+                } else {
+                    members.get(&from).cloned().unwrap_or_default()
+                },
+                text: to_searchable_text(&text(m, "text").unwrap_or_default()),
+                timecreated: int(m, "timecreated"),
+            }
+        }));
+    }
+
+    index.refreshed_at = chrono::Utc::now().timestamp();
+    index.save(path).await?;
+    Ok(index.messages.len() - before)
+}
+
+pub async fn search_local_messages(path: &Path, query: &str, limit: usize) -> Result<Vec<LocalMatch>> {
+    let _guard = INDEX_LOCK.get_or_init(|| Mutex::new(())).lock().await;
+    Ok(MessageIndex::load(path).await?.search(query, limit))
+}
@@ -1,5 +1,9 @@
+pub mod conversations;
 pub mod delete_message;
 pub mod get_conversation_messages;
 pub mod get_conversations;
+pub mod message_index;
+pub mod search_messages;
 pub mod send_instant_message;
 pub mod send_message;
+pub mod watch_messages;
@@ -0,0 +1,140 @@
+use super::conversations::ConversationMember;
+use super::message_index::{search_local_messages, snippet, tokenize};
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::html::to_plain_text;
+use crate::moodle::json::{int, list, text};
+use crate::moodle::site::get_current_user_id;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchSource {
+    Server,
+    Local,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageSearchResult {
+    pub conversation_id: i64,
+    /// Group name, or the other person's name for private conversations
+    pub conversation_name: String,
+    pub message_id: i64,
+    pub user_id_from: Option<i64>,
+    pub sender_name: Option<String>,
+    pub profile_image_url: Option<String>,
+    pub snippet: String,
+    pub timecreated: i64,
+    pub source: SearchSource,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserSearchResults {
+    pub contacts: Vec<ConversationMember>,
+    pub non_contacts: Vec<ConversationMember>,
+}
+
+/// Moodle's own message search (`core_message_data_for_messagearea_search_messages`).
+pub async fn search_server_messages(query: &str, limit: i64) -> Result<Vec<MessageSearchResult>> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+
+    let form: Vec<(String, String)> = vec![
+        ("userid".to_string(), user_id.to_string()),
+        ("search".to_string(), query.to_string()),
+        ("limitfrom".to_string(), "0".to_string()),
+        ("limitnum".to_string(), limit.to_string()),
+    ];
+    let json = client
+        .post("core_message_data_for_messagearea_search_messages", &form)
+        .await
+        .and_then(check_exception)?;
+
+    let words = tokenize(query);
+    Ok(list(&json, "contacts")
+        .iter()
+        .map(|hit| {
+            let message = to_plain_text(&text(hit, "lastmessage").unwrap_or_default());
+            let fullname = text(hit, "fullname").unwrap_or_default();
+            let from_me = hit.get("sentfromcurrentuser").and_then(|s| s.as_bool()).unwrap_or(false);
+            MessageSearchResult {
+                conversation_id: int(hit, "conversationid"),
+                conversation_name: fullname.clone(),
+                message_id: int(hit, "messageid"),
+                user_id_from: Some(if from_me { user_id } else { int(hit, "userid") }),
+                sender_name: Some(if from_me { "You".to_string() } else { fullname }),
+                profile_image_url: text(hit, "profileimageurl"),
+                snippet: snippet(&message, &words),
+                timecreated: int(hit, "lastmessagedate"),
+                source: SearchSource::Server,
+            }
+        })
+        .collect())
+}
+
+/// Searches Moodle and the local index together, newest first.
+///
+/// The server only searches private conversations; the local index also
+/// covers group conversations and keeps working offline.
+pub async fn search_messages(query: &str, index_path: &Path, limit: i64) -> Result<Vec<MessageSearchResult>> {
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let local = search_local_messages(index_path, query, limit as usize).await?;
+    let mut results: Vec<MessageSearchResult> = match search_server_messages(query, limit).await {
+        Ok(results) => results,
+        Err(e) if local.is_empty() => return Err(e),
+        // Offline: local results are still useful
+        Err(_) => Vec::new(),
+    };
+
+    for hit in local {
+        if results.iter().any(|r| r.message_id == hit.message.message_id) {
+            continue;
+        }
+        results.push(MessageSearchResult {
+            conversation_id: hit.message.conversation_id,
+            conversation_name: hit
+                .message
+                .conversation_name
+                .clone()
+                .unwrap_or_else(|| hit.message.sender_name.clone()),
+            message_id: hit.message.message_id,
+            user_id_from: Some(hit.message.user_id_from),
+            sender_name: Some(hit.message.sender_name),
+            profile_image_url: None,
+            snippet: hit.snippet,
+            timecreated: hit.message.timecreated,
+            source: SearchSource::Local,
+        });
+    }
+
+    results.sort_by_key(|r| std::cmp::Reverse(r.timecreated));
+    results.truncate(limit as usize);
+    Ok(results)
+}
+
+/// People to start a conversation with (`core_message_message_search_users`).
+pub async fn search_users(query: &str, limit: i64) -> Result<UserSearchResults> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+
+    let form: Vec<(String, String)> = vec![
+        ("userid".to_string(), user_id.to_string()),
+        ("search".to_string(), query.to_string()),
+        ("limitfrom".to_string(), "0".to_string()),
+        ("limitnum".to_string(), limit.to_string()),
+    ];
+    let json = client
+        .post("core_message_message_search_users", &form)
+        .await
+        .and_then(check_exception)?;
+
+    Ok(UserSearchResults {
+        contacts: list(&json, "contacts").iter().map(ConversationMember::from_json).collect(),
+        non_contacts: list(&json, "noncontacts").iter().map(ConversationMember::from_json).collect(),
+    })
+}
@@ -0,0 +1,179 @@
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::html::{extract_links, to_plain_text};
+use crate::moodle::json::{int, list, text};
+use crate::moodle::site::get_current_user_id;
+use anyhow::Result;
+use moodle_api::core::message::get_conversations;
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Conversations checked per poll; anything older is not "new".
+const WATCHED_CONVERSATIONS: i64 = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewMessage {
+    pub conversation_id: i64,
+    /// Set for group conversations
+    pub conversation_name: Option<String>,
+    pub message_id: i64,
+    pub user_id_from: i64,
+    pub sender_name: String,
+    pub text: String,
+    /// Link targets in the message, which `text` leaves out
+    pub links: Vec<String>,
+    pub timecreated: i64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Cursor {
+    message_id: i64,
+    timecreated: i64,
+}
+
+/// Remembers the newest message seen in each conversation so repeated polls
+/// only report what arrived in between. Keeps one client and the user id
+/// instead of logging in and looking up site info on every poll.
+pub struct MessageWatcher {
+    client: MoodleClient,
+    user_id: i64,
+    cursors: HashMap<i64, Cursor>,
+    unread_count: i64,
+    primed: bool,
+}
+
+impl MessageWatcher {
+    pub async fn connect() -> Result<Self> {
+        let mut client = login().await?;
+        let user_id = get_current_user_id(&mut client).await?;
+        Ok(MessageWatcher {
+            client,
+            user_id,
+            cursors: HashMap::new(),
+            unread_count: -1,
+            primed: false,
+        })
+    }
+
+    pub fn unread_count(&self) -> i64 {
+        self.unread_count.max(0)
+    }
+
+    /// Number of conversations with unread messages.
+    pub async fn fetch_unread_count(&mut self) -> Result<i64> {
+        fetch_unread_conversations_count(&mut self.client, self.user_id).await
+    }
+
+    /// Returns messages from other users received since the previous poll,
+    /// oldest first. The first poll only records where each conversation is.
+    pub async fn poll(&mut self) -> Result<Vec<NewMessage>> {
+        let unread = self.fetch_unread_count().await?;
+        // Nothing changed: the count only moves when messages arrive or are read
+        if self.primed && unread == self.unread_count && unread == 0 {
+            return Ok(Vec::new());
+        }
+        self.unread_count = unread;
+
+        let conversations = get_conversations::call_raw(
+            &mut self.client,
+            &mut get_conversations::Params {
+                userid: Some(self.user_id),
+                limitfrom: Some(0),
+                limitnum: Some(WATCHED_CONVERSATIONS),
+                r#type: None,
+                favourites: None,
+                mergeself: Some(1),
+            },
+        )
+        .await
+        .and_then(check_exception)?;
+
+        let mut new_messages = Vec::new();
+        for conversation in list(&conversations, "conversations") {
+            let conversation_id = int(conversation, "id");
+            let latest = match list(conversation, "messages").first() {
+                Some(latest) => Cursor {
+                    message_id: int(latest, "id"),
+                    timecreated: int(latest, "timecreated"),
+                },
+                None => continue,
+            };
+
+            let previous = self.cursors.insert(conversation_id, latest);
+            if !self.primed {
+                continue;
+            }
+            let since = match previous {
+                Some(previous) if previous.message_id >= latest.message_id => continue,
+                Some(previous) => previous,
+                // A conversation that just appeared in the recent list
+                None => Cursor {
+                    message_id: 0,
+                    timecreated: latest.timecreated,
+                },
+            };
+
+            let members: HashMap<i64, String> = list(conversation, "members")
+                .iter()
+                .map(|m| (int(m, "id"), text(m, "fullname").unwrap_or_default()))
+                .collect();
+            let conversation_name = text(conversation, "name");
+
+            // Posted as a form: the typed params serialise `newest` as "true",
+            // which PARAM_BOOL rejects
+            let form: Vec<(String, String)> = vec![
+                ("currentuserid".to_string(), self.user_id.to_string()),
+                ("convid".to_string(), conversation_id.to_string()),
+                ("limitfrom".to_string(), "0".to_string()),
+                ("limitnum".to_string(), "50".to_string()),
+                ("newest".to_string(), "1".to_string()),
+                ("timefrom".to_string(), since.timecreated.to_string()),
+            ];
+            let messages = self
+                .client
+                .post("core_message_get_conversation_messages", &form)
+                .await
+                .and_then(check_exception)?;
+
+            let mut fresh: Vec<NewMessage> = list(&messages, "messages")
+                .iter()
+                .filter(|m| int(m, "id") > since.message_id && int(m, "useridfrom") != self.user_id)
+                .map(|m| {
+                    let from = int(m, "useridfrom");
+                    NewMessage {
+                        conversation_id,
+                        conversation_name: conversation_name.clone(),
+                        message_id: int(m, "id"),
+                        user_id_from: from,
+                        sender_name: members.get(&from).cloned().unwrap_or_default(),
+                        text: to_plain_text(&text(m, "text").unwrap_or_default()),
+                        links: extract_links(&text(m, "text").unwrap_or_default()),
+                        timecreated: int(m, "timecreated"),
+                    }
+                })
+                .collect();
+            fresh.sort_by_key(|m| m.message_id);
+            new_messages.extend(fresh);
+        }
+
+        self.primed = true;
+        new_messages.sort_by_key(|m| m.timecreated);
+        Ok(new_messages)
+    }
+}
+
+pub async fn fetch_unread_conversations_count(client: &mut MoodleClient, user_id: i64) -> Result<i64> {
+    let form: Vec<(String, String)> = vec![("useridto".to_string(), user_id.to_string())];
+    let json = client
+        .post("core_message_get_unread_conversations_count", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(json.as_i64().unwrap_or(0))
+}
+
+pub async fn get_unread_conversations_count() -> Result<i64> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+    fetch_unread_conversations_count(&mut client, user_id).await
+}
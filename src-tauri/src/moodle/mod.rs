@@ -1,5 +1,24 @@
+pub mod assignments;
+pub mod backup;
+pub mod badges;
 pub mod calendar;
+pub mod competencies;
 pub mod contacts;
+pub mod content;
 pub mod courses;
+pub mod exception;
+pub mod files;
+pub mod forms;
+pub mod forums;
+pub mod grades;
+pub mod h5p;
+pub mod html;
+pub mod json;
+pub mod knowledge;
 pub mod messages;
+pub mod notifications;
+pub mod outbox;
+pub mod scorm;
 pub mod site;
+pub mod timeline;
+pub mod workshop;
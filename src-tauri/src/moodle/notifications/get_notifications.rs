@@ -0,0 +1,169 @@
+use super::routes::{resolve_route, AppRoute};
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::html::to_plain_text;
+use crate::moodle::json::{flag, int, list, text};
+use crate::moodle::site::get_current_user_id;
+use anyhow::Result;
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub id: i64,
+    pub user_id_from: i64,
+    pub subject: String,
+    /// Plain-text body
+    pub text: String,
+    /// e.g. `mod_forum`, `mod_assign`, `moodle` (core)
+    pub component: String,
+    pub event_type: Option<String>,
+    pub context_url: Option<String>,
+    pub context_url_name: Option<String>,
+    pub route: Option<AppRoute>,
+    pub icon_url: Option<String>,
+    pub read: bool,
+    pub timecreated: i64,
+    pub time_created_pretty: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationGroup {
+    pub component: String,
+    /// Display name such as "Forum" or "Assignment"
+    pub label: String,
+    pub unread: i64,
+    pub notifications: Vec<Notification>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationPage {
+    pub notifications: Vec<Notification>,
+    /// The same notifications grouped by component, in order of each group's newest item
+    pub groups: Vec<NotificationGroup>,
+    pub unread_count: i64,
+    /// Offset for the next page
+    pub next_offset: i64,
+    pub has_more: bool,
+}
+
+impl Notification {
+    pub fn from_json(notification: &serde_json::Value) -> Self {
+        let context_url = text(notification, "contexturl");
+        Notification {
+            id: int(notification, "id"),
+            user_id_from: int(notification, "useridfrom"),
+            subject: text(notification, "subject").unwrap_or_default(),
+            text: text(notification, "smallmessage")
+                .or_else(|| text(notification, "fullmessagehtml").map(|h| to_plain_text(&h)))
+                .or_else(|| text(notification, "fullmessage"))
+                .unwrap_or_default(),
+            component: text(notification, "component").unwrap_or_else(|| "moodle".to_string()),
+            event_type: text(notification, "eventtype"),
+            route: context_url.as_deref().and_then(resolve_route),
+            context_url,
+            context_url_name: text(notification, "contexturlname"),
+            icon_url: text(notification, "iconurl"),
+            read: flag(notification, "read"),
+            timecreated: int(notification, "timecreated"),
+            time_created_pretty: text(notification, "timecreatedpretty"),
+        }
+    }
+}
+
+pub fn component_label(component: &str) -> String {
+    match component {
+        "mod_forum" => "Forum".to_string(),
+        "mod_assign" => "Assignment".to_string(),
+        "mod_quiz" => "Quiz".to_string(),
+        "mod_feedback" => "Feedback".to_string(),
+        "mod_lesson" => "Lesson".to_string(),
+        "mod_workshop" => "Workshop".to_string(),
+        "moodle" | "core" => "System".to_string(),
+        "core_badges" | "badges" => "Badges".to_string(),
+        "core_calendar" => "Calendar".to_string(),
+        "core_competency" => "Competencies".to_string(),
+        "enrol_self" | "enrol_manual" | "enrol_guest" => "Enrolment".to_string(),
+        other => {
+            // mod_xyz -> Xyz
+            let name = other.split('_').next_back().unwrap_or(other);
+            let mut chars = name.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => other.to_string(),
+            }
+        }
+    }
+}
+
+pub fn group_notifications(notifications: &[Notification]) -> Vec<NotificationGroup> {
+    let mut groups: Vec<NotificationGroup> = Vec::new();
+    for notification in notifications {
+        let group = match groups.iter_mut().find(|g| g.component == notification.component) {
+            Some(group) => group,
+            None => {
+                groups.push(NotificationGroup {
+                    component: notification.component.clone(),
+                    label: component_label(&notification.component),
+                    unread: 0,
+                    notifications: Vec::new(),
+                });
+                groups.last_mut().expect("group was just pushed")
+            }
+        };
+        if !notification.read {
+            group.unread += 1;
+        }
+        group.notifications.push(notification.clone());
+    }
+    groups
+}
+
+/// One page of popup notifications, newest first.
+pub async fn get_notifications(offset: Option<i64>, limit: Option<i64>) -> Result<NotificationPage> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(20);
+    let form: Vec<(String, String)> = vec![
+        ("useridto".to_string(), user_id.to_string()),
+        ("newestfirst".to_string(), "1".to_string()),
+        ("limit".to_string(), limit.to_string()),
+        ("offset".to_string(), offset.to_string()),
+    ];
+    let json = client
+        .post("message_popup_get_popup_notifications", &form)
+        .await
+        .and_then(check_exception)?;
+
+    let notifications: Vec<Notification> = list(&json, "notifications")
+        .iter()
+        .filter(|n| !flag(n, "deleted"))
+        .map(Notification::from_json)
+        .collect();
+    let fetched = list(&json, "notifications").len() as i64;
+
+    Ok(NotificationPage {
+        groups: group_notifications(&notifications),
+        unread_count: int(&json, "unreadcount"),
+        next_offset: offset + fetched,
+        has_more: fetched >= limit,
+        notifications,
+    })
+}
+
+pub async fn fetch_unread_notification_count(client: &mut MoodleClient, user_id: i64) -> Result<i64> {
+    let form: Vec<(String, String)> = vec![("useridto".to_string(), user_id.to_string())];
+    let json = client
+        .post("message_popup_get_unread_popup_notification_count", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(json.as_i64().unwrap_or(0))
+}
+
+pub async fn get_unread_notification_count() -> Result<i64> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+    fetch_unread_notification_count(&mut client, user_id).await
+}
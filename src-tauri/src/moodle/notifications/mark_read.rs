@@ -0,0 +1,39 @@
+use super::get_notifications::fetch_unread_notification_count;
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::site::get_current_user_id;
+use anyhow::Result;
+
+/// Marks one notification as read and returns the remaining unread count.
+pub async fn mark_notification_read(notification_id: i64) -> Result<i64> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+
+    let form: Vec<(String, String)> = vec![
+        ("notificationid".to_string(), notification_id.to_string()),
+        ("timeread".to_string(), chrono::Utc::now().timestamp().to_string()),
+    ];
+    client
+        .post("core_message_mark_notification_read", &form)
+        .await
+        .and_then(check_exception)?;
+    fetch_unread_notification_count(&mut client, user_id).await
+}
+
+/// Marks every notification up to now as read.
+pub async fn mark_all_notifications_read() -> Result<()> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+
+    let form: Vec<(String, String)> = vec![
+        ("useridto".to_string(), user_id.to_string()),
+        // 0 means from any user, including the system
+        ("useridfrom".to_string(), "0".to_string()),
+        ("timecreatedto".to_string(), chrono::Utc::now().timestamp().to_string()),
+    ];
+    client
+        .post("core_message_mark_all_notifications_as_read", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(())
+}
@@ -0,0 +1,7 @@
+pub mod get_notifications;
+pub mod mark_read;
+pub mod routes;
+
+pub use get_notifications::*;
+pub use mark_read::*;
+pub use routes::*;
@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+
+/// Where the app should navigate when a notification is opened.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "route", rename_all = "snake_case")]
+pub enum AppRoute {
+    Course { course_id: i64 },
+    Assignment { cmid: i64 },
+    Discussion { discussion_id: i64, post_id: Option<i64> },
+    Forum { cmid: i64 },
+    /// Any other activity page, e.g. quiz or choice
+    Activity { module: String, cmid: i64 },
+    Grades { course_id: Option<i64> },
+    Badges,
+    Badge { hash: String },
+    Messages { user_id: Option<i64> },
+    Calendar,
+    Profile { user_id: i64 },
+}
+
+/// Maps a Moodle web URL (a notification's `contexturl`) to an in-app route.
+///
+/// Returns `None` for pages the app has no screen for, which the UI opens in
+/// the browser instead.
+pub fn resolve_route(url: &str) -> Option<AppRoute> {
+    // Drop scheme and host; Moodle may live under a sub-path such as /moodle
+    let after_host = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let path_and_query = after_host.find('/').map(|i| &after_host[i..]).unwrap_or("");
+    let (path_and_query, fragment) = path_and_query.split_once('#').unwrap_or((path_and_query, ""));
+    let (path, query) = path_and_query.split_once('?').unwrap_or((path_and_query, ""));
+
+    let param = |key: &str| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.to_string())
+    };
+    let number = |key: &str| param(key).and_then(|v| v.parse::<i64>().ok());
+
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let tail = |n: usize| segments[segments.len().saturating_sub(n)..].join("/");
+
+    if tail(3) == "mod/forum/discuss.php" {
+        return Some(AppRoute::Discussion {
+            discussion_id: number("d")?,
+            post_id: fragment.strip_prefix('p').and_then(|p| p.parse().ok()),
+        });
+    }
+    if segments.len() >= 3 && segments[segments.len() - 3] == "mod" && segments[segments.len() - 1] == "view.php" {
+        let module = segments[segments.len() - 2];
+        let cmid = number("id")?;
+        return Some(match module {
+            "assign" => AppRoute::Assignment { cmid },
+            "forum" => AppRoute::Forum { cmid },
+            _ => AppRoute::Activity {
+                module: module.to_string(),
+                cmid,
+            },
+        });
+    }
+
+    match tail(2).as_str() {
+        "course/view.php" => return Some(AppRoute::Course { course_id: number("id")? }),
+        "badges/mybadges.php" => return Some(AppRoute::Badges),
+        "badges/badge.php" => return Some(AppRoute::Badge { hash: param("hash")? }),
+        "message/index.php" => {
+            return Some(AppRoute::Messages {
+                user_id: number("id").or_else(|| number("user2")),
+            })
+        }
+        "calendar/view.php" => return Some(AppRoute::Calendar),
+        "user/profile.php" => return Some(AppRoute::Profile { user_id: number("id")? }),
+        _ => {}
+    }
+    if tail(4) == "grade/report/user/index.php" || tail(4) == "grade/report/overview/index.php" {
+        return Some(AppRoute::Grades { course_id: number("id") });
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_activity_pages() {
+        assert_eq!(
+            resolve_route("https://moodle.example.com/mod/assign/view.php?id=42"),
+            Some(AppRoute::Assignment { cmid: 42 })
+        );
+        assert_eq!(
+            resolve_route("https://moodle.example.com/mod/forum/view.php?id=7"),
+            Some(AppRoute::Forum { cmid: 7 })
+        );
+        assert_eq!(
+            resolve_route("https://example.com/moodle/mod/quiz/view.php?id=3"),
+            Some(AppRoute::Activity {
+                module: "quiz".to_string(),
+                cmid: 3
+            })
+        );
+        assert_eq!(resolve_route("https://moodle.example.com/mod/quiz/view.php"), None);
+    }
+
+    #[test]
+    fn resolves_discussion_with_post_anchor() {
+        assert_eq!(
+            resolve_route("https://moodle.example.com/mod/forum/discuss.php?d=12#p345"),
+            Some(AppRoute::Discussion {
+                discussion_id: 12,
+                post_id: Some(345)
+            })
+        );
+        assert_eq!(
+            resolve_route("https://moodle.example.com/mod/forum/discuss.php?d=12"),
+            Some(AppRoute::Discussion {
+                discussion_id: 12,
+                post_id: None
+            })
+        );
+    }
+
+    #[test]
+    fn resolves_site_pages() {
+        assert_eq!(
+            resolve_route("https://moodle.example.com/course/view.php?id=5"),
+            Some(AppRoute::Course { course_id: 5 })
+        );
+        assert_eq!(
+            resolve_route("https://moodle.example.com/message/index.php?user2=9"),
+            Some(AppRoute::Messages { user_id: Some(9) })
+        );
+        assert_eq!(
+            resolve_route("https://moodle.example.com/badges/badge.php?hash=abc"),
+            Some(AppRoute::Badge { hash: "abc".to_string() })
+        );
+        assert_eq!(
+            resolve_route("https://moodle.example.com/grade/report/user/index.php?id=5"),
+            Some(AppRoute::Grades { course_id: Some(5) })
+        );
+        assert_eq!(
+            resolve_route("https://moodle.example.com/grade/report/overview/index.php"),
+            Some(AppRoute::Grades { course_id: None })
+        );
+        assert_eq!(resolve_route("https://moodle.example.com/admin/index.php"), None);
+    }
+}
@@ -0,0 +1,337 @@
+use super::queue::{due_items, update_item, wake_pending, OutboxItem, OutboxPayload, OutboxStatus};
+use crate::moodle::assignments::save_submission_draft;
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::forums::{reply_to_post, NewForumPost};
+use crate::moodle::h5p::post_xapi_statement;
+use crate::moodle::html::to_plain_text;
+use crate::moodle::json::{int, list, text};
+use crate::moodle::site::{get_current_user_id, Account};
+use anyhow::Result;
+use moodle_client::MoodleClient;
+use std::path::Path;
+
+pub enum DeliveryOutcome {
+    /// Delivered, with the id of the created message or post when known
+    Delivered(Option<i64>),
+    /// The network or server is unavailable; try again later
+    Retry(String),
+    /// Moodle rejected the item
+    Rejected(String),
+}
+
+/// True for failures worth retrying: no connection, timeouts, a captive
+/// portal or maintenance page instead of JSON, or an expired token.
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    let network = error.chain().any(|cause| {
+        cause
+            .downcast_ref::<reqwest::Error>()
+            .is_some_and(|e| e.is_connect() || e.is_timeout() || e.is_request() || e.is_body() || e.is_decode())
+    });
+    let message = error.to_string();
+    network || message.contains("(invalidtoken)") || message == "Login failed"
+}
+
+fn classify(error: anyhow::Error) -> DeliveryOutcome {
+    if is_transient(&error) {
+        DeliveryOutcome::Retry(error.to_string())
+    } else {
+        DeliveryOutcome::Rejected(error.to_string())
+    }
+}
+
+pub async fn deliver_item(item: &OutboxItem) -> DeliveryOutcome {
+    let result = match &item.payload {
+        OutboxPayload::Message {
+            to_user_id,
+            conversation_id,
+            text,
+        } => deliver_message(item, *to_user_id, *conversation_id, text).await,
+        OutboxPayload::ForumReply { post_id, post } => deliver_forum_reply(item, *post_id, post).await,
+        OutboxPayload::AssignmentDraft { assign_id, online_text } => {
+            save_submission_draft(*assign_id, Some(online_text.clone()), None)
+                .await
+                .map(|result| {
+                    if result.success {
+                        DeliveryOutcome::Delivered(None)
+                    } else {
+                        let messages: Vec<String> =
+                            result.warnings.iter().filter_map(|w| w.message.clone()).collect();
+                        DeliveryOutcome::Rejected(messages.join("; "))
+                    }
+                })
+        }
+        OutboxPayload::XapiStatement { component, statement } => post_xapi_statement(component, statement)
+            .await
+            .map(|stored| {
+                if stored {
+                    DeliveryOutcome::Delivered(None)
+                } else {
+                    DeliveryOutcome::Rejected("Moodle did not store the xAPI statement".to_string())
+                }
+            }),
+    };
+    result.unwrap_or_else(classify)
+}
+
+async fn deliver_message(
+    item: &OutboxItem,
+    to_user_id: Option<i64>,
+    conversation_id: Option<i64>,
+    message: &str,
+) -> Result<DeliveryOutcome> {
+    let mut client = login().await?;
+    let user_id = get_current_user_id(&mut client).await?;
+
+    // An earlier attempt may have reached Moodle before the connection dropped
+    if item.attempts > 0 {
+        let conversation_id = match conversation_id {
+            Some(id) => Some(id),
+            None => find_private_conversation(&mut client, user_id, to_user_id.unwrap_or(0)).await?,
+        };
+        if let Some(conversation_id) = conversation_id {
+            if let Some(id) = find_sent_message(&mut client, user_id, conversation_id, item, message).await? {
+                return Ok(DeliveryOutcome::Delivered(Some(id)));
+            }
+        }
+    }
+
+    match (conversation_id, to_user_id) {
+        (Some(conversation_id), _) => {
+            let form: Vec<(String, String)> = vec![
+                ("conversationid".to_string(), conversation_id.to_string()),
+                ("messages[0][text]".to_string(), message.to_string()),
+                ("messages[0][textformat]".to_string(), "1".to_string()),
+            ];
+            let json = client
+                .post("core_message_send_messages_to_conversation", &form)
+                .await
+                .and_then(check_exception)?;
+            let id = json.as_array().and_then(|m| m.first()).map(|m| int(m, "id"));
+            Ok(DeliveryOutcome::Delivered(id))
+        }
+        (None, Some(to_user_id)) => {
+            let form: Vec<(String, String)> = vec![
+                ("messages[0][touserid]".to_string(), to_user_id.to_string()),
+                ("messages[0][text]".to_string(), message.to_string()),
+                ("messages[0][textformat]".to_string(), "1".to_string()),
+                ("messages[0][clientmsgid]".to_string(), item.client_msg_id.clone()),
+            ];
+            let json = client
+                .post("core_message_send_instant_messages", &form)
+                .await
+                .and_then(check_exception)?;
+            let sent = json
+                .as_array()
+                .into_iter()
+                .flatten()
+                .find(|m| text(m, "clientmsgid").as_deref() == Some(item.client_msg_id.as_str()));
+            match sent {
+                Some(sent) if int(sent, "msgid") > 0 => Ok(DeliveryOutcome::Delivered(Some(int(sent, "msgid")))),
+                Some(sent) => Ok(DeliveryOutcome::Rejected(
+                    text(sent, "errormessage").unwrap_or_else(|| "Moodle did not send the message".to_string()),
+                )),
+                None => Ok(DeliveryOutcome::Rejected("Moodle did not send the message".to_string())),
+            }
+        }
+        (None, None) => Ok(DeliveryOutcome::Rejected("The message has no recipient".to_string())),
+    }
+}
+
+async fn deliver_forum_reply(item: &OutboxItem, post_id: i64, post: &NewForumPost) -> Result<DeliveryOutcome> {
+    // An earlier attempt may have posted the reply before the connection dropped
+    if item.attempts > 0 {
+        let mut client = login().await?;
+        let user_id = get_current_user_id(&mut client).await?;
+        if let Some(id) = find_posted_reply(&mut client, user_id, post_id, item, &post.message).await? {
+            return Ok(DeliveryOutcome::Delivered(Some(id)));
+        }
+    }
+    let id = reply_to_post(post_id, post.clone()).await?;
+    Ok(DeliveryOutcome::Delivered(Some(id)))
+}
+
+/// Our reply to `post_id` with the same text, created since the item was queued.
+async fn find_posted_reply(
+    client: &mut MoodleClient,
+    user_id: i64,
+    post_id: i64,
+    item: &OutboxItem,
+    message: &str,
+) -> Result<Option<i64>> {
+    let parent_form = vec![("postid".to_string(), post_id.to_string())];
+    let parent = client
+        .post("mod_forum_get_discussion_post", &parent_form)
+        .await
+        .and_then(check_exception)?;
+    let discussion_id = parent.get("post").map(|p| int(p, "discussionid")).unwrap_or(0);
+    if discussion_id == 0 {
+        return Ok(None);
+    }
+
+    let form: Vec<(String, String)> = vec![
+        ("discussionid".to_string(), discussion_id.to_string()),
+        ("sortby".to_string(), "created".to_string()),
+        ("sortdirection".to_string(), "DESC".to_string()),
+    ];
+    let json = client
+        .post("mod_forum_get_discussion_posts", &form)
+        .await
+        .and_then(check_exception)?;
+    let wanted = to_plain_text(message);
+    Ok(list(&json, "posts")
+        .iter()
+        .find(|p| {
+            int(p, "parentid") == post_id
+                && p.get("author").map(|a| int(a, "id")) == Some(user_id)
+                && int(p, "timecreated") >= item.created_at
+                && to_plain_text(&text(p, "message").unwrap_or_default()) == wanted
+        })
+        .map(|p| int(p, "id")))
+}
+
+async fn find_private_conversation(
+    client: &mut MoodleClient,
+    user_id: i64,
+    other_user_id: i64,
+) -> Result<Option<i64>> {
+    let form: Vec<(String, String)> = vec![
+        ("userid".to_string(), user_id.to_string()),
+        ("otheruserid".to_string(), other_user_id.to_string()),
+        ("messagelimit".to_string(), "0".to_string()),
+    ];
+    let json = client.post("core_message_get_conversation_between_users", &form).await?;
+    // No conversation yet means nothing was sent
+    Ok(json.get("id").and_then(|id| id.as_i64()))
+}
+
+async fn find_sent_message(
+    client: &mut MoodleClient,
+    user_id: i64,
+    conversation_id: i64,
+    item: &OutboxItem,
+    message: &str,
+) -> Result<Option<i64>> {
+    let form: Vec<(String, String)> = vec![
+        ("currentuserid".to_string(), user_id.to_string()),
+        ("convid".to_string(), conversation_id.to_string()),
+        ("limitnum".to_string(), "20".to_string()),
+        ("newest".to_string(), "1".to_string()),
+        ("timefrom".to_string(), item.created_at.to_string()),
+    ];
+    let json = client
+        .post("core_message_get_conversation_messages", &form)
+        .await
+        .and_then(check_exception)?;
+    let wanted = to_plain_text(message);
+    Ok(list(&json, "messages")
+        .iter()
+        .find(|m| {
+            int(m, "useridfrom") == user_id && to_plain_text(&text(m, "text").unwrap_or_default()) == wanted
+        })
+        .map(|m| int(m, "id")))
+}
+
+/// Attempts every due item of `account` in order, persisting and reporting
+/// each status change. Items of other accounts wait until they sign in again.
+///
+/// Stops at the first transient failure since the rest would fail the same way.
+/// Returns the number of items delivered.
+pub async fn process_outbox(path: &Path, account: &Account, on_change: impl Fn(&OutboxItem)) -> Result<usize> {
+    let now = chrono::Utc::now().timestamp();
+    let mut delivered = 0;
+
+    for mut item in due_items(path, now).await? {
+        match &item.account {
+            Some(owner) if owner != account => continue,
+            Some(_) => {}
+            // Sending it as whoever is signed in now could post as the wrong user
+            None => {
+                item.status = OutboxStatus::Failed;
+                item.last_error = Some("Queued without an account; send it again".to_string());
+                update_item(path, &item).await?;
+                on_change(&item);
+                continue;
+            }
+        }
+        item.status = OutboxStatus::Sending;
+        update_item(path, &item).await?;
+        on_change(&item);
+
+        let outcome = deliver_item(&item).await;
+        let now = chrono::Utc::now().timestamp();
+        item.attempts += 1;
+        let stop = match outcome {
+            DeliveryOutcome::Delivered(remote_id) => {
+                item.status = OutboxStatus::Sent;
+                item.sent_at = Some(now);
+                item.remote_id = remote_id;
+                item.last_error = None;
+                delivered += 1;
+                false
+            }
+            DeliveryOutcome::Retry(error) => {
+                item.status = OutboxStatus::Pending;
+                item.next_attempt_at = now + OutboxItem::backoff(item.attempts);
+                item.last_error = Some(error);
+                true
+            }
+            DeliveryOutcome::Rejected(error) => {
+                item.status = OutboxStatus::Failed;
+                item.last_error = Some(error);
+                false
+            }
+        };
+        update_item(path, &item).await?;
+        on_change(&item);
+        if stop {
+            break;
+        }
+    }
+
+    // Something got through, so items backing off from the outage can go now
+    if delivered > 0 {
+        wake_pending(path).await?;
+    }
+    Ok(delivered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moodle::outbox::list_outbox;
+
+    fn account(user_id: i64) -> Account {
+        Account {
+            site_url: "https://moodle.example.com".to_string(),
+            user_id,
+        }
+    }
+
+    fn message(user_id: i64) -> OutboxItem {
+        let payload = OutboxPayload::Message {
+            to_user_id: Some(7),
+            conversation_id: None,
+            text: "Hello".to_string(),
+        };
+        OutboxItem::new(account(user_id), payload)
+    }
+
+    #[tokio::test]
+    async fn leaves_other_accounts_items_alone() {
+        let held = message(1);
+        let mut unowned = message(2);
+        unowned.account = None;
+        let path = std::env::temp_dir().join(format!("outbox-{}.json", held.id));
+        std::fs::write(&path, serde_json::to_vec(&[&held, &unowned]).unwrap()).unwrap();
+
+        let delivered = process_outbox(&path, &account(2), |_| {}).await.unwrap();
+        let items = list_outbox(&path).await.unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(delivered, 0);
+        assert_eq!(items[0].status, OutboxStatus::Pending);
+        assert_eq!(items[0].attempts, 0);
+        assert_eq!(items[1].status, OutboxStatus::Failed);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod deliver;
+pub mod queue;
+
+pub use deliver::*;
+pub use queue::*;
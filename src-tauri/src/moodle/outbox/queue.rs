@@ -0,0 +1,217 @@
+use crate::moodle::forums::NewForumPost;
+use crate::moodle::site::Account;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::OnceLock;
+use tokio::sync::Mutex;
+
+/// First retry after this many seconds, doubling up to `MAX_BACKOFF_SECONDS`.
+pub const BASE_BACKOFF_SECONDS: i64 = 15;
+pub const MAX_BACKOFF_SECONDS: i64 = 30 * 60;
+
+/// Serialises every read-modify-write of the outbox file.
+static OUTBOX_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OutboxPayload {
+    /// A private message to a user, or a message into an existing conversation
+    Message {
+        to_user_id: Option<i64>,
+        conversation_id: Option<i64>,
+        text: String,
+    },
+    ForumReply { post_id: i64, post: NewForumPost },
+    /// Online-text draft of an assignment submission
+    AssignmentDraft { assign_id: i64, online_text: String },
+    /// xAPI statement recorded by a local H5P player
+    XapiStatement { component: String, statement: serde_json::Value },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxStatus {
+    /// Waiting for its next attempt
+    Pending,
+    Sending,
+    Sent,
+    /// Rejected by Moodle; retrying would fail the same way
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxItem {
+    pub id: String,
+    /// Sent as `clientmsgid` and used to recognise a message that already
+    /// arrived when an earlier attempt timed out
+    pub client_msg_id: String,
+    pub payload: OutboxPayload,
+    /// Who queued the item; it is only sent while that account is signed in.
+    /// Missing on items queued before accounts were recorded.
+    #[serde(default)]
+    pub account: Option<Account>,
+    pub status: OutboxStatus,
+    pub attempts: i64,
+    pub created_at: i64,
+    pub next_attempt_at: i64,
+    pub last_error: Option<String>,
+    pub sent_at: Option<i64>,
+    /// Id of the created message or post once sent
+    pub remote_id: Option<i64>,
+}
+
+impl OutboxItem {
+    pub fn new(account: Account, payload: OutboxPayload) -> Self {
+        let now = chrono::Utc::now().timestamp();
+        let id = uuid::Uuid::new_v4().to_string();
+        OutboxItem {
+            client_msg_id: id.clone(),
+            id,
+            payload,
+            account: Some(account),
+            status: OutboxStatus::Pending,
+            attempts: 0,
+            created_at: now,
+            next_attempt_at: now,
+            last_error: None,
+            sent_at: None,
+            remote_id: None,
+        }
+    }
+
+    /// Delay before the next attempt after `attempts` failures.
+    pub fn backoff(attempts: i64) -> i64 {
+        let exponent = attempts.clamp(1, 16) - 1;
+        (BASE_BACKOFF_SECONDS << exponent).min(MAX_BACKOFF_SECONDS)
+    }
+}
+
+async fn read_items(path: &Path) -> Result<Vec<OutboxItem>> {
+    match tokio::fs::read(path).await {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+async fn write_items(path: &Path, items: &[OutboxItem]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    // Write then rename so a crash never leaves a truncated outbox
+    let tmp = path.with_extension("json.tmp");
+    tokio::fs::write(&tmp, serde_json::to_vec_pretty(items)?).await?;
+    tokio::fs::rename(&tmp, path).await?;
+    Ok(())
+}
+
+/// Applies `change` to the stored items and writes them back.
+async fn modify<T>(path: &Path, change: impl FnOnce(&mut Vec<OutboxItem>) -> T) -> Result<T> {
+    let _guard = OUTBOX_LOCK.get_or_init(|| Mutex::new(())).lock().await;
+    let mut items = read_items(path).await?;
+    let result = change(&mut items);
+    write_items(path, &items).await?;
+    Ok(result)
+}
+
+pub async fn enqueue(path: &Path, account: Account, payload: OutboxPayload) -> Result<OutboxItem> {
+    enqueue_item(path, OutboxItem::new(account, payload)).await
+}
+
+/// Queues an item whose direct send failed without a clear answer from Moodle.
+///
+/// It counts as one attempt made at `started_at`, so delivery first looks for
+/// a copy that arrived before the connection dropped.
+pub async fn enqueue_unconfirmed(
+    path: &Path,
+    account: Account,
+    payload: OutboxPayload,
+    started_at: i64,
+) -> Result<OutboxItem> {
+    let mut item = OutboxItem::new(account, payload);
+    item.attempts = 1;
+    item.created_at = started_at;
+    enqueue_item(path, item).await
+}
+
+async fn enqueue_item(path: &Path, item: OutboxItem) -> Result<OutboxItem> {
+    let stored = item.clone();
+    modify(path, move |items| items.push(stored)).await?;
+    Ok(item)
+}
+
+pub async fn list_outbox(path: &Path) -> Result<Vec<OutboxItem>> {
+    let _guard = OUTBOX_LOCK.get_or_init(|| Mutex::new(())).lock().await;
+    read_items(path).await
+}
+
+/// Replaces the stored copy of `item`.
+pub async fn update_item(path: &Path, item: &OutboxItem) -> Result<()> {
+    let item = item.clone();
+    modify(path, move |items| {
+        if let Some(stored) = items.iter_mut().find(|i| i.id == item.id) {
+            *stored = item;
+        }
+    })
+    .await
+}
+
+pub async fn remove_item(path: &Path, id: &str) -> Result<()> {
+    let found = modify(path, |items| {
+        let before = items.len();
+        items.retain(|i| i.id != id);
+        before != items.len()
+    })
+    .await?;
+    if found {
+        Ok(())
+    } else {
+        Err(anyhow!("Outbox item {} not found", id))
+    }
+}
+
+/// Makes a pending or failed item due now.
+pub async fn retry_item(path: &Path, id: &str) -> Result<OutboxItem> {
+    let now = chrono::Utc::now().timestamp();
+    modify(path, |items| {
+        items.iter_mut().find(|i| i.id == id).map(|item| {
+            if item.status != OutboxStatus::Sent {
+                item.status = OutboxStatus::Pending;
+                item.next_attempt_at = now;
+            }
+            item.clone()
+        })
+    })
+    .await?
+    .ok_or_else(|| anyhow!("Outbox item {} not found", id))
+}
+
+/// Makes every pending item due now, e.g. once the connection is back.
+pub async fn wake_pending(path: &Path) -> Result<()> {
+    let now = chrono::Utc::now().timestamp();
+    modify(path, |items| {
+        for item in items.iter_mut().filter(|i| i.status == OutboxStatus::Pending) {
+            item.next_attempt_at = item.next_attempt_at.min(now);
+        }
+    })
+    .await
+}
+
+/// Drops delivered items older than `max_age` seconds.
+pub async fn prune_sent(path: &Path, max_age: i64) -> Result<()> {
+    let cutoff = chrono::Utc::now().timestamp() - max_age;
+    modify(path, |items| {
+        items.retain(|i| i.status != OutboxStatus::Sent || i.sent_at.unwrap_or(0) > cutoff)
+    })
+    .await
+}
+
+/// Items due for an attempt. Items left `Sending` by a crash are retried too.
+pub async fn due_items(path: &Path, now: i64) -> Result<Vec<OutboxItem>> {
+    Ok(list_outbox(path)
+        .await?
+        .into_iter()
+        .filter(|i| matches!(i.status, OutboxStatus::Pending | OutboxStatus::Sending) && i.next_attempt_at <= now)
+        .collect())
+}
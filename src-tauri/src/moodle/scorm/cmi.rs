@@ -0,0 +1,448 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// SCORM 1.2 error codes returned by `LMSGetLastError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScormError {
+    NoError = 0,
+    General = 101,
+    InvalidArgument = 201,
+    CannotHaveChildren = 202,
+    NotAnArray = 203,
+    NotInitialized = 301,
+    NotImplemented = 401,
+    IsKeyword = 402,
+    ReadOnly = 403,
+    WriteOnly = 404,
+    IncorrectDataType = 405,
+}
+
+impl ScormError {
+    pub fn code(self) -> u16 {
+        self as u16
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        let error = match code.trim() {
+            "0" => ScormError::NoError,
+            "101" => ScormError::General,
+            "201" => ScormError::InvalidArgument,
+            "202" => ScormError::CannotHaveChildren,
+            "203" => ScormError::NotAnArray,
+            "301" => ScormError::NotInitialized,
+            "401" => ScormError::NotImplemented,
+            "402" => ScormError::IsKeyword,
+            "403" => ScormError::ReadOnly,
+            "404" => ScormError::WriteOnly,
+            "405" => ScormError::IncorrectDataType,
+            _ => return None,
+        };
+        Some(error)
+    }
+
+    pub fn message(self) -> &'static str {
+        match self {
+            ScormError::NoError => "No error",
+            ScormError::General => "General exception",
+            ScormError::InvalidArgument => "Invalid argument error",
+            ScormError::CannotHaveChildren => "Element cannot have children",
+            ScormError::NotAnArray => "Element not an array - cannot have count",
+            ScormError::NotInitialized => "Not initialized",
+            ScormError::NotImplemented => "Not implemented error",
+            ScormError::IsKeyword => "Invalid set value, element is a keyword",
+            ScormError::ReadOnly => "Element is read only",
+            ScormError::WriteOnly => "Element is write only",
+            ScormError::IncorrectDataType => "Incorrect Data Type",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    ReadOnly,
+    WriteOnly,
+    ReadWrite,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DataType {
+    String255,
+    String4096,
+    Identifier,
+    /// Score: blank or a decimal between 0 and 100
+    Score,
+    Decimal,
+    Time,
+    Timespan,
+    Vocabulary(&'static [&'static str]),
+    /// Interaction responses; format depends on the interaction type
+    Feedback,
+}
+
+const LESSON_STATUS: &[&str] = &["passed", "completed", "failed", "incomplete", "browsed"];
+const EXIT: &[&str] = &["time-out", "suspend", "logout", ""];
+const OBJECTIVE_STATUS: &[&str] = &["passed", "completed", "failed", "incomplete", "browsed", "not attempted"];
+const INTERACTION_TYPE: &[&str] = &[
+    "true-false",
+    "choice",
+    "fill-in",
+    "matching",
+    "performance",
+    "sequencing",
+    "likert",
+    "numeric",
+];
+const INTERACTION_RESULT: &[&str] = &["correct", "wrong", "unanticipated", "neutral"];
+
+/// Elements of the SCORM 1.2 data model; array indices are written as `n`.
+const ELEMENTS: &[(&str, Access, DataType)] = &[
+    ("cmi.core.student_id", Access::ReadOnly, DataType::Identifier),
+    ("cmi.core.student_name", Access::ReadOnly, DataType::String255),
+    ("cmi.core.lesson_location", Access::ReadWrite, DataType::String255),
+    ("cmi.core.credit", Access::ReadOnly, DataType::String255),
+    ("cmi.core.lesson_status", Access::ReadWrite, DataType::Vocabulary(LESSON_STATUS)),
+    ("cmi.core.entry", Access::ReadOnly, DataType::String255),
+    ("cmi.core.score.raw", Access::ReadWrite, DataType::Score),
+    ("cmi.core.score.min", Access::ReadWrite, DataType::Score),
+    ("cmi.core.score.max", Access::ReadWrite, DataType::Score),
+    ("cmi.core.total_time", Access::ReadOnly, DataType::Timespan),
+    ("cmi.core.lesson_mode", Access::ReadOnly, DataType::String255),
+    ("cmi.core.exit", Access::WriteOnly, DataType::Vocabulary(EXIT)),
+    ("cmi.core.session_time", Access::WriteOnly, DataType::Timespan),
+    ("cmi.suspend_data", Access::ReadWrite, DataType::String4096),
+    ("cmi.launch_data", Access::ReadOnly, DataType::String4096),
+    ("cmi.comments", Access::ReadWrite, DataType::String4096),
+    ("cmi.comments_from_lms", Access::ReadOnly, DataType::String4096),
+    ("cmi.objectives.n.id", Access::ReadWrite, DataType::Identifier),
+    ("cmi.objectives.n.score.raw", Access::ReadWrite, DataType::Score),
+    ("cmi.objectives.n.score.min", Access::ReadWrite, DataType::Score),
+    ("cmi.objectives.n.score.max", Access::ReadWrite, DataType::Score),
+    ("cmi.objectives.n.status", Access::ReadWrite, DataType::Vocabulary(OBJECTIVE_STATUS)),
+    ("cmi.student_data.mastery_score", Access::ReadOnly, DataType::Score),
+    ("cmi.student_data.max_time_allowed", Access::ReadOnly, DataType::Timespan),
+    ("cmi.student_data.time_limit_action", Access::ReadOnly, DataType::String255),
+    ("cmi.student_preference.audio", Access::ReadWrite, DataType::Decimal),
+    ("cmi.student_preference.language", Access::ReadWrite, DataType::String255),
+    ("cmi.student_preference.speed", Access::ReadWrite, DataType::Decimal),
+    ("cmi.student_preference.text", Access::ReadWrite, DataType::Decimal),
+    ("cmi.interactions.n.id", Access::WriteOnly, DataType::Identifier),
+    ("cmi.interactions.n.objectives.n.id", Access::WriteOnly, DataType::Identifier),
+    ("cmi.interactions.n.time", Access::WriteOnly, DataType::Time),
+    ("cmi.interactions.n.type", Access::WriteOnly, DataType::Vocabulary(INTERACTION_TYPE)),
+    ("cmi.interactions.n.correct_responses.n.pattern", Access::WriteOnly, DataType::Feedback),
+    ("cmi.interactions.n.weighting", Access::WriteOnly, DataType::Decimal),
+    ("cmi.interactions.n.student_response", Access::WriteOnly, DataType::Feedback),
+    ("cmi.interactions.n.result", Access::WriteOnly, DataType::Feedback),
+    ("cmi.interactions.n.latency", Access::WriteOnly, DataType::Timespan),
+];
+
+const CHILDREN: &[(&str, &str)] = &[
+    ("cmi.core", "student_id,student_name,lesson_location,credit,lesson_status,entry,score,total_time,lesson_mode,exit,session_time"),
+    ("cmi.core.score", "raw,min,max"),
+    ("cmi.objectives", "id,score,status"),
+    ("cmi.objectives.n.score", "raw,min,max"),
+    ("cmi.student_data", "mastery_score,max_time_allowed,time_limit_action"),
+    ("cmi.student_preference", "audio,language,speed,text"),
+    ("cmi.interactions", "id,objectives,time,type,correct_responses,weighting,student_response,result,latency"),
+];
+
+const ARRAYS: &[&str] = &[
+    "cmi.objectives",
+    "cmi.interactions",
+    "cmi.interactions.n.objectives",
+    "cmi.interactions.n.correct_responses",
+];
+
+/// Replaces array indices with `n` so the element can be looked up.
+fn pattern_of(element: &str) -> String {
+    element
+        .split('.')
+        .map(|part| if part.chars().all(|c| c.is_ascii_digit()) && !part.is_empty() { "n" } else { part })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn is_decimal(value: &str) -> bool {
+    !value.is_empty() && value.parse::<f64>().is_ok_and(|v| v.is_finite())
+}
+
+/// `HH:MM:SS` with an optional fraction of seconds.
+fn is_time(value: &str) -> bool {
+    let parts: Vec<&str> = value.split(':').collect();
+    parts.len() == 3
+        && parts[0].len() == 2
+        && parts[1].len() == 2
+        && parts[0].parse::<u8>().is_ok_and(|h| h < 24)
+        && parts[1].parse::<u8>().is_ok_and(|m| m < 60)
+        && parts[2].split('.').next().is_some_and(|s| s.len() == 2 && s.parse::<u8>().is_ok_and(|s| s < 60))
+}
+
+/// `HHHH:MM:SS.SS`: 2-4 digit hours, seconds with up to two decimals.
+fn is_timespan(value: &str) -> bool {
+    let parts: Vec<&str> = value.split(':').collect();
+    if parts.len() != 3 || !(2..=4).contains(&parts[0].len()) || parts[1].len() != 2 {
+        return false;
+    }
+    let mut seconds = parts[2].splitn(2, '.');
+    let whole = seconds.next().unwrap_or("");
+    let fraction = seconds.next();
+    parts[0].chars().all(|c| c.is_ascii_digit())
+        && parts[1].parse::<u8>().is_ok_and(|m| m < 60)
+        && whole.len() == 2
+        && whole.parse::<u8>().is_ok_and(|s| s < 60)
+        && fraction.map_or(true, |f| (1..=2).contains(&f.len()) && f.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Seconds in a `HHHH:MM:SS.SS` timespan.
+pub fn timespan_seconds(value: &str) -> f64 {
+    let parts: Vec<f64> = value.split(':').map(|p| p.parse().unwrap_or(0.0)).collect();
+    match parts.as_slice() {
+        [h, m, s] => h * 3600.0 + m * 60.0 + s,
+        _ => 0.0,
+    }
+}
+
+pub fn format_timespan(seconds: f64) -> String {
+    let total = (seconds.max(0.0) * 100.0).round() / 100.0;
+    let hours = (total / 3600.0).floor();
+    let minutes = ((total - hours * 3600.0) / 60.0).floor();
+    let secs = total - hours * 3600.0 - minutes * 60.0;
+    format!("{:04}:{:02}:{:05.2}", hours as u64, minutes as u64, secs)
+}
+
+fn check_type(data_type: DataType, value: &str) -> bool {
+    match data_type {
+        DataType::String255 | DataType::Feedback => value.chars().count() <= 255,
+        DataType::String4096 => value.chars().count() <= 4096,
+        DataType::Identifier => value.chars().count() <= 255 && !value.is_empty() && !value.contains(char::is_whitespace),
+        DataType::Score => value.is_empty() || (is_decimal(value) && value.parse::<f64>().is_ok_and(|v| (0.0..=100.0).contains(&v))),
+        DataType::Decimal => is_decimal(value),
+        DataType::Time => is_time(value),
+        DataType::Timespan => is_timespan(value),
+        DataType::Vocabulary(words) => words.contains(&value),
+    }
+}
+
+/// The CMI data of one SCO attempt, with the read/write and data type rules
+/// of SCORM 1.2 applied to every call from the content.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CmiData {
+    pub values: BTreeMap<String, String>,
+    /// Elements set since the last commit
+    pub changed: BTreeSet<String>,
+}
+
+impl CmiData {
+    pub fn new(values: BTreeMap<String, String>) -> Self {
+        CmiData {
+            values,
+            changed: BTreeSet::new(),
+        }
+    }
+
+    fn count(&self, array: &str) -> usize {
+        let prefix = format!("{}.", array);
+        self.values
+            .keys()
+            .filter_map(|k| k.strip_prefix(&prefix))
+            .filter_map(|rest| rest.split('.').next()?.parse::<usize>().ok())
+            .map(|i| i + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn get(&self, element: &str) -> Result<String, ScormError> {
+        let element = element.trim();
+        if element.is_empty() {
+            return Err(ScormError::InvalidArgument);
+        }
+        let pattern = pattern_of(element);
+        if let Some(parent) = pattern.strip_suffix("._children") {
+            return CHILDREN
+                .iter()
+                .find(|(p, _)| *p == parent)
+                .map(|(_, children)| children.to_string())
+                .ok_or(ScormError::CannotHaveChildren);
+        }
+        if let Some(array) = element.strip_suffix("._count") {
+            return if ARRAYS.contains(&pattern_of(array).as_str()) {
+                Ok(self.count(array).to_string())
+            } else {
+                Err(ScormError::NotAnArray)
+            };
+        }
+        match ELEMENTS.iter().find(|(p, _, _)| *p == pattern) {
+            Some((_, Access::WriteOnly, _)) => Err(ScormError::WriteOnly),
+            Some(_) => Ok(self.values.get(element).cloned().unwrap_or_default()),
+            None if element.starts_with("cmi.") => Err(ScormError::NotImplemented),
+            None => Err(ScormError::InvalidArgument),
+        }
+    }
+
+    pub fn set(&mut self, element: &str, value: &str) -> Result<(), ScormError> {
+        let element = element.trim();
+        let pattern = pattern_of(element);
+        if pattern.ends_with("._children") || pattern.ends_with("._count") {
+            return Err(ScormError::IsKeyword);
+        }
+        let Some((_, access, data_type)) = ELEMENTS.iter().find(|(p, _, _)| *p == pattern) else {
+            return Err(if element.starts_with("cmi.") {
+                ScormError::NotImplemented
+            } else {
+                ScormError::InvalidArgument
+            });
+        };
+        if *access == Access::ReadOnly {
+            return Err(ScormError::ReadOnly);
+        }
+        if !check_type(*data_type, value) {
+            return Err(ScormError::IncorrectDataType);
+        }
+
+        // Array items must be added in order: index n is only valid once n-1 exists
+        let parts: Vec<&str> = element.split('.').collect();
+        for (i, part) in parts.iter().enumerate() {
+            if let Ok(index) = part.parse::<usize>() {
+                let array = parts[..i].join(".");
+                if index > self.count(&array) {
+                    return Err(ScormError::InvalidArgument);
+                }
+            }
+        }
+
+        self.values.insert(element.to_string(), value.to_string());
+        self.changed.insert(element.to_string());
+        Ok(())
+    }
+
+    /// Applies the LMS side of `LMSFinish`: default lesson status, pass/fail
+    /// from the mastery score, and the entry mode for the next launch.
+    pub fn finish(&mut self) {
+        let status = self.values.get("cmi.core.lesson_status").cloned().unwrap_or_default();
+        let credit = self.values.get("cmi.core.credit").map(|c| c.as_str()) != Some("no-credit");
+        let mastery = self
+            .values
+            .get("cmi.student_data.mastery_score")
+            .and_then(|m| m.parse::<f64>().ok());
+        let raw = self.values.get("cmi.core.score.raw").and_then(|r| r.parse::<f64>().ok());
+
+        let new_status = match (mastery, raw) {
+            (Some(mastery), Some(raw)) if credit => Some(if raw >= mastery { "passed" } else { "failed" }),
+            _ if status.is_empty() || status == "not attempted" => Some("completed"),
+            _ => None,
+        };
+        if let Some(new_status) = new_status {
+            if status != new_status {
+                self.values.insert("cmi.core.lesson_status".to_string(), new_status.to_string());
+                self.changed.insert("cmi.core.lesson_status".to_string());
+            }
+        }
+
+        let suspended = self.values.get("cmi.core.exit").map(|e| e.as_str()) == Some("suspend");
+        self.values.insert(
+            "cmi.core.entry".to_string(),
+            if suspended { "resume" } else { "" }.to_string(),
+        );
+    }
+
+    /// Elements changed since the last commit, which are then marked committed.
+    pub fn take_changes(&mut self) -> BTreeMap<String, String> {
+        let changed = std::mem::take(&mut self.changed);
+        changed
+            .into_iter()
+            .filter_map(|k| self.values.get(&k).map(|v| (k, v.clone())))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(values: &[(&str, &str)]) -> CmiData {
+        CmiData::new(values.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect())
+    }
+
+    #[test]
+    fn timespans() {
+        assert!(is_timespan("00:00:00"));
+        assert!(is_timespan("0001:30:05.5"));
+        assert!(is_timespan("9999:59:59.99"));
+        assert!(!is_timespan("1:00:00"));
+        assert!(!is_timespan("00:60:00"));
+        assert!(!is_timespan("00:00:60"));
+        assert!(!is_timespan("00:00:00.123"));
+        assert!(!is_timespan("00:00:00."));
+        assert!(!is_timespan("00:00"));
+        assert!(!is_timespan("aa:00:00"));
+    }
+
+    #[test]
+    fn get_applies_access_rules() {
+        let cmi = data(&[("cmi.core.student_id", "42"), ("cmi.core.session_time", "00:01:00")]);
+        assert_eq!(cmi.get("cmi.core.student_id"), Ok("42".to_string()));
+        assert_eq!(cmi.get("cmi.core.lesson_location"), Ok(String::new()));
+        assert_eq!(cmi.get("cmi.core.session_time"), Err(ScormError::WriteOnly));
+        assert_eq!(cmi.get("cmi.core.score._children"), Ok("raw,min,max".to_string()));
+        assert_eq!(cmi.get("cmi.core.student_id._children"), Err(ScormError::CannotHaveChildren));
+        assert_eq!(cmi.get("cmi.core._count"), Err(ScormError::NotAnArray));
+        assert_eq!(cmi.get("cmi.unknown"), Err(ScormError::NotImplemented));
+        assert_eq!(cmi.get("other"), Err(ScormError::InvalidArgument));
+        assert_eq!(cmi.get(""), Err(ScormError::InvalidArgument));
+    }
+
+    #[test]
+    fn set_validates_and_tracks_changes() {
+        let mut cmi = CmiData::default();
+        assert_eq!(cmi.set("cmi.core.lesson_status", "passed"), Ok(()));
+        assert_eq!(cmi.set("cmi.core.lesson_status", "done"), Err(ScormError::IncorrectDataType));
+        assert_eq!(cmi.set("cmi.core.score.raw", "101"), Err(ScormError::IncorrectDataType));
+        assert_eq!(cmi.set("cmi.core.score.raw", ""), Ok(()));
+        assert_eq!(cmi.set("cmi.core.student_id", "1"), Err(ScormError::ReadOnly));
+        assert_eq!(cmi.set("cmi.objectives._count", "1"), Err(ScormError::IsKeyword));
+        assert_eq!(cmi.set("cmi.core.session_time", "00:05:00"), Ok(()));
+
+        let changes = cmi.take_changes();
+        assert_eq!(changes.get("cmi.core.lesson_status").map(|v| v.as_str()), Some("passed"));
+        assert!(cmi.changed.is_empty());
+    }
+
+    #[test]
+    fn set_adds_array_items_in_order() {
+        let mut cmi = CmiData::default();
+        assert_eq!(cmi.set("cmi.objectives.1.id", "b"), Err(ScormError::InvalidArgument));
+        assert_eq!(cmi.set("cmi.objectives.0.id", "a"), Ok(()));
+        assert_eq!(cmi.set("cmi.objectives.1.id", "b"), Ok(()));
+        assert_eq!(cmi.get("cmi.objectives._count"), Ok("2".to_string()));
+        assert_eq!(cmi.set("cmi.interactions.1.objectives.0.id", "a"), Err(ScormError::InvalidArgument));
+    }
+
+    #[test]
+    fn finish_sets_status_and_entry() {
+        let mut cmi = CmiData::default();
+        cmi.finish();
+        assert_eq!(cmi.values["cmi.core.lesson_status"], "completed");
+        assert_eq!(cmi.values["cmi.core.entry"], "");
+
+        let mut cmi = data(&[
+            ("cmi.student_data.mastery_score", "80"),
+            ("cmi.core.score.raw", "75"),
+            ("cmi.core.lesson_status", "completed"),
+            ("cmi.core.exit", "suspend"),
+        ]);
+        cmi.finish();
+        assert_eq!(cmi.values["cmi.core.lesson_status"], "failed");
+        assert!(cmi.changed.contains("cmi.core.lesson_status"));
+        assert_eq!(cmi.values["cmi.core.entry"], "resume");
+
+        let mut cmi = data(&[
+            ("cmi.student_data.mastery_score", "80"),
+            ("cmi.core.score.raw", "75"),
+            ("cmi.core.credit", "no-credit"),
+            ("cmi.core.lesson_status", "incomplete"),
+        ]);
+        cmi.finish();
+        assert_eq!(cmi.values["cmi.core.lesson_status"], "incomplete");
+        assert!(cmi.changed.is_empty());
+    }
+}
@@ -0,0 +1,9 @@
+pub mod cmi;
+pub mod package;
+pub mod player;
+pub mod tracks;
+
+pub use cmi::{CmiData, ScormError};
+pub use package::*;
+pub use player::*;
+pub use tracks::*;
@@ -0,0 +1,144 @@
+use crate::moodle::calendar::login;
+use crate::moodle::content::fetch_course_module;
+use crate::moodle::exception::check_exception;
+use crate::moodle::files::{archive_file, extract_zip};
+use crate::moodle::json::{int, list, text};
+use anyhow::{anyhow, Result};
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Package details saved next to the extracted files, so a package can be
+/// played without a connection.
+const PACKAGE_INFO: &str = ".package.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sco {
+    pub id: i64,
+    pub identifier: String,
+    pub title: String,
+    /// Path of the launch file inside the package, possibly with a query string
+    pub launch: String,
+    /// `sco` talks to the API, `asset` is plain content
+    pub scorm_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScormPackage {
+    pub scorm_id: i64,
+    pub cmid: i64,
+    pub course_id: i64,
+    pub name: String,
+    /// `SCORM_1.2`, `SCORM_13` or `AICC`
+    pub version: String,
+    pub revision: i64,
+    pub max_attempts: i64,
+    pub scoes: Vec<Sco>,
+    pub directory: PathBuf,
+}
+
+impl ScormPackage {
+    /// The SCO to open first: the requested one or the first launchable.
+    pub fn sco(&self, sco_id: Option<i64>) -> Option<&Sco> {
+        match sco_id {
+            Some(id) => self.scoes.iter().find(|s| s.id == id),
+            None => self.scoes.iter().find(|s| s.scorm_type == "sco").or(self.scoes.first()),
+        }
+    }
+}
+
+pub fn package_directory(packages_dir: &Path, scorm_id: i64) -> PathBuf {
+    packages_dir.join(scorm_id.to_string())
+}
+
+/// The downloaded package, if any.
+pub fn load_package(packages_dir: &Path, scorm_id: i64) -> Option<ScormPackage> {
+    let info = std::fs::read_to_string(package_directory(packages_dir, scorm_id).join(PACKAGE_INFO)).ok()?;
+    serde_json::from_str(&info).ok()
+}
+
+async fn fetch_scoes(client: &mut MoodleClient, scorm_id: i64) -> Result<Vec<Sco>> {
+    let form: Vec<(String, String)> = vec![("scormid".to_string(), scorm_id.to_string())];
+    let json = client
+        .post("mod_scorm_get_scorm_scoes", &form)
+        .await
+        .and_then(check_exception)?;
+    let mut scoes: Vec<(i64, Sco)> = list(&json, "scoes")
+        .iter()
+        .filter(|s| text(s, "launch").is_some())
+        .map(|s| {
+            (
+                int(s, "sortorder"),
+                Sco {
+                    id: int(s, "id"),
+                    identifier: text(s, "identifier").unwrap_or_default(),
+                    title: text(s, "title").unwrap_or_default(),
+                    launch: text(s, "launch").unwrap_or_default(),
+                    scorm_type: text(s, "scormtype").unwrap_or_else(|| "sco".to_string()),
+                },
+            )
+        })
+        .collect();
+    scoes.sort_by_key(|(order, _)| *order);
+    Ok(scoes.into_iter().map(|(_, sco)| sco).collect())
+}
+
+/// Downloads and extracts a SCORM activity's package, unless the same
+/// revision is already on disk.
+pub async fn fetch_scorm_package(client: &mut MoodleClient, cmid: i64, packages_dir: &Path) -> Result<ScormPackage> {
+    let module = fetch_course_module(client, cmid, "scorm").await?;
+    let form: Vec<(String, String)> = vec![("courseids[0]".to_string(), module.course_id.to_string())];
+    let json = client
+        .post("mod_scorm_get_scorms_by_courses", &form)
+        .await
+        .and_then(check_exception)?;
+    let scorm = list(&json, "scorms")
+        .iter()
+        .find(|s| int(s, "coursemodule") == cmid)
+        .ok_or_else(|| anyhow!("SCORM package {} is not available", cmid))?;
+
+    let scorm_id = int(scorm, "id");
+    let revision = int(scorm, "revision");
+    if let Some(existing) = load_package(packages_dir, scorm_id).filter(|p| p.revision == revision) {
+        return Ok(existing);
+    }
+
+    let version = text(scorm, "version").unwrap_or_default();
+    if version == "AICC" {
+        return Err(anyhow!("AICC packages can't be played in the app"));
+    }
+    let package_url = text(scorm, "packageurl").ok_or_else(|| anyhow!("This SCORM package can't be downloaded"))?;
+    let bytes = client.download(&package_url).await?;
+
+    let directory = package_directory(packages_dir, scorm_id);
+    if directory.exists() {
+        tokio::fs::remove_dir_all(&directory).await?;
+    }
+    tokio::fs::create_dir_all(&directory).await?;
+    let target = directory.clone();
+    tokio::task::spawn_blocking(move || extract_zip(&bytes, &target)).await??;
+
+    let package = ScormPackage {
+        scorm_id,
+        cmid,
+        course_id: module.course_id,
+        name: text(scorm, "name").unwrap_or(module.name),
+        version,
+        revision,
+        max_attempts: int(scorm, "maxattempt"),
+        scoes: fetch_scoes(client, scorm_id).await?,
+        directory: directory.clone(),
+    };
+    tokio::fs::write(directory.join(PACKAGE_INFO), serde_json::to_vec_pretty(&package)?).await?;
+    Ok(package)
+}
+
+pub async fn download_scorm_package(cmid: i64, packages_dir: &Path) -> Result<ScormPackage> {
+    let mut client = login().await?;
+    fetch_scorm_package(&mut client, cmid, packages_dir).await
+}
+
+/// A file inside an extracted package; `None` for anything outside it.
+pub fn package_file(packages_dir: &Path, scorm_id: i64, relative: &str) -> Option<PathBuf> {
+    archive_file(&package_directory(packages_dir, scorm_id), relative)
+}
@@ -0,0 +1,452 @@
+use super::cmi::{CmiData, ScormError};
+use super::package::{fetch_scorm_package, load_package, ScormPackage, Sco};
+use super::tracks::{commit_tracks, latest_local_attempt, local_tracks};
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{int, list, text};
+use crate::moodle::site::{get_current_user_id, Account};
+use anyhow::{anyhow, Result};
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionState {
+    NotInitialized,
+    Running,
+    Finished,
+}
+
+/// One SCO being played, backing the `API` object the content talks to.
+#[derive(Debug, Clone)]
+pub struct ScormSession {
+    pub id: String,
+    pub scorm_id: i64,
+    pub sco_id: i64,
+    pub attempt: i64,
+    pub title: String,
+    pub launch: String,
+    pub state: SessionState,
+    pub last_error: ScormError,
+    pub cmi: CmiData,
+    account: Account,
+    tracks_path: PathBuf,
+}
+
+/// What the UI needs to open the player.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScormLaunch {
+    pub session_id: String,
+    pub scorm_id: i64,
+    pub sco_id: i64,
+    pub attempt: i64,
+    pub title: String,
+    /// The player page on the `scorm` protocol, to load in an iframe
+    pub player_url: String,
+    pub scoes: Vec<Sco>,
+    /// Launched from the downloaded copy without reaching Moodle
+    pub offline: bool,
+}
+
+/// Base URL of the `scorm` protocol; Windows and Android webviews expose custom protocols over http.
+pub fn protocol_base() -> &'static str {
+    if cfg!(any(windows, target_os = "android")) {
+        "http://scorm.localhost/"
+    } else {
+        "scorm://localhost/"
+    }
+}
+
+/// Finds a downloaded package by course module id, for offline launches.
+fn find_local_package(packages_dir: &Path, cmid: i64) -> Option<ScormPackage> {
+    std::fs::read_dir(packages_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<i64>().ok())
+        .filter_map(|scorm_id| load_package(packages_dir, scorm_id))
+        .find(|package| package.cmid == cmid)
+}
+
+async fn fetch_attempt_count(client: &mut MoodleClient, scorm_id: i64) -> Result<i64> {
+    let user_id = get_current_user_id(client).await?;
+    let form: Vec<(String, String)> = vec![
+        ("scormid".to_string(), scorm_id.to_string()),
+        ("userid".to_string(), user_id.to_string()),
+    ];
+    let json = client
+        .post("mod_scorm_get_scorm_attempt_count", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(int(&json, "attemptscount"))
+}
+
+/// Moodle's default values for the SCO (student name, credit, mastery score)
+/// overlaid with what was already tracked in this attempt.
+async fn fetch_user_data(client: &mut MoodleClient, scorm_id: i64, sco_id: i64, attempt: i64) -> Result<BTreeMap<String, String>> {
+    let form: Vec<(String, String)> = vec![
+        ("scormid".to_string(), scorm_id.to_string()),
+        ("attempt".to_string(), attempt.to_string()),
+    ];
+    let json = client
+        .post("mod_scorm_get_scorm_user_data", &form)
+        .await
+        .and_then(check_exception)?;
+    let mut values = BTreeMap::new();
+    if let Some(sco) = list(&json, "data").iter().find(|d| int(d, "scoid") == sco_id) {
+        for item in list(sco, "defaultdata").iter().chain(list(sco, "userdata")) {
+            let Some(element) = text(item, "element").filter(|e| e.starts_with("cmi.")) else {
+                continue;
+            };
+            values.insert(element, item.get("value").and_then(|v| v.as_str()).unwrap_or("").to_string());
+        }
+    }
+    Ok(values)
+}
+
+fn apply_defaults(values: &mut BTreeMap<String, String>) {
+    let fresh = values
+        .get("cmi.core.lesson_status")
+        .map_or(true, |status| status.is_empty() || status == "not attempted");
+    let defaults = [
+        ("cmi.core.lesson_status", "not attempted"),
+        ("cmi.core.credit", "credit"),
+        ("cmi.core.lesson_mode", "normal"),
+        ("cmi.core.total_time", "0000:00:00.00"),
+        ("cmi.core.entry", if fresh { "ab-initio" } else { "" }),
+    ];
+    for (element, value) in defaults {
+        values.entry(element.to_string()).or_insert_with(|| value.to_string());
+    }
+}
+
+/// Prepares a SCO for playing: downloads the package when needed, picks the
+/// attempt and loads its CMI data. Without a connection the downloaded copy
+/// and the locally stored tracks are used.
+pub async fn launch_scorm(
+    cmid: i64,
+    sco_id: Option<i64>,
+    new_attempt: bool,
+    account: &Account,
+    packages_dir: &Path,
+    tracks_path: &Path,
+) -> Result<(ScormSession, ScormLaunch)> {
+    let online: Result<(MoodleClient, ScormPackage)> = async {
+        let mut client = login().await?;
+        let package = fetch_scorm_package(&mut client, cmid, packages_dir).await?;
+        Ok((client, package))
+    }
+    .await;
+
+    let (mut client, package, offline) = match online {
+        Ok((client, package)) => (Some(client), package, false),
+        Err(e) => match find_local_package(packages_dir, cmid) {
+            Some(package) => (None, package, true),
+            None => return Err(e),
+        },
+    };
+    if !package.version.starts_with("SCORM_1.2") {
+        return Err(anyhow!("Only SCORM 1.2 packages can be played in the app ({})", package.version));
+    }
+    let sco = package.sco(sco_id).cloned().ok_or_else(|| anyhow!("This package has nothing to launch"))?;
+
+    let local_attempt = latest_local_attempt(tracks_path, account, package.scorm_id).unwrap_or(0);
+    let server_attempts = match client.as_mut() {
+        Some(client) => fetch_attempt_count(client, package.scorm_id).await.unwrap_or(0),
+        None => 0,
+    };
+    let current = server_attempts.max(local_attempt);
+    let can_start_new = package.max_attempts == 0 || current < package.max_attempts;
+    let attempt = if new_attempt && can_start_new { current + 1 } else { current.max(1) };
+
+    let mut values = match client.as_mut() {
+        Some(client) => fetch_user_data(client, package.scorm_id, sco.id, attempt).await.unwrap_or_default(),
+        None => BTreeMap::new(),
+    };
+    // Anything committed here but maybe not yet synced is newer than Moodle's copy
+    if let Some(local) = local_tracks(tracks_path, account, package.scorm_id, sco.id, attempt) {
+        values.extend(local.values);
+    }
+    apply_defaults(&mut values);
+
+    if let Some(client) = client.as_mut() {
+        let form: Vec<(String, String)> = vec![
+            ("scormid".to_string(), package.scorm_id.to_string()),
+            ("scoid".to_string(), sco.id.to_string()),
+        ];
+        let _ = client.post("mod_scorm_launch_sco", &form).await;
+    }
+
+    let session = ScormSession {
+        id: uuid::Uuid::new_v4().to_string(),
+        scorm_id: package.scorm_id,
+        sco_id: sco.id,
+        attempt,
+        title: sco.title.clone(),
+        launch: sco.launch.clone(),
+        state: SessionState::NotInitialized,
+        last_error: ScormError::NoError,
+        cmi: CmiData::new(values),
+        account: account.clone(),
+        tracks_path: tracks_path.to_path_buf(),
+    };
+    let launch = ScormLaunch {
+        session_id: session.id.clone(),
+        scorm_id: package.scorm_id,
+        sco_id: sco.id,
+        attempt,
+        title: sco.title,
+        player_url: format!("{}__player/{}", protocol_base(), session.id),
+        scoes: package.scoes,
+        offline,
+    };
+    Ok((session, launch))
+}
+
+/// Result of one API call: the string returned to the content and whether
+/// tracks were committed and should be synced.
+pub struct ApiResponse {
+    pub value: String,
+    pub committed: bool,
+}
+
+impl ApiResponse {
+    fn value(value: impl Into<String>) -> Self {
+        ApiResponse {
+            value: value.into(),
+            committed: false,
+        }
+    }
+}
+
+impl ScormSession {
+    fn fail(&mut self, error: ScormError, value: &str) -> ApiResponse {
+        self.last_error = error;
+        ApiResponse::value(value)
+    }
+
+    fn commit(&mut self) -> Result<()> {
+        let changes = self.cmi.take_changes();
+        commit_tracks(
+            &self.tracks_path,
+            &self.account,
+            self.scorm_id,
+            self.sco_id,
+            self.attempt,
+            &self.cmi.values,
+            &changes,
+        )
+    }
+
+    /// Runs a SCORM 1.2 API function as called by the content.
+    pub fn call(&mut self, method: &str, element: &str, value: &str) -> ApiResponse {
+        let running = self.state == SessionState::Running;
+        match method {
+            "LMSInitialize" => {
+                if !element.is_empty() {
+                    return self.fail(ScormError::InvalidArgument, "false");
+                }
+                if self.state != SessionState::NotInitialized {
+                    return self.fail(ScormError::General, "false");
+                }
+                self.state = SessionState::Running;
+                self.fail(ScormError::NoError, "true")
+            }
+            "LMSFinish" | "LMSCommit" => {
+                if !element.is_empty() {
+                    return self.fail(ScormError::InvalidArgument, "false");
+                }
+                if !running {
+                    return self.fail(ScormError::NotInitialized, "false");
+                }
+                if method == "LMSFinish" {
+                    self.cmi.finish();
+                    self.state = SessionState::Finished;
+                }
+                match self.commit() {
+                    Ok(()) => {
+                        self.last_error = ScormError::NoError;
+                        ApiResponse {
+                            value: "true".to_string(),
+                            committed: true,
+                        }
+                    }
+                    Err(_) => self.fail(ScormError::General, "false"),
+                }
+            }
+            "LMSGetValue" => {
+                if !running {
+                    return self.fail(ScormError::NotInitialized, "");
+                }
+                match self.cmi.get(element) {
+                    Ok(value) => self.fail(ScormError::NoError, &value),
+                    Err(error) => self.fail(error, ""),
+                }
+            }
+            "LMSSetValue" => {
+                if !running {
+                    return self.fail(ScormError::NotInitialized, "false");
+                }
+                match self.cmi.set(element, value) {
+                    Ok(()) => self.fail(ScormError::NoError, "true"),
+                    Err(error) => self.fail(error, "false"),
+                }
+            }
+            "LMSGetLastError" => ApiResponse::value(self.last_error.code().to_string()),
+            "LMSGetErrorString" | "LMSGetDiagnostic" => {
+                let error = ScormError::from_code(element).unwrap_or(self.last_error);
+                ApiResponse::value(error.message())
+            }
+            _ => ApiResponse::value("false"),
+        }
+    }
+}
+
+/// A request on the `scorm` protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScormRequest {
+    /// `/__player/<session>`: the page hosting the API and the SCO
+    Player { session_id: String },
+    /// `/__api/<session>/<function>?a=..&b=..`: a synchronous API call
+    Api {
+        session_id: String,
+        method: String,
+        element: String,
+        value: String,
+    },
+    /// `/<scorm id>/<path>`: a file from the extracted package
+    File { scorm_id: i64, path: String },
+    NotFound,
+}
+
+fn query_param(query: &str, name: &str) -> String {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .and_then(|(_, value)| urlencoding::decode(value).ok())
+        .map(|value| value.into_owned())
+        .unwrap_or_default()
+}
+
+pub fn parse_request(path: &str, query: Option<&str>) -> ScormRequest {
+    let path = urlencoding::decode(path).map(|p| p.into_owned()).unwrap_or_else(|_| path.to_string());
+    let mut parts = path.trim_start_matches('/').splitn(2, '/');
+    let first = parts.next().unwrap_or("");
+    let rest = parts.next().unwrap_or("");
+    match first {
+        "__player" if !rest.is_empty() => ScormRequest::Player {
+            session_id: rest.trim_end_matches('/').to_string(),
+        },
+        "__api" => match rest.split_once('/') {
+            Some((session_id, method)) => ScormRequest::Api {
+                session_id: session_id.to_string(),
+                method: method.to_string(),
+                element: query_param(query.unwrap_or(""), "a"),
+                value: query_param(query.unwrap_or(""), "b"),
+            },
+            None => ScormRequest::NotFound,
+        },
+        _ => match first.parse::<i64>() {
+            Ok(scorm_id) => ScormRequest::File {
+                scorm_id,
+                path: rest.to_string(),
+            },
+            Err(_) => ScormRequest::NotFound,
+        },
+    }
+}
+
+fn escape_attribute(value: &str) -> String {
+    value.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;")
+}
+
+/// The page that exposes `window.API` to the SCO in its iframe. Calls go to
+/// the `/__api` route with synchronous requests, as SCORM 1.2 requires
+/// return values immediately.
+pub fn player_html(session: &ScormSession) -> String {
+    let launch = session.launch.trim_start_matches('/').replace(' ', "%20");
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>html, body, iframe {{ margin: 0; padding: 0; border: 0; width: 100%; height: 100%; overflow: hidden; }}</style>
+<script>
+(function () {{
+  var base = "../__api/{session}/";
+  function call(method, a, b) {{
+    var request = new XMLHttpRequest();
+    var query = "?a=" + encodeURIComponent(a == null ? "" : String(a)) + "&b=" + encodeURIComponent(b == null ? "" : String(b));
+    request.open("GET", base + method + query, false);
+    request.send(null);
+    return request.status === 200 ? request.responseText : "false";
+  }}
+  window.API = {{
+    LMSInitialize: function (p) {{ return call("LMSInitialize", p); }},
+    LMSFinish: function (p) {{ return call("LMSFinish", p); }},
+    LMSGetValue: function (e) {{ return call("LMSGetValue", e); }},
+    LMSSetValue: function (e, v) {{ return call("LMSSetValue", e, v); }},
+    LMSCommit: function (p) {{ return call("LMSCommit", p); }},
+    LMSGetLastError: function () {{ return call("LMSGetLastError"); }},
+    LMSGetErrorString: function (c) {{ return call("LMSGetErrorString", c); }},
+    LMSGetDiagnostic: function (c) {{ return call("LMSGetDiagnostic", c); }}
+  }};
+  // Content that is closed without calling LMSFinish still gets its data saved
+  window.addEventListener("pagehide", function () {{ call("LMSFinish", ""); }});
+}})();
+</script>
+</head>
+<body><iframe src="../{scorm_id}/{launch}" allow="autoplay; fullscreen"></iframe></body>
+</html>
+"#,
+        title = escape_attribute(&session.title),
+        session = session.id,
+        scorm_id = session.scorm_id,
+        launch = escape_attribute(&launch),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_protocol_requests() {
+        assert_eq!(
+            parse_request("/__player/abc", None),
+            ScormRequest::Player {
+                session_id: "abc".to_string()
+            }
+        );
+        assert_eq!(
+            parse_request("/__api/abc/LMSSetValue", Some("a=cmi.core.lesson_location&b=page%202")),
+            ScormRequest::Api {
+                session_id: "abc".to_string(),
+                method: "LMSSetValue".to_string(),
+                element: "cmi.core.lesson_location".to_string(),
+                value: "page 2".to_string(),
+            }
+        );
+        assert_eq!(
+            parse_request("/__api/abc/LMSInitialize", None),
+            ScormRequest::Api {
+                session_id: "abc".to_string(),
+                method: "LMSInitialize".to_string(),
+                element: String::new(),
+                value: String::new(),
+            }
+        );
+        assert_eq!(
+            parse_request("/12/shared/launch%20page.html", None),
+            ScormRequest::File {
+                scorm_id: 12,
+                path: "shared/launch page.html".to_string()
+            }
+        );
+        assert_eq!(parse_request("/__api/abc", None), ScormRequest::NotFound);
+        assert_eq!(parse_request("/__player/", None), ScormRequest::NotFound);
+        assert_eq!(parse_request("/favicon.ico", None), ScormRequest::NotFound);
+    }
+}
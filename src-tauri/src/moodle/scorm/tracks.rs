@@ -0,0 +1,258 @@
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{list, text};
+use crate::moodle::site::Account;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+/// Serialises every read-modify-write of the tracks file. Commits come from
+/// the protocol handler, which is synchronous, so this is a std mutex.
+static TRACKS_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+
+/// The CMI data of one attempt at one SCO as last committed on this device.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttemptTracks {
+    /// The learner the attempt belongs to; missing on tracks stored before
+    /// accounts were recorded, which are never synced
+    #[serde(default)]
+    pub account: Option<Account>,
+    pub scorm_id: i64,
+    pub sco_id: i64,
+    pub attempt: i64,
+    pub values: BTreeMap<String, String>,
+    /// Elements not yet sent to Moodle
+    pub unsynced: BTreeSet<String>,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrackStore {
+    pub attempts: Vec<AttemptTracks>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrackSyncSummary {
+    pub synced: usize,
+    pub pending: usize,
+    pub errors: Vec<String>,
+}
+
+fn read_store(path: &Path) -> Result<TrackStore> {
+    match std::fs::read(path) {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(TrackStore::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn write_store(path: &Path, store: &TrackStore) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Write then rename so a crash never loses committed tracks
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec(store)?)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+impl AttemptTracks {
+    fn is(&self, account: &Account, scorm_id: i64, sco_id: i64, attempt: i64) -> bool {
+        self.account.as_ref() == Some(account)
+            && self.scorm_id == scorm_id
+            && self.sco_id == sco_id
+            && self.attempt == attempt
+    }
+}
+
+fn modify<T>(path: &Path, change: impl FnOnce(&mut TrackStore) -> T) -> Result<T> {
+    let _guard = TRACKS_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .map_err(|_| anyhow!("SCORM track store lock poisoned"))?;
+    let mut store = read_store(path)?;
+    let result = change(&mut store);
+    write_store(path, &store)?;
+    Ok(result)
+}
+
+/// The locally stored values of an attempt, if `account` ever played it here.
+pub fn local_tracks(path: &Path, account: &Account, scorm_id: i64, sco_id: i64, attempt: i64) -> Option<AttemptTracks> {
+    let _guard = TRACKS_LOCK.get_or_init(|| Mutex::new(())).lock().ok()?;
+    read_store(path)
+        .ok()?
+        .attempts
+        .into_iter()
+        .find(|a| a.is(account, scorm_id, sco_id, attempt))
+}
+
+/// The highest attempt number `account` has stored locally for a package.
+pub fn latest_local_attempt(path: &Path, account: &Account, scorm_id: i64) -> Option<i64> {
+    let _guard = TRACKS_LOCK.get_or_init(|| Mutex::new(())).lock().ok()?;
+    read_store(path)
+        .ok()?
+        .attempts
+        .iter()
+        .filter(|a| a.account.as_ref() == Some(account) && a.scorm_id == scorm_id)
+        .map(|a| a.attempt)
+        .max()
+}
+
+/// Saves a commit: the full CMI state plus which elements still need syncing.
+pub fn commit_tracks(
+    path: &Path,
+    account: &Account,
+    scorm_id: i64,
+    sco_id: i64,
+    attempt: i64,
+    values: &BTreeMap<String, String>,
+    changed: &BTreeMap<String, String>,
+) -> Result<()> {
+    modify(path, |store| {
+        let now = chrono::Utc::now().timestamp();
+        match store.attempts.iter_mut().find(|a| a.is(account, scorm_id, sco_id, attempt)) {
+            Some(existing) => {
+                existing.values = values.clone();
+                existing.unsynced.extend(changed.keys().cloned());
+                existing.updated_at = now;
+            }
+            None => store.attempts.push(AttemptTracks {
+                account: Some(account.clone()),
+                scorm_id,
+                sco_id,
+                attempt,
+                values: values.clone(),
+                unsynced: changed.keys().cloned().collect(),
+                updated_at: now,
+            }),
+        }
+    })
+}
+
+/// Sends every unsynced element of `account`, the signed-in user, to Moodle
+/// with `mod_scorm_insert_scorm_tracks`. Attempts that fail stay queued for
+/// the next sync; other accounts' attempts wait until they sign in again.
+pub async fn sync_scorm_tracks(path: &Path, account: &Account) -> Result<TrackSyncSummary> {
+    let pending: Vec<AttemptTracks> = {
+        let _guard = TRACKS_LOCK
+            .get_or_init(|| Mutex::new(()))
+            .lock()
+            .map_err(|_| anyhow!("SCORM track store lock poisoned"))?;
+        read_store(path)?
+            .attempts
+            .into_iter()
+            .filter(|a| a.account.as_ref() == Some(account) && !a.unsynced.is_empty())
+            .collect()
+    };
+    let mut summary = TrackSyncSummary::default();
+    if pending.is_empty() {
+        return Ok(summary);
+    }
+
+    let client = login().await?;
+    for attempt in pending {
+        let mut form: Vec<(String, String)> = vec![
+            ("scoid".to_string(), attempt.sco_id.to_string()),
+            ("attempt".to_string(), attempt.attempt.to_string()),
+        ];
+        for (i, element) in attempt.unsynced.iter().enumerate() {
+            let value = attempt.values.get(element).cloned().unwrap_or_default();
+            form.push((format!("tracks[{}][element]", i), element.clone()));
+            form.push((format!("tracks[{}][value]", i), value));
+        }
+
+        let result = client
+            .post("mod_scorm_insert_scorm_tracks", &form)
+            .await
+            .and_then(check_exception);
+        match result {
+            Ok(json) => {
+                let warnings = list(&json, "warnings");
+                for warning in warnings {
+                    summary.errors.push(text(warning, "message").unwrap_or_default());
+                }
+                // Elements Moodle did not save stay pending, like a failed request
+                let rejected = rejected_elements(warnings, &attempt.unsynced);
+                // Elements committed again while syncing stay unsynced
+                let sent: BTreeSet<String> = attempt.unsynced.difference(&rejected).cloned().collect();
+                let sent_values = attempt.values.clone();
+                modify(path, |store| {
+                    if let Some(stored) = store
+                        .attempts
+                        .iter_mut()
+                        .find(|a| a.is(account, attempt.scorm_id, attempt.sco_id, attempt.attempt))
+                    {
+                        stored
+                            .unsynced
+                            .retain(|e| !sent.contains(e) || stored.values.get(e) != sent_values.get(e));
+                    }
+                })?;
+                if rejected.is_empty() {
+                    summary.synced += 1;
+                } else {
+                    summary.pending += 1;
+                }
+            }
+            Err(e) => {
+                summary.errors.push(e.to_string());
+                summary.pending += 1;
+            }
+        }
+    }
+    Ok(summary)
+}
+
+/// Elements named in `insert_scorm_tracks` warnings ("Element: x was not
+/// saved"). Any other warning, e.g. no permission to attempt, means nothing
+/// was saved.
+fn rejected_elements(warnings: &[serde_json::Value], sent: &BTreeSet<String>) -> BTreeSet<String> {
+    let mut rejected = BTreeSet::new();
+    for warning in warnings {
+        let message = text(warning, "message").unwrap_or_default();
+        match message
+            .strip_prefix("Element: ")
+            .and_then(|rest| rest.strip_suffix(" was not saved"))
+        {
+            Some(element) => {
+                rejected.insert(element.to_string());
+            }
+            None => return sent.clone(),
+        }
+    }
+    rejected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(user_id: i64) -> Account {
+        Account {
+            site_url: "https://moodle.example.com".to_string(),
+            user_id,
+        }
+    }
+
+    #[tokio::test]
+    async fn keeps_attempts_apart_per_account() {
+        let path = std::env::temp_dir().join(format!("scorm-tracks-{}.json", uuid::Uuid::new_v4()));
+        let values = BTreeMap::from([("cmi.core.score.raw".to_string(), "80".to_string())]);
+        commit_tracks(&path, &account(1), 3, 4, 2, &values, &values).unwrap();
+
+        let other = account(2);
+        let local = local_tracks(&path, &other, 3, 4, 2);
+        let latest = latest_local_attempt(&path, &other, 3);
+        // Nothing of the other user's is sent, so this needs no connection
+        let summary = sync_scorm_tracks(&path, &other).await.unwrap();
+        let own = local_tracks(&path, &account(1), 3, 4, 2);
+        let _ = std::fs::remove_file(&path);
+
+        assert!(local.is_none());
+        assert_eq!(latest, None);
+        assert_eq!((summary.synced, summary.pending), (0, 0));
+        assert_eq!(own.unwrap().unsynced.len(), 1);
+    }
+}
@@ -0,0 +1,102 @@
+use super::get_current_user_id;
+use crate::moodle::calendar::{credentials, login};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tokio::sync::Mutex;
+
+/// Accounts resolved in this run, by site URL and username.
+static RESOLVED: OnceLock<Mutex<HashMap<String, Account>>> = OnceLock::new();
+
+/// A user on a site. Queued and cached data records the account it belongs
+/// to, so nothing is sent or shown as someone else after switching accounts.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Account {
+    /// Site URL without a trailing slash
+    pub site_url: String,
+    pub user_id: i64,
+}
+
+impl Account {
+    /// Directory under `root` for files that belong to this account.
+    pub fn data_dir(&self, root: &Path) -> PathBuf {
+        let site = self.site_url.split_once("://").map_or(self.site_url.as_str(), |(_, rest)| rest);
+        let site: String = site
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+            .collect();
+        root.join("accounts").join(format!("{}_{}", site, self.user_id))
+    }
+}
+
+/// The configured credentials, as a key that does not change with the password.
+fn login_key() -> (String, String) {
+    let (base_url, username, _) = credentials();
+    let site_url = base_url.trim_end_matches('/').to_string();
+    (format!("{}|{}", site_url, username), site_url)
+}
+
+async fn read_known(path: &Path) -> Result<HashMap<String, Account>> {
+    match tokio::fs::read(path).await {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes).unwrap_or_default()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The account the configured credentials sign in to.
+///
+/// The user id is looked up online once per site and username and kept in
+/// `known_path`, so work can still be queued and read offline.
+pub async fn current_account(known_path: &Path) -> Result<Account> {
+    let mut resolved = RESOLVED.get_or_init(|| Mutex::new(HashMap::new())).lock().await;
+    let (key, site_url) = login_key();
+    if let Some(account) = resolved.get(&key) {
+        return Ok(account.clone());
+    }
+
+    let mut known = read_known(known_path).await?;
+    let account = match known.get(&key) {
+        Some(account) => account.clone(),
+        None => {
+            let mut client = login().await?;
+            let account = Account {
+                site_url,
+                user_id: get_current_user_id(&mut client).await?,
+            };
+            known.insert(key.clone(), account.clone());
+            if let Some(parent) = known_path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::write(known_path, serde_json::to_vec_pretty(&known)?).await?;
+            account
+        }
+    };
+    resolved.insert(key, account.clone());
+    Ok(account)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_dir_separates_sites_and_users() {
+        let root = Path::new("/data");
+        let account = Account {
+            site_url: "https://moodle.example.com/lms".to_string(),
+            user_id: 42,
+        };
+        assert_eq!(account.data_dir(root), Path::new("/data/accounts/moodle.example.com_lms_42"));
+
+        let other_user = Account { user_id: 43, ..account.clone() };
+        let other_site = Account {
+            site_url: "https://moodle.example.org/lms".to_string(),
+            ..account.clone()
+        };
+        assert_ne!(account.data_dir(root), other_user.data_dir(root));
+        assert_ne!(account.data_dir(root), other_site.data_dir(root));
+    }
+}
@@ -1,6 +1,7 @@
 use super::super::calendar::login;
 use anyhow::Result;
 use moodle_api::core::webservice::get_site_info;
+use moodle_client::MoodleClient;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -31,3 +32,17 @@ pub async fn get_current_site_info() -> Result<SiteInfo> {
 
     Ok(site_info)
 }
+
+/// Resolves the logged-in user's id on an existing client, for functions that
+/// would otherwise return data for every user a teacher can see.
+pub async fn get_current_user_id(client: &mut MoodleClient) -> Result<i64> {
+    let mut params = get_site_info::Params {
+        serviceshortnames: None,
+    };
+
+    let response = get_site_info::call_raw(client, &mut params).await?;
+    response
+        .get("userid")
+        .and_then(|id| id.as_i64())
+        .ok_or_else(|| anyhow::anyhow!("Could not determine the current user"))
+}
@@ -1,3 +1,5 @@
+pub mod account;
 pub mod get_site_info;
 
+pub use account::*;
 pub use get_site_info::*;
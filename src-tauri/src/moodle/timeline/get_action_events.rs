@@ -0,0 +1,135 @@
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{flag, int, list, text};
+use anyhow::Result;
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DueState {
+    Overdue,
+    DueThisWeek,
+    Later,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineItem {
+    pub event_id: i64,
+    pub name: String,
+    pub activity_name: Option<String>,
+    pub course_id: i64,
+    pub course_name: Option<String>,
+    /// `assign`, `quiz`, `forum`, `workshop`, ...
+    pub module: Option<String>,
+    pub instance: i64,
+    pub cmid: Option<i64>,
+    pub event_type: Option<String>,
+    pub timesort: i64,
+    pub due_state: DueState,
+    /// e.g. "Add submission", "Attempt quiz now"
+    pub action_name: Option<String>,
+    pub action_url: Option<String>,
+    /// False when the action exists but can't be taken yet (e.g. quiz not open)
+    pub actionable: bool,
+    pub item_count: i64,
+    pub url: Option<String>,
+}
+
+pub const WEEK_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+pub fn due_state(timesort: i64, overdue: bool, now: i64) -> DueState {
+    if overdue || timesort < now {
+        DueState::Overdue
+    } else if timesort <= now + WEEK_SECONDS {
+        DueState::DueThisWeek
+    } else {
+        DueState::Later
+    }
+}
+
+pub fn build_item(event: &serde_json::Value, now: i64) -> TimelineItem {
+    let course = event.get("course");
+    let action = event.get("action");
+    let url = text(event, "url");
+    let timesort = int(event, "timesort");
+
+    TimelineItem {
+        event_id: int(event, "id"),
+        name: text(event, "name").unwrap_or_default(),
+        activity_name: text(event, "activityname"),
+        course_id: course.map(|c| int(c, "id")).unwrap_or(0),
+        course_name: course.and_then(|c| text(c, "fullname")),
+        module: text(event, "modulename"),
+        instance: int(event, "instance"),
+        // Activity events link to mod/<name>/view.php?id=<cmid>
+        cmid: url.as_deref().and_then(|u| {
+            u.split(['?', '&'])
+                .find_map(|p| p.strip_prefix("id="))
+                .and_then(|id| id.parse().ok())
+        }),
+        event_type: text(event, "eventtype"),
+        timesort,
+        due_state: due_state(timesort, flag(event, "overdue"), now),
+        action_name: action.and_then(|a| text(a, "name")),
+        action_url: action.and_then(|a| text(a, "url")),
+        actionable: action.map(|a| flag(a, "actionable")).unwrap_or(false),
+        item_count: action.map(|a| int(a, "itemcount")).unwrap_or(0),
+        url,
+    }
+}
+
+/// One page of `core_calendar_get_action_events_by_timesort`, continuing
+/// after `after_event_id` when given.
+pub async fn fetch_action_events(
+    client: &mut MoodleClient,
+    timesort_from: i64,
+    after_event_id: Option<i64>,
+    limit: i64,
+) -> Result<(Vec<TimelineItem>, Option<i64>)> {
+    let mut form: Vec<(String, String)> = vec![
+        ("timesortfrom".to_string(), timesort_from.to_string()),
+        ("limitnum".to_string(), limit.to_string()),
+        ("limittononsuspendedevents".to_string(), "1".to_string()),
+    ];
+    if let Some(after_event_id) = after_event_id {
+        form.push(("aftereventid".to_string(), after_event_id.to_string()));
+    }
+
+    let json = client
+        .post("core_calendar_get_action_events_by_timesort", &form)
+        .await
+        .and_then(check_exception)?;
+
+    let now = chrono::Utc::now().timestamp();
+    let items = list(&json, "events").iter().map(|e| build_item(e, now)).collect();
+    let last_id = json.get("lastid").and_then(|l| l.as_i64()).filter(|l| *l > 0);
+    Ok((items, last_id))
+}
+
+/// Upcoming action events grouped per course (`core_calendar_get_action_events_by_courses`).
+pub async fn fetch_course_action_events(
+    client: &mut MoodleClient,
+    course_ids: &[i64],
+    timesort_from: i64,
+    limit: i64,
+) -> Result<Vec<TimelineItem>> {
+    let mut form: Vec<(String, String)> = course_ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (format!("courseids[{}]", i), id.to_string()))
+        .collect();
+    form.push(("timesortfrom".to_string(), timesort_from.to_string()));
+    form.push(("limitnum".to_string(), limit.to_string()));
+
+    let json = client
+        .post("core_calendar_get_action_events_by_courses", &form)
+        .await
+        .and_then(check_exception)?;
+
+    let now = chrono::Utc::now().timestamp();
+    Ok(list(&json, "groups")
+        .iter()
+        .flat_map(|g| list(g, "events").iter())
+        .map(|e| build_item(e, now))
+        .collect())
+}
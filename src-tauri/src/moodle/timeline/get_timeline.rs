@@ -0,0 +1,199 @@
+use super::get_action_events::{fetch_action_events, fetch_course_action_events, DueState, TimelineItem};
+use crate::moodle::calendar::login;
+use crate::moodle::site::Account;
+use anyhow::Result;
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+use tokio::sync::Mutex;
+
+/// Overdue items older than this are left out, like Moodle's timeline block
+const OVERDUE_WINDOW_SECONDS: i64 = 14 * 24 * 60 * 60;
+/// The cached first page is served as-is while younger than this
+const CACHE_TTL_SECONDS: i64 = 5 * 60;
+const DEFAULT_PAGE_SIZE: i64 = 25;
+const MAX_COUNT_PAGES: usize = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Timeline {
+    pub overdue: Vec<TimelineItem>,
+    pub due_this_week: Vec<TimelineItem>,
+    pub later: Vec<TimelineItem>,
+    /// Pass back as `after_event_id` to load the next page
+    pub last_event_id: Option<i64>,
+    pub has_more: bool,
+    pub fetched_at: i64,
+    /// True when served from the cache (possibly because Moodle was unreachable)
+    pub cached: bool,
+}
+
+impl Timeline {
+    fn from_items(items: Vec<TimelineItem>, last_event_id: Option<i64>, has_more: bool) -> Self {
+        let mut timeline = Timeline {
+            overdue: Vec::new(),
+            due_this_week: Vec::new(),
+            later: Vec::new(),
+            last_event_id,
+            has_more,
+            fetched_at: chrono::Utc::now().timestamp(),
+            cached: false,
+        };
+        for item in items {
+            match item.due_state {
+                DueState::Overdue => timeline.overdue.push(item),
+                DueState::DueThisWeek => timeline.due_this_week.push(item),
+                DueState::Later => timeline.later.push(item),
+            }
+        }
+        timeline
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = &TimelineItem> {
+        self.overdue
+            .iter()
+            .chain(self.due_this_week.iter())
+            .chain(self.later.iter())
+    }
+}
+
+/// First pages by page size, so a small dashboard widget and the full
+/// timeline don't overwrite each other
+type TimelineCache = HashMap<i64, Timeline>;
+
+/// Per account, so switching accounts never shows the previous one's deadlines
+fn cache() -> &'static Mutex<HashMap<Account, TimelineCache>> {
+    static CACHE: OnceLock<Mutex<HashMap<Account, TimelineCache>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// The cached first page for `limit`, loading the copy saved by the last run
+/// on first use so the timeline shows at launch before Moodle answers.
+/// `cache_path` is the account's own file.
+async fn cached_page(account: &Account, cache_path: Option<&Path>, limit: i64) -> Option<Timeline> {
+    let mut cache = cache().lock().await;
+    if !cache.contains_key(account) {
+        let saved = match cache_path {
+            Some(path) => tokio::fs::read(path)
+                .await
+                .ok()
+                .and_then(|bytes| serde_json::from_slice(&bytes).ok()),
+            None => None,
+        };
+        cache.insert(account.clone(), saved.unwrap_or_default());
+    }
+    let mut cached = cache.get(account)?.get(&limit)?.clone();
+    cached.cached = true;
+    Some(cached)
+}
+
+async fn store_page(account: &Account, cache_path: Option<&Path>, limit: i64, timeline: &Timeline) {
+    let mut cache = cache().lock().await;
+    let pages = cache.entry(account.clone()).or_default();
+    pages.insert(limit, timeline.clone());
+    if let Some(path) = cache_path {
+        // Only a startup optimisation, so failing to save is not an error
+        if let Some(parent) = path.parent() {
+            let _ = tokio::fs::create_dir_all(parent).await;
+        }
+        if let Ok(bytes) = serde_json::to_vec(pages) {
+            let _ = tokio::fs::write(path, bytes).await;
+        }
+    }
+}
+
+async fn fetch_page(client: &mut MoodleClient, after_event_id: Option<i64>, limit: i64) -> Result<Timeline> {
+    let now = chrono::Utc::now().timestamp();
+    let (items, last_event_id) =
+        fetch_action_events(client, now - OVERDUE_WINDOW_SECONDS, after_event_id, limit).await?;
+    let has_more = items.len() as i64 >= limit;
+    Ok(Timeline::from_items(items, last_event_id, has_more))
+}
+
+fn assignment_count(page: &Timeline) -> u32 {
+    page.items().filter(|item| item.module.as_deref() == Some("assign")).count() as u32
+}
+
+/// Deadlines across all courses, split into overdue / due this week / later.
+///
+/// The first page is cached per `account` and `limit`, in memory and in
+/// `cache_path`: it is returned immediately while fresh, and as a fallback
+/// when Moodle can't be reached. Later pages (`after_event_id`) always go to
+/// the server.
+pub async fn get_timeline(
+    account: &Account,
+    cache_path: Option<&Path>,
+    after_event_id: Option<i64>,
+    limit: Option<i64>,
+    refresh: bool,
+) -> Result<Timeline> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let now = chrono::Utc::now().timestamp();
+
+    if after_event_id.is_none() && !refresh {
+        if let Some(cached) = cached_page(account, cache_path, limit).await {
+            if now - cached.fetched_at < CACHE_TTL_SECONDS {
+                return Ok(cached);
+            }
+        }
+    }
+
+    let fetched = async {
+        let mut client = login().await?;
+        fetch_page(&mut client, after_event_id, limit).await
+    }
+    .await;
+
+    match fetched {
+        Ok(timeline) => {
+            if after_event_id.is_none() {
+                store_page(account, cache_path, limit, &timeline).await;
+            }
+            Ok(timeline)
+        }
+        Err(e) if after_event_id.is_none() => cached_page(account, cache_path, limit).await.ok_or(e),
+        Err(e) => Err(e),
+    }
+}
+
+/// Pending assignments across every page of the timeline. Offline, only the
+/// cached first page is counted.
+pub async fn count_pending_assignments(account: &Account, cache_path: Option<&Path>) -> Result<u32> {
+    let mut client = match login().await {
+        Ok(client) => client,
+        Err(e) => {
+            return cached_page(account, cache_path, DEFAULT_PAGE_SIZE)
+                .await
+                .map(|page| assignment_count(&page))
+                .ok_or(e)
+        }
+    };
+
+    let mut count = 0;
+    let mut after_event_id = None;
+    // Bounded in case Moodle keeps reporting more pages
+    for _ in 0..MAX_COUNT_PAGES {
+        let page = fetch_page(&mut client, after_event_id, DEFAULT_PAGE_SIZE).await?;
+        if after_event_id.is_none() {
+            store_page(account, cache_path, DEFAULT_PAGE_SIZE, &page).await;
+        }
+        count += assignment_count(&page);
+        if !page.has_more || page.last_event_id.is_none() || page.last_event_id == after_event_id {
+            break;
+        }
+        after_event_id = page.last_event_id;
+    }
+    Ok(count)
+}
+
+/// Deadlines for specific courses, e.g. on a course page.
+pub async fn get_course_timeline(course_ids: Vec<i64>, limit: Option<i64>) -> Result<Timeline> {
+    let mut client = login().await?;
+    let now = chrono::Utc::now().timestamp();
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
+
+    let mut items = fetch_course_action_events(&mut client, &course_ids, now - OVERDUE_WINDOW_SECONDS, limit).await?;
+    items.sort_by_key(|i| i.timesort);
+    Ok(Timeline::from_items(items, None, false))
+}
@@ -0,0 +1,5 @@
+pub mod get_action_events;
+pub mod get_timeline;
+
+pub use get_action_events::*;
+pub use get_timeline::*;
@@ -0,0 +1,583 @@
+use super::phase::WorkshopAction;
+use super::submission::{
+    ensure_status, fetch_overview, parse_files, parse_submission, require_action, WorkshopFile, WorkshopSubmission,
+};
+use crate::moodle::calendar::login;
+use crate::moodle::content::{sanitize_html, UrlRewriter};
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{int, list, text};
+use anyhow::{anyhow, Result};
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A peer assessment allocated to the current user.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllocatedAssessment {
+    pub assessment_id: i64,
+    pub submission: WorkshopSubmission,
+    /// `None` until the assessment is saved
+    pub grade: Option<f64>,
+    pub time_modified: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CriterionOption {
+    pub value: String,
+    pub label: String,
+}
+
+/// How a criterion is answered, by grading strategy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CriterionInput {
+    /// Accumulative grading with points
+    Points { max: i64 },
+    /// Accumulative grading with a scale, or number of errors (yes/no)
+    Choice { options: Vec<CriterionOption> },
+    /// Rubric: one level per criterion, the option value is the level id
+    Level { options: Vec<CriterionOption> },
+    /// Comments strategy: only the comment is filled in
+    Comment,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssessmentCriterion {
+    pub dimension_id: i64,
+    /// Sanitized HTML
+    pub description: String,
+    pub weight: i64,
+    pub input: CriterionInput,
+    /// Whether the criterion takes a comment
+    pub commentable: bool,
+    /// Value already saved, if any
+    pub value: Option<String>,
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssessmentForm {
+    pub assessment_id: i64,
+    pub strategy: String,
+    pub criteria: Vec<AssessmentCriterion>,
+    /// Overall feedback to the author
+    pub feedback: Option<String>,
+    /// False outside the assessment phase; the form is read-only then
+    pub editable: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CriterionAnswer {
+    pub dimension_id: i64,
+    pub value: Option<String>,
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssessmentError {
+    pub dimension_id: i64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AssessmentSubmission {
+    /// False when validation failed and nothing was sent
+    pub accepted: bool,
+    pub errors: Vec<AssessmentError>,
+    /// Grade for the submission computed from this assessment
+    pub raw_grade: Option<f64>,
+}
+
+/// Strategy form fields are flattened as `name__idx_N` and, for rubric
+/// levels, `name__idx_N__idy_M`.
+fn split_field(name: &str) -> Option<(&str, usize, Option<usize>)> {
+    let (base, rest) = name.split_once("__idx_")?;
+    match rest.split_once("__idy_") {
+        Some((idx, idy)) => Some((base, idx.parse().ok()?, Some(idy.parse().ok()?))),
+        None => Some((base, rest.parse().ok()?, None)),
+    }
+}
+
+type FieldMap = BTreeMap<usize, BTreeMap<String, String>>;
+type LevelMap = BTreeMap<(usize, usize), BTreeMap<String, String>>;
+
+fn group_fields(fields: &[serde_json::Value]) -> (FieldMap, LevelMap) {
+    let mut dimensions = FieldMap::new();
+    let mut levels = LevelMap::new();
+    for field in fields {
+        let (Some(name), Some(value)) = (text(field, "name"), text(field, "value")) else {
+            continue;
+        };
+        match split_field(&name) {
+            Some((base, idx, None)) => {
+                dimensions.entry(idx).or_default().insert(base.to_string(), value);
+            }
+            Some((base, idx, Some(idy))) => {
+                levels.entry((idx, idy)).or_default().insert(base.to_string(), value);
+            }
+            None => {}
+        }
+    }
+    (dimensions, levels)
+}
+
+fn field_int(fields: &BTreeMap<String, String>, name: &str) -> i64 {
+    fields.get(name).and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0) as i64
+}
+
+fn criterion_input(
+    strategy: &str,
+    idx: usize,
+    fields: &BTreeMap<String, String>,
+    levels: &LevelMap,
+    info: Option<&serde_json::Value>,
+) -> CriterionInput {
+    match strategy {
+        "comments" => CriterionInput::Comment,
+        // -1 marks an error, 1 that the assertion holds
+        "numerrors" => CriterionInput::Choice {
+            options: vec![
+                CriterionOption {
+                    value: "-1".to_string(),
+                    label: fields.get("grade0").cloned().unwrap_or_else(|| "No".to_string()),
+                },
+                CriterionOption {
+                    value: "1".to_string(),
+                    label: fields.get("grade1").cloned().unwrap_or_else(|| "Yes".to_string()),
+                },
+            ],
+        },
+        "rubric" => CriterionInput::Level {
+            options: levels
+                .range((idx, 0)..(idx + 1, 0))
+                .map(|(_, level)| CriterionOption {
+                    value: level.get("levelid").cloned().unwrap_or_default(),
+                    label: format!(
+                        "{} ({})",
+                        level.get("definition").cloned().unwrap_or_default(),
+                        field_int(level, "grade")
+                    ),
+                })
+                .collect(),
+        },
+        _ => {
+            let scale = info.and_then(|i| text(i, "scale")).unwrap_or_default();
+            if scale.is_empty() {
+                CriterionInput::Points {
+                    max: info.map(|i| int(i, "max")).unwrap_or_else(|| field_int(fields, "grade")),
+                }
+            } else {
+                CriterionInput::Choice {
+                    options: scale
+                        .split(',')
+                        .enumerate()
+                        .map(|(i, item)| CriterionOption {
+                            value: (i + 1).to_string(),
+                            label: item.trim().to_string(),
+                        })
+                        .collect(),
+                }
+            }
+        }
+    }
+}
+
+async fn fetch_assessment(client: &MoodleClient, assessment_id: i64) -> Result<serde_json::Value> {
+    let form: Vec<(String, String)> = vec![("assessmentid".to_string(), assessment_id.to_string())];
+    let json = client
+        .post("mod_workshop_get_assessment", &form)
+        .await
+        .and_then(check_exception)?;
+    json.get("assessment")
+        .cloned()
+        .ok_or_else(|| anyhow!("Assessment {} is not available", assessment_id))
+}
+
+async fn fetch_form_definition(client: &MoodleClient, assessment_id: i64) -> Result<serde_json::Value> {
+    let form: Vec<(String, String)> = vec![
+        ("assessmentid".to_string(), assessment_id.to_string()),
+        ("mode".to_string(), "assessment".to_string()),
+    ];
+    client
+        .post("mod_workshop_get_assessment_form_definition", &form)
+        .await
+        .and_then(check_exception)
+}
+
+fn grade_of(json: &serde_json::Value, key: &str) -> Option<f64> {
+    json.get(key).and_then(|g| match g {
+        serde_json::Value::String(s) => s.parse().ok(),
+        other => other.as_f64(),
+    })
+}
+
+/// Builds the form from `mod_workshop_get_assessment_form_definition`.
+fn parse_form(
+    assessment_id: i64,
+    strategy: &str,
+    definition: &serde_json::Value,
+    assessment: &serde_json::Value,
+    editable: bool,
+    client: &MoodleClient,
+) -> AssessmentForm {
+    let rewriter = UrlRewriter::new(client, None);
+    let (dimensions, levels) = group_fields(list(definition, "fields"));
+    let (current, _) = group_fields(list(definition, "current"));
+    let infos = list(definition, "dimensionsinfo");
+
+    let criteria = dimensions
+        .iter()
+        .filter(|(_, fields)| fields.contains_key("dimensionid"))
+        .map(|(idx, fields)| {
+            let dimension_id = field_int(fields, "dimensionid");
+            let info = infos.iter().find(|i| int(i, "id") == dimension_id);
+            let saved = current.get(idx);
+            let value_key = if strategy == "rubric" { "chosenlevelid" } else { "grade" };
+            AssessmentCriterion {
+                dimension_id,
+                description: fields
+                    .get("description")
+                    .map(|html| sanitize_html(html, &rewriter))
+                    .unwrap_or_default(),
+                weight: info.map(|i| int(i, "weight")).unwrap_or_else(|| field_int(fields, "weight")),
+                input: criterion_input(strategy, *idx, fields, &levels, info),
+                commentable: strategy != "rubric",
+                value: saved
+                    .and_then(|s| s.get(value_key))
+                    .filter(|v| strategy != "comments" && !v.is_empty())
+                    // Grades come back as decimals, option values are whole numbers
+                    .map(|v| v.parse::<f64>().map(|g| (g as i64).to_string()).unwrap_or_else(|_| v.clone())),
+                comment: saved.and_then(|s| s.get("peercomment")).filter(|c| !c.is_empty()).cloned(),
+            }
+        })
+        .collect();
+
+    AssessmentForm {
+        assessment_id,
+        strategy: strategy.to_string(),
+        criteria,
+        feedback: text(assessment, "feedbackauthor"),
+        editable,
+    }
+}
+
+/// Peer assessments allocated to the current user, with the submissions to assess.
+pub async fn get_allocated_assessments(cmid: i64) -> Result<Vec<AllocatedAssessment>> {
+    let mut client = login().await?;
+    let overview = fetch_overview(&mut client, cmid).await?;
+    let form: Vec<(String, String)> = vec![("workshopid".to_string(), overview.workshop.workshop_id.to_string())];
+    let json = client
+        .post("mod_workshop_get_reviewer_assessments", &form)
+        .await
+        .and_then(check_exception)?;
+
+    let mut allocated = Vec::new();
+    for assessment in list(&json, "assessments") {
+        let form: Vec<(String, String)> = vec![("submissionid".to_string(), int(assessment, "submissionid").to_string())];
+        let submission = client
+            .post("mod_workshop_get_submission", &form)
+            .await
+            .and_then(check_exception)?;
+        allocated.push(AllocatedAssessment {
+            assessment_id: int(assessment, "id"),
+            submission: parse_submission(&submission["submission"], &client),
+            grade: grade_of(assessment, "grade"),
+            time_modified: int(assessment, "timemodified"),
+        });
+    }
+    Ok(allocated)
+}
+
+/// The assessment form of an allocated assessment, with any saved answers.
+pub async fn get_assessment_form(cmid: i64, assessment_id: i64) -> Result<AssessmentForm> {
+    let mut client = login().await?;
+    let overview = fetch_overview(&mut client, cmid).await?;
+    let assessment = fetch_assessment(&client, assessment_id).await?;
+    let definition = fetch_form_definition(&client, assessment_id).await?;
+    Ok(parse_form(
+        assessment_id,
+        &overview.workshop.strategy,
+        &definition,
+        &assessment,
+        overview.actions.contains(&WorkshopAction::AssessPeers),
+        &client,
+    ))
+}
+
+/// Checks answers against the form; an empty list means they can be sent.
+pub fn validate_assessment(form: &AssessmentForm, answers: &[CriterionAnswer]) -> Vec<AssessmentError> {
+    let mut errors = Vec::new();
+    for criterion in &form.criteria {
+        let answer = answers.iter().find(|a| a.dimension_id == criterion.dimension_id);
+        let value = answer.and_then(|a| a.value.as_deref()).filter(|v| !v.is_empty());
+        let comment = answer.and_then(|a| a.comment.as_deref()).filter(|c| !c.trim().is_empty());
+        let error = match (&criterion.input, value) {
+            (CriterionInput::Comment, _) if comment.is_none() => Some("A comment is required".to_string()),
+            (CriterionInput::Comment, _) => None,
+            (_, None) => Some("This criterion needs to be assessed".to_string()),
+            (CriterionInput::Points { max }, Some(value)) => match value.parse::<i64>() {
+                Ok(points) if (0..=*max).contains(&points) => None,
+                _ => Some(format!("Enter a grade between 0 and {}", max)),
+            },
+            (CriterionInput::Choice { options } | CriterionInput::Level { options }, Some(value)) => {
+                if options.iter().any(|o| o.value == value) {
+                    None
+                } else {
+                    Some("Select one of the options".to_string())
+                }
+            }
+        };
+        if let Some(message) = error {
+            errors.push(AssessmentError {
+                dimension_id: criterion.dimension_id,
+                message,
+            });
+        }
+    }
+    errors
+}
+
+/// Validates and saves a peer assessment during the assessment phase.
+pub async fn submit_assessment(
+    cmid: i64,
+    assessment_id: i64,
+    answers: Vec<CriterionAnswer>,
+    feedback: Option<String>,
+) -> Result<AssessmentSubmission> {
+    let mut client = login().await?;
+    let overview = fetch_overview(&mut client, cmid).await?;
+    require_action(&overview, WorkshopAction::AssessPeers)?;
+
+    let assessment = fetch_assessment(&client, assessment_id).await?;
+    let definition = fetch_form_definition(&client, assessment_id).await?;
+    let form = parse_form(assessment_id, &overview.workshop.strategy, &definition, &assessment, true, &client);
+    let errors = validate_assessment(&form, &answers);
+    if !errors.is_empty() {
+        return Ok(AssessmentSubmission {
+            accepted: false,
+            errors,
+            raw_grade: None,
+        });
+    }
+
+    // Saved grade ids must be sent back so existing grades are updated
+    let (current, _) = group_fields(list(&definition, "current"));
+    let (dimensions, _) = group_fields(list(&definition, "fields"));
+    let mut data: Vec<(String, String)> = Vec::new();
+    let mut sent = BTreeSet::new();
+    for (idx, fields) in &dimensions {
+        let dimension_id = field_int(fields, "dimensionid");
+        let Some(answer) = answers.iter().find(|a| a.dimension_id == dimension_id) else {
+            continue;
+        };
+        if !sent.insert(dimension_id) {
+            continue;
+        }
+        let grade_id = current.get(idx).map(|c| field_int(c, "gradeid")).unwrap_or(0);
+        data.push((format!("dimensionid__idx_{}", idx), dimension_id.to_string()));
+        data.push((format!("gradeid__idx_{}", idx), grade_id.to_string()));
+        if let Some(value) = &answer.value {
+            let key = if form.strategy == "rubric" { "chosenlevelid" } else { "grade" };
+            data.push((format!("{}__idx_{}", key, idx), value.clone()));
+        }
+        if let Some(comment) = &answer.comment {
+            data.push((format!("peercomment__idx_{}", idx), comment.clone()));
+        }
+    }
+    data.push(("nodims".to_string(), sent.len().to_string()));
+    data.push(("feedbackauthor".to_string(), feedback.unwrap_or_default()));
+    data.push(("feedbackauthorformat".to_string(), "1".to_string()));
+
+    let mut params: Vec<(String, String)> = vec![("assessmentid".to_string(), assessment_id.to_string())];
+    for (i, (name, value)) in data.into_iter().enumerate() {
+        params.push((format!("data[{}][name]", i), name));
+        params.push((format!("data[{}][value]", i), value));
+    }
+    let json = client
+        .post("mod_workshop_update_assessment", &params)
+        .await
+        .and_then(check_exception)?;
+    ensure_status(&json)?;
+    Ok(AssessmentSubmission {
+        accepted: true,
+        errors: Vec::new(),
+        raw_grade: grade_of(&json, "rawgrade"),
+    })
+}
+
+/// An assessment of the user's own submission.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReceivedAssessment {
+    pub assessment_id: i64,
+    pub grade: Option<f64>,
+    /// Sanitized HTML
+    pub feedback: Option<String>,
+    pub attachments: Vec<WorkshopFile>,
+    pub time_modified: i64,
+}
+
+pub(crate) async fn fetch_received_assessments(
+    client: &MoodleClient,
+    submission_id: i64,
+) -> Result<Vec<ReceivedAssessment>> {
+    let form: Vec<(String, String)> = vec![("submissionid".to_string(), submission_id.to_string())];
+    let json = client
+        .post("mod_workshop_get_submission_assessments", &form)
+        .await
+        .and_then(check_exception)?;
+    let rewriter = UrlRewriter::new(client, None);
+    Ok(list(&json, "assessments")
+        .iter()
+        .map(|a| ReceivedAssessment {
+            assessment_id: int(a, "id"),
+            grade: grade_of(a, "grade"),
+            feedback: text(a, "feedbackauthor")
+                .map(|html| sanitize_html(&html, &rewriter))
+                .filter(|html| !html.is_empty()),
+            attachments: parse_files(list(a, "feedbackattachmentfiles"), client),
+            time_modified: int(a, "timemodified"),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn validates_each_kind_of_criterion() {
+        let level = CriterionInput::Level {
+            options: ["7", "8"]
+                .iter()
+                .map(|v| CriterionOption {
+                    value: v.to_string(),
+                    label: v.to_string(),
+                })
+                .collect(),
+        };
+        let choice = CriterionInput::Choice {
+            options: ["0", "1"]
+                .iter()
+                .map(|v| CriterionOption {
+                    value: v.to_string(),
+                    label: v.to_string(),
+                })
+                .collect(),
+        };
+        let points = CriterionInput::Points { max: 10 };
+        let cases = [
+            (&points, Some("10"), None, None),
+            (&points, Some("0"), None, None),
+            (
+                &points,
+                Some("11"),
+                None,
+                Some("Enter a grade between 0 and 10"),
+            ),
+            (
+                &points,
+                Some("-1"),
+                None,
+                Some("Enter a grade between 0 and 10"),
+            ),
+            (
+                &points,
+                Some("5.5"),
+                None,
+                Some("Enter a grade between 0 and 10"),
+            ),
+            (
+                &points,
+                Some("ten"),
+                None,
+                Some("Enter a grade between 0 and 10"),
+            ),
+            (&level, Some("8"), None, None),
+            (
+                &level,
+                Some(""),
+                None,
+                Some("This criterion needs to be assessed"),
+            ),
+            (&choice, Some("1"), None, None),
+            (&choice, Some("2"), None, Some("Select one of the options")),
+            (&CriterionInput::Comment, None, Some("Well argued"), None),
+            (
+                &CriterionInput::Comment,
+                None,
+                Some("  "),
+                Some("A comment is required"),
+            ),
+        ];
+        for (i, (input, value, comment, expected)) in cases.into_iter().enumerate() {
+            let form = AssessmentForm {
+                assessment_id: 1,
+                strategy: "accumulative".to_string(),
+                criteria: vec![AssessmentCriterion {
+                    dimension_id: 1,
+                    description: String::new(),
+                    weight: 1,
+                    input: input.clone(),
+                    commentable: true,
+                    value: None,
+                    comment: None,
+                }],
+                feedback: None,
+                editable: true,
+            };
+            let answers = [CriterionAnswer {
+                dimension_id: 1,
+                value: value.map(|v| v.to_string()),
+                comment: comment.map(|c| c.to_string()),
+            }];
+            let errors = validate_assessment(&form, &answers);
+            assert_eq!(
+                errors.first().map(|e| e.message.as_str()),
+                expected,
+                "case {}",
+                i
+            );
+            assert!(errors.len() <= 1, "case {}", i);
+
+            // A criterion without any answer always needs one
+            assert_eq!(validate_assessment(&form, &[]).len(), 1, "case {}", i);
+        }
+    }
+
+    #[test]
+    fn splits_field_names() {
+        let cases = [
+            ("grade__idx_0", Some(("grade", 0, None))),
+            ("description__idx_12", Some(("description", 12, None))),
+            ("definition__idx_2__idy_3", Some(("definition", 2, Some(3)))),
+            ("nodimensions", None),
+            ("grade__idx_x", None),
+            ("definition__idx_2__idy_", None),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(split_field(name), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn groups_fields_by_dimension_and_level() {
+        let fields = [
+            json!({ "name": "dimensionid__idx_0", "value": "41" }),
+            json!({ "name": "grade__idx_0", "value": "10" }),
+            json!({ "name": "dimensionid__idx_1", "value": "42" }),
+            json!({ "name": "definition__idx_1__idy_0", "value": "Poor" }),
+            json!({ "name": "grade__idx_1__idy_0", "value": "1" }),
+            json!({ "name": "norepeats", "value": "2" }),
+            json!({ "name": "grade__idx_2" }),
+        ];
+        let (dimensions, levels) = group_fields(&fields);
+        assert_eq!(dimensions.keys().copied().collect::<Vec<_>>(), [0, 1]);
+        assert_eq!(dimensions[&0]["grade"], "10");
+        assert_eq!(dimensions[&1]["dimensionid"], "42");
+        assert!(!dimensions[&1].contains_key("definition"));
+        assert_eq!(levels[&(1, 0)]["definition"], "Poor");
+        assert_eq!(levels[&(1, 0)]["grade"], "1");
+        assert_eq!(levels.len(), 1);
+    }
+}
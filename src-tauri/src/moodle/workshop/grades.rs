@@ -0,0 +1,62 @@
+use super::assessment::{fetch_received_assessments, ReceivedAssessment};
+use super::phase::WorkshopAction;
+use super::submission::fetch_overview;
+use crate::moodle::calendar::login;
+use crate::moodle::exception::check_exception;
+use crate::moodle::json::{flag, text};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkshopGrades {
+    /// Grade for the submission, e.g. `64.00 / 80.00`
+    pub submission_grade: Option<String>,
+    /// Grade for assessing peers
+    pub assessment_grade: Option<String>,
+    pub submission_grade_hidden: bool,
+    pub assessment_grade_hidden: bool,
+    pub received_assessments: Vec<ReceivedAssessment>,
+}
+
+/// The user's grades and the assessments of their submission, once the
+/// workshop reached the evaluation phase.
+pub async fn get_workshop_grades(cmid: i64) -> Result<WorkshopGrades> {
+    let mut client = login().await?;
+    let overview = fetch_overview(&mut client, cmid).await?;
+    let can_view_received = overview.actions.contains(&WorkshopAction::ViewReceivedAssessments);
+    let can_view_grades = overview.actions.contains(&WorkshopAction::ViewGrades);
+    if !can_view_received && !can_view_grades {
+        return Err(anyhow!(
+            "Grades are not available in the {}",
+            overview.workshop.phase.label().to_lowercase()
+        ));
+    }
+
+    let received_assessments = match (&overview.submission, can_view_received) {
+        (Some(submission), true) => fetch_received_assessments(&client, submission.id).await?,
+        _ => Vec::new(),
+    };
+    let (submission_grade, assessment_grade, submission_grade_hidden, assessment_grade_hidden) = if can_view_grades {
+        let form: Vec<(String, String)> = vec![("workshopid".to_string(), overview.workshop.workshop_id.to_string())];
+        let json = client
+            .post("mod_workshop_get_grades", &form)
+            .await
+            .and_then(check_exception)?;
+        (
+            text(&json, "submissionlongstrgrade"),
+            text(&json, "assessmentlongstrgrade"),
+            flag(&json, "submissiongradehidden"),
+            flag(&json, "assessmentgradehidden"),
+        )
+    } else {
+        (None, None, false, false)
+    };
+
+    Ok(WorkshopGrades {
+        submission_grade,
+        assessment_grade,
+        submission_grade_hidden,
+        assessment_grade_hidden,
+        received_assessments,
+    })
+}
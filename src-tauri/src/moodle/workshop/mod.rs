@@ -0,0 +1,9 @@
+pub mod assessment;
+pub mod grades;
+pub mod phase;
+pub mod submission;
+
+pub use assessment::*;
+pub use grades::*;
+pub use phase::*;
+pub use submission::*;
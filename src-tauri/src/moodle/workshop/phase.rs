@@ -0,0 +1,229 @@
+use crate::moodle::json::flag;
+use serde::{Deserialize, Serialize};
+
+/// The phases a workshop goes through, in order. The teacher switches
+/// phases by hand, except that the submission phase can switch to the
+/// assessment phase when the submission deadline passes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkshopPhase {
+    Setup,
+    Submission,
+    Assessment,
+    Evaluation,
+    Closed,
+}
+
+impl WorkshopPhase {
+    /// Maps Moodle's `workshop::PHASE_*` constants.
+    pub fn from_code(code: i64) -> Self {
+        match code {
+            20 => WorkshopPhase::Submission,
+            30 => WorkshopPhase::Assessment,
+            40 => WorkshopPhase::Evaluation,
+            50 => WorkshopPhase::Closed,
+            _ => WorkshopPhase::Setup,
+        }
+    }
+
+    pub fn code(self) -> i64 {
+        match self {
+            WorkshopPhase::Setup => 10,
+            WorkshopPhase::Submission => 20,
+            WorkshopPhase::Assessment => 30,
+            WorkshopPhase::Evaluation => 40,
+            WorkshopPhase::Closed => 50,
+        }
+    }
+
+    pub fn next(self) -> Option<Self> {
+        match self {
+            WorkshopPhase::Setup => Some(WorkshopPhase::Submission),
+            WorkshopPhase::Submission => Some(WorkshopPhase::Assessment),
+            WorkshopPhase::Assessment => Some(WorkshopPhase::Evaluation),
+            WorkshopPhase::Evaluation => Some(WorkshopPhase::Closed),
+            WorkshopPhase::Closed => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            WorkshopPhase::Setup => "Setup phase",
+            WorkshopPhase::Submission => "Submission phase",
+            WorkshopPhase::Assessment => "Assessment phase",
+            WorkshopPhase::Evaluation => "Grading evaluation phase",
+            WorkshopPhase::Closed => "Closed",
+        }
+    }
+}
+
+/// What a student can do in a workshop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkshopAction {
+    AddSubmission,
+    EditSubmission,
+    DeleteSubmission,
+    AssessPeers,
+    ViewReceivedAssessments,
+    ViewGrades,
+}
+
+/// The capabilities and time windows from `mod_workshop_get_workshop_access_information`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkshopAccess {
+    pub can_submit: bool,
+    pub can_peer_assess: bool,
+    pub can_delete_submissions: bool,
+    /// Within the submission window of the current phase
+    pub creating_submission_allowed: bool,
+    pub modifying_submission_allowed: bool,
+    /// Within the assessment window of the current phase
+    pub assessing_allowed: bool,
+}
+
+impl WorkshopAccess {
+    pub fn from_json(json: &serde_json::Value) -> Self {
+        WorkshopAccess {
+            can_submit: flag(json, "cansubmit"),
+            can_peer_assess: flag(json, "canpeerassess"),
+            can_delete_submissions: flag(json, "candeletesubmissions"),
+            creating_submission_allowed: flag(json, "creatingsubmissionallowed"),
+            modifying_submission_allowed: flag(json, "modifyingsubmissionallowed"),
+            assessing_allowed: flag(json, "assessingallowed"),
+        }
+    }
+}
+
+/// The actions offered in a phase. Moodle enforces the same rules, so
+/// anything not listed here would be rejected by the site anyway.
+pub fn available_actions(phase: WorkshopPhase, access: &WorkshopAccess, has_submission: bool) -> Vec<WorkshopAction> {
+    let mut actions = Vec::new();
+    match phase {
+        WorkshopPhase::Setup => {}
+        WorkshopPhase::Submission => {
+            if access.can_submit && !has_submission && access.creating_submission_allowed {
+                actions.push(WorkshopAction::AddSubmission);
+            }
+            if access.can_submit && has_submission && access.modifying_submission_allowed {
+                actions.push(WorkshopAction::EditSubmission);
+                actions.push(WorkshopAction::DeleteSubmission);
+            }
+        }
+        WorkshopPhase::Assessment => {
+            if access.can_peer_assess && access.assessing_allowed {
+                actions.push(WorkshopAction::AssessPeers);
+            }
+        }
+        WorkshopPhase::Evaluation => {
+            if has_submission {
+                actions.push(WorkshopAction::ViewReceivedAssessments);
+            }
+        }
+        WorkshopPhase::Closed => {
+            if has_submission {
+                actions.push(WorkshopAction::ViewReceivedAssessments);
+            }
+            actions.push(WorkshopAction::ViewGrades);
+        }
+    }
+    // Teachers may remove submissions in any phase
+    if has_submission && access.can_delete_submissions && !actions.contains(&WorkshopAction::DeleteSubmission) {
+        actions.push(WorkshopAction::DeleteSubmission);
+    }
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn student() -> WorkshopAccess {
+        WorkshopAccess {
+            can_submit: true,
+            can_peer_assess: true,
+            creating_submission_allowed: true,
+            modifying_submission_allowed: true,
+            assessing_allowed: true,
+            ..WorkshopAccess::default()
+        }
+    }
+
+    #[test]
+    fn submission_phase_actions() {
+        let access = student();
+        assert_eq!(
+            available_actions(WorkshopPhase::Submission, &access, false),
+            vec![WorkshopAction::AddSubmission]
+        );
+        assert_eq!(
+            available_actions(WorkshopPhase::Submission, &access, true),
+            vec![WorkshopAction::EditSubmission, WorkshopAction::DeleteSubmission]
+        );
+
+        let closed_window = WorkshopAccess {
+            creating_submission_allowed: false,
+            modifying_submission_allowed: false,
+            ..student()
+        };
+        assert!(available_actions(WorkshopPhase::Submission, &closed_window, false).is_empty());
+        assert!(available_actions(WorkshopPhase::Submission, &closed_window, true).is_empty());
+    }
+
+    #[test]
+    fn later_phase_actions() {
+        let access = student();
+        assert!(available_actions(WorkshopPhase::Setup, &access, false).is_empty());
+        assert_eq!(
+            available_actions(WorkshopPhase::Assessment, &access, true),
+            vec![WorkshopAction::AssessPeers]
+        );
+        let outside_window = WorkshopAccess {
+            assessing_allowed: false,
+            ..student()
+        };
+        assert!(available_actions(WorkshopPhase::Assessment, &outside_window, true).is_empty());
+        assert_eq!(
+            available_actions(WorkshopPhase::Evaluation, &access, true),
+            vec![WorkshopAction::ViewReceivedAssessments]
+        );
+        assert_eq!(
+            available_actions(WorkshopPhase::Closed, &access, false),
+            vec![WorkshopAction::ViewGrades]
+        );
+    }
+
+    #[test]
+    fn teachers_can_delete_in_any_phase() {
+        let teacher = WorkshopAccess {
+            can_delete_submissions: true,
+            ..WorkshopAccess::default()
+        };
+        assert_eq!(
+            available_actions(WorkshopPhase::Assessment, &teacher, true),
+            vec![WorkshopAction::DeleteSubmission]
+        );
+        assert!(available_actions(WorkshopPhase::Assessment, &teacher, false).is_empty());
+
+        let both = WorkshopAccess {
+            can_delete_submissions: true,
+            ..student()
+        };
+        let actions = available_actions(WorkshopPhase::Submission, &both, true);
+        assert_eq!(
+            actions.iter().filter(|a| **a == WorkshopAction::DeleteSubmission).count(),
+            1
+        );
+    }
+
+    #[test]
+    fn phase_codes_round_trip() {
+        let mut phase = WorkshopPhase::Setup;
+        while let Some(next) = phase.next() {
+            assert_eq!(WorkshopPhase::from_code(next.code()), next);
+            assert!(next > phase);
+            phase = next;
+        }
+        assert_eq!(phase, WorkshopPhase::Closed);
+    }
+}
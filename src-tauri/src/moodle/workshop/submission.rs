@@ -0,0 +1,359 @@
+use super::phase::{available_actions, WorkshopAccess, WorkshopAction, WorkshopPhase};
+use crate::moodle::calendar::login;
+use crate::moodle::content::{fetch_course_module, sanitize_html, UrlRewriter};
+use crate::moodle::exception::check_exception;
+use crate::moodle::files::upload_files_to_draft_area;
+use crate::moodle::json::{flag, int, list, text};
+use crate::moodle::site::get_current_user_id;
+use anyhow::{anyhow, Result};
+use moodle_client::MoodleClient;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkshopFile {
+    pub file_name: String,
+    pub url: String,
+    pub size: i64,
+}
+
+/// A step of the user plan shown on the workshop page.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanTask {
+    pub title: String,
+    pub details: Option<String>,
+    /// `None` when the task has no completion state, e.g. informational tasks
+    pub completed: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanPhase {
+    pub phase: WorkshopPhase,
+    pub title: String,
+    pub active: bool,
+    pub tasks: Vec<PlanTask>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkshopSubmission {
+    pub id: i64,
+    pub title: String,
+    /// Sanitized HTML
+    pub content: String,
+    pub attachments: Vec<WorkshopFile>,
+    pub time_modified: i64,
+    pub late: bool,
+}
+
+/// Which parts of a submission the workshop takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmissionPart {
+    Disabled,
+    Optional,
+    Required,
+}
+
+impl SubmissionPart {
+    fn from_code(code: i64) -> Self {
+        match code {
+            0 => SubmissionPart::Disabled,
+            2 => SubmissionPart::Required,
+            _ => SubmissionPart::Optional,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workshop {
+    pub workshop_id: i64,
+    pub cmid: i64,
+    pub course_id: i64,
+    pub name: String,
+    pub intro: Option<String>,
+    pub instructions_authors: Option<String>,
+    pub instructions_reviewers: Option<String>,
+    pub conclusion: Option<String>,
+    pub phase: WorkshopPhase,
+    /// `accumulative`, `comments`, `numerrors` or `rubric`
+    pub strategy: String,
+    pub text_submission: SubmissionPart,
+    pub file_submission: SubmissionPart,
+    pub max_attachments: i64,
+    pub submission_start: i64,
+    pub submission_end: i64,
+    pub assessment_start: i64,
+    pub assessment_end: i64,
+    /// The submission phase ends by itself at `submission_end`
+    pub phase_switch_at_deadline: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkshopOverview {
+    pub workshop: Workshop,
+    pub access: WorkshopAccess,
+    pub plan: Vec<PlanPhase>,
+    pub submission: Option<WorkshopSubmission>,
+    pub actions: Vec<WorkshopAction>,
+}
+
+pub(crate) fn parse_files(files: &[serde_json::Value], client: &MoodleClient) -> Vec<WorkshopFile> {
+    files
+        .iter()
+        .filter_map(|f| {
+            Some(WorkshopFile {
+                file_name: text(f, "filename")?,
+                url: client.tokenize_url(&text(f, "fileurl")?),
+                size: int(f, "filesize"),
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn parse_submission(submission: &serde_json::Value, client: &MoodleClient) -> WorkshopSubmission {
+    let rewriter = UrlRewriter::new(client, None);
+    WorkshopSubmission {
+        id: int(submission, "id"),
+        title: text(submission, "title").unwrap_or_default(),
+        content: text(submission, "content")
+            .map(|html| sanitize_html(&html, &rewriter))
+            .unwrap_or_default(),
+        attachments: parse_files(list(submission, "attachmentfiles"), client),
+        time_modified: int(submission, "timemodified"),
+        late: flag(submission, "late"),
+    }
+}
+
+fn rich_text(json: &serde_json::Value, key: &str, rewriter: &UrlRewriter) -> Option<String> {
+    text(json, key)
+        .map(|html| sanitize_html(&html, rewriter))
+        .filter(|html| !html.is_empty())
+}
+
+pub(crate) async fn fetch_workshop(client: &mut MoodleClient, cmid: i64) -> Result<Workshop> {
+    let module = fetch_course_module(client, cmid, "workshop").await?;
+    let form: Vec<(String, String)> = vec![("courseids[0]".to_string(), module.course_id.to_string())];
+    let json = client
+        .post("mod_workshop_get_workshops_by_courses", &form)
+        .await
+        .and_then(check_exception)?;
+    let workshop = list(&json, "workshops")
+        .iter()
+        .find(|w| int(w, "coursemodule") == cmid)
+        .ok_or_else(|| anyhow!("Workshop {} is not available", cmid))?;
+
+    let rewriter = UrlRewriter::new(client, None);
+    Ok(Workshop {
+        workshop_id: int(workshop, "id"),
+        cmid,
+        course_id: module.course_id,
+        name: text(workshop, "name").unwrap_or(module.name),
+        intro: rich_text(workshop, "intro", &rewriter),
+        instructions_authors: rich_text(workshop, "instructauthors", &rewriter),
+        instructions_reviewers: rich_text(workshop, "instructreviewers", &rewriter),
+        conclusion: rich_text(workshop, "conclusion", &rewriter),
+        phase: WorkshopPhase::from_code(int(workshop, "phase")),
+        strategy: text(workshop, "strategy").unwrap_or_else(|| "accumulative".to_string()),
+        text_submission: SubmissionPart::from_code(int(workshop, "submissiontypetext")),
+        file_submission: SubmissionPart::from_code(int(workshop, "submissiontypefile")),
+        max_attachments: int(workshop, "nattachments"),
+        submission_start: int(workshop, "submissionstart"),
+        submission_end: int(workshop, "submissionend"),
+        assessment_start: int(workshop, "assessmentstart"),
+        assessment_end: int(workshop, "assessmentend"),
+        phase_switch_at_deadline: flag(workshop, "phaseswitchassessment"),
+    })
+}
+
+pub(crate) async fn fetch_access(client: &MoodleClient, workshop_id: i64) -> Result<WorkshopAccess> {
+    let form: Vec<(String, String)> = vec![("workshopid".to_string(), workshop_id.to_string())];
+    let json = client
+        .post("mod_workshop_get_workshop_access_information", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(WorkshopAccess::from_json(&json))
+}
+
+async fn fetch_plan(client: &MoodleClient, workshop_id: i64) -> Result<Vec<PlanPhase>> {
+    let form: Vec<(String, String)> = vec![("workshopid".to_string(), workshop_id.to_string())];
+    let json = client
+        .post("mod_workshop_get_user_plan", &form)
+        .await
+        .and_then(check_exception)?;
+    let plan = json.get("userplan").cloned().unwrap_or_default();
+    Ok(list(&plan, "phases")
+        .iter()
+        .map(|p| PlanPhase {
+            phase: WorkshopPhase::from_code(int(p, "code")),
+            title: text(p, "title").unwrap_or_default(),
+            active: flag(p, "active"),
+            tasks: list(p, "tasks")
+                .iter()
+                .map(|t| PlanTask {
+                    title: text(t, "title").unwrap_or_default(),
+                    details: text(t, "details"),
+                    completed: t.get("completed").and_then(|c| c.as_bool()),
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+/// The current user's submission, if they made one.
+pub(crate) async fn fetch_own_submission(
+    client: &mut MoodleClient,
+    workshop_id: i64,
+) -> Result<Option<WorkshopSubmission>> {
+    let user_id = get_current_user_id(client).await?;
+    let form: Vec<(String, String)> = vec![
+        ("workshopid".to_string(), workshop_id.to_string()),
+        ("userid".to_string(), user_id.to_string()),
+    ];
+    let json = client
+        .post("mod_workshop_get_submissions", &form)
+        .await
+        .and_then(check_exception)?;
+    Ok(list(&json, "submissions")
+        .iter()
+        .find(|s| !flag(s, "example"))
+        .map(|s| parse_submission(s, client)))
+}
+
+/// Fails unless the current phase offers `action`.
+pub(crate) fn require_action(overview: &WorkshopOverview, action: WorkshopAction) -> Result<()> {
+    if overview.actions.contains(&action) {
+        Ok(())
+    } else {
+        Err(anyhow!("This isn't possible in the {}", overview.workshop.phase.label().to_lowercase()))
+    }
+}
+
+pub(crate) async fn fetch_overview(client: &mut MoodleClient, cmid: i64) -> Result<WorkshopOverview> {
+    let workshop = fetch_workshop(client, cmid).await?;
+    let access = fetch_access(client, workshop.workshop_id).await?;
+    let plan = fetch_plan(client, workshop.workshop_id).await?;
+    let submission = fetch_own_submission(client, workshop.workshop_id).await?;
+    let actions = available_actions(workshop.phase, &access, submission.is_some());
+    Ok(WorkshopOverview {
+        workshop,
+        access,
+        plan,
+        submission,
+        actions,
+    })
+}
+
+/// The workshop's phase, the user plan, the user's submission and the
+/// actions available to them right now.
+pub async fn get_workshop(cmid: i64) -> Result<WorkshopOverview> {
+    let mut client = login().await?;
+    fetch_overview(&mut client, cmid).await
+}
+
+/// Adds or replaces the user's submission. `file_paths` replaces all the
+/// attachments; `None` keeps the current ones.
+pub async fn save_workshop_submission(
+    cmid: i64,
+    title: String,
+    content: Option<String>,
+    file_paths: Option<Vec<String>>,
+) -> Result<i64> {
+    let mut client = login().await?;
+    let overview = fetch_overview(&mut client, cmid).await?;
+    let workshop = &overview.workshop;
+    let existing = overview.submission.as_ref();
+    require_action(
+        &overview,
+        if existing.is_some() {
+            WorkshopAction::EditSubmission
+        } else {
+            WorkshopAction::AddSubmission
+        },
+    )?;
+
+    if title.trim().is_empty() {
+        return Err(anyhow!("The submission needs a title"));
+    }
+    let content = content.filter(|c| !c.trim().is_empty());
+    if content.is_some() && workshop.text_submission == SubmissionPart::Disabled {
+        return Err(anyhow!("This workshop does not accept text submissions"));
+    }
+    if content.is_none() && workshop.text_submission == SubmissionPart::Required {
+        return Err(anyhow!("The submission text is required"));
+    }
+    let attachment_count = match &file_paths {
+        Some(paths) => paths.len(),
+        None => existing.map_or(0, |s| s.attachments.len()),
+    };
+    if attachment_count > 0 && workshop.file_submission == SubmissionPart::Disabled {
+        return Err(anyhow!("This workshop does not accept attachments"));
+    }
+    if attachment_count == 0 && workshop.file_submission == SubmissionPart::Required {
+        return Err(anyhow!("At least one attachment is required"));
+    }
+    if workshop.max_attachments > 0 && attachment_count as i64 > workshop.max_attachments {
+        return Err(anyhow!("At most {} attachments can be submitted", workshop.max_attachments));
+    }
+
+    let mut form: Vec<(String, String)> = vec![
+        ("title".to_string(), title),
+        ("content".to_string(), content.unwrap_or_default()),
+        ("contentformat".to_string(), "1".to_string()),
+    ];
+    if let Some(paths) = &file_paths {
+        let draft_id = upload_files_to_draft_area(&mut client, paths).await?;
+        form.push(("attachmentsid".to_string(), draft_id.to_string()));
+    }
+
+    match existing {
+        Some(submission) => {
+            form.push(("submissionid".to_string(), submission.id.to_string()));
+            let json = client
+                .post("mod_workshop_update_submission", &form)
+                .await
+                .and_then(check_exception)?;
+            ensure_status(&json)?;
+            Ok(submission.id)
+        }
+        None => {
+            form.push(("workshopid".to_string(), workshop.workshop_id.to_string()));
+            let json = client
+                .post("mod_workshop_add_submission", &form)
+                .await
+                .and_then(check_exception)?;
+            ensure_status(&json)?;
+            Ok(int(&json, "submissionid"))
+        }
+    }
+}
+
+pub async fn delete_workshop_submission(cmid: i64) -> Result<()> {
+    let mut client = login().await?;
+    let overview = fetch_overview(&mut client, cmid).await?;
+    require_action(&overview, WorkshopAction::DeleteSubmission)?;
+    let submission = overview
+        .submission
+        .as_ref()
+        .ok_or_else(|| anyhow!("There is no submission to delete"))?;
+    let form: Vec<(String, String)> = vec![("submissionid".to_string(), submission.id.to_string())];
+    let json = client
+        .post("mod_workshop_delete_submission", &form)
+        .await
+        .and_then(check_exception)?;
+    ensure_status(&json)
+}
+
+/// The write functions report validation problems as warnings with `status: false`.
+pub(crate) fn ensure_status(json: &serde_json::Value) -> Result<()> {
+    if flag(json, "status") {
+        return Ok(());
+    }
+    let messages: Vec<String> = list(json, "warnings")
+        .iter()
+        .filter_map(|w| text(w, "message"))
+        .collect();
+    Err(anyhow!(if messages.is_empty() {
+        "Moodle rejected the change".to_string()
+    } else {
+        messages.join("; ")
+    }))
+}